@@ -0,0 +1,173 @@
+//! Appointment booking.
+//!
+//! Works out which start times are free for a service, given opening hours,
+//! slot spacing, capacity, and existing bookings. Times are naive local
+//! times in the business's `utc_offset`, stored as "YYYY-MM-DD HH:MM" so
+//! they sort and compare as strings.
+
+use crate::config::BookingConfig;
+use crate::store::{BookingRecord, BookingStatus, Store};
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// Storage format for booking times.
+pub const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+/// Storage format for dates.
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Highest number of `bookings` running at any instant within [start, end).
+pub fn max_concurrent(bookings: &[BookingRecord], start: NaiveDateTime, end: NaiveDateTime) -> usize {
+    let intervals: Vec<(NaiveDateTime, NaiveDateTime)> = bookings
+        .iter()
+        .filter(|b| b.status == BookingStatus::Confirmed)
+        .filter_map(|b| Some((b.start()?, b.end()?)))
+        .filter(|(s, e)| *s < end && *e > start)
+        .collect();
+
+    // Concurrency only rises where a booking starts, so checking the window
+    // start and every booking start inside it is enough
+    let mut points = vec![start];
+    points.extend(intervals.iter().map(|(s, _)| *s).filter(|s| *s > start));
+
+    points
+        .iter()
+        .map(|p| intervals.iter().filter(|(s, e)| s <= p && e > p).count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Earliest start time a booking made at `now` may have.
+pub fn earliest_start(cfg: &BookingConfig, now: NaiveDateTime) -> NaiveDateTime {
+    now + Duration::minutes(cfg.min_notice_minutes as i64)
+}
+
+/// Free start times on `date` for an appointment of `duration_minutes`.
+///
+/// `day_bookings` should hold the bookings starting on that date; booking
+/// `exclude` (the one being rescheduled) is ignored.
+pub fn available_slots(
+    cfg: &BookingConfig,
+    day_bookings: &[BookingRecord],
+    date: NaiveDate,
+    duration_minutes: u32,
+    now: NaiveDateTime,
+    exclude: Option<i64>,
+) -> Vec<NaiveTime> {
+    let Some((open, close)) = cfg.hours.for_day(date.weekday()) else {
+        return Vec::new();
+    };
+
+    let others: Vec<BookingRecord> = day_bookings
+        .iter()
+        .filter(|b| Some(b.id) != exclude)
+        .cloned()
+        .collect();
+    let earliest = earliest_start(cfg, now);
+    let duration = Duration::minutes(duration_minutes as i64);
+    let step = Duration::minutes(cfg.slot_minutes as i64);
+
+    let mut slots = Vec::new();
+    let mut start = date.and_time(open);
+    let closing = date.and_time(close);
+    while start + duration <= closing {
+        if start >= earliest && max_concurrent(&others, start, start + duration) < cfg.capacity as usize {
+            slots.push(start.time());
+        }
+        start += step;
+    }
+    slots
+}
+
+/// Upcoming dates (within `days_ahead`) that have at least one free slot.
+pub fn available_days(
+    cfg: &BookingConfig,
+    store: &Store,
+    duration_minutes: u32,
+    now: NaiveDateTime,
+    exclude: Option<i64>,
+) -> Result<Vec<NaiveDate>> {
+    let mut days = Vec::new();
+    for offset in 0..cfg.days_ahead {
+        let date = now.date() + Duration::days(offset as i64);
+        if cfg.hours.for_day(date.weekday()).is_none() {
+            continue;
+        }
+        let bookings = store.list_bookings_on(date)?;
+        if !available_slots(cfg, &bookings, date, duration_minutes, now, exclude).is_empty() {
+            days.push(date);
+        }
+    }
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> BookingConfig {
+        serde_yaml::from_str(
+            r#"
+hours:
+  mon: "09:00-12:00"
+slot_minutes: 30
+capacity: 1
+min_notice_minutes: 0
+"#,
+        )
+        .unwrap()
+    }
+
+    fn monday() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 2).unwrap()
+    }
+
+    fn booking(id: i64, start: &str, end: &str) -> BookingRecord {
+        BookingRecord {
+            id,
+            customer_phone: "+1".to_string(),
+            service: "Cut".to_string(),
+            price: 0.0,
+            start_at: format!("2026-03-02 {}", start),
+            end_at: format!("2026-03-02 {}", end),
+            status: BookingStatus::Confirmed,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_slots_respect_hours_and_duration() {
+        let now = monday().and_hms_opt(0, 0, 0).unwrap();
+        let slots = available_slots(&cfg(), &[], monday(), 60, now, None);
+        let times: Vec<String> = slots.iter().map(|t| t.format("%H:%M").to_string()).collect();
+        assert_eq!(times, vec!["09:00", "09:30", "10:00", "10:30", "11:00"]);
+
+        // Closed on Tuesday
+        let tuesday = monday().succ_opt().unwrap();
+        assert!(available_slots(&cfg(), &[], tuesday, 60, now, None).is_empty());
+    }
+
+    #[test]
+    fn test_slots_skip_booked_and_past_times() {
+        let now = monday().and_hms_opt(9, 10, 0).unwrap();
+        let bookings = vec![booking(1, "10:00", "11:00")];
+        let slots = available_slots(&cfg(), &bookings, monday(), 30, now, None);
+        let times: Vec<String> = slots.iter().map(|t| t.format("%H:%M").to_string()).collect();
+        assert_eq!(times, vec!["09:30", "11:00", "11:30"]);
+
+        // Rescheduling booking 1 can reuse its own time
+        let slots = available_slots(&cfg(), &bookings, monday(), 30, now, Some(1));
+        assert_eq!(slots.len(), 5);
+    }
+
+    #[test]
+    fn test_max_concurrent() {
+        let at = |h: u32, m: u32| monday().and_hms_opt(h, m, 0).unwrap();
+        let bookings = vec![booking(1, "09:00", "10:00"), booking(2, "10:00", "11:00")];
+        // Overlaps both, but never at the same instant
+        assert_eq!(max_concurrent(&bookings, at(9, 30), at(10, 30)), 1);
+
+        let bookings = vec![booking(1, "09:00", "10:00"), booking(2, "09:30", "11:00")];
+        assert_eq!(max_concurrent(&bookings, at(9, 0), at(11, 0)), 2);
+    }
+}
@@ -4,22 +4,60 @@
 //! tracks where they are in the bot's flow. State transitions happen in
 //! handlers and are persisted to SQLite.
 
+use crate::config::MenuItem;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// A single item in an order being built.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderItem {
     pub name: String,
+    /// Unit price, including the variant and add-ons
     pub price: f64,
     pub quantity: u32,
     pub emoji: Option<String>,
+    /// Chosen variant (e.g. "Large"), for items that have them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    /// Chosen add-ons (e.g. "Extra cheese")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addons: Vec<String>,
 }
 
 impl OrderItem {
+    /// `quantity` of a menu item at its base price, with no options chosen.
+    pub fn from_menu(item: &MenuItem, quantity: u32) -> Self {
+        Self {
+            name: item.name.clone(),
+            price: item.price,
+            quantity,
+            emoji: item.emoji.clone(),
+            variant: None,
+            addons: Vec::new(),
+        }
+    }
+
     pub fn subtotal(&self) -> f64 {
         self.price * self.quantity as f64
     }
 
+    /// Whether `other` is the same item with the same options.
+    pub fn same_choice(&self, other: &OrderItem) -> bool {
+        self.name == other.name && self.variant == other.variant && self.addons == other.addons
+    }
+
+    /// Item name with its options, e.g. "Pizza (Large) + Olives, Extra cheese"
+    pub fn label(&self) -> String {
+        let mut label = self.name.clone();
+        if let Some(ref variant) = self.variant {
+            label.push_str(&format!(" ({})", variant));
+        }
+        if !self.addons.is_empty() {
+            label.push_str(&format!(" + {}", self.addons.join(", ")));
+        }
+        label
+    }
+
     /// Format this item for display, e.g. "2x 🌯 Kota — R70.00"
     pub fn display(&self, currency: &str) -> String {
         let emoji = self.emoji.as_deref().unwrap_or("");
@@ -28,19 +66,31 @@ impl OrderItem {
                 "{}x {} {} — {}{:.2}",
                 self.quantity,
                 emoji,
-                self.name,
+                self.label(),
                 currency,
                 self.subtotal()
             )
         } else {
             format!(
                 "{} {} — {}{:.2}",
-                emoji, self.name, currency, self.price
+                emoji, self.label(), currency, self.price
             )
         }
     }
 }
 
+/// Items picked from the menu that still need a variant or add-ons chosen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemChoice {
+    /// Items already in the cart, options settled
+    pub cart: Vec<OrderItem>,
+    /// Items waiting for options (name, quantity); the first is being asked about
+    pub pending: Vec<(String, u32)>,
+    /// Variant chosen for the first pending item, once picked
+    #[serde(default)]
+    pub variant: Option<String>,
+}
+
 /// An order that has been confirmed (items + total locked in).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
@@ -89,15 +139,39 @@ impl Order {
     }
 }
 
+/// An appointment being booked (or rescheduled).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookingDraft {
+    pub service: String,
+    pub price: f64,
+    pub duration_minutes: u32,
+    /// Booking being moved, when rescheduling
+    #[serde(default)]
+    pub reschedule_id: Option<i64>,
+    /// Chosen date ("YYYY-MM-DD"), once picked
+    #[serde(default)]
+    pub date: Option<String>,
+    /// Dates or times offered in the last prompt, in the order numbered
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
 /// Tracks where a user is in the conversation flow.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ConversationState {
     /// Default state — waiting for user input.
     Idle,
 
-    /// User is viewing the menu and can add items.
+    /// User is viewing the menu and can add items (or, for menus with
+    /// categories, pick a category).
     ViewingMenu,
 
+    /// User is viewing one category of the menu and can add items.
+    ViewingCategory(String),
+
+    /// User is choosing a variant or add-ons for items they picked.
+    ChoosingItemOptions(ItemChoice),
+
     /// User is building an order (selecting items, adjusting quantities).
     BuildingOrder(Vec<OrderItem>),
 
@@ -107,11 +181,44 @@ pub enum ConversationState {
     /// Order confirmed — waiting for delivery location/address.
     AwaitingLocation(Order),
 
+    /// Order placed — customer is choosing how to pay for it.
+    ChoosingPaymentMethod(i64),
+
     /// User is entering a voucher code.
     RedeemingVoucher,
 
     /// Admin mode — admin commands routed by number instead of text prefix.
     AdminMode,
+
+    /// Booking: choosing a day for an appointment.
+    PickingBookingDate(BookingDraft),
+
+    /// Booking: choosing a start time on the chosen day.
+    PickingBookingSlot(BookingDraft),
+
+    /// User is looking at My Orders; holds the orders they can still change.
+    ViewingOrders(Vec<i64>),
+
+    /// User picked one of their orders and is choosing what to do with it.
+    ManagingOrder(i64),
+
+    /// User asked to cancel an order and must confirm.
+    ConfirmingOrderCancel(i64),
+
+    /// User is sending a new delivery address for an order.
+    ChangingOrderLocation(i64),
+
+    /// User asked for the language picker and is choosing a language.
+    ChoosingLanguage,
+
+    /// User is in a config-defined flow (see `FlowConfig`), answering `step`.
+    /// `vars` holds answers collected so far, keyed by step id.
+    Flow {
+        flow: String,
+        step: String,
+        #[serde(default)]
+        vars: BTreeMap<String, String>,
+    },
 }
 
 impl Default for ConversationState {
@@ -136,11 +243,22 @@ impl ConversationState {
         match self {
             Self::Idle => "idle",
             Self::ViewingMenu => "viewing_menu",
+            Self::ViewingCategory(_) => "viewing_category",
+            Self::ChoosingItemOptions(_) => "choosing_item_options",
             Self::BuildingOrder(_) => "building_order",
             Self::ConfirmingOrder(_) => "confirming_order",
             Self::AwaitingLocation(_) => "awaiting_location",
+            Self::ChoosingPaymentMethod(_) => "choosing_payment_method",
             Self::RedeemingVoucher => "redeeming_voucher",
             Self::AdminMode => "admin_mode",
+            Self::PickingBookingDate(_) => "picking_booking_date",
+            Self::PickingBookingSlot(_) => "picking_booking_slot",
+            Self::ViewingOrders(_) => "viewing_orders",
+            Self::ManagingOrder(_) => "managing_order",
+            Self::ConfirmingOrderCancel(_) => "confirming_order_cancel",
+            Self::ChangingOrderLocation(_) => "changing_order_location",
+            Self::ChoosingLanguage => "choosing_language",
+            Self::Flow { .. } => "flow",
         }
     }
 
@@ -153,7 +271,10 @@ impl ConversationState {
     pub fn is_in_order_flow(&self) -> bool {
         matches!(
             self,
-            Self::BuildingOrder(_) | Self::ConfirmingOrder(_) | Self::AwaitingLocation(_)
+            Self::BuildingOrder(_)
+                | Self::ChoosingItemOptions(_)
+                | Self::ConfirmingOrder(_)
+                | Self::AwaitingLocation(_)
         )
     }
 }
@@ -169,8 +290,20 @@ mod tests {
             price: 35.0,
             quantity: 2,
             emoji: Some("🌯".to_string()),
+            variant: None,
+            addons: Vec::new(),
         };
         assert_eq!(item.display("R"), "2x 🌯 Kota — R70.00");
+
+        let pizza = OrderItem {
+            name: "Pizza".to_string(),
+            price: 14.0,
+            quantity: 1,
+            emoji: None,
+            variant: Some("Large".to_string()),
+            addons: vec!["Olives".to_string(), "Extra cheese".to_string()],
+        };
+        assert_eq!(pizza.display("$"), " Pizza (Large) + Olives, Extra cheese — $14.00");
     }
 
     #[test]
@@ -181,12 +314,16 @@ mod tests {
                 price: 35.0,
                 quantity: 1,
                 emoji: None,
+                variant: None,
+                addons: Vec::new(),
             },
             OrderItem {
                 name: "Gatsby".to_string(),
                 price: 60.0,
                 quantity: 1,
                 emoji: None,
+                variant: None,
+                addons: Vec::new(),
             },
         ];
         let order = Order::from_cart(items, 10.0);
@@ -201,9 +338,30 @@ mod tests {
             price: 10.0,
             quantity: 1,
             emoji: None,
+            variant: None,
+            addons: Vec::new(),
         }]);
         let json = state.to_json();
         let restored = ConversationState::from_json(&json);
         assert!(matches!(restored, ConversationState::BuildingOrder(_)));
     }
+
+    #[test]
+    fn test_flow_state_roundtrip() {
+        let mut vars = BTreeMap::new();
+        vars.insert("service".to_string(), "Haircut".to_string());
+        let state = ConversationState::Flow {
+            flow: "booking".to_string(),
+            step: "when".to_string(),
+            vars,
+        };
+        match ConversationState::from_json(&state.to_json()) {
+            ConversationState::Flow { flow, step, vars } => {
+                assert_eq!(flow, "booking");
+                assert_eq!(step, "when");
+                assert_eq!(vars["service"], "Haircut");
+            }
+            other => panic!("unexpected state {:?}", other),
+        }
+    }
 }
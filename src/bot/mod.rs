@@ -1,45 +1,62 @@
-//! Bot engine — wraps whatsapp-rust, routes incoming messages through handlers.
+//! Bot engine — routes incoming messages through handlers.
 //!
 //! The engine:
-//! 1. Connects to WhatsApp via whatsapp-rust's Bot builder
-//! 2. Listens for incoming messages via the Event system
+//! 1. Runs a `MessagingTransport` (WhatsApp in production)
+//! 2. Receives `InboundMessage`s from the transport
 //! 3. Looks up conversation state for each sender
 //! 4. Routes through the handler chain
-//! 5. Sends responses and persists state
+//! 5. Sends responses through the transport and persists state
 
 pub mod conversation;
+pub mod simulator;
 
 use crate::config::HiveConfig;
+use crate::config::live::LiveConfig;
 use crate::handlers::{self, HandlerResult, MessageContext};
+use crate::i18n;
 use crate::network::service::{NetworkNotifier, NetworkService};
-use crate::payments::{MpesaClient, PaymentProvider};
-use crate::store::Store;
+use crate::payments::{PaymentProviders, RefundProvider, reconcile};
+use crate::store::{CustomerUpdate, Store};
+use crate::transport::{self, InboundMessage, MessagingTransport};
 use anyhow::Result;
 use conversation::ConversationState;
-use log::{error, info, warn};
+use log::{error, info};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use whatsapp_rust::bot::{Bot, MessageContext as WaMessageContext};
-use whatsapp_rust::pair_code::PairCodeOptions;
-use whatsapp_rust::types::events::Event;
-use whatsapp_rust_sqlite_storage::SqliteStore as WaSqliteStore;
-use whatsapp_rust_tokio_transport::TokioWebSocketTransportFactory;
-use whatsapp_rust_ureq_http_client::UreqHttpClient;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinSet;
+
+/// How often to look for payments whose callback never arrived.
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(60);
 
 /// Core bot engine that ties everything together.
 pub struct BotEngine {
-    config: Arc<HiveConfig>,
+    config: LiveConfig,
     store: Store,
-    project_dir: PathBuf,
-    phone_number: Option<String>,
+    transport: Arc<dyn MessagingTransport>,
     network_notifier: NetworkNotifier,
-    payment_provider: Option<Arc<dyn PaymentProvider>>,
-    wa_client_shared: Option<Arc<tokio::sync::RwLock<Option<Arc<whatsapp_rust::client::Client>>>>>,
+    payment_providers: PaymentProviders,
+    refund_provider: Option<Arc<dyn RefundProvider>>,
 }
 
 impl BotEngine {
-    /// Create a new bot engine.
-    pub async fn new(config: HiveConfig, store: Store, project_dir: PathBuf) -> Result<Self> {
+    /// Create a new bot engine that talks to customers over `transport` and
+    /// refunds them through `refund_provider`.
+    ///
+    /// Each message is handled with the config current when it arrives, so
+    /// reloads of `config` apply from the next message on.
+    pub async fn new(
+        live_config: LiveConfig,
+        store: Store,
+        project_dir: PathBuf,
+        transport: Arc<dyn MessagingTransport>,
+        refund_provider: Option<Arc<dyn RefundProvider>>,
+    ) -> Result<Self> {
+        // Network and payments are set up once, from the startup config
+        let config = live_config.get();
+
         // Initialize Reality Network integration if enabled
         let network_notifier = if config.network.enabled {
             let (service, notifier) = NetworkService::new(
@@ -61,245 +78,126 @@ impl BotEngine {
             NetworkNotifier::disabled()
         };
 
-        // Initialize payment provider if configured
-        let payment_provider: Option<Arc<dyn PaymentProvider>> = if config.payments.enabled {
-            if let Some(ref mpesa_cfg) = config.payments.mpesa {
-                info!("💰 M-Pesa payments enabled ({})", 
-                      if mpesa_cfg.sandbox { "sandbox" } else { "production" });
-                let mpesa_config = crate::payments::mpesa::MpesaConfig {
-                    consumer_key: mpesa_cfg.consumer_key.clone(),
-                    consumer_secret: mpesa_cfg.consumer_secret.clone(),
-                    shortcode: mpesa_cfg.shortcode.clone(),
-                    passkey: mpesa_cfg.passkey.clone(),
-                    callback_url: mpesa_cfg.callback_url.clone(),
-                    sandbox: mpesa_cfg.sandbox,
-                };
-                Some(Arc::new(MpesaClient::new(mpesa_config)))
-            } else {
-                warn!("💰 Payments enabled but no provider configured");
-                None
-            }
-        } else {
-            None
-        };
+        // Every provider configured under `payments` takes payments
+        let payment_providers = PaymentProviders::from_config(&config);
+
+        // Settle payments whose callback went missing
+        if !payment_providers.is_empty() {
+            tokio::spawn(reconcile::run(
+                live_config.clone(),
+                store.clone(),
+                transport.clone(),
+                payment_providers.clone(),
+                refund_provider.clone(),
+                RECONCILE_INTERVAL,
+            ));
+        }
 
         Ok(Self {
-            config: Arc::new(config),
+            config: live_config,
             store,
-            project_dir,
-            phone_number: None,
+            transport,
             network_notifier,
-            payment_provider,
-            wa_client_shared: None,
+            payment_providers,
+            refund_provider,
         })
     }
 
-    /// Set a phone number for pair code authentication (alternative to QR scanning).
-    pub fn with_phone_number(mut self, phone: String) -> Self {
-        self.phone_number = Some(phone);
-        self
-    }
-
-    /// Set shared WhatsApp client for dashboard access.
-    pub fn with_wa_client_shared(
-        mut self,
-        shared: Arc<tokio::sync::RwLock<Option<Arc<whatsapp_rust::client::Client>>>>,
-    ) -> Self {
-        self.wa_client_shared = Some(shared);
-        self
-    }
-
-    /// Start the bot — connects to WhatsApp and begins processing messages.
+    /// Start the bot — runs the transport and processes inbound messages
+    /// until the transport shuts down and the messages it delivered have
+    /// been handled.
     pub async fn run(&mut self) -> Result<()> {
-        info!("Initializing WhatsApp connection...");
-
-        // Set up the whatsapp-rust storage backend
-        let wa_db_path = self
-            .project_dir
-            .join("data")
-            .join("whatsapp.db")
-            .to_string_lossy()
-            .to_string();
-
-        let backend = Arc::new(WaSqliteStore::new(&wa_db_path).await?)
-            as Arc<dyn whatsapp_rust::store::traits::Backend>;
-
-        // Build shared state for the event handler closure
-        let config = self.config.clone();
-        let store = self.store.clone();
-        let network_notifier = self.network_notifier.clone();
-        let payment_provider = self.payment_provider.clone();
-        let wa_client_shared = self.wa_client_shared.clone();
-
-        let mut builder = Bot::builder()
-            .with_backend(backend)
-            .with_transport_factory(TokioWebSocketTransportFactory::new())
-            .with_http_client(UreqHttpClient::new());
-
-        // If phone number is provided, use pair code authentication instead of QR
-        if let Some(ref phone) = self.phone_number {
-            info!("📱 Using pair code authentication for {}", phone);
-            builder = builder.with_pair_code(PairCodeOptions {
-                phone_number: phone.clone(),
-                ..Default::default()
-            });
-        }
-
-        let mut bot = builder
-            .on_event(move |event, client| {
-                let config = config.clone();
-                let store = store.clone();
-                let network_notifier = network_notifier.clone();
-                let payment_provider = payment_provider.clone();
-                let wa_client_shared = wa_client_shared.clone();
-                async move {
-                    match event {
-                        Event::PairingQrCode { code, timeout } => {
-                            println!("\n📱 Scan this QR code with WhatsApp:");
-                            // Generate QR code for terminal display
-                            if let Ok(qr) = qrcode::QrCode::new(&code) {
-                                let string = qr
-                                    .render::<char>()
-                                    .quiet_zone(true)
-                                    .module_dimensions(2, 1)
-                                    .build();
-                                println!("{}", string);
-
-                                // Also save as PNG for remote scanning
-                                let img = qr.render::<image::Luma<u8>>()
-                                    .quiet_zone(true)
-                                    .min_dimensions(600, 600)
-                                    .build();
-                                let png_path = "/tmp/hive-qr.png";
-                                if let Err(e) = img.save(png_path) {
-                                    warn!("Failed to save QR PNG: {}", e);
-                                } else {
-                                    info!("📸 QR code saved to {}", png_path);
-                                }
-                            } else {
-                                println!("QR Data: {}", code);
-                            }
-                            println!(
-                                "⏱  Code expires in {} seconds\n",
-                                timeout.as_secs()
-                            );
-                        }
-                        Event::PairingCode { code, timeout } => {
-                            println!(
-                                "\n🔑 Enter this pairing code on your phone: {}",
-                                code
-                            );
-                            println!(
-                                "⏱  Code expires in {} seconds\n",
-                                timeout.as_secs()
-                            );
-                        }
-                        Event::Connected(_) => {
-                            info!("✅ Connected to WhatsApp!");
-                            
-                            // Populate shared client for dashboard webhook access
-                            if let Some(ref shared) = wa_client_shared {
-                                let mut client_lock = shared.write().await;
-                                *client_lock = Some(client.clone());
-                                info!("📡 WhatsApp client shared with dashboard");
-                            }
-                        }
-                        Event::Disconnected(_) => {
-                            warn!("⚠️  Disconnected from WhatsApp");
-                        }
-                        Event::LoggedOut(logout) => {
-                            error!(
-                                "🚫 Logged out from WhatsApp: {:?}",
-                                logout.reason
-                            );
-                        }
-                        Event::Message(message, info) => {
-                            // Build our context from the whatsapp-rust event
-                            let wa_ctx = WaMessageContext {
-                                message,
-                                info: info.clone(),
-                                client: client.clone(),
-                            };
-
-                            match handle_incoming_message(&config, &store, &wa_ctx, &payment_provider).await {
-                                Ok(state_changed) => {
-                                    if state_changed {
-                                        network_notifier.mark_dirty();
-                                    }
-                                }
-                                Err(e) => {
-                                    error!(
-                                        "Error handling message from {}: {}",
-                                        info.source.sender, e
-                                    );
-                                }
-                            }
-                        }
-                        _ => {
-                            // Ignore other events (receipts, presence, etc.)
+        info!("🐝 Starting {} transport", self.transport.name());
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<InboundMessage>(256);
+
+        let transport = self.transport.clone();
+        let transport_task = tokio::spawn(async move { transport.run(tx).await });
+
+        // Each sender's latest message, which closes once it's handled
+        let mut in_progress: HashMap<String, oneshot::Receiver<()>> = HashMap::new();
+        let mut tasks = JoinSet::new();
+
+        while let Some(msg) = rx.recv().await {
+            while tasks.try_join_next().is_some() {}
+            in_progress.retain(|_, done| matches!(done.try_recv(), Err(oneshot::error::TryRecvError::Empty)));
+
+            let config = self.config.get();
+            let store = self.store.clone();
+            let transport = self.transport.clone();
+            let network_notifier = self.network_notifier.clone();
+            let payment_providers = self.payment_providers.clone();
+            let refund_provider = self.refund_provider.clone();
+            let (done_tx, done_rx) = oneshot::channel::<()>();
+            let previous = in_progress.insert(msg.sender.clone(), done_rx);
+
+            // Handle each message on its own task so a slow reply to one
+            // customer doesn't hold up everyone else. A sender's messages
+            // wait for the one before, so they all see the state it left.
+            tasks.spawn(async move {
+                let _done = done_tx;
+                if let Some(previous) = previous {
+                    let _ = previous.await;
+                }
+                let sender = msg.sender.clone();
+                match handle_incoming_message(&config, &store, &transport, msg, &payment_providers, &refund_provider).await {
+                    Ok(state_changed) => {
+                        if state_changed {
+                            network_notifier.mark_dirty();
                         }
                     }
+                    Err(e) => {
+                        error!("Error handling message from {}: {}", sender, e);
+                    }
                 }
-            })
-            .build()
-            .await?;
-
-        info!("🐝 Bot is starting — waiting for WhatsApp connection...");
+            });
+        }
 
-        // Run the bot (blocks until disconnected)
-        let handle = bot.run().await?;
-        handle.await.map_err(|e| anyhow::anyhow!("Bot task panicked: {}", e))?;
+        transport_task
+            .await
+            .map_err(|e| anyhow::anyhow!("Transport task panicked: {}", e))??;
 
+        while tasks.join_next().await.is_some() {}
         Ok(())
     }
 }
 
-/// Handle a single incoming WhatsApp message.
+/// Handle a single inbound message from any transport.
 ///
 /// Returns Ok(true) if store state changed (order/voucher), triggering
 /// a Reality Network snapshot submission.
 ///
 /// This is the core routing logic:
-/// 1. Extract text from the message
+/// 1. Skip our own and empty messages
 /// 2. Load conversation state for this sender
 /// 3. Run through the handler chain
 /// 4. Send response(s) and persist updated state
-async fn handle_incoming_message(
+pub async fn handle_incoming_message(
     config: &HiveConfig,
     store: &Store,
-    wa_ctx: &WaMessageContext,
-    payment_provider: &Option<Arc<dyn PaymentProvider>>,
+    transport: &Arc<dyn MessagingTransport>,
+    msg: InboundMessage,
+    payment_providers: &PaymentProviders,
+    refund_provider: &Option<Arc<dyn RefundProvider>>,
 ) -> Result<bool> {
-    use wacore::proto_helpers::MessageExt;
-
-    let sender = wa_ctx.info.source.sender.to_string();
-    let is_from_me = wa_ctx.info.source.is_from_me;
-
     // Skip messages from ourselves
-    if is_from_me {
+    if msg.is_from_me {
         return Ok(false);
     }
 
-    // Extract text content from the message
-    let base_msg = wa_ctx.message.get_base_message();
-    let text = base_msg
-        .text_content()
-        .or_else(|| base_msg.get_caption())
-        .unwrap_or("")
-        .trim()
-        .to_string();
-
-    if text.is_empty() {
-        // Handle location messages for orders awaiting location
-        let has_location = base_msg.location_message.is_some()
-            || base_msg.live_location_message.is_some();
-
-        if !has_location {
-            return Ok(false);
-        }
+    // Only location messages may arrive without text (orders awaiting location)
+    if msg.text.is_empty() && !msg.has_location {
+        return Ok(false);
     }
 
-    info!("📨 Message from {}: {}", sender, if text.len() > 50 { &text[..50] } else { &text });
+    let sender = msg.sender.clone();
+    let text = msg.text.clone();
+
+    let preview: String = text.chars().take(50).collect();
+    info!("📨 Message from {}: {}", sender, preview);
+
+    // Group chats aren't served, so their members aren't customers
+    let new_customer = !msg.is_group && store.touch_customer(&sender, msg.sender_name.as_deref())?;
 
     // Load or initialize conversation state
     let mut state = store
@@ -308,20 +206,37 @@ async fn handle_incoming_message(
         .unwrap_or_default();
 
     let is_admin = config.is_admin(&sender);
+    let mut lang = config.customer_language(store, &sender);
+
+    // Answer a new customer in the language they wrote in
+    if new_customer
+        && !is_admin
+        && config.business.detect_language
+        && let Some(detected) = i18n::detect_language(&text, &sender, &config.offered_languages())
+        && detected != lang
+    {
+        let update = CustomerUpdate {
+            language: Some(detected.code().to_string()),
+            ..Default::default()
+        };
+        store.update_customer(&sender, &update)?;
+        info!("🌐 {} seems to speak {}", sender, detected.code());
+        lang = detected;
+    }
 
     // Build our handler context
     let ctx = MessageContext {
         sender: sender.clone(),
         text: text.clone(),
         is_admin,
-        is_group: wa_ctx.info.source.is_group,
-        has_location: base_msg.location_message.is_some()
-            || base_msg.live_location_message.is_some(),
-        location_text: extract_location_text(base_msg),
-        raw_message: wa_ctx.message.clone(),
-        wa_client: wa_ctx.client.clone(),
-        chat_jid: wa_ctx.info.source.chat.clone(),
-        payment_provider: payment_provider.clone(),
+        is_group: msg.is_group,
+        has_location: msg.has_location,
+        location_text: msg.location_text,
+        transport: transport.clone(),
+        chat: msg.chat,
+        payment_providers: payment_providers.clone(),
+        refund_provider: refund_provider.clone(),
+        lang,
     };
 
     // Check for cancel/reset commands (but not when in AdminMode — let the admin router handle it)
@@ -334,7 +249,7 @@ async fn handle_incoming_message(
         {
             if state.is_in_order_flow() || !matches!(state, ConversationState::Idle) {
                 state.reset();
-                send_text_reply(&ctx, &config.business.welcome).await?;
+                send_text_reply(&ctx, &config.welcome(lang)).await?;
                 store.save_conversation_state(&sender, &state.to_json())?;
                 return Ok(false);
             }
@@ -351,18 +266,12 @@ async fn handle_incoming_message(
 
     // Send response(s)
     let state_changed = !matches!(result, HandlerResult::NoReply);
-    match result {
-        HandlerResult::Reply(text) => {
-            send_text_reply(&ctx, &text).await?;
-        }
-        HandlerResult::MultiReply(messages) => {
-            for msg in messages {
-                send_text_reply(&ctx, &msg).await?;
-                // Small delay between messages to maintain order
-                tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-            }
+    for (i, message) in result.into_messages().iter().enumerate() {
+        if i > 0 {
+            // Small delay between messages to maintain order
+            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
         }
-        HandlerResult::NoReply => {}
+        transport::send_message(ctx.transport.as_ref(), &ctx.chat, message).await?;
     }
 
     // Persist updated conversation state
@@ -371,43 +280,7 @@ async fn handle_incoming_message(
     Ok(state_changed)
 }
 
-/// Extract a text representation of a location message.
-fn extract_location_text(msg: &waproto::whatsapp::Message) -> Option<String> {
-    if let Some(ref loc) = msg.location_message {
-        let lat = loc.degrees_latitude.unwrap_or(0.0);
-        let lng = loc.degrees_longitude.unwrap_or(0.0);
-        let name = loc.name.as_deref().unwrap_or("");
-        let address = loc.address.as_deref().unwrap_or("");
-        if !name.is_empty() || !address.is_empty() {
-            Some(format!("{} {} ({}, {})", name, address, lat, lng))
-        } else {
-            Some(format!("{}, {}", lat, lng))
-        }
-    } else if let Some(ref loc) = msg.live_location_message {
-        let lat = loc.degrees_latitude.unwrap_or(0.0);
-        let lng = loc.degrees_longitude.unwrap_or(0.0);
-        Some(format!("{}, {}", lat, lng))
-    } else {
-        None
-    }
-}
-
 /// Send a simple text reply to the chat.
 async fn send_text_reply(ctx: &MessageContext, text: &str) -> Result<()> {
-    use waproto::whatsapp as wa;
-
-    let message = wa::Message {
-        extended_text_message: Some(Box::new(wa::message::ExtendedTextMessage {
-            text: Some(text.to_string()),
-            ..Default::default()
-        })),
-        ..Default::default()
-    };
-
-    ctx.wa_client
-        .send_message(ctx.chat_jid.clone(), message)
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to send message: {}", e))?;
-
-    Ok(())
+    ctx.transport.send_text(&ctx.chat, text).await
 }
@@ -0,0 +1,1374 @@
+//! Conversation simulator.
+//!
+//! Drives the real message pipeline (`handle_incoming_message` → handler
+//! chain → store) over a `MemoryTransport`, so whole conversations can be
+//! scripted without a WhatsApp connection. Every outbound message is
+//! recorded, including out-of-band sends such as payment prompts and admin
+//! notifications.
+
+use super::conversation::ConversationState;
+use super::handle_incoming_message;
+use crate::config::HiveConfig;
+use crate::payments::{PaymentProvider, PaymentProviders, RefundProvider};
+use crate::store::Store;
+use crate::transport::{InboundMessage, MemoryTransport, MessagingTransport, OutboundMessage};
+use anyhow::Result;
+use std::sync::Arc;
+
+/// Simulated chat session against a config and store.
+pub struct ChatSimulator {
+    config: HiveConfig,
+    store: Store,
+    transport: Arc<MemoryTransport>,
+    payment_providers: PaymentProviders,
+    refund_provider: Option<Arc<dyn RefundProvider>>,
+}
+
+impl ChatSimulator {
+    /// Create a simulator with a fresh in-memory store.
+    pub fn new(config: HiveConfig) -> Result<Self> {
+        let store = Store::new(":memory:")?;
+        store.seed_menu(&config.menu)?;
+        Ok(Self::with_store(config, store))
+    }
+
+    /// Create a simulator on top of an existing store.
+    pub fn with_store(config: HiveConfig, store: Store) -> Self {
+        Self {
+            config,
+            store,
+            transport: Arc::new(MemoryTransport::new()),
+            payment_providers: PaymentProviders::default(),
+            refund_provider: None,
+        }
+    }
+
+    /// Parse and validate a YAML config, then create a simulator for it.
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let config: HiveConfig = serde_yaml::from_str(yaml)?;
+        config.validate()?;
+        Self::new(config)
+    }
+
+    /// Offer a payment provider at checkout (e.g. a fake M-Pesa client),
+    /// after any already added.
+    pub fn with_payment_provider(mut self, provider: Arc<dyn PaymentProvider>) -> Self {
+        self.payment_providers.add(provider);
+        self
+    }
+
+    /// Use a refund provider for customer cancellations (e.g. a fake B2C client).
+    pub fn with_refund_provider(mut self, provider: Arc<dyn RefundProvider>) -> Self {
+        self.refund_provider = Some(provider);
+        self
+    }
+
+    /// Send through `transport`, e.g. one `with_rich_messages`.
+    pub fn with_transport(mut self, transport: MemoryTransport) -> Self {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    pub fn config(&self) -> &HiveConfig {
+        &self.config
+    }
+
+    /// Mutable access to the simulated config (e.g. to toggle admin numbers).
+    pub fn config_mut(&mut self) -> &mut HiveConfig {
+        &mut self.config
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    pub fn transport(&self) -> &Arc<MemoryTransport> {
+        &self.transport
+    }
+
+    /// Send a text message from `from` and return every message the bot
+    /// sent while handling it.
+    pub async fn send(&self, from: &str, text: &str) -> Result<Vec<OutboundMessage>> {
+        self.deliver(InboundMessage {
+            sender: from.to_string(),
+            chat: from.to_string(),
+            text: text.trim().to_string(),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Share a location from `from`.
+    pub async fn send_location(&self, from: &str, location: &str) -> Result<Vec<OutboundMessage>> {
+        self.deliver(InboundMessage {
+            sender: from.to_string(),
+            chat: from.to_string(),
+            has_location: true,
+            location_text: Some(location.to_string()),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Run an arbitrary inbound message through the pipeline.
+    pub async fn deliver(&self, msg: InboundMessage) -> Result<Vec<OutboundMessage>> {
+        self.transport.take_outbox();
+        let transport: Arc<dyn MessagingTransport> = self.transport.clone();
+        handle_incoming_message(
+            &self.config,
+            &self.store,
+            &transport,
+            msg,
+            &self.payment_providers,
+            &self.refund_provider,
+        )
+        .await?;
+        Ok(self.transport.take_outbox())
+    }
+
+    /// Current conversation state for a sender.
+    pub fn state(&self, phone: &str) -> Result<ConversationState> {
+        Ok(self
+            .store
+            .get_conversation_state(phone)?
+            .map(|json| ConversationState::from_json(&json))
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payments::{Charge, PaymentCheck, PaymentMethod, PaymentRequest, PaymentStatus};
+    use crate::store::OrderStatus;
+    use crate::config::BusinessLocation;
+    use crate::transport::OutgoingMessage;
+
+    const CUSTOMER: &str = "254700000001";
+    const ADMIN: &str = "254711111111";
+
+    const CONFIG: &str = r#"
+business:
+  name: "Test Kitchen"
+  currency: "KES "
+menu:
+  - name: "Kota"
+    price: 35
+    emoji: "🌯"
+  - name: "Gatsby"
+    price: 60
+  - name: "Sold Out Special"
+    price: 99
+    available: false
+delivery:
+  fee: 10
+admin_numbers: ["+254 711 111111"]
+flows:
+  - name: consult
+    title: "Consultation"
+    triggers: ["5", "consult"]
+    steps:
+      - id: service
+        prompt: "Which service?"
+        input: choice
+        options:
+          - label: "Catering"
+          - label: "Just asking"
+            next: question
+      - id: guests
+        prompt: "How many guests?"
+        input: number
+        min: 1
+        max: 500
+        next: end
+      - id: question
+        prompt: "What would you like to know?"
+    complete: "Thanks! {service} noted."
+"#;
+
+    const BOOKING_CONFIG: &str = r#"
+business:
+  name: "Test Salon"
+menu:
+  - name: "Haircut"
+    price: 25
+    duration_minutes: 60
+admin_numbers: ["+254 711 111111"]
+bookings:
+  hours:
+    mon: "00:00-23:59"
+    tue: "00:00-23:59"
+    wed: "00:00-23:59"
+    thu: "00:00-23:59"
+    fri: "00:00-23:59"
+    sat: "00:00-23:59"
+    sun: "00:00-23:59"
+  capacity: 1
+  days_ahead: 3
+  min_notice_minutes: 0
+"#;
+
+    const STOCK_CONFIG: &str = r#"
+business:
+  name: "Test Tickets"
+  currency: "KES "
+menu:
+  - name: "Concert"
+    price: 500
+    stock: 3
+  - name: "Poster"
+    price: 50
+admin_numbers: ["+254 711 111111"]
+inventory:
+  low_stock_threshold: 1
+"#;
+
+    const OPTIONS_CONFIG: &str = r#"
+business:
+  name: "Test Pizzeria"
+  currency: "$"
+menu:
+  - name: "Pizza"
+    price: 10
+    category: "Pizzas"
+    variants:
+      - { name: "Small", price: 8 }
+      - { name: "Large", price: 12 }
+    addons:
+      - { name: "Olives", price: 1 }
+      - { name: "Extra cheese", price: 1.5 }
+  - name: "Garlic Bread"
+    price: 4
+    category: "Sides"
+  - name: "Water"
+    price: 1
+admin_numbers: ["+254 711 111111"]
+"#;
+
+    fn sim() -> ChatSimulator {
+        ChatSimulator::from_yaml(CONFIG).unwrap()
+    }
+
+    fn texts(out: &[OutboundMessage]) -> Vec<&str> {
+        out.iter().map(|m| m.text.as_str()).collect()
+    }
+
+    /// M-Pesa payments whose status query says `.0`.
+    struct FakePayments(PaymentStatus);
+
+    #[async_trait::async_trait]
+    impl PaymentProvider for FakePayments {
+        fn method(&self) -> PaymentMethod {
+            PaymentMethod::MPesa
+        }
+
+        async fn initiate_payment(&self, _: f64, _: &str, _: &str, reference: &str) -> Result<PaymentRequest> {
+            Ok(PaymentRequest {
+                provider_ref: format!("ws_CO_{}", reference),
+                checkout_url: None,
+            })
+        }
+
+        async fn check_status(&self, _: &str) -> Result<PaymentCheck> {
+            Ok(self.0.clone().into())
+        }
+    }
+
+    /// PayStack checkouts at a fake URL, which verify says were paid `.0`
+    /// when it's set and are still open otherwise.
+    struct FakePaystack(Option<Charge>);
+
+    #[async_trait::async_trait]
+    impl PaymentProvider for FakePaystack {
+        fn method(&self) -> PaymentMethod {
+            PaymentMethod::PayStack
+        }
+
+        async fn initiate_payment(&self, _: f64, _: &str, _: &str, reference: &str) -> Result<PaymentRequest> {
+            Ok(PaymentRequest {
+                provider_ref: format!("{}-ps", reference),
+                checkout_url: Some(format!("https://checkout.paystack.com/{}", reference)),
+            })
+        }
+
+        async fn check_status(&self, _: &str) -> Result<PaymentCheck> {
+            Ok(match self.0 {
+                Some(ref charge) => PaymentCheck {
+                    status: PaymentStatus::Completed,
+                    charged: Some(charge.clone()),
+                },
+                None => PaymentStatus::Processing.into(),
+            })
+        }
+    }
+
+    struct FakeRefunds;
+
+    #[async_trait::async_trait]
+    impl RefundProvider for FakeRefunds {
+        fn method(&self) -> PaymentMethod {
+            PaymentMethod::MPesa
+        }
+
+        async fn refund_payment(&self, _: f64, _: &str, order_id: i64) -> Result<String> {
+            Ok(format!("AG_{}", order_id))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_order_flow() {
+        let sim = sim();
+
+        let out = sim.send(CUSTOMER, "menu").await.unwrap();
+        assert!(out[0].text.contains("Test Kitchen Menu"));
+        assert!(!out[0].text.contains("Sold Out Special"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::ViewingMenu));
+
+        let out = sim.send(CUSTOMER, "1, 2x2").await.unwrap();
+        assert!(out[0].text.contains("Total: KES 165.00"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::ConfirmingOrder(_)));
+
+        let out = sim.send(CUSTOMER, "yes").await.unwrap();
+        assert!(out[0].text.contains("delivery address"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::AwaitingLocation(_)));
+
+        let out = sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].to, ADMIN);
+        assert!(out[0].text.contains("New Order #1"));
+        assert!(out[0].text.contains("12 Main Street"));
+        assert_eq!(out[1].to, CUSTOMER);
+        assert!(out[1].text.contains("Order #1 confirmed"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::Idle));
+
+        let order = sim.store().get_order(1).unwrap().unwrap();
+        assert_eq!(order.total, 165.0);
+        assert_eq!(order.status, OrderStatus::Confirmed);
+        assert_eq!(order.location.as_deref(), Some("12 Main Street"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_engine_handles_each_senders_messages_in_order() {
+        use crate::bot::BotEngine;
+        use crate::config::live::LiveConfig;
+
+        let sim = sim();
+        let customers: Vec<String> = (1..=5).map(|i| format!("25470000000{}", i)).collect();
+        let message = |from: &str, text: &str| InboundMessage {
+            sender: from.to_string(),
+            chat: from.to_string(),
+            text: text.to_string(),
+            ..Default::default()
+        };
+        // Several customers ordering at once, each typing faster than the bot replies
+        let mut script = Vec::new();
+        for text in ["menu", "1", "yes", "12 Main Street"] {
+            script.extend(customers.iter().map(|phone| message(phone, text)));
+        }
+        let transport = Arc::new(MemoryTransport::new().with_script(script));
+        let live_config = LiveConfig::new(sim.config().clone(), std::path::Path::new("."));
+        let mut engine = BotEngine::new(live_config, sim.store().clone(), ".".into(), transport.clone(), None).await.unwrap();
+        engine.run().await.unwrap();
+
+        for phone in &customers {
+            let replies = transport.sent_to(phone);
+            assert_eq!(replies.len(), 4, "{:?}", replies);
+            assert!(replies[0].contains("Test Kitchen Menu"));
+            assert!(replies[1].contains("Total: KES 45.00"));
+            assert!(replies[2].contains("delivery address"));
+            assert!(replies[3].contains("confirmed"));
+            assert!(matches!(sim.state(phone).unwrap(), ConversationState::Idle));
+        }
+        for id in 1..=5 {
+            assert_eq!(sim.store().get_order(id).unwrap().unwrap().status, OrderStatus::Confirmed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_items_before_confirming() {
+        let sim = sim();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+
+        let out = sim.send(CUSTOMER, "ADD").await.unwrap();
+        assert!(out[0].text.contains("add to your order"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::BuildingOrder(_)));
+
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert!(out[0].text.contains("2x 🌯 Kota"));
+        match sim.state(CUSTOMER).unwrap() {
+            ConversationState::ConfirmingOrder(order) => assert_eq!(order.total, 80.0),
+            other => panic!("unexpected state {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_location_share_completes_order() {
+        let sim = sim();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "2").await.unwrap();
+        sim.send(CUSTOMER, "yes").await.unwrap();
+
+        let out = sim.send_location(CUSTOMER, "-1.28, 36.82").await.unwrap();
+        assert!(texts(&out).iter().any(|t| t.contains("confirmed")));
+        let order = sim.store().get_order(1).unwrap().unwrap();
+        assert_eq!(order.location.as_deref(), Some("-1.28, 36.82"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_resets_mid_order() {
+        let sim = sim();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+
+        let out = sim.send(CUSTOMER, "cancel").await.unwrap();
+        assert_eq!(out[0].text, sim.config().business.welcome);
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::Idle));
+        assert!(sim.store().get_order(1).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_item_selection() {
+        let sim = sim();
+        sim.send(CUSTOMER, "1").await.unwrap();
+
+        let out = sim.send(CUSTOMER, "9").await.unwrap();
+        assert!(out[0].text.contains("Invalid item number(s): 9"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::ViewingMenu));
+    }
+
+    #[tokio::test]
+    async fn test_menu_accepts_multiple_items() {
+        let sim = sim();
+        sim.send(CUSTOMER, "1").await.unwrap();
+
+        let out = sim.send(CUSTOMER, "2x1").await.unwrap();
+        assert!(out[0].text.contains("2x 🌯 Kota"));
+        match sim.state(CUSTOMER).unwrap() {
+            ConversationState::ConfirmingOrder(order) => assert_eq!(order.total, 80.0),
+            other => panic!("unexpected state {:?}", other),
+        }
+
+        sim.send(CUSTOMER, "cancel").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        let out = sim.send(CUSTOMER, "1 2").await.unwrap();
+        assert!(out[0].text.contains("Total: KES 105.00"));
+    }
+
+    #[tokio::test]
+    async fn test_voucher_redemption() {
+        let sim = sim();
+        sim.store().create_voucher("HIVE-ABCD-EFGH", 50.0).unwrap();
+
+        let out = sim.send(CUSTOMER, "3").await.unwrap();
+        assert!(out[0].text.contains("voucher code"));
+
+        let out = sim.send(CUSTOMER, "hive-abcd-efgh").await.unwrap();
+        assert!(out[0].text.contains("HIVE-ABCD-EFGH redeemed"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::Idle));
+    }
+
+    #[tokio::test]
+    async fn test_customer_picks_payment_method() {
+        use crate::payments::paystack::{PaystackEvent, process_webhook};
+
+        let mut sim = sim()
+            .with_payment_provider(Arc::new(FakePayments(PaymentStatus::Pending)))
+            .with_payment_provider(Arc::new(FakePaystack(None)));
+        sim.config_mut().payments.paystack = Some(crate::config::PaystackConfig {
+            secret_key: "sk_test_secret".to_string(),
+            email_domain: "example.com".to_string(),
+            currency: None,
+            callback_url: None,
+            base_url: None,
+        });
+        let transport: Arc<dyn MessagingTransport> = sim.transport().clone();
+        let place_order = async |sim: &ChatSimulator, customer: &str| {
+            sim.send(customer, "1").await.unwrap();
+            sim.send(customer, "1").await.unwrap();
+            sim.send(customer, "yes").await.unwrap();
+            sim.send(customer, "12 Main Street").await.unwrap()
+        };
+
+        // Both providers are offered, in order
+        let out = place_order(&sim, CUSTOMER).await;
+        assert_eq!(out.len(), 1);
+        assert!(out[0].text.contains("How would you like to pay KES 45.00 for order #1?"));
+        assert!(out[0].text.contains("1. M-Pesa\n2. PayStack"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::ChoosingPaymentMethod(1)));
+        assert_eq!(sim.store().get_order(1).unwrap().unwrap().status, OrderStatus::Pending);
+
+        let out = sim.send(CUSTOMER, "3").await.unwrap();
+        assert!(out[0].text.starts_with("❌ Invalid choice"));
+
+        // PayStack sends a checkout link
+        let out = sim.send(CUSTOMER, "2").await.unwrap();
+        assert_eq!(out.len(), 1);
+        assert!(out[0].text.contains("Pay KES 45.00 for order #1 here:\nhttps://checkout.paystack.com/Order-1"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::Idle));
+        let payment = sim.store().get_payment_by_provider_ref("Order-1-ps").unwrap().unwrap();
+        assert_eq!(payment.method, PaymentMethod::PayStack);
+        assert_eq!(payment.status, PaymentStatus::Processing);
+
+        // Its webhook confirms the order; a short payment or one in another
+        // currency doesn't
+        let event = |amount: u64, currency: &str| -> PaystackEvent {
+            serde_json::from_value(serde_json::json!({
+                "event": "charge.success",
+                "data": { "reference": "Order-1-ps", "amount": amount, "currency": currency, "status": "success" }
+            }))
+            .unwrap()
+        };
+        assert!(process_webhook(event(4000, "KES"), sim.store(), sim.config(), Some(transport.clone()), None).await.is_err());
+        assert!(process_webhook(event(4500, "NGN"), sim.store(), sim.config(), Some(transport.clone()), None).await.is_err());
+        assert_eq!(sim.store().get_order(1).unwrap().unwrap().status, OrderStatus::Pending);
+        process_webhook(event(4500, "KES"), sim.store(), sim.config(), Some(transport.clone()), None).await.unwrap();
+        assert_eq!(sim.store().get_order(1).unwrap().unwrap().status, OrderStatus::Confirmed);
+        let out = sim.transport().take_outbox();
+        assert_eq!(out[0].to, CUSTOMER);
+        assert!(out[0].text.starts_with("💰 Payment of KES 45.00 received."));
+        assert!(out[1].text.contains("Paid with: PayStack\nReceipt: Order-1-ps"));
+        let events = sim.store().order_events(1).unwrap();
+        assert_eq!(events.last().unwrap().actor, "paystack");
+
+        // M-Pesa, by name, sends the STK Push
+        let other = "254700000002";
+        place_order(&sim, other).await;
+        let out = sim.send(other, "m-pesa").await.unwrap();
+        assert!(out[0].text.contains("Payment Request Sent"));
+        let payment = sim.store().get_payment_by_provider_ref("ws_CO_Order-2").unwrap().unwrap();
+        assert_eq!(payment.method, PaymentMethod::MPesa);
+    }
+
+    #[tokio::test]
+    async fn test_payment_prompt_is_sent_out_of_band() {
+        let sim = sim().with_payment_provider(Arc::new(FakePayments(PaymentStatus::Pending)));
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "yes").await.unwrap();
+
+        let out = sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].to, CUSTOMER);
+        assert!(out[0].text.contains("Payment Request Sent"));
+
+        let payment = sim.store().get_payment_by_provider_ref("ws_CO_Order-1").unwrap().unwrap();
+        assert_eq!(payment.status, PaymentStatus::Processing);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_payment_without_callback() {
+        use crate::payments::reconcile::reconcile_payments;
+
+        let sim = sim().with_payment_provider(Arc::new(FakePayments(PaymentStatus::Pending)));
+        let transport: Arc<dyn MessagingTransport> = sim.transport().clone();
+        let place_order = async |sim: &ChatSimulator, customer: &str| {
+            sim.send(customer, "1").await.unwrap();
+            sim.send(customer, "1").await.unwrap();
+            sim.send(customer, "yes").await.unwrap();
+            sim.send(customer, "12 Main Street").await.unwrap();
+        };
+        place_order(&sim, CUSTOMER).await;
+
+        // Too recent to chase yet
+        let waiting = FakePayments(PaymentStatus::Pending);
+        let paid = FakePayments(PaymentStatus::Completed);
+        assert_eq!(reconcile_payments(sim.config(), sim.store(), &transport, &paid, None, 5, None).await.unwrap(), 0);
+
+        // Still waiting on the customer: nothing changes
+        assert_eq!(reconcile_payments(sim.config(), sim.store(), &transport, &waiting, None, 0, None).await.unwrap(), 0);
+
+        // Paid: the order is confirmed and everyone is told
+        sim.transport().take_outbox();
+        assert_eq!(reconcile_payments(sim.config(), sim.store(), &transport, &paid, None, 0, None).await.unwrap(), 1);
+        let payment = sim.store().get_payment_by_provider_ref("ws_CO_Order-1").unwrap().unwrap();
+        assert_eq!(payment.status, PaymentStatus::Completed);
+        assert_eq!(sim.store().get_order(1).unwrap().unwrap().status, OrderStatus::Confirmed);
+        let out = sim.transport().take_outbox();
+        assert_eq!(out[0].to, CUSTOMER);
+        assert!(out[0].text.starts_with("💰 Payment of KES 45.00 received."));
+        assert!(out[1].text.contains("Receipt: (confirmed by status query)"));
+
+        // Declined: the order stays unpaid and the customer hears why
+        let other = "254700000002";
+        place_order(&sim, other).await;
+        let declined = FakePayments(PaymentStatus::Cancelled);
+        assert_eq!(reconcile_payments(sim.config(), sim.store(), &transport, &declined, None, 0, None).await.unwrap(), 1);
+        let payment = sim.store().get_payment_by_provider_ref("ws_CO_Order-2").unwrap().unwrap();
+        assert_eq!(payment.status, PaymentStatus::Failed);
+        assert_eq!(sim.store().get_order(2).unwrap().unwrap().status, OrderStatus::Pending);
+        let out = sim.transport().take_outbox();
+        assert_eq!(out[0].to, other);
+        assert!(out[0].text.contains("didn't go through"));
+
+        // Still processing long after it started: given up on as cancelled
+        let third = "254700000003";
+        place_order(&sim, third).await;
+        sim.transport().take_outbox();
+        assert_eq!(reconcile_payments(sim.config(), sim.store(), &transport, &waiting, None, 0, Some(60)).await.unwrap(), 0);
+        assert_eq!(reconcile_payments(sim.config(), sim.store(), &transport, &waiting, None, 0, Some(0)).await.unwrap(), 1);
+        let payment = sim.store().get_payment_by_provider_ref("ws_CO_Order-3").unwrap().unwrap();
+        assert_eq!(payment.status, PaymentStatus::Cancelled);
+        assert_eq!(sim.store().get_order(3).unwrap().unwrap().status, OrderStatus::Pending);
+        let out = sim.transport().take_outbox();
+        assert_eq!(out[0].to, third);
+        assert!(out[0].text.contains("didn't go through"));
+        assert_eq!(reconcile_payments(sim.config(), sim.store(), &transport, &waiting, None, 0, Some(0)).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_checks_paystack_charge() {
+        use crate::payments::reconcile::reconcile_payments;
+
+        let mut sim = sim().with_payment_provider(Arc::new(FakePaystack(None)));
+        sim.config_mut().payments.paystack = Some(crate::config::PaystackConfig {
+            secret_key: "sk_test_secret".to_string(),
+            email_domain: "example.com".to_string(),
+            currency: None,
+            callback_url: None,
+            base_url: None,
+        });
+        let transport: Arc<dyn MessagingTransport> = sim.transport().clone();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "yes").await.unwrap();
+        sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+        sim.transport().take_outbox();
+
+        // Verify says it was paid, but not what the payment asked for
+        let charged = |amount: f64, currency: &str| {
+            FakePaystack(Some(Charge {
+                amount,
+                currency: currency.to_string(),
+            }))
+        };
+        for wrong in [charged(40.0, "KES"), charged(45.0, "NGN")] {
+            assert_eq!(reconcile_payments(sim.config(), sim.store(), &transport, &wrong, None, 0, None).await.unwrap(), 0);
+        }
+        let payment = sim.store().get_payment_by_provider_ref("Order-1-ps").unwrap().unwrap();
+        assert_eq!(payment.status, PaymentStatus::Processing);
+        assert_eq!(sim.store().get_order(1).unwrap().unwrap().status, OrderStatus::Pending);
+        assert!(sim.transport().take_outbox().is_empty());
+
+        let paid = charged(45.0, "kes");
+        assert_eq!(reconcile_payments(sim.config(), sim.store(), &transport, &paid, None, 0, None).await.unwrap(), 1);
+        assert_eq!(sim.store().get_order(1).unwrap().unwrap().status, OrderStatus::Confirmed);
+    }
+
+    #[tokio::test]
+    async fn test_admin_marks_order_delivered() {
+        let sim = sim();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "yes").await.unwrap();
+        sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+
+        let out = sim.send(ADMIN, "ADMIN").await.unwrap();
+        assert!(out[0].text.contains("Admin Mode"));
+
+        let out = sim.send(ADMIN, "1").await.unwrap();
+        assert!(out[0].text.contains("DONE 1"));
+
+        let out = sim.send(ADMIN, "DONE 1").await.unwrap();
+        assert_eq!(out[0].to, CUSTOMER);
+        assert!(out[0].text.contains("Order #1 has been delivered"));
+        assert_eq!(out[1].to, ADMIN);
+        assert!(out[1].text.contains("marked as delivered"));
+
+        let order = sim.store().get_order(1).unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Delivered);
+
+        // Finished orders can't be marked again
+        let out = sim.send(ADMIN, "DONE 1").await.unwrap();
+        assert_eq!(out.len(), 1);
+        assert!(out[0].text.contains("Order #1 is already delivered"));
+
+        // Unpaid orders have to be confirmed first
+        let pending = sim.store().create_order(CUSTOMER, "[]", 35.0, 0.0, 35.0, None).unwrap();
+        let out = sim.send(ADMIN, &format!("DONE {}", pending)).await.unwrap();
+        assert_eq!(out.len(), 1);
+        assert!(out[0].text.contains("can't be marked delivered from pending — confirm it first"));
+        let order = sim.store().get_order(pending).unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Pending);
+
+        // The customer can see how the order progressed
+        let out = sim.send(CUSTOMER, "2").await.unwrap();
+        let text = &out[0].text;
+        assert!(text.contains("🎉 Order #1"));
+        let placed = text.find("⏳ Placed").unwrap();
+        let confirmed = text.find("✅ Confirmed").unwrap();
+        let delivered = text.find("🎉 Delivered").unwrap();
+        assert!(placed < confirmed && confirmed < delivered);
+        let events = sim.store().order_events(1).unwrap();
+        assert_eq!(events[2].actor, format!("admin:{}", ADMIN));
+
+        let out = sim.send(ADMIN, "EXIT").await.unwrap();
+        assert_eq!(out[0].text, sim.config().business.welcome);
+    }
+
+    #[tokio::test]
+    async fn test_customer_cancels_paid_order() {
+        let sim = sim()
+            .with_payment_provider(Arc::new(FakePayments(PaymentStatus::Pending)))
+            .with_refund_provider(Arc::new(FakeRefunds));
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "yes").await.unwrap();
+        sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+
+        // M-Pesa confirms the payment
+        let payment = sim.store().get_payment_by_provider_ref("ws_CO_Order-1").unwrap().unwrap();
+        sim.store().update_payment_status(&payment.id, "completed", Some("ws_CO_Order-1")).unwrap();
+        sim.store().transition_order(1, &OrderStatus::Confirmed, "mpesa", None).unwrap();
+
+        let out = sim.send(CUSTOMER, "2").await.unwrap();
+        assert!(out[0].text.contains("Reply with an order number"));
+        let out = sim.send(CUSTOMER, "#1").await.unwrap();
+        assert!(out[0].text.contains("1. ❌ Cancel order"));
+        assert!(out[0].text.contains("📍 12 Main Street"));
+
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert!(out[0].text.contains("Your payment of KES 45.00 will be refunded"));
+        let out = sim.send(CUSTOMER, "yes").await.unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].to, ADMIN);
+        assert!(out[0].text.contains("Order #1 (KES 45.00) was cancelled"));
+        assert!(out[0].text.contains("sent to 254700000001"));
+        assert_eq!(out[1].to, CUSTOMER);
+        assert!(out[1].text.contains("Order #1 has been cancelled"));
+        assert!(out[1].text.contains("is being refunded"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::Idle));
+
+        let order = sim.store().get_order(1).unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Cancelled);
+        let events = sim.store().order_events(1).unwrap();
+        assert_eq!(events.last().unwrap().actor, format!("customer:{}", CUSTOMER));
+        let refunds = sim.store().get_payment_refunds(&payment.id).unwrap();
+        assert_eq!(refunds.len(), 1);
+        assert_eq!(refunds[0].status, crate::store::RefundStatus::Processing);
+        assert_eq!(refunds[0].conversation_id.as_deref(), Some("AG_1"));
+
+        // Nothing left to change, so order numbers go back to the menu
+        let out = sim.send(CUSTOMER, "2").await.unwrap();
+        assert!(!out[0].text.contains("Reply with an order number"));
+
+        // M-Pesa reports the payout; a retried result doesn't notify twice
+        use crate::payments::refunds::process_b2c_callback;
+        let callback: crate::payments::b2c::B2CCallback = serde_json::from_value(serde_json::json!({
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "ConversationID": "AG_1",
+                "TransactionID": "RKL51ZDR4F"
+            }
+        }))
+        .unwrap();
+        let transport: Arc<dyn MessagingTransport> = sim.transport().clone();
+        sim.transport().take_outbox();
+        for _ in 0..2 {
+            let refund = process_b2c_callback(&callback, sim.store(), sim.config(), Some(transport.clone()))
+                .await
+                .unwrap();
+            assert_eq!(refund.status, crate::store::RefundStatus::Completed);
+            assert!(refund.completed_at.is_some());
+        }
+        let out = sim.transport().take_outbox();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].to, CUSTOMER);
+        assert!(out[0].text.contains("Your refund of KES 45.00 for order #1 has been sent — M-Pesa receipt RKL51ZDR4F"));
+    }
+
+    #[tokio::test]
+    async fn test_payment_after_cancel_is_refunded() {
+        use crate::payments::{MpesaCallback, process_callback};
+
+        let sim = sim()
+            .with_payment_provider(Arc::new(FakePayments(PaymentStatus::Pending)))
+            .with_refund_provider(Arc::new(FakeRefunds));
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "yes").await.unwrap();
+        sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+
+        // The customer cancels while the STK Push is still open: nothing to refund yet
+        sim.send(CUSTOMER, "2").await.unwrap();
+        sim.send(CUSTOMER, "#1").await.unwrap();
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert!(!out[0].text.contains("will be refunded"));
+        sim.send(CUSTOMER, "yes").await.unwrap();
+        assert_eq!(sim.store().get_order(1).unwrap().unwrap().status, OrderStatus::Cancelled);
+
+        // Then they pay anyway
+        let callback: MpesaCallback = serde_json::from_value(serde_json::json!({
+            "Body": { "stkCallback": {
+                "MerchantRequestID": "29115-34620561-1",
+                "CheckoutRequestID": "ws_CO_Order-1",
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "CallbackMetadata": { "Item": [
+                    { "Name": "Amount", "Value": 45.0 },
+                    { "Name": "MpesaReceiptNumber", "Value": "NLJ7RT61SV" },
+                    { "Name": "TransactionDate", "Value": 20191219102115u64 },
+                    { "Name": "PhoneNumber", "Value": 254700000001u64 }
+                ]}
+            }}
+        }))
+        .unwrap();
+        let transport: Arc<dyn MessagingTransport> = sim.transport().clone();
+        sim.transport().take_outbox();
+        process_callback(callback, sim.store(), sim.config(), Some(transport), Some(&FakeRefunds))
+            .await
+            .unwrap();
+
+        // The order stays cancelled and the money goes back
+        assert_eq!(sim.store().get_order(1).unwrap().unwrap().status, OrderStatus::Cancelled);
+        let payment = sim.store().get_payment_by_provider_ref("ws_CO_Order-1").unwrap().unwrap();
+        assert_eq!(payment.status, PaymentStatus::Completed);
+        let refunds = sim.store().get_payment_refunds(&payment.id).unwrap();
+        assert_eq!(refunds.len(), 1);
+        assert_eq!(refunds[0].status, crate::store::RefundStatus::Processing);
+
+        let out = sim.transport().take_outbox();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].to, CUSTOMER);
+        assert!(out[0].text.contains("We received KES 45.00 for order #1, but the order had already been cancelled"));
+        assert!(out[0].text.contains("is being refunded to 254700000001"));
+        assert_eq!(out[1].to, ADMIN);
+        assert!(out[1].text.contains("Paid After Cancel"));
+        assert!(out[1].text.contains("receipt NLJ7RT61SV"));
+        assert!(out[1].text.contains("sent to 254700000001"));
+        assert!(!out[1].text.contains("ready to prepare"));
+    }
+
+    #[tokio::test]
+    async fn test_refunds_for_one_order_get_their_own_ids() {
+        use crate::payments::refunds::refund_payment;
+
+        let sim = sim();
+        let store = sim.store();
+        let order = store.create_order(CUSTOMER, "[]", 90.0, 0.0, 90.0, None).unwrap();
+        for id in ["PAY-1-a", "PAY-1-b"] {
+            store.create_payment(id, order, 45.0, "KES ", "mpesa", CUSTOMER, "Order #1").unwrap();
+            store.update_payment_status(id, "completed", None).unwrap();
+        }
+
+        // Both in the same second, as when a customer cancels
+        let mut ids = Vec::new();
+        for payment in crate::payments::refunds::refundable_payments(store, order).unwrap() {
+            ids.push(refund_payment(store, None, &payment, "test", None).await.unwrap().id);
+        }
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+
+        // Nor can the same payment be refunded twice
+        let payment = store.get_payment("PAY-1-a").unwrap().unwrap();
+        let err = refund_payment(store, None, &payment, "again", None).await.unwrap_err();
+        assert!(err.downcast_ref::<crate::store::RefundInProgress>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failed_refund_payout_alerts_admin() {
+        use crate::payments::refunds::{process_b2c_callback, refund_payment};
+
+        let sim = sim().with_payment_provider(Arc::new(FakePayments(PaymentStatus::Pending)));
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "yes").await.unwrap();
+        sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+        let payment = sim.store().get_payment_by_provider_ref("ws_CO_Order-1").unwrap().unwrap();
+        sim.store().update_payment_status(&payment.id, "completed", Some("ws_CO_Order-1")).unwrap();
+
+        let refund = refund_payment(sim.store(), Some(&FakeRefunds), &payment, "test", None).await.unwrap();
+        assert_eq!(refund.status, crate::store::RefundStatus::Processing);
+        assert!(refund.completed_at.is_none());
+
+        let callback: crate::payments::b2c::B2CCallback = serde_json::from_value(serde_json::json!({
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 2001,
+                "ResultDesc": "The initiator information is invalid.",
+                "ConversationID": "AG_1"
+            }
+        }))
+        .unwrap();
+        let transport: Arc<dyn MessagingTransport> = sim.transport().clone();
+        sim.transport().take_outbox();
+        let refund = process_b2c_callback(&callback, sim.store(), sim.config(), Some(transport)).await.unwrap();
+        assert_eq!(refund.status, crate::store::RefundStatus::Failed);
+        assert!(refund.completed_at.is_none());
+        let out = sim.transport().take_outbox();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].to, ADMIN);
+        assert!(out[0].text.contains("Refund Failed"));
+        assert!(out[0].text.contains("The initiator information is invalid."));
+    }
+
+    #[tokio::test]
+    async fn test_customer_changes_delivery_address() {
+        let sim = sim();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "yes").await.unwrap();
+        sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+        sim.store().transition_order(1, &OrderStatus::Preparing, "dashboard:owner", None).unwrap();
+
+        // Too late to cancel, but the address can still change
+        sim.send(CUSTOMER, "2").await.unwrap();
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert!(!out[0].text.contains("Cancel order"));
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert!(out[0].text.contains("2. 📍 Change delivery address"));
+
+        sim.send(CUSTOMER, "2").await.unwrap();
+        let out = sim.send_location(CUSTOMER, "-1.2921, 36.8219").await.unwrap();
+        assert_eq!(out[0].to, ADMIN);
+        assert!(out[0].text.contains("-1.2921, 36.8219\n(was: 12 Main Street)"));
+        assert_eq!(out[1].to, CUSTOMER);
+        assert!(out[1].text.contains("Order #1 will be delivered to"));
+
+        let order = sim.store().get_order(1).unwrap().unwrap();
+        assert_eq!(order.location.as_deref(), Some("-1.2921, 36.8219"));
+        assert_eq!(order.status, OrderStatus::Preparing);
+    }
+
+    #[tokio::test]
+    async fn test_reorder_with_same_address() {
+        let sim = sim();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "2x1, 2").await.unwrap();
+        sim.send(CUSTOMER, "yes").await.unwrap();
+        sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+
+        // Kota has gone up since, and Gatsby is off the menu
+        sim.send(ADMIN, "PRICE 1 40").await.unwrap();
+        sim.send(ADMIN, "HIDE 2").await.unwrap();
+
+        let out = sim.send(CUSTOMER, "reorder").await.unwrap();
+        let text = &out[0].text;
+        assert!(text.contains("Order #1 again"));
+        assert!(text.contains("2x 🌯 Kota — KES 80.00"));
+        assert!(text.contains("No longer available: Gatsby"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::BuildingOrder(_)));
+
+        let out = sim.send(CUSTOMER, "yes").await.unwrap();
+        assert!(out[0].text.contains("reply *SAME* to use your last address:\n📍 12 Main Street"));
+
+        sim.send(CUSTOMER, "same").await.unwrap();
+        let order = sim.store().get_order(2).unwrap().unwrap();
+        assert_eq!(order.total, 90.0);
+        assert_eq!(order.location.as_deref(), Some("12 Main Street"));
+
+        // Only your own orders can be reordered
+        let out = sim.send("254700000002", "REORDER 1").await.unwrap();
+        assert!(out[0].text.contains("No previous order found"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_edits_menu_live() {
+        let sim = sim();
+
+        let out = sim.send(ADMIN, "ITEMS").await.unwrap();
+        assert!(out[0].text.contains("3. Sold Out Special — KES 99.00 🙈 hidden"));
+
+        let out = sim.send(ADMIN, "PRICE kota 40").await.unwrap();
+        assert!(out[0].text.contains("Kota now costs KES 40.00 (was KES 35.00)"));
+        let out = sim.send(ADMIN, "HIDE 2").await.unwrap();
+        assert!(out[0].text.contains("Gatsby is hidden"));
+        let out = sim.send(ADMIN, "SHOW 3").await.unwrap();
+        assert!(out[0].text.contains("Sold Out Special is back"));
+
+        let out = sim.send(CUSTOMER, "menu").await.unwrap();
+        assert!(out[0].text.contains("*Kota* — KES 40.00"));
+        assert!(!out[0].text.contains("Gatsby"));
+        assert!(out[0].text.contains("2. • *Sold Out Special*"));
+
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert!(out[0].text.contains("Total: KES 50.00"));
+
+        // Customers can't edit the menu
+        let out = sim.send("254700000002", "PRICE 1 0").await.unwrap();
+        assert_eq!(out[0].text, sim.config().business.welcome);
+    }
+
+    #[tokio::test]
+    async fn test_non_admin_cannot_use_admin_commands() {
+        let sim = sim();
+        let out = sim.send(CUSTOMER, "ADMIN").await.unwrap();
+        assert_eq!(out[0].text, sim.config().business.welcome);
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::Idle));
+    }
+
+    #[tokio::test]
+    async fn test_config_flow_collects_answers() {
+        let sim = sim();
+
+        let out = sim.send(CUSTOMER, "consult").await.unwrap();
+        assert_eq!(out[0].text, "Which service?\n1. Catering\n2. Just asking");
+
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert_eq!(out[0].text, "How many guests?");
+
+        let out = sim.send(CUSTOMER, "lots").await.unwrap();
+        assert!(out[0].text.contains("Please reply with a number"));
+        let out = sim.send(CUSTOMER, "900").await.unwrap();
+        assert!(out[0].text.contains("no greater than 500"));
+        for not_a_number in ["NaN", "inf", "-inf"] {
+            let out = sim.send(CUSTOMER, not_a_number).await.unwrap();
+            assert!(out[0].text.contains("Please reply with a number"));
+        }
+        match sim.state(CUSTOMER).unwrap() {
+            ConversationState::Flow { step, vars, .. } => {
+                assert_eq!(step, "guests");
+                assert_eq!(vars["service"], "Catering");
+            }
+            other => panic!("unexpected state {:?}", other),
+        }
+
+        // `next: end` skips the remaining step
+        let out = sim.send(CUSTOMER, "40").await.unwrap();
+        assert_eq!(out[0].to, ADMIN);
+        assert!(out[0].text.contains("New Consultation from +254700000001"));
+        assert!(out[0].text.contains("• guests: 40"));
+        assert_eq!(out[1].text, "Thanks! Catering noted.");
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::Idle));
+    }
+
+    #[tokio::test]
+    async fn test_config_flow_choice_branches() {
+        let sim = sim();
+        sim.send(CUSTOMER, "5").await.unwrap();
+
+        let out = sim.send(CUSTOMER, "just asking").await.unwrap();
+        assert_eq!(out[0].text, "What would you like to know?");
+
+        let out = sim.send(CUSTOMER, "Do you deliver?").await.unwrap();
+        assert!(out[0].text.contains("• question: Do you deliver?"));
+        assert!(!out[0].text.contains("guests"));
+    }
+
+    /// Walk a customer from the menu to the time picker for the first free day.
+    async fn pick_first_day(sim: &ChatSimulator, phone: &str) {
+        let out = sim.send(phone, "1").await.unwrap();
+        assert!(out[0].text.contains("Reply with a service number to book"));
+        let out = sim.send(phone, "1").await.unwrap();
+        assert!(out[0].text.contains("Which day suits you?"));
+        let out = sim.send(phone, "1").await.unwrap();
+        assert!(out[0].text.contains("pick a time"));
+    }
+
+    #[tokio::test]
+    async fn test_booking_flow() {
+        let sim = ChatSimulator::from_yaml(BOOKING_CONFIG).unwrap();
+        pick_first_day(&sim, CUSTOMER).await;
+
+        let time = match sim.state(CUSTOMER).unwrap() {
+            ConversationState::PickingBookingSlot(draft) => draft.options[0].clone(),
+            other => panic!("unexpected state {:?}", other),
+        };
+
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert_eq!(out[0].to, ADMIN);
+        assert!(out[0].text.contains("New Booking #1"));
+        assert!(out[1].text.contains("Booking #1 confirmed"));
+        assert!(out[1].text.contains(&time));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::Idle));
+
+        let booking = sim.store().get_booking(1).unwrap().unwrap();
+        assert_eq!(booking.service, "Haircut");
+        assert!(booking.start_at.ends_with(&time));
+
+        let out = sim.send(CUSTOMER, "2").await.unwrap();
+        assert!(out[0].text.contains("#1 — Haircut"));
+    }
+
+    #[tokio::test]
+    async fn test_booking_slot_taken_while_choosing() {
+        let sim = ChatSimulator::from_yaml(BOOKING_CONFIG).unwrap();
+        let other = "254700000002";
+        pick_first_day(&sim, CUSTOMER).await;
+        pick_first_day(&sim, other).await;
+
+        sim.send(CUSTOMER, "1").await.unwrap();
+
+        let out = sim.send(other, "1").await.unwrap();
+        assert!(out[0].text.contains("that time was just taken"));
+        assert!(matches!(sim.state(other).unwrap(), ConversationState::PickingBookingSlot(_)));
+        assert!(sim.store().get_booking(2).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_booking_slot_passed_while_choosing() {
+        let sim = ChatSimulator::from_yaml(BOOKING_CONFIG).unwrap();
+        pick_first_day(&sim, CUSTOMER).await;
+
+        // The customer comes back to the times offered yesterday
+        let ConversationState::PickingBookingSlot(mut draft) = sim.state(CUSTOMER).unwrap() else {
+            panic!("not picking a slot");
+        };
+        let yesterday = sim.config().bookings.as_ref().unwrap().now().date() - chrono::Duration::days(1);
+        draft.date = Some(yesterday.format(crate::bookings::DATE_FORMAT).to_string());
+        let stale = ConversationState::PickingBookingSlot(draft);
+        sim.store().save_conversation_state(CUSTOMER, &stale.to_json()).unwrap();
+
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert!(out[0].text.contains("that time is no longer available"));
+        assert!(out[0].text.contains("Which day suits you?"));
+        assert!(sim.store().get_booking(1).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reschedule_and_cancel_booking() {
+        let sim = ChatSimulator::from_yaml(BOOKING_CONFIG).unwrap();
+        pick_first_day(&sim, CUSTOMER).await;
+        sim.send(CUSTOMER, "1").await.unwrap();
+        let original = sim.store().get_booking(1).unwrap().unwrap().start_at;
+
+        // Another customer can't touch it
+        let out = sim.send("254700000002", "CANCEL 1").await.unwrap();
+        assert!(out[0].text.contains("don't have an upcoming booking #1"));
+
+        let out = sim.send(CUSTOMER, "reschedule 1").await.unwrap();
+        assert!(out[0].text.contains("Which day suits you?"));
+        sim.send(CUSTOMER, "1").await.unwrap();
+        let out = sim.send(CUSTOMER, "2").await.unwrap();
+        assert!(out[0].text.contains("moved to"));
+        assert!(out[1].text.contains("Booking #1 confirmed"));
+        assert_ne!(sim.store().get_booking(1).unwrap().unwrap().start_at, original);
+
+        let out = sim.send(CUSTOMER, "CANCEL 1").await.unwrap();
+        assert!(out[0].text.contains("cancelled by"));
+        assert!(out[1].text.contains("has been cancelled"));
+        assert_eq!(
+            sim.store().get_booking(1).unwrap().unwrap().status,
+            crate::store::BookingStatus::Cancelled
+        );
+    }
+
+    async fn checkout(sim: &ChatSimulator, phone: &str, items: &str) -> Vec<OutboundMessage> {
+        sim.send(phone, "menu").await.unwrap();
+        sim.send(phone, items).await.unwrap();
+        sim.send(phone, "yes").await.unwrap();
+        sim.send(phone, "1 Stadium Road").await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stock_runs_out() {
+        let sim = ChatSimulator::from_yaml(STOCK_CONFIG).unwrap();
+        let other = "254700000002";
+
+        // Crossing the threshold alerts admins
+        let out = checkout(&sim, CUSTOMER, "2x1").await;
+        assert!(texts(&out).iter().any(|t| t.contains("Low stock: only 1 left of *Concert*")));
+        assert_eq!(sim.store().get_stock("Concert").unwrap(), Some(1));
+
+        sim.send(other, "menu").await.unwrap();
+        let out = sim.send(other, "2x1").await.unwrap();
+        assert!(out[0].text.contains("Only 1 left of Concert"));
+        assert!(matches!(sim.state(other).unwrap(), ConversationState::ViewingMenu));
+
+        let out = checkout(&sim, other, "1").await;
+        assert!(texts(&out).iter().any(|t| t.contains("*Concert* is now sold out")));
+
+        // Sold out items drop off the menu
+        let out = sim.send(CUSTOMER, "menu").await.unwrap();
+        assert!(!out[0].text.contains("Concert"));
+        assert!(out[0].text.contains("1. • *Poster*"));
+
+        // Cancelling puts the tickets back
+        sim.store().transition_order(1, &OrderStatus::Cancelled, "test", None).unwrap();
+        let out = sim.send(CUSTOMER, "menu").await.unwrap();
+        assert!(out[0].text.contains("Concert"));
+    }
+
+    #[tokio::test]
+    async fn test_item_sells_out_during_checkout() {
+        let sim = ChatSimulator::from_yaml(STOCK_CONFIG).unwrap();
+        sim.send(CUSTOMER, "menu").await.unwrap();
+        sim.send(CUSTOMER, "2x1").await.unwrap();
+        sim.send(CUSTOMER, "yes").await.unwrap();
+
+        sim.store().set_stock("Concert", 1).unwrap();
+
+        let out = sim.send(CUSTOMER, "1 Stadium Road").await.unwrap();
+        assert_eq!(out.len(), 1);
+        assert!(out[0].text.contains("Concert just sold out"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::Idle));
+        assert!(sim.store().get_order(1).unwrap().is_none());
+        assert_eq!(sim.store().get_stock("Concert").unwrap(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_categories_variants_and_addons() {
+        let sim = ChatSimulator::from_yaml(OPTIONS_CONFIG).unwrap();
+
+        let out = sim.send(CUSTOMER, "menu").await.unwrap();
+        assert!(out[0].text.contains("1. Pizzas (1)\n2. Sides (1)\n3. Other (1)"));
+
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert!(out[0].text.contains("1. • *Pizza* — from $8.00"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::ViewingCategory(_)));
+
+        let out = sim.send(CUSTOMER, "2x1").await.unwrap();
+        assert!(out[0].text.contains("which one would you like?"));
+        let out = sim.send(CUSTOMER, "3").await.unwrap();
+        assert!(out[0].text.contains("from 1 to 2"));
+
+        let out = sim.send(CUSTOMER, "large").await.unwrap();
+        assert!(out[0].text.contains("2. Extra cheese +$1.50"));
+        let out = sim.send(CUSTOMER, "2, 1").await.unwrap();
+        assert!(out[0].text.contains("2x  Pizza (Large) + Extra cheese, Olives — $29.00"));
+
+        // Adding from the flat list after the first pick
+        sim.send(CUSTOMER, "ADD").await.unwrap();
+        let out = sim.send(CUSTOMER, "3").await.unwrap();
+        assert!(out[0].text.contains("Water — $1.00"));
+        assert!(out[0].text.contains("Total: $30.00"));
+
+        sim.send(CUSTOMER, "yes").await.unwrap();
+        let out = sim.send(CUSTOMER, "1 Pizza Lane").await.unwrap();
+        assert!(out[0].text.contains("Pizza (Large) + Extra cheese, Olives"));
+
+        let order = sim.store().get_order(1).unwrap().unwrap();
+        assert!(order.items_json.contains(r#""variant":"Large""#));
+        assert!(order.items_json.contains(r#""addons":["Extra cheese","Olives"]"#));
+    }
+
+    #[tokio::test]
+    async fn test_addons_can_be_skipped() {
+        let sim = ChatSimulator::from_yaml(OPTIONS_CONFIG).unwrap();
+        sim.send(CUSTOMER, "menu").await.unwrap();
+        sim.send(CUSTOMER, "Pizzas").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+
+        let out = sim.send(CUSTOMER, "no").await.unwrap();
+        assert!(out[0].text.contains("Pizza (Small) — $8.00"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::ConfirmingOrder(_)));
+    }
+
+    #[tokio::test]
+    async fn test_customer_picks_language() {
+        let sim = sim();
+        let out = sim.send(CUSTOMER, "LANGUAGE").await.unwrap();
+        assert!(out[0].text.contains("2. Kiswahili"));
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::ChoosingLanguage));
+
+        let out = sim.send(CUSTOMER, "sw").await.unwrap();
+        assert!(out[0].text.starts_with("✅ Lugha imebadilishwa kuwa Kiswahili."));
+        assert!(out[0].text.contains("Karibu!"));
+        assert_eq!(sim.store().customer_language(CUSTOMER).unwrap().as_deref(), Some("sw"));
+
+        // Everything after that is in Swahili, and YES can be NDIYO
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert!(out[0].text.contains("Menyu ya Test Kitchen"));
+        sim.send(CUSTOMER, "1").await.unwrap();
+        let out = sim.send(CUSTOMER, "ndiyo").await.unwrap();
+        assert!(matches!(sim.state(CUSTOMER).unwrap(), ConversationState::AwaitingLocation(_)));
+        assert!(out[0].text.starts_with("📍"));
+        assert!(!out[0].text.contains("address"));
+    }
+
+    #[tokio::test]
+    async fn test_first_message_sets_language() {
+        let sim = sim();
+        let out = sim.send(CUSTOMER, "Habari, nataka chakula").await.unwrap();
+        assert!(out[0].text.contains("Karibu!"));
+        assert_eq!(sim.store().customer_language(CUSTOMER).unwrap().as_deref(), Some("sw"));
+
+        // Only the first message counts
+        let out = sim.send(CUSTOMER, "Good morning, can I see the food?").await.unwrap();
+        assert!(out[0].text.contains("Karibu!"));
+        assert_eq!(sim.store().customer_language(CUSTOMER).unwrap().as_deref(), Some("sw"));
+
+        // A first message with nothing to go on leaves the business default
+        let other = "254700000002";
+        sim.send(other, "1").await.unwrap();
+        assert_eq!(sim.store().customer_language(other).unwrap(), None);
+
+        // A long message in a script with multi-byte characters
+        let hindi = "919800000000";
+        sim.send(hindi, "नमस्ते, मुझे खाना ऑर्डर करना है, कृपया मेनू भेजें").await.unwrap();
+        assert_eq!(sim.store().customer_language(hindi).unwrap().as_deref(), Some("hi"));
+    }
+
+    #[tokio::test]
+    async fn test_order_updates_in_business_language() {
+        let mut sim = sim();
+        sim.config_mut().business.language = "pt".to_string();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        assert!(out[0].text.contains("O Seu Pedido"));
+        sim.send(CUSTOMER, "sim").await.unwrap();
+        let out = sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+        assert!(texts(&out).iter().any(|t| t.contains("Pedido #1 confirmado")));
+
+        // The customer's update is translated, the admin's reply isn't
+        sim.send(ADMIN, "ADMIN").await.unwrap();
+        let out = sim.send(ADMIN, "DONE 1").await.unwrap();
+        assert_eq!(out[0].to, CUSTOMER);
+        assert!(out[0].text.contains("O pedido #1 foi entregue"));
+        assert!(out[1].text.contains("marked as delivered"));
+    }
+
+    #[tokio::test]
+    async fn test_rich_messages_and_receipts() {
+        let mut sim = sim().with_transport(MemoryTransport::new().with_rich_messages());
+        sim.config_mut().business.location = Some(BusinessLocation {
+            latitude: -1.2921,
+            longitude: 36.8219,
+            address: Some("Moi Avenue".to_string()),
+        });
+
+        // Confirming the cart offers buttons; the text still says what to type
+        sim.send(CUSTOMER, "1").await.unwrap();
+        let out = sim.send(CUSTOMER, "1").await.unwrap();
+        let Some(OutgoingMessage::Buttons(ref buttons)) = out[0].rich else {
+            panic!("expected buttons, got {:?}", out[0].rich);
+        };
+        let ids: Vec<&str> = buttons.buttons.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, ["YES", "0"]);
+        assert!(out[0].text.contains("YES"));
+        sim.send(CUSTOMER, "YES").await.unwrap();
+        sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+
+        // My Orders mentions receipts, which come as a PDF
+        let out = sim.send(CUSTOMER, "2").await.unwrap();
+        assert!(out[0].text.contains("RECEIPT <number>"));
+        let out = sim.send(CUSTOMER, "receipt #1").await.unwrap();
+        let Some(OutgoingMessage::Document(ref receipt)) = out[0].rich else {
+            panic!("expected a document, got {:?}", out[0].rich);
+        };
+        assert_eq!(receipt.file_name, "receipt-1.pdf");
+        assert!(receipt.data.starts_with(b"%PDF-"));
+        assert_eq!(receipt.caption.as_deref(), Some("Receipt for order #1"));
+
+        // Only for the customer's own orders
+        let out = sim.send("254700000002", "RECEIPT 1").await.unwrap();
+        assert_eq!(texts(&out), ["❌ You don't have an order #1."]);
+
+        // About sends the business's location as a pin
+        let out = sim.send(CUSTOMER, "4").await.unwrap();
+        assert!(matches!(out[1].rich, Some(OutgoingMessage::Location(ref pin)) if pin.latitude == -1.2921));
+    }
+
+    #[tokio::test]
+    async fn test_text_fallbacks() {
+        let sim = sim();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "1").await.unwrap();
+        sim.send(CUSTOMER, "YES").await.unwrap();
+        sim.send(CUSTOMER, "12 Main Street").await.unwrap();
+
+        // A text-only transport gets the receipt as a message
+        let out = sim.send(CUSTOMER, "RECEIPT 1").await.unwrap();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].rich, None);
+        let text = &out[0].text;
+        assert!(text.starts_with("🧾 *Test Kitchen*\n*Receipt for order #1*\n"));
+        assert!(text.contains("🌯 Kota — KES 35.00"));
+        assert!(text.contains("Delivery: KES 10.00"));
+        assert!(text.contains("*Total: KES 45.00*"));
+        assert!(text.ends_with("Status: Confirmed"));
+    }
+}
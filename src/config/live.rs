@@ -0,0 +1,230 @@
+//! Hot reload of config.yaml and the `locales/` files.
+//!
+//! `LiveConfig` is a cheaply cloneable handle to the current `HiveConfig`.
+//! Readers call `get()` once per request and keep that snapshot, so a
+//! reload never changes the config halfway through handling a message.
+//! `reload()` re-reads and validates config.yaml and swaps it in only if it
+//! is valid; `watch()` does this whenever config.yaml or a locale file
+//! changes on disk.
+
+use super::HiveConfig;
+use crate::store::Store;
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+/// Sections that are only read at startup, so changing them needs a restart.
+const RESTART_SECTIONS: &[&str] = &["dashboard", "network", "payments", "whatsapp"];
+
+/// Shared, swappable config.
+#[derive(Clone)]
+pub struct LiveConfig {
+    current: Arc<RwLock<Arc<HiveConfig>>>,
+    project_dir: PathBuf,
+}
+
+/// What a reload changed.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadSummary {
+    /// Top-level sections that differ from the previous config
+    pub changed: Vec<String>,
+    /// Changed sections that only take effect after a restart
+    pub restart_required: Vec<String>,
+    /// Menu items added, updated or removed in the store
+    pub menu_items_synced: usize,
+}
+
+impl LiveConfig {
+    /// Wrap an already-loaded config for the bot in `project_dir`.
+    pub fn new(config: HiveConfig, project_dir: &Path) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(config))),
+            project_dir: project_dir.to_path_buf(),
+        }
+    }
+
+    /// The current config.
+    pub fn get(&self) -> Arc<HiveConfig> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Path of the watched config file.
+    pub fn path(&self) -> PathBuf {
+        self.project_dir.join("config.yaml")
+    }
+
+    /// Re-read config.yaml and, if it validates, make it the current config.
+    ///
+    /// Menu changes are applied to the store (see `Store::sync_menu`). On
+    /// error the current config stays in place.
+    pub fn reload(&self, store: &Store) -> Result<ReloadSummary> {
+        let new = HiveConfig::load(&self.project_dir)?;
+        let old = self.get();
+
+        let menu_items_synced = store.sync_menu(&old.menu, &new.menu)?;
+        let changed = changed_sections(&old, &new)?;
+        let restart_required = changed
+            .iter()
+            .filter(|s| RESTART_SECTIONS.contains(&s.as_str()))
+            .cloned()
+            .collect();
+
+        *self.current.write().unwrap() = Arc::new(new);
+        Ok(ReloadSummary {
+            changed,
+            restart_required,
+            menu_items_synced,
+        })
+    }
+
+    /// Reload whenever config.yaml or a file in `locales/` is modified,
+    /// checking every `interval`. Runs until the task is dropped.
+    pub async fn watch(self, store: Store, interval: Duration) {
+        let path = self.path();
+        let mut last_modified = self.last_modified();
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+            let modified = self.last_modified();
+            if modified.is_none() || modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            match self.reload(&store) {
+                Ok(summary) if summary.changed.is_empty() => {
+                    log::debug!("🔄 {} saved with no changes", path.display());
+                }
+                Ok(summary) => {
+                    log::info!("🔄 Reloaded {} — changed: {}", path.display(), summary.changed.join(", "));
+                    if !summary.restart_required.is_empty() {
+                        log::warn!(
+                            "⚠️ Restart Hive to apply changes to: {}",
+                            summary.restart_required.join(", ")
+                        );
+                    }
+                }
+                Err(e) => {
+                    log::error!("❌ Not reloading {}, keeping the previous config: {:#}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    /// Latest modification time of config.yaml, the `locales/` directory
+    /// (so removing a file counts) and the files in it.
+    fn last_modified(&self) -> Option<SystemTime> {
+        let locales = self.project_dir.join("locales");
+        let mut latest = modified(&self.path())?;
+        let mut paths = vec![locales.clone()];
+        if let Ok(entries) = std::fs::read_dir(&locales) {
+            paths.extend(entries.flatten().map(|e| e.path()));
+        }
+        for path in paths {
+            if let Some(m) = modified(&path) {
+                latest = latest.max(m);
+            }
+        }
+        Some(latest)
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Names of top-level config sections that differ between `old` and `new`,
+/// plus `locales` if the locale files changed.
+fn changed_sections(old: &HiveConfig, new: &HiveConfig) -> Result<Vec<String>> {
+    let mut changed = Vec::new();
+    let old_value = serde_json::to_value(old)?;
+    let new_value = serde_json::to_value(new)?;
+    if let (Some(old_value), Some(new_value)) = (old_value.as_object(), new_value.as_object()) {
+        changed.extend(
+            new_value
+                .iter()
+                .filter(|(key, value)| old_value.get(*key) != Some(value))
+                .map(|(key, _)| key.clone()),
+        );
+    }
+    if old.i18n != new.i18n {
+        changed.push("locales".to_string());
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+business:
+  name: "Test Kitchen"
+menu:
+  - name: "Kota"
+    price: 35
+  - name: "Gatsby"
+    price: 60
+"#;
+
+    #[test]
+    fn test_reload_swaps_valid_config_only() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("config.yaml"), CONFIG).unwrap();
+        let store = Store::new(":memory:").unwrap();
+        let config = HiveConfig::load(dir.path()).unwrap();
+        store.seed_menu(&config.menu).unwrap();
+        let live = LiveConfig::new(config, dir.path());
+        let before = live.get();
+
+        let edited = CONFIG
+            .replace("price: 35", "price: 40")
+            .replace("  - name: \"Gatsby\"\n    price: 60\n", "")
+            + "admin_numbers: [\"+1 555 0100\"]\ndashboard:\n  port: 9090\n";
+        std::fs::write(live.path(), edited).unwrap();
+
+        let summary = live.reload(&store).unwrap();
+        assert_eq!(summary.changed, vec!["admin_numbers", "dashboard", "menu"]);
+        assert_eq!(summary.restart_required, vec!["dashboard"]);
+        assert_eq!(summary.menu_items_synced, 2);
+        assert!(live.get().is_admin("+15550100"));
+        // Snapshots taken before the reload are unaffected
+        assert!(before.admin_numbers.is_empty());
+
+        let menu = store.available_menu().unwrap();
+        assert_eq!(menu.len(), 1);
+        assert_eq!(menu[0].price, 40.0);
+
+        // Invalid config is rejected and the current one kept
+        std::fs::write(live.path(), "business:\n  name: \"\"\nmenu: []\n").unwrap();
+        assert!(live.reload(&store).is_err());
+        assert_eq!(live.get().dashboard.port, 9090);
+    }
+
+    #[test]
+    fn test_reload_picks_up_locale_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("config.yaml"), CONFIG).unwrap();
+        let store = Store::new(":memory:").unwrap();
+        let live = LiveConfig::new(HiveConfig::load(dir.path()).unwrap(), dir.path());
+        assert!(live.get().i18n.language("zu").is_none());
+
+        std::fs::create_dir(dir.path().join("locales")).unwrap();
+        std::fs::write(
+            dir.path().join("locales/zu.yaml"),
+            "name: isiZulu\nmenu:\n  Kota:\n    name: \"Ikota\"\n",
+        )
+        .unwrap();
+        let summary = live.reload(&store).unwrap();
+        assert_eq!(summary.changed, vec!["locales"]);
+        assert!(summary.restart_required.is_empty());
+
+        let config = live.get();
+        let zulu = config.i18n.language("zu").unwrap();
+        assert_eq!(config.i18n.item_name(zulu, "Kota"), "Ikota");
+        assert_eq!(config.i18n.item_name(zulu, "Gatsby"), "Gatsby");
+    }
+}
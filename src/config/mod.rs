@@ -2,10 +2,17 @@
 //!
 //! All bot behavior is driven by a single YAML config file. This module
 //! defines the config schema, loads it from disk, and validates it.
+//! `live` handles reloading it while the bot runs.
 
+pub mod live;
+
+use crate::i18n::{self, Language, TranslationKey, Translations};
+use crate::store::Store;
 use anyhow::{Context, Result};
+use chrono::{FixedOffset, NaiveDateTime, NaiveTime, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::sync::Arc;
 
 /// Top-level Hive configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +31,17 @@ pub struct HiveConfig {
     pub network: NetworkConfig,
     #[serde(default)]
     pub payments: PaymentConfig,
+    #[serde(default)]
+    pub flows: Vec<FlowConfig>,
+    #[serde(default)]
+    pub bookings: Option<BookingConfig>,
+    #[serde(default)]
+    pub inventory: InventoryConfig,
+    #[serde(default)]
+    pub whatsapp: WhatsAppConfig,
+    /// Built-in translations plus the project's `locales/` files
+    #[serde(skip, default = "i18n::built_in")]
+    pub i18n: Arc<Translations>,
 }
 
 /// Business identity and messaging.
@@ -38,18 +56,43 @@ pub struct BusinessConfig {
     pub about: Option<String>,
     #[serde(default)]
     pub phone: Option<String>,
+    /// Language (ISO code) customers are served in until they pick one
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// Languages offered by the LANGUAGE command (default: all built-in ones)
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Guess a new customer's language from their first message
+    #[serde(default = "default_true")]
+    pub detect_language: bool,
+    /// Where the business is, sent as a map pin with the "About Us" reply
+    #[serde(default)]
+    pub location: Option<BusinessLocation>,
+}
+
+/// A place on the map.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BusinessLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(default)]
+    pub address: Option<String>,
 }
 
 fn default_currency() -> String {
     "USD".to_string()
 }
 
+fn default_language() -> String {
+    "en".to_string()
+}
+
 fn default_welcome() -> String {
     "Welcome! Reply with a number:\n1. 📋 View Menu\n2. 📦 My Orders\n3. 🎟️ Redeem Voucher\n4. ℹ️ About Us".to_string()
 }
 
 /// A single menu item.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MenuItem {
     pub name: String,
     pub price: f64,
@@ -59,6 +102,78 @@ pub struct MenuItem {
     pub emoji: Option<String>,
     #[serde(default = "default_true")]
     pub available: bool,
+    /// Appointment length in minutes (bookings only; defaults to `bookings.default_duration_minutes`)
+    #[serde(default)]
+    pub duration_minutes: Option<u32>,
+    /// Initial stock count. Omit for items that never run out.
+    #[serde(default)]
+    pub stock: Option<u32>,
+    /// Menu section, e.g. "Drinks". Menus with categories are browsed by category.
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Sizes or versions with their own prices; the customer must pick one.
+    #[serde(default)]
+    pub variants: Vec<MenuVariant>,
+    /// Optional extras the customer can add, each adding to the price.
+    #[serde(default)]
+    pub addons: Vec<MenuAddon>,
+    /// Photo (JPEG, PNG or WebP), relative to the project directory, sent
+    /// with the item when the menu is shown.
+    #[serde(default)]
+    pub image: Option<String>,
+}
+
+/// A size or version of a menu item, e.g. "Large".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MenuVariant {
+    pub name: String,
+    pub price: f64,
+}
+
+/// An optional extra for a menu item, e.g. "Extra cheese".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MenuAddon {
+    pub name: String,
+    /// Amount added to the item's price
+    pub price: f64,
+}
+
+/// Category for menu items that don't set one, when others do.
+pub const OTHER_CATEGORY: &str = "Other";
+
+impl MenuItem {
+    /// Whether ordering this item needs a variant or add-on step.
+    pub fn has_options(&self) -> bool {
+        !self.variants.is_empty() || !self.addons.is_empty()
+    }
+
+    /// The item's category, or `OTHER_CATEGORY`.
+    pub fn category(&self) -> &str {
+        self.category.as_deref().unwrap_or(OTHER_CATEGORY)
+    }
+
+    /// Price as shown on the menu, e.g. "$12.00" or "from $9.00" for items
+    /// with variants.
+    pub fn price_label(&self, currency: &str) -> String {
+        self.price_label_in(currency, &i18n::built_in(), Language::English)
+    }
+
+    /// `price_label` in the customer's language.
+    pub fn price_label_in(&self, currency: &str, i18n: &Translations, lang: Language) -> String {
+        let cheapest = self
+            .variants
+            .iter()
+            .map(|v| v.price)
+            .fold(None, |min: Option<f64>, p| Some(min.map_or(p, |m| m.min(p))));
+        match cheapest {
+            Some(price) => i18n.render(
+                lang,
+                TranslationKey::PriceFrom,
+                &[("price", &format!("{}{:.2}", currency, price))],
+            ),
+            None => format!("{}{:.2}", currency, self.price),
+        }
+    }
 }
 
 fn default_true() -> bool {
@@ -80,43 +195,254 @@ pub struct DeliveryConfig {
 impl DeliveryConfig {
     /// Format the delivery estimate as a human-readable string.
     pub fn estimate_string(&self) -> String {
-        match &self.estimate_minutes {
-            Some(range) if range.len() == 2 => format!("{}-{} minutes", range[0], range[1]),
-            Some(range) if range.len() == 1 => format!("{} minutes", range[0]),
-            _ => "30-45 minutes".to_string(),
+        self.estimate_in(&i18n::built_in(), Language::English)
+    }
+
+    /// `estimate_string` in the customer's language.
+    pub fn estimate_in(&self, i18n: &Translations, lang: Language) -> String {
+        let minutes = match &self.estimate_minutes {
+            Some(range) if range.len() == 2 => format!("{}-{}", range[0], range[1]),
+            Some(range) if range.len() == 1 => range[0].to_string(),
+            _ => "30-45".to_string(),
+        };
+        i18n.render(lang, TranslationKey::Minutes, &[("minutes", &minutes)])
+    }
+}
+
+/// Stock tracking settings (per-item counts live in `MenuItem::stock`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryConfig {
+    /// Alert admins when an item's stock drops to this level or below
+    #[serde(default = "default_low_stock_threshold")]
+    pub low_stock_threshold: u32,
+}
+
+impl Default for InventoryConfig {
+    fn default() -> Self {
+        Self {
+            low_stock_threshold: default_low_stock_threshold(),
+        }
+    }
+}
+
+fn default_low_stock_threshold() -> u32 {
+    3
+}
+
+/// WhatsApp transport settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WhatsAppConfig {
+    /// Send button and list messages. WhatsApp only shows them on some
+    /// accounts, so by default customers get numbered text menus instead.
+    #[serde(default)]
+    pub interactive: bool,
+}
+
+/// Appointment booking configuration.
+///
+/// When present, choosing a menu item starts a date and time picker instead
+/// of the order cart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookingConfig {
+    /// Opening hours per weekday, e.g. `mon: "09:00-18:00"`. Missing days are closed.
+    #[serde(default)]
+    pub hours: OpeningHours,
+    /// Minutes between bookable start times
+    #[serde(default = "default_slot_minutes")]
+    pub slot_minutes: u32,
+    /// Appointment length for menu items without `duration_minutes`
+    #[serde(default = "default_duration_minutes")]
+    pub default_duration_minutes: u32,
+    /// Appointments that can run at the same time (staff, chairs, rooms)
+    #[serde(default = "default_capacity")]
+    pub capacity: u32,
+    /// How many days ahead customers can book (including today)
+    #[serde(default = "default_days_ahead")]
+    pub days_ahead: u32,
+    /// Minimum notice before an appointment starts
+    #[serde(default = "default_min_notice_minutes")]
+    pub min_notice_minutes: u32,
+    /// Business time zone as a UTC offset, e.g. "+03:00"
+    #[serde(default = "default_utc_offset")]
+    pub utc_offset: String,
+}
+
+/// Opening hours as "HH:MM-HH:MM" per weekday.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpeningHours {
+    #[serde(default)]
+    pub mon: Option<String>,
+    #[serde(default)]
+    pub tue: Option<String>,
+    #[serde(default)]
+    pub wed: Option<String>,
+    #[serde(default)]
+    pub thu: Option<String>,
+    #[serde(default)]
+    pub fri: Option<String>,
+    #[serde(default)]
+    pub sat: Option<String>,
+    #[serde(default)]
+    pub sun: Option<String>,
+}
+
+fn default_slot_minutes() -> u32 {
+    30
+}
+fn default_duration_minutes() -> u32 {
+    60
+}
+fn default_capacity() -> u32 {
+    1
+}
+fn default_days_ahead() -> u32 {
+    7
+}
+fn default_min_notice_minutes() -> u32 {
+    60
+}
+fn default_utc_offset() -> String {
+    "+00:00".to_string()
+}
+
+impl OpeningHours {
+    fn raw(&self, day: Weekday) -> Option<&str> {
+        match day {
+            Weekday::Mon => self.mon.as_deref(),
+            Weekday::Tue => self.tue.as_deref(),
+            Weekday::Wed => self.wed.as_deref(),
+            Weekday::Thu => self.thu.as_deref(),
+            Weekday::Fri => self.fri.as_deref(),
+            Weekday::Sat => self.sat.as_deref(),
+            Weekday::Sun => self.sun.as_deref(),
         }
     }
+
+    /// Opening and closing time for a weekday, or `None` if closed.
+    pub fn for_day(&self, day: Weekday) -> Option<(NaiveTime, NaiveTime)> {
+        self.raw(day).and_then(|s| parse_hours(s).ok())
+    }
+}
+
+/// Parse an "HH:MM-HH:MM" opening hours range.
+fn parse_hours(s: &str) -> Result<(NaiveTime, NaiveTime)> {
+    let (open, close) = s
+        .split_once('-')
+        .with_context(|| format!("expected HH:MM-HH:MM, got '{}'", s))?;
+    let open = NaiveTime::parse_from_str(open.trim(), "%H:%M")
+        .with_context(|| format!("invalid opening time in '{}'", s))?;
+    let close = NaiveTime::parse_from_str(close.trim(), "%H:%M")
+        .with_context(|| format!("invalid closing time in '{}'", s))?;
+    if close <= open {
+        anyhow::bail!("closing time must be after opening time in '{}'", s);
+    }
+    Ok((open, close))
+}
+
+impl BookingConfig {
+    /// Business time zone offset.
+    pub fn offset(&self) -> FixedOffset {
+        self.utc_offset
+            .parse()
+            .unwrap_or_else(|_| FixedOffset::east_opt(0).unwrap())
+    }
+
+    /// Current local time at the business.
+    pub fn now(&self) -> NaiveDateTime {
+        Utc::now().with_timezone(&self.offset()).naive_local()
+    }
+
+    /// Appointment length for a menu item.
+    pub fn duration_for(&self, item: &MenuItem) -> u32 {
+        item.duration_minutes.unwrap_or(self.default_duration_minutes)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for day in [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ] {
+            if let Some(raw) = self.hours.raw(day) {
+                parse_hours(raw)
+                    .with_context(|| format!("bookings.hours.{}", day.to_string().to_lowercase()))?;
+            }
+        }
+        if self.slot_minutes == 0 {
+            anyhow::bail!("bookings.slot_minutes must be > 0");
+        }
+        if self.default_duration_minutes == 0 {
+            anyhow::bail!("bookings.default_duration_minutes must be > 0");
+        }
+        if self.capacity == 0 {
+            anyhow::bail!("bookings.capacity must be > 0");
+        }
+        if self.utc_offset.parse::<FixedOffset>().is_err() {
+            anyhow::bail!("bookings.utc_offset must look like \"+03:00\"");
+        }
+        Ok(())
+    }
 }
 
 /// Customizable message templates with placeholder support.
 ///
 /// Supported placeholders: `{id}`, `{items}`, `{total}`, `{currency}`,
-/// `{location}`, `{estimate}`, `{code}`, `{amount}`
+/// `{location}`, `{estimate}`, `{code}`, `{amount}`, and for bookings
+/// `{service}`, `{date}`, `{time}`, `{phone}`.
+///
+/// The `order_accepted` … `order_cancelled` updates are sent when an order
+/// changes status; set one to "" to stop sending it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageTemplates {
     #[serde(default = "default_order_confirmed")]
     pub order_confirmed: String,
     #[serde(default = "default_order_received_admin")]
     pub order_received_admin: String,
+    #[serde(default = "default_order_accepted")]
+    pub order_accepted: String,
+    #[serde(default = "default_order_preparing")]
+    pub order_preparing: String,
+    #[serde(default = "default_order_delivering")]
+    pub order_delivering: String,
     #[serde(default = "default_order_delivered")]
     pub order_delivered: String,
+    #[serde(default = "default_order_cancelled")]
+    pub order_cancelled: String,
     #[serde(default = "default_voucher_created")]
     pub voucher_created: String,
     #[serde(default = "default_voucher_redeemed")]
     pub voucher_redeemed: String,
     #[serde(default = "default_voucher_invalid")]
     pub voucher_invalid: String,
+    #[serde(default = "default_booking_confirmed")]
+    pub booking_confirmed: String,
+    #[serde(default = "default_booking_received_admin")]
+    pub booking_received_admin: String,
 }
 
+/// Business texts a locale file's `messages:` may translate besides the
+/// customer-facing templates.
+const LOCALE_MESSAGES: &[&str] = &["welcome", "about"];
+
 impl Default for MessageTemplates {
     fn default() -> Self {
         Self {
             order_confirmed: default_order_confirmed(),
             order_received_admin: default_order_received_admin(),
+            order_accepted: default_order_accepted(),
+            order_preparing: default_order_preparing(),
+            order_delivering: default_order_delivering(),
             order_delivered: default_order_delivered(),
+            order_cancelled: default_order_cancelled(),
             voucher_created: default_voucher_created(),
             voucher_redeemed: default_voucher_redeemed(),
             voucher_invalid: default_voucher_invalid(),
+            booking_confirmed: default_booking_confirmed(),
+            booking_received_admin: default_booking_received_admin(),
         }
     }
 }
@@ -128,9 +454,21 @@ fn default_order_confirmed() -> String {
 fn default_order_received_admin() -> String {
     "🔔 New Order #{id}\n{items}\nTotal: {currency}{total}\n📍 {location}\nReply DONE {id} when delivered".to_string()
 }
+fn default_order_accepted() -> String {
+    "✅ Order #{id} has been accepted — we'll let you know when it's being prepared.".to_string()
+}
+fn default_order_preparing() -> String {
+    "🍳 Order #{id} is being prepared.".to_string()
+}
+fn default_order_delivering() -> String {
+    "🚗 Order #{id} is on its way!\n⏱ Estimated delivery: {estimate}".to_string()
+}
 fn default_order_delivered() -> String {
     "🎉 Order #{id} has been delivered! Enjoy your meal!\nRate us: ⭐⭐⭐⭐⭐".to_string()
 }
+fn default_order_cancelled() -> String {
+    "❌ Order #{id} has been cancelled.\nReply 1 to order again.".to_string()
+}
 fn default_voucher_created() -> String {
     "🎟️ Voucher created: {code} — {currency}{amount}".to_string()
 }
@@ -140,6 +478,103 @@ fn default_voucher_redeemed() -> String {
 fn default_voucher_invalid() -> String {
     "❌ That voucher code is invalid or already used.".to_string()
 }
+fn default_booking_confirmed() -> String {
+    "✅ Booking #{id} confirmed!\n{service}\n📅 {date} at {time}\n\nReply RESCHEDULE {id} or CANCEL {id} if your plans change.".to_string()
+}
+fn default_booking_received_admin() -> String {
+    "🔔 New Booking #{id}\n{service}\n📅 {date} at {time}\n👤 {phone}".to_string()
+}
+
+/// A declarative conversation flow (questionnaire, booking request, etc.).
+///
+/// A flow starts when an idle user sends one of its `triggers`, then walks
+/// through `steps` in order (or as directed by `next`), collecting answers
+/// into variables named after each step's `id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowConfig {
+    pub name: String,
+    /// Title shown in admin notifications (defaults to `name`)
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Messages that start the flow (case-insensitive), e.g. ["5", "book"]
+    pub triggers: Vec<String>,
+    pub steps: Vec<FlowStep>,
+    /// Message sent when the flow completes. Placeholders: collected variables.
+    #[serde(default = "default_flow_complete")]
+    pub complete: String,
+    /// Send the collected answers to admin numbers on completion
+    #[serde(default = "default_true")]
+    pub notify_admin: bool,
+}
+
+/// A single step in a flow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowStep {
+    /// Step identifier; the answer is stored under this variable name
+    pub id: String,
+    /// Question sent to the user. Placeholders: previously collected variables.
+    pub prompt: String,
+    #[serde(default)]
+    pub input: FlowInput,
+    /// Options for `choice` steps (reply by number or label)
+    #[serde(default)]
+    pub options: Vec<FlowOption>,
+    /// Minimum value (`number`) or length (`text`)
+    #[serde(default)]
+    pub min: Option<f64>,
+    /// Maximum value (`number`) or length (`text`)
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// Message sent when the answer fails validation
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Step to go to next ("end" to finish). Defaults to the following step.
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+/// Expected input type for a flow step.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowInput {
+    #[default]
+    Text,
+    Number,
+    Location,
+    Choice,
+}
+
+/// An option in a `choice` step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowOption {
+    pub label: String,
+    /// Step to jump to when this option is picked (overrides the step's `next`)
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+/// Step name that ends a flow.
+pub const FLOW_END: &str = "end";
+
+fn default_flow_complete() -> String {
+    "✅ Thanks! We've received your details and will be in touch shortly.".to_string()
+}
+
+impl FlowConfig {
+    pub fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn step(&self, id: &str) -> Option<&FlowStep> {
+        self.steps.iter().find(|s| s.id == id)
+    }
+
+    /// The step after `id` in declaration order, if any.
+    pub fn following_step(&self, id: &str) -> Option<&FlowStep> {
+        let pos = self.steps.iter().position(|s| s.id == id)?;
+        self.steps.get(pos + 1)
+    }
+}
 
 /// Dashboard / admin panel configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -148,6 +583,13 @@ pub struct DashboardConfig {
     pub port: u16,
     #[serde(default = "default_true")]
     pub enabled: bool,
+    /// How long a dashboard login lasts before signing in again.
+    #[serde(default = "default_session_hours")]
+    pub session_hours: u32,
+    /// Other origins allowed to call the API from a browser (CORS).
+    /// Empty means only the dashboard itself.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
 }
 
 impl Default for DashboardConfig {
@@ -155,6 +597,8 @@ impl Default for DashboardConfig {
         Self {
             port: default_port(),
             enabled: true,
+            session_hours: default_session_hours(),
+            allowed_origins: Vec::new(),
         }
     }
 }
@@ -163,6 +607,10 @@ fn default_port() -> u16 {
     8080
 }
 
+fn default_session_hours() -> u32 {
+    12
+}
+
 /// Reality Network integration configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkConfig {
@@ -212,6 +660,57 @@ pub struct PaymentConfig {
     /// M-Pesa configuration (Kenya).
     #[serde(default)]
     pub mpesa: Option<MpesaConfig>,
+    /// M-Pesa B2C payouts, for refunding customers.
+    #[serde(default)]
+    pub mpesa_b2c: Option<MpesaB2CConfig>,
+    /// PayStack configuration (Nigeria, Ghana, South Africa, Kenya).
+    #[serde(default)]
+    pub paystack: Option<PaystackConfig>,
+    /// Ask the provider about payments still processing after this many
+    /// minutes, in case their callback never arrived. 0 turns this off.
+    #[serde(default = "default_reconcile_after_minutes")]
+    pub reconcile_after_minutes: u32,
+    /// Give up on payments the provider still calls processing this many
+    /// minutes after they started (abandoned checkouts, prompts nobody
+    /// answered) and mark them cancelled. Checked while reconciling; 0
+    /// turns this off.
+    #[serde(default = "default_expire_after_minutes")]
+    pub expire_after_minutes: u32,
+}
+
+fn default_reconcile_after_minutes() -> u32 {
+    5
+}
+
+fn default_expire_after_minutes() -> u32 {
+    60
+}
+
+/// M-Pesa B2C (business to customer) credentials. The app keys, `sandbox`
+/// and `base_url` default to the `mpesa` section's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MpesaB2CConfig {
+    #[serde(default)]
+    pub consumer_key: Option<String>,
+    #[serde(default)]
+    pub consumer_secret: Option<String>,
+    /// B2C shortcode the payouts come from
+    pub shortcode: String,
+    /// API operator username from the M-Pesa portal
+    pub initiator_name: String,
+    /// Initiator password encrypted with Safaricom's certificate
+    pub security_credential: String,
+    /// Where M-Pesa sends payout results, e.g.
+    /// `https://yourdomain.com/api/mpesa/b2c/callback`
+    pub result_url: String,
+    /// Where M-Pesa reports payouts that timed out in its queue; defaults
+    /// to `result_url`
+    #[serde(default)]
+    pub timeout_url: Option<String>,
+    #[serde(default)]
+    pub sandbox: Option<bool>,
+    #[serde(default)]
+    pub base_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -223,6 +722,40 @@ pub struct MpesaConfig {
     pub callback_url: String,
     #[serde(default)]
     pub sandbox: bool,
+    /// Daraja API to use instead of Safaricom's, e.g.
+    /// `http://localhost:8090` for `hive mpesa-sim`
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+/// PayStack credentials. Customers pay on PayStack's checkout page, and
+/// PayStack reports the result to `/api/paystack/webhook`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaystackConfig {
+    /// Secret key from the PayStack dashboard (`sk_test_…` or `sk_live_…`);
+    /// also signs the webhooks
+    pub secret_key: String,
+    /// PayStack wants an email for every payment and WhatsApp customers
+    /// don't have one, so they're given `<phone>@<email_domain>`
+    pub email_domain: String,
+    /// ISO currency code to charge in, e.g. `NGN`; defaults to
+    /// `business.currency` when that is a code
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// Page to send the customer to after paying
+    #[serde(default)]
+    pub callback_url: Option<String>,
+    /// PayStack API to use instead of `https://api.paystack.co`
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+impl PaystackConfig {
+    /// The ISO currency code payments are made in.
+    pub fn currency_code(&self, business_currency: &str) -> Option<String> {
+        let code = self.currency.as_deref().unwrap_or(business_currency).trim();
+        (code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())).then(|| code.to_ascii_uppercase())
+    }
 }
 
 impl Default for PaymentConfig {
@@ -230,6 +763,10 @@ impl Default for PaymentConfig {
         Self {
             enabled: false,
             mpesa: None,
+            mpesa_b2c: None,
+            paystack: None,
+            reconcile_after_minutes: default_reconcile_after_minutes(),
+            expire_after_minutes: default_expire_after_minutes(),
         }
     }
 }
@@ -243,6 +780,56 @@ impl MessageTemplates {
         }
         result
     }
+
+    /// The templates for a customer who speaks `lang`. A translation in
+    /// `lang`'s locale file wins; otherwise customer-facing templates still
+    /// set to their default are swapped for the built-in translation and
+    /// customized ones are used as written.
+    pub fn localized(&self, i18n: &Translations, lang: Language) -> MessageTemplates {
+        let translate = |template: &String, default: fn() -> String, key: TranslationKey| {
+            if *template == default() {
+                i18n.text(lang, key)
+            } else {
+                template.clone()
+            }
+        };
+        let mut localized = MessageTemplates {
+            order_confirmed: translate(&self.order_confirmed, default_order_confirmed, TranslationKey::OrderConfirmedMessage),
+            order_accepted: translate(&self.order_accepted, default_order_accepted, TranslationKey::OrderAcceptedMessage),
+            order_preparing: translate(&self.order_preparing, default_order_preparing, TranslationKey::OrderPreparingMessage),
+            order_delivering: translate(&self.order_delivering, default_order_delivering, TranslationKey::OrderDeliveringMessage),
+            order_delivered: translate(&self.order_delivered, default_order_delivered, TranslationKey::OrderDeliveredMessage),
+            order_cancelled: translate(&self.order_cancelled, default_order_cancelled, TranslationKey::OrderCancelledMessage),
+            voucher_redeemed: translate(&self.voucher_redeemed, default_voucher_redeemed, TranslationKey::VoucherRedeemedMessage),
+            voucher_invalid: translate(&self.voucher_invalid, default_voucher_invalid, TranslationKey::VoucherInvalidMessage),
+            booking_confirmed: translate(&self.booking_confirmed, default_booking_confirmed, TranslationKey::BookingConfirmedMessage),
+            ..self.clone()
+        };
+        if let Some(locale) = i18n.locale(lang) {
+            for (name, text) in &locale.messages {
+                if let Some(template) = localized.customer_template_mut(name) {
+                    *template = text.clone();
+                }
+            }
+        }
+        localized
+    }
+
+    /// The customer-facing template called `name`, e.g. `order_confirmed`.
+    fn customer_template_mut(&mut self, name: &str) -> Option<&mut String> {
+        match name {
+            "order_confirmed" => Some(&mut self.order_confirmed),
+            "order_accepted" => Some(&mut self.order_accepted),
+            "order_preparing" => Some(&mut self.order_preparing),
+            "order_delivering" => Some(&mut self.order_delivering),
+            "order_delivered" => Some(&mut self.order_delivered),
+            "order_cancelled" => Some(&mut self.order_cancelled),
+            "voucher_redeemed" => Some(&mut self.voucher_redeemed),
+            "voucher_invalid" => Some(&mut self.voucher_invalid),
+            "booking_confirmed" => Some(&mut self.booking_confirmed),
+            _ => None,
+        }
+    }
 }
 
 impl HiveConfig {
@@ -252,10 +839,18 @@ impl HiveConfig {
         let contents = std::fs::read_to_string(&config_path)
             .with_context(|| format!("Could not read {}", config_path.display()))?;
 
-        let config: HiveConfig = serde_yaml::from_str(&contents)
+        let mut config: HiveConfig = serde_yaml::from_str(&contents)
             .with_context(|| format!("Invalid YAML in {}", config_path.display()))?;
+        config.i18n = Arc::new(Translations::load(project_dir)?);
 
         config.validate()?;
+        for (i, item) in config.menu.iter().enumerate() {
+            if let Some(ref image) = item.image
+                && !project_dir.join(image).is_file()
+            {
+                anyhow::bail!("menu[{}].image: {} not found", i, project_dir.join(image).display());
+            }
+        }
         Ok(config)
     }
 
@@ -274,10 +869,119 @@ impl HiveConfig {
             if item.price < 0.0 {
                 anyhow::bail!("menu[{}].price cannot be negative", i);
             }
+            for (j, variant) in item.variants.iter().enumerate() {
+                if variant.name.is_empty() {
+                    anyhow::bail!("menu[{}].variants[{}].name cannot be empty", i, j);
+                }
+                if variant.price < 0.0 {
+                    anyhow::bail!("menu[{}].variants[{}].price cannot be negative", i, j);
+                }
+            }
+            for (j, addon) in item.addons.iter().enumerate() {
+                if addon.name.is_empty() {
+                    anyhow::bail!("menu[{}].addons[{}].name cannot be empty", i, j);
+                }
+                if addon.price < 0.0 {
+                    anyhow::bail!("menu[{}].addons[{}].price cannot be negative", i, j);
+                }
+            }
         }
         if self.dashboard.port == 0 {
             anyhow::bail!("dashboard.port must be > 0");
         }
+        if self.dashboard.session_hours == 0 {
+            anyhow::bail!("dashboard.session_hours must be > 0");
+        }
+        if self.i18n.language(&self.business.language).is_none() {
+            anyhow::bail!("business.language: unknown language '{}'", self.business.language);
+        }
+        for code in &self.business.languages {
+            if self.i18n.language(code).is_none() {
+                anyhow::bail!("business.languages: unknown language '{}'", code);
+            }
+        }
+        for lang in self.i18n.languages() {
+            let Some(locale) = self.i18n.locale(lang) else {
+                continue;
+            };
+            let mut templates = MessageTemplates::default();
+            for name in locale.messages.keys() {
+                if !LOCALE_MESSAGES.contains(&name.as_str()) && templates.customer_template_mut(name).is_none() {
+                    anyhow::bail!("locales/{}.yaml: unknown message '{}'", lang.code(), name);
+                }
+            }
+        }
+        if let Some(ref b2c) = self.payments.mpesa_b2c
+            && self.payments.mpesa.is_none()
+            && (b2c.consumer_key.is_none() || b2c.consumer_secret.is_none())
+        {
+            anyhow::bail!("payments.mpesa_b2c needs consumer_key and consumer_secret when there's no payments.mpesa section");
+        }
+        if let Some(ref paystack) = self.payments.paystack
+            && paystack.currency_code(&self.business.currency).is_none()
+        {
+            anyhow::bail!(
+                "payments.paystack.currency must be set to a currency code like NGN (business.currency is '{}')",
+                self.business.currency
+            );
+        }
+        self.validate_flows()?;
+        if let Some(ref bookings) = self.bookings {
+            bookings.validate()?;
+        }
+        Ok(())
+    }
+
+    fn validate_flows(&self) -> Result<()> {
+        let mut names = std::collections::HashSet::new();
+        for (i, flow) in self.flows.iter().enumerate() {
+            if flow.name.is_empty() {
+                anyhow::bail!("flows[{}].name cannot be empty", i);
+            }
+            if !names.insert(flow.name.as_str()) {
+                anyhow::bail!("flows[{}]: duplicate flow name '{}'", i, flow.name);
+            }
+            if flow.triggers.iter().all(|t| t.trim().is_empty()) {
+                anyhow::bail!("flow '{}' needs at least one trigger", flow.name);
+            }
+            if flow.steps.is_empty() {
+                anyhow::bail!("flow '{}' must have at least one step", flow.name);
+            }
+
+            let mut ids = std::collections::HashSet::new();
+            for step in &flow.steps {
+                if step.id.is_empty() || step.id == FLOW_END {
+                    anyhow::bail!("flow '{}': invalid step id '{}'", flow.name, step.id);
+                }
+                if !ids.insert(step.id.as_str()) {
+                    anyhow::bail!("flow '{}': duplicate step id '{}'", flow.name, step.id);
+                }
+                if step.input == FlowInput::Choice && step.options.is_empty() {
+                    anyhow::bail!(
+                        "flow '{}': choice step '{}' needs options",
+                        flow.name,
+                        step.id
+                    );
+                }
+            }
+
+            for step in &flow.steps {
+                let targets = step
+                    .next
+                    .iter()
+                    .chain(step.options.iter().filter_map(|o| o.next.as_ref()));
+                for target in targets {
+                    if target != FLOW_END && flow.step(target).is_none() {
+                        anyhow::bail!(
+                            "flow '{}': step '{}' points to unknown step '{}'",
+                            flow.name,
+                            step.id,
+                            target
+                        );
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
@@ -292,9 +996,87 @@ impl HiveConfig {
         })
     }
 
-    /// Get available menu items only.
-    pub fn available_menu(&self) -> Vec<&MenuItem> {
-        self.menu.iter().filter(|m| m.available).collect()
+    /// Find the flow started by a trigger message, if any.
+    pub fn flow_for_trigger(&self, text: &str) -> Option<&FlowConfig> {
+        let text = text.trim();
+        self.flows
+            .iter()
+            .find(|f| f.triggers.iter().any(|t| t.trim().eq_ignore_ascii_case(text)))
+    }
+
+    pub fn flow(&self, name: &str) -> Option<&FlowConfig> {
+        self.flows.iter().find(|f| f.name == name)
+    }
+
+    /// Language customers are served in until they pick one.
+    pub fn default_language(&self) -> Language {
+        self.i18n.language(&self.business.language).unwrap_or(Language::English)
+    }
+
+    /// Languages customers can pick from with the LANGUAGE command.
+    pub fn offered_languages(&self) -> Vec<Language> {
+        let offered: Vec<Language> = self
+            .business
+            .languages
+            .iter()
+            .filter_map(|code| self.i18n.language(code))
+            .collect();
+        if offered.is_empty() {
+            self.i18n.languages()
+        } else {
+            offered
+        }
+    }
+
+    /// The language `phone` picked, or the business default. Falls back to
+    /// the default if the store can't be read.
+    pub fn customer_language(&self, store: &Store, phone: &str) -> Language {
+        match store.customer_language(phone) {
+            Ok(code) => code
+                .and_then(|code| self.i18n.language(&code))
+                .unwrap_or_else(|| self.default_language()),
+            Err(e) => {
+                log::error!("Failed to load language for {}: {}", phone, e);
+                self.default_language()
+            }
+        }
+    }
+
+    /// The welcome message in `lang`: from `lang`'s locale file if it has
+    /// one, else a customized `business.welcome` as written, else the
+    /// translated default.
+    pub fn welcome(&self, lang: Language) -> String {
+        if let Some(welcome) = self.i18n.message(lang, "welcome") {
+            welcome.to_string()
+        } else if self.business.welcome == default_welcome() {
+            self.i18n.text(lang, TranslationKey::WelcomeMenu)
+        } else {
+            self.business.welcome.clone()
+        }
+    }
+
+    /// The "About Us" reply in `lang`, chosen like `welcome`.
+    pub fn about(&self, lang: Language) -> String {
+        match (self.i18n.message(lang, "about"), &self.business.about) {
+            (Some(about), _) => about.to_string(),
+            (None, Some(about)) => about.clone(),
+            (None, None) => self.i18n.text(lang, TranslationKey::AboutDefault),
+        }
+    }
+
+    /// The `messages:` templates for a customer who speaks `lang`.
+    pub fn messages_in(&self, lang: Language) -> MessageTemplates {
+        self.messages.localized(&self.i18n, lang)
+    }
+
+    /// Get the orderable menu from `store`, which holds the live menu
+    /// (seeded from `menu`). Falls back to the config menu if the store
+    /// can't be read.
+    pub fn available_menu(&self, store: &Store) -> Vec<MenuItem> {
+        store.available_menu().unwrap_or_else(|e| {
+            log::error!("Failed to load menu from store: {}", e);
+            self.menu.iter().filter(|m| m.available).cloned().collect()
+        })
     }
 }
 
@@ -319,5 +1101,114 @@ mod tests {
             radius_km: None,
         };
         assert_eq!(cfg.estimate_string(), "30-45 minutes");
+        assert_eq!(cfg.estimate_in(&i18n::built_in(), Language::Swahili), "dakika 30-45");
+    }
+
+    #[test]
+    fn test_localized_templates() {
+        let mut i18n = Translations::new();
+        let mut messages = MessageTemplates::default();
+        // English translations match the built-in templates
+        let english = messages.localized(&i18n, Language::English);
+        assert_eq!(
+            serde_json::to_string(&english).unwrap(),
+            serde_json::to_string(&messages).unwrap()
+        );
+
+        messages.order_delivered = "Imefika!".to_string();
+        let swahili = messages.localized(&i18n, Language::Swahili);
+        assert_eq!(swahili.order_preparing, "🍳 Agizo #{id} linaandaliwa.");
+        assert_eq!(swahili.order_delivered, "Imefika!");
+        // Admin messages stay as they are
+        assert_eq!(swahili.order_received_admin, messages.order_received_admin);
+
+        // A locale file's translation wins, even over a customized template
+        let mut locale = crate::i18n::Locale::default();
+        locale
+            .messages
+            .insert("order_delivered".to_string(), "🎉 Agizo #{id} limefika!".to_string());
+        i18n.add_locale(Language::Swahili, locale);
+        let swahili = messages.localized(&i18n, Language::Swahili);
+        assert_eq!(swahili.order_delivered, "🎉 Agizo #{id} limefika!");
+        assert_eq!(messages.localized(&i18n, Language::English).order_delivered, "Imefika!");
+    }
+
+    #[test]
+    fn test_locale_files_load_with_config() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("config.yaml"),
+            "business:\n  name: Test\n  language: zu\n  languages: [zu, en]\nmenu:\n  - { name: Kota, price: 35 }\n",
+        )
+        .unwrap();
+        // zu isn't a language Hive knows until it has a locale file
+        assert!(HiveConfig::load(dir.path()).is_err());
+
+        std::fs::create_dir(dir.path().join("locales")).unwrap();
+        std::fs::write(
+            dir.path().join("locales/zu.yaml"),
+            "name: isiZulu\nmessages:\n  welcome: Sawubona!\n",
+        )
+        .unwrap();
+        let config = HiveConfig::load(dir.path()).unwrap();
+        let zulu = config.default_language();
+        assert_eq!(zulu.code(), "zu");
+        assert_eq!(config.offered_languages(), vec![zulu, Language::English]);
+        assert_eq!(config.welcome(zulu), "Sawubona!");
+        assert_eq!(config.welcome(Language::English), config.business.welcome);
+
+        std::fs::write(
+            dir.path().join("locales/zu.yaml"),
+            "name: isiZulu\nmessages:\n  order_received_admin: Ioda!\n",
+        )
+        .unwrap();
+        let err = HiveConfig::load(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("unknown message 'order_received_admin'"));
+    }
+
+    #[test]
+    fn test_real_estate_template_flow() {
+        let config: HiveConfig =
+            serde_yaml::from_str(include_str!("../../templates/real-estate.yaml")).unwrap();
+        config.validate().unwrap();
+
+        let flow = config.flow_for_trigger("Schedule").unwrap();
+        assert_eq!(flow.name, "viewing");
+        assert_eq!(flow.steps[0].input, FlowInput::Choice);
+        assert_eq!(flow.following_step("name").unwrap().id, "occupants");
+    }
+
+    #[test]
+    fn test_food_delivery_template_options() {
+        let config: HiveConfig =
+            serde_yaml::from_str(include_str!("../../templates/food-delivery.yaml")).unwrap();
+        config.validate().unwrap();
+
+        let pizza = &config.menu[1];
+        assert_eq!(pizza.category(), "Mains");
+        assert!(pizza.has_options());
+        assert_eq!(pizza.price_label("$"), "from $12.00");
+        assert_eq!(config.menu[0].price_label("$"), "$12.00");
+    }
+
+    #[test]
+    fn test_menu_validation_rejects_negative_addon() {
+        let mut config: HiveConfig =
+            serde_yaml::from_str(include_str!("../../templates/food-delivery.yaml")).unwrap();
+        config.menu[1].addons.push(MenuAddon {
+            name: "Discount".to_string(),
+            price: -20.0,
+        });
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("menu[1].addons[") && err.contains("cannot be negative"), "{}", err);
+    }
+
+    #[test]
+    fn test_flow_validation_rejects_unknown_step() {
+        let mut config: HiveConfig =
+            serde_yaml::from_str(include_str!("../../templates/real-estate.yaml")).unwrap();
+        config.flows[0].steps[1].next = Some("nowhere".to_string());
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("unknown step 'nowhere'"), "{}", err);
     }
 }
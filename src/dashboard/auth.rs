@@ -0,0 +1,355 @@
+//! Dashboard logins, tokens and roles.
+//!
+//! Users sign in with a username and password (stored as an Argon2 hash)
+//! and get a session token; scripts use API keys instead. Both are sent as
+//! `Authorization: Bearer <token>` and only their SHA-256 is stored.
+//! Every API route requires a role — see `required_role`.
+//!
+//! Endpoints:
+//! - POST   /api/login          — exchange username/password for a session token
+//! - POST   /api/logout         — end the current session
+//! - GET    /api/me             — the signed-in user
+//! - GET    /api/users          — list users (owner)
+//! - POST   /api/users          — add a user (owner)
+//! - DELETE /api/users/:name    — remove a user and their tokens (owner)
+//! - GET    /api/api-keys       — list API keys (owner)
+//! - POST   /api/api-keys       — create an API key, shown once (owner)
+//! - DELETE /api/api-keys/:id   — revoke an API key (owner)
+
+use super::{ApiError, AppState};
+use crate::store::{TOKEN_API_KEY, TOKEN_SESSION, UserRecord, UserRole};
+use anyhow::Result;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use axum::{
+    Extension, Json,
+    extract::{MatchedPath, Path, Request, State},
+    http::{HeaderMap, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Shortest password accepted for a dashboard user.
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// Hash a password for storage.
+pub fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())
+        .map_err(|e| anyhow::anyhow!("Failed to generate salt: {}", e))?;
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("Failed to hash password: {}", e))?;
+    Ok(hash.to_string())
+}
+
+/// Check a password against a stored hash.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
+
+/// Check a new username/password pair before storing it.
+pub fn validate_credentials(username: &str, password: &str) -> Result<()> {
+    if username.is_empty() || username.chars().any(char::is_whitespace) {
+        anyhow::bail!("Username must be non-empty and contain no spaces");
+    }
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        anyhow::bail!("Password must be at least {} characters", MIN_PASSWORD_LEN);
+    }
+    Ok(())
+}
+
+/// A fresh random session token or API key.
+pub fn new_token() -> String {
+    format!("hive_{}", hex::encode(rand::random::<[u8; 32]>()))
+}
+
+/// What gets stored for a token.
+pub fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Least privileged role that may call `route` (the matched route pattern)
+/// with `method`.
+///
+/// Reading is open to every role; changing things needs staff; money,
+/// vouchers, config and user management need the owner.
+pub fn required_role(method: &Method, route: &str) -> UserRole {
+    match (method, route) {
+        (_, "/api/users" | "/api/users/{username}" | "/api/api-keys" | "/api/api-keys/{id}") => UserRole::Owner,
+        (&Method::POST, "/api/payments/{id}/refund" | "/api/vouchers" | "/api/admin/reload") => UserRole::Owner,
+        (&Method::POST, "/api/logout") => UserRole::Accountant,
+        (&Method::GET, _) => UserRole::Accountant,
+        _ => UserRole::Staff,
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+fn error(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(ApiError { error: message.into() })).into_response()
+}
+
+/// Middleware for protected routes: resolves the bearer token to a user,
+/// checks their role against the route, and makes the `UserRecord`
+/// available to handlers as an `Extension`.
+pub(super) async fn require_auth(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+    let user = match bearer_token(req.headers()).map(|t| state.store.user_for_token(&token_hash(t))) {
+        Some(Ok(Some(user))) => user,
+        Some(Err(e)) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        _ => {
+            if state.store.count_users().unwrap_or(0) == 0 {
+                return error(
+                    StatusCode::UNAUTHORIZED,
+                    "No dashboard users yet. Create one with: hive user add <path> <username> --role owner",
+                );
+            }
+            return error(StatusCode::UNAUTHORIZED, "Sign in required");
+        }
+    };
+
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+    let required = required_role(req.method(), &route);
+    if !user.role.allows(required) {
+        return error(
+            StatusCode::FORBIDDEN,
+            format!("This needs the {} role (you are {})", required.as_str(), user.role.as_str()),
+        );
+    }
+
+    req.extensions_mut().insert(user);
+    next.run(req).await
+}
+
+// ─── Handlers ────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub(super) struct LoginRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct CreateUserRequest {
+    username: String,
+    password: String,
+    role: UserRole,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct CreateApiKeyRequest {
+    #[serde(default)]
+    name: Option<String>,
+    /// Whose permissions the key carries (default: the caller)
+    #[serde(default)]
+    username: Option<String>,
+}
+
+/// Sign in and get a session token
+pub(super) async fn login(State(state): State<AppState>, Json(req): Json<LoginRequest>) -> impl IntoResponse {
+    let user = match state.store.user_credentials(req.username.trim()) {
+        Ok(Some((user, hash))) if verify_password(&req.password, &hash) => user,
+        Ok(_) => {
+            log::warn!("🔒 Failed dashboard login for '{}'", req.username);
+            return error(StatusCode::UNAUTHORIZED, "Invalid username or password");
+        }
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    let token = new_token();
+    let ttl = chrono::Duration::hours(state.config.get().dashboard.session_hours as i64);
+    match state.store.create_token(user.id, &token_hash(&token), TOKEN_SESSION, None, Some(ttl)) {
+        Ok(expires_at) => {
+            log::info!("🔓 {} signed in to the dashboard", user.username);
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "token": token, "expires_at": expires_at, "user": user })),
+            )
+                .into_response()
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// End the current session
+pub(super) async fn logout(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    let Some(token) = bearer_token(&headers) else {
+        return error(StatusCode::UNAUTHORIZED, "Sign in required");
+    };
+    match state.store.delete_token(&token_hash(token)) {
+        Ok(_) => (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// The signed-in user
+pub(super) async fn me(Extension(user): Extension<UserRecord>) -> impl IntoResponse {
+    (StatusCode::OK, Json(user))
+}
+
+/// List dashboard users
+pub(super) async fn list_users(State(state): State<AppState>) -> impl IntoResponse {
+    match state.store.list_users() {
+        Ok(users) => (StatusCode::OK, Json(users)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Add a dashboard user
+pub(super) async fn create_user(
+    State(state): State<AppState>,
+    Extension(actor): Extension<UserRecord>,
+    Json(req): Json<CreateUserRequest>,
+) -> impl IntoResponse {
+    let username = req.username.trim();
+    if let Err(e) = validate_credentials(username, &req.password) {
+        return error(StatusCode::BAD_REQUEST, e.to_string());
+    }
+    let hash = match hash_password(&req.password) {
+        Ok(hash) => hash,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    match state.store.create_user(username, &hash, req.role) {
+        Ok(user) => {
+            log::info!("👤 {} added dashboard user {} ({})", actor.username, user.username, user.role.as_str());
+            (StatusCode::CREATED, Json(user)).into_response()
+        }
+        Err(e) => error(StatusCode::CONFLICT, e.to_string()),
+    }
+}
+
+/// Remove a dashboard user
+pub(super) async fn delete_user(
+    State(state): State<AppState>,
+    Extension(actor): Extension<UserRecord>,
+    Path(username): Path<String>,
+) -> impl IntoResponse {
+    if username.eq_ignore_ascii_case(&actor.username) {
+        return error(StatusCode::BAD_REQUEST, "You can't remove your own account");
+    }
+    match state.store.delete_user(&username) {
+        Ok(true) => {
+            log::info!("👤 {} removed dashboard user {}", actor.username, username);
+            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+        }
+        Ok(false) => error(StatusCode::NOT_FOUND, format!("No user named '{}'", username)),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// List API keys
+pub(super) async fn list_api_keys(State(state): State<AppState>) -> impl IntoResponse {
+    match state.store.list_api_keys() {
+        Ok(keys) => (StatusCode::OK, Json(keys)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Create an API key; the key itself is only returned here
+pub(super) async fn create_api_key(
+    State(state): State<AppState>,
+    Extension(actor): Extension<UserRecord>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> impl IntoResponse {
+    let owner = match req.username.as_deref() {
+        None => actor.clone(),
+        Some(username) => match state.store.user_credentials(username) {
+            Ok(Some((user, _))) => user,
+            Ok(None) => return error(StatusCode::NOT_FOUND, format!("No user named '{}'", username)),
+            Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        },
+    };
+
+    let key = new_token();
+    let name = req.name.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    match state.store.create_token(owner.id, &token_hash(&key), TOKEN_API_KEY, name, None) {
+        Ok(_) => {
+            log::info!("🔑 {} created an API key for {}", actor.username, owner.username);
+            (
+                StatusCode::CREATED,
+                Json(serde_json::json!({ "key": key, "name": name, "username": owner.username, "role": owner.role })),
+            )
+                .into_response()
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Revoke an API key
+pub(super) async fn revoke_api_key(State(state): State<AppState>, Path(id): Path<i64>) -> impl IntoResponse {
+    match state.store.revoke_api_key(id) {
+        Ok(true) => (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response(),
+        Ok(false) => error(StatusCode::NOT_FOUND, format!("API key {} not found", id)),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_hashing() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("wrong horse", &hash));
+        assert!(!verify_password("correct horse", "not a hash"));
+        // Salted, so the same password hashes differently
+        assert_ne!(hash, hash_password("correct horse").unwrap());
+
+        assert!(validate_credentials("amina", "short").is_err());
+        assert!(validate_credentials("two words", "long enough").is_err());
+        assert!(validate_credentials("amina", "long enough").is_ok());
+    }
+
+    #[test]
+    fn test_tokens() {
+        let token = new_token();
+        assert!(token.starts_with("hive_"));
+        assert_ne!(token, new_token());
+        assert_eq!(token_hash(&token), token_hash(&token));
+        assert_ne!(token_hash(&token), token);
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(header::AUTHORIZATION, "Bearer abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("abc"));
+        headers.insert(header::AUTHORIZATION, "Basic abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+    }
+
+    #[test]
+    fn test_route_roles() {
+        use UserRole::*;
+        assert_eq!(required_role(&Method::GET, "/api/orders"), Accountant);
+        assert_eq!(required_role(&Method::GET, "/api/refunds"), Accountant);
+        assert_eq!(required_role(&Method::POST, "/api/logout"), Accountant);
+        assert_eq!(required_role(&Method::PUT, "/api/menu/{id}"), Staff);
+        assert_eq!(required_role(&Method::POST, "/api/stock/{name}"), Staff);
+        assert_eq!(required_role(&Method::POST, "/api/payments/{id}/refund"), Owner);
+        assert_eq!(required_role(&Method::POST, "/api/vouchers"), Owner);
+        assert_eq!(required_role(&Method::GET, "/api/users"), Owner);
+        assert_eq!(required_role(&Method::DELETE, "/api/api-keys/{id}"), Owner);
+
+        assert!(Owner.allows(Staff));
+        assert!(Staff.allows(Accountant));
+        assert!(!Accountant.allows(Staff));
+        assert!(!Staff.allows(Owner));
+    }
+}
@@ -1,26 +1,57 @@
 //! Admin web dashboard.
 //!
 //! Simple Axum-based JSON API for managing the bot from a browser.
+//! Everything except the page itself, health and payment callbacks needs a
+//! signed-in user with a suitable role (see `auth`).
 //! Endpoints:
 //! - GET  /api/orders       — list orders (optional ?status= filter)
 //! - GET  /api/orders/:id   — get single order
-//! - GET  /api/menu         — current menu from config
-//! - POST /api/menu         — update a menu item (hot-reload not supported yet)
+//! - POST /api/orders/:id/status — move an order to a new status and notify the customer
+//! - GET  /api/orders/:id/events — the order's status history (who, when, why)
+//! - GET  /api/menu         — all menu items, including hidden ones
+//! - POST /api/menu         — add a menu item
+//! - PUT  /api/menu/:id     — change a menu item (takes effect immediately)
+//! - DELETE /api/menu/:id   — remove a menu item
 //! - GET  /api/vouchers     — list all vouchers
 //! - POST /api/vouchers     — create a new voucher
+//! - GET  /api/customers    — customers, most recently seen first (optional ?search=, ?tag=, ?limit=)
+//! - GET  /api/customers/:phone — a customer with their orders, payments and vouchers
+//! - PUT  /api/customers/:phone — change a customer's name, language, tags, opt-in or notes
 //! - GET  /api/stats        — aggregate statistics
+//! - GET  /api/bookings     — bookings for a day (optional ?date=YYYY-MM-DD, default today)
+//! - GET  /api/bookings/calendar — a day's opening hours and slot occupancy
+//! - POST /api/bookings/:id/cancel — cancel a booking and notify the customer
+//! - GET  /api/stock        — stock levels for tracked items
+//! - POST /api/admin/reload — re-read config.yaml and apply it without a restart
+//! - POST /api/stock/:name  — set (`quantity`) or adjust (`adjust`) an item's stock
+//! - POST /api/payments/:id/refund — refund a completed payment via B2C
+//! - POST /api/paystack/webhook — PayStack payment results (signed, no login)
+//! - POST /api/login, /api/logout, GET /api/me, /api/users, /api/api-keys — see `auth`
+
+pub mod auth;
 
 use crate::config::HiveConfig;
-use crate::payments::{B2CClient, MpesaCallback, process_callback};
-use crate::store::{OrderStatus, Store};
+use crate::config::live::LiveConfig;
+use crate::payments::b2c::B2CCallback;
+use crate::payments::paystack::{self, PaystackEvent};
+use crate::payments::refunds::{RefundProvider, process_b2c_callback};
+use crate::payments::{MpesaCallback, process_callback};
+use crate::config::{MenuAddon, MenuItem, MenuVariant};
+use crate::store::{
+    CustomerUpdate, InvalidTransition, MenuItemUpdate, OrderStatus, RefundInProgress, Store, UserRecord,
+};
+use crate::transport::MessagingTransport;
 use anyhow::Result;
 use axum::{
-    Json, Router,
+    Extension, Json, Router,
+    body::Bytes,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    middleware,
     response::{Html, IntoResponse},
-    routing::{get, post},
+    routing::{delete, get, post, put},
 };
+use chrono::{Datelike, NaiveDate};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tower_http::cors::CorsLayer;
@@ -28,46 +59,62 @@ use tower_http::cors::CorsLayer;
 /// Shared state for Axum handlers.
 #[derive(Clone)]
 struct AppState {
-    config: Arc<HiveConfig>,
+    config: LiveConfig,
     store: Store,
-    wa_client: Arc<tokio::sync::RwLock<Option<Arc<whatsapp_rust::client::Client>>>>,
-    b2c_client: Option<Arc<B2CClient>>,
+    transport: Option<Arc<dyn MessagingTransport>>,
+    refund_provider: Option<Arc<dyn RefundProvider>>,
 }
 
 /// Embedded dashboard HTML (compiled into binary).
 const DASHBOARD_HTML: &str = include_str!("../../static/dashboard.html");
 
 /// Start the dashboard web server.
+///
+/// `transport` is used to notify customers/admins from webhooks; pass `None`
+/// when running the dashboard without a bot. Refunds go out through
+/// `refund_provider`, the same one the bot uses.
 pub async fn run_dashboard(
-    config: HiveConfig,
+    live_config: LiveConfig,
     store: Store,
-    wa_client: Arc<tokio::sync::RwLock<Option<Arc<whatsapp_rust::client::Client>>>>,
+    transport: Option<Arc<dyn MessagingTransport>>,
+    refund_provider: Option<Arc<dyn RefundProvider>>,
 ) -> Result<()> {
-    // Initialize B2C client if configured
-    let b2c_client = if let Some(ref mpesa_cfg) = config.payments.mpesa {
-        // B2C requires additional config beyond STK Push
-        // For now, skip B2C initialization (requires separate credentials)
-        // TODO: Add b2c config to payments section
-        None
-    } else {
-        None
-    };
+    let config = live_config.get();
 
     let state = AppState {
-        config: Arc::new(config.clone()),
+        config: live_config,
         store,
-        wa_client,
-        b2c_client,
+        transport,
+        refund_provider,
     };
 
-    let app = Router::new()
-        .route("/", get(serve_dashboard))
+    if state.store.count_users()? == 0 {
+        log::warn!("🔒 No dashboard users yet — the API is locked until you run: hive user add <path> <username> --role owner");
+    }
+
+    let protected = Router::new()
+        .route("/api/logout", post(auth::logout))
+        .route("/api/me", get(auth::me))
+        .route("/api/users", get(auth::list_users).post(auth::create_user))
+        .route("/api/users/{username}", delete(auth::delete_user))
+        .route("/api/api-keys", get(auth::list_api_keys).post(auth::create_api_key))
+        .route("/api/api-keys/{id}", delete(auth::revoke_api_key))
         .route("/api/orders", get(list_orders))
         .route("/api/orders/{id}", get(get_order))
-        .route("/api/menu", get(get_menu))
+        .route("/api/orders/{id}/status", post(update_order_status))
+        .route("/api/orders/{id}/events", get(order_events))
+        .route("/api/menu", get(get_menu).post(create_menu_item))
+        .route("/api/menu/{id}", put(update_menu_item).delete(delete_menu_item))
         .route("/api/vouchers", get(list_vouchers).post(create_voucher))
+        .route("/api/customers", get(list_customers))
+        .route("/api/customers/{phone}", get(get_customer).put(update_customer))
         .route("/api/stats", get(get_stats))
-        .route("/api/health", get(health_check))
+        .route("/api/bookings", get(list_bookings))
+        .route("/api/bookings/calendar", get(booking_calendar))
+        .route("/api/bookings/{id}/cancel", post(cancel_booking))
+        .route("/api/stock", get(list_stock))
+        .route("/api/stock/{name}", post(update_stock))
+        .route("/api/admin/reload", post(reload_config))
         .route("/api/payments", get(list_payments))
         .route("/api/payments/{id}", get(get_payment))
         .route("/api/payments/{id}/refund", post(refund_payment))
@@ -76,11 +123,41 @@ pub async fn run_dashboard(
         .route("/api/export/ledger", get(export_ledger))
         .route("/api/analytics/payments", get(payment_analytics))
         .route("/api/reconciliation/report", get(reconciliation_report))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_auth));
+
+    let mut app = Router::new()
+        .route("/", get(serve_dashboard))
+        .route("/api/health", get(health_check))
+        .route("/api/login", post(auth::login))
         .route("/api/mpesa/callback", post(mpesa_callback))
         .route("/api/mpesa/b2c/callback", post(mpesa_b2c_callback))
-        .layer(CorsLayer::permissive())
+        .route("/api/mpesa/b2c/timeout", post(mpesa_b2c_callback))
+        .route("/api/paystack/webhook", post(paystack_webhook))
+        .merge(protected)
         .with_state(state);
 
+    // The dashboard page is same-origin; other browser origins must be listed
+    let origins: Vec<HeaderValue> = config
+        .dashboard
+        .allowed_origins
+        .iter()
+        .filter_map(|origin| match origin.parse() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                log::warn!("Ignoring invalid dashboard.allowed_origins entry: {}", origin);
+                None
+            }
+        })
+        .collect();
+    if !origins.is_empty() {
+        app = app.layer(
+            CorsLayer::new()
+                .allow_origin(origins)
+                .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+                .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE]),
+        );
+    }
+
     let addr = format!("0.0.0.0:{}", config.dashboard.port);
     log::info!("🌐 Dashboard running at http://localhost:{}", config.dashboard.port);
 
@@ -97,6 +174,37 @@ struct OrdersQuery {
     status: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct UpdateOrderStatusRequest {
+    status: OrderStatus,
+    /// Reason or comment kept in the order's history
+    #[serde(default)]
+    note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CustomersQuery {
+    /// Part of a phone number or name
+    search: Option<String>,
+    tag: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BookingsQuery {
+    date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateStockRequest {
+    /// New absolute stock level
+    #[serde(default)]
+    quantity: Option<i64>,
+    /// Amount to add (or remove, if negative)
+    #[serde(default)]
+    adjust: Option<i64>,
+}
+
 #[derive(Debug, Deserialize)]
 struct CreateVoucherRequest {
     amount: f64,
@@ -123,7 +231,19 @@ async fn list_orders(
     State(state): State<AppState>,
     Query(params): Query<OrdersQuery>,
 ) -> impl IntoResponse {
-    let status_filter = params.status.as_deref().map(OrderStatus::from_str);
+    let status_filter = match params.status.as_deref().map(|s| (s, OrderStatus::parse(s))) {
+        None => None,
+        Some((_, Some(status))) => Some(status),
+        Some((raw, None)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    error: format!("Unknown order status '{}'", raw),
+                }),
+            )
+                .into_response();
+        }
+    };
 
     match state.store.list_orders(status_filter.as_ref()) {
         Ok(orders) => (StatusCode::OK, Json(serde_json::to_value(orders).unwrap())).into_response(),
@@ -160,8 +280,252 @@ async fn get_order(
     }
 }
 
+/// Move an order along (or cancel it) and send the customer an update
+async fn update_order_status(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserRecord>,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateOrderStatusRequest>,
+) -> impl IntoResponse {
+    let actor = format!("dashboard:{}", user.username);
+    let note = req.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    let order = match state.store.transition_order(id, &req.status, &actor, note) {
+        Ok(Some(order)) => order,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ApiError {
+                    error: format!("Order {} not found", id),
+                }),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            let status = if e.downcast_ref::<InvalidTransition>().is_some() {
+                StatusCode::CONFLICT
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            return (status, Json(ApiError { error: e.to_string() })).into_response();
+        }
+    };
+    log::info!("📦 {} moved order #{} to {}", user.username, id, order.status.as_str());
+
+    let config = state.config.get();
+    let mut notified = false;
+    let lang = config.customer_language(&state.store, &order.customer_phone);
+    if let Some(ref transport) = state.transport
+        && let Some(msg) = crate::handlers::order::order_status_message(&config, lang, &order)
+    {
+        match transport.send_to_phone(&order.customer_phone, &msg).await {
+            Ok(()) => notified = true,
+            Err(e) => log::error!("Failed to notify customer {}: {}", order.customer_phone, e),
+        }
+    }
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "order": order, "notified": notified })),
+    )
+        .into_response()
+}
+
+/// An order's status history, oldest first
+async fn order_events(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match state.store.get_order(id) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ApiError {
+                    error: format!("Order {} not found", id),
+                }),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    error: e.to_string(),
+                }),
+            )
+                .into_response();
+        }
+    }
+
+    match state.store.order_events(id) {
+        Ok(events) => (StatusCode::OK, Json(events)).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
 async fn get_menu(State(state): State<AppState>) -> impl IntoResponse {
-    Json(serde_json::to_value(&state.config.menu).unwrap())
+    match state.store.list_menu_items() {
+        Ok(items) => (StatusCode::OK, Json(serde_json::to_value(items).unwrap())).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// Check a new name/price, returning the error to report if invalid.
+fn validate_menu_fields(
+    store: &Store,
+    id: Option<i64>,
+    name: Option<&str>,
+    price: Option<f64>,
+    variants: Option<&[MenuVariant]>,
+    addons: Option<&[MenuAddon]>,
+) -> Result<(), (StatusCode, String)> {
+    if let Some(price) = price
+        && price < 0.0
+    {
+        return Err((StatusCode::BAD_REQUEST, "Price cannot be negative".to_string()));
+    }
+    if variants.unwrap_or_default().iter().any(|v| v.name.trim().is_empty() || v.price < 0.0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Variants need a name and a non-negative price".to_string(),
+        ));
+    }
+    if addons.unwrap_or_default().iter().any(|a| a.name.trim().is_empty() || a.price < 0.0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Add-ons need a name and a non-negative price".to_string(),
+        ));
+    }
+    if let Some(name) = name {
+        if name.trim().is_empty() {
+            return Err((StatusCode::BAD_REQUEST, "Name cannot be empty".to_string()));
+        }
+        match store.find_menu_item(name) {
+            Ok(Some(existing)) if Some(existing.id) != id => {
+                return Err((
+                    StatusCode::CONFLICT,
+                    format!("A menu item named '{}' already exists", existing.name),
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+        }
+    }
+    Ok(())
+}
+
+async fn create_menu_item(
+    State(state): State<AppState>,
+    Json(item): Json<MenuItem>,
+) -> impl IntoResponse {
+    if let Err((status, error)) = validate_menu_fields(
+        &state.store,
+        None,
+        Some(&item.name),
+        Some(item.price),
+        Some(&item.variants),
+        Some(&item.addons),
+    ) {
+        return (status, Json(ApiError { error })).into_response();
+    }
+
+    match state.store.create_menu_item(&item) {
+        Ok(id) => {
+            log::info!("📋 Added menu item {} ({})", id, item.name);
+            let item = state.store.get_menu_item(id).ok().flatten();
+            (StatusCode::CREATED, Json(serde_json::to_value(item).unwrap())).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+async fn update_menu_item(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(update): Json<MenuItemUpdate>,
+) -> impl IntoResponse {
+    if let Err((status, error)) =
+        validate_menu_fields(
+            &state.store,
+            Some(id),
+            update.name.as_deref(),
+            update.price,
+            update.variants.as_deref(),
+            update.addons.as_deref(),
+        )
+    {
+        return (status, Json(ApiError { error })).into_response();
+    }
+
+    match state.store.update_menu_item(id, &update) {
+        Ok(true) => {
+            log::info!("📋 Updated menu item {}", id);
+            let item = state.store.get_menu_item(id).ok().flatten();
+            (StatusCode::OK, Json(serde_json::to_value(item).unwrap())).into_response()
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiError {
+                error: format!("Menu item {} not found", id),
+            }),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+async fn delete_menu_item(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match state.store.delete_menu_item(id) {
+        Ok(true) => {
+            log::info!("📋 Deleted menu item {}", id);
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "id": id, "deleted": true })),
+            )
+                .into_response()
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiError {
+                error: format!("Menu item {} not found", id),
+            }),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
 }
 
 async fn list_vouchers(State(state): State<AppState>) -> impl IntoResponse {
@@ -214,6 +578,103 @@ async fn create_voucher(
     }
 }
 
+async fn list_customers(
+    State(state): State<AppState>,
+    Query(query): Query<CustomersQuery>,
+) -> impl IntoResponse {
+    let search = query.search.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    let tag = query.tag.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    let limit = query.limit.unwrap_or(100).min(1000);
+    match state.store.list_customers(search, tag, limit) {
+        Ok(customers) => (StatusCode::OK, Json(serde_json::to_value(customers).unwrap())).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// A customer with their orders, payments and redeemed vouchers
+async fn get_customer(
+    State(state): State<AppState>,
+    Path(phone): Path<String>,
+) -> impl IntoResponse {
+    let store = &state.store;
+    let detail = store.get_customer(&phone).and_then(|customer| {
+        let Some(customer) = customer else {
+            return Ok(None);
+        };
+        Ok(Some(serde_json::json!({
+            "customer": customer,
+            "orders": store.get_customer_orders(&phone, 100)?,
+            "payments": store.get_customer_payments(&phone)?,
+            "vouchers": store.get_customer_vouchers(&phone)?,
+        })))
+    });
+    match detail {
+        Ok(Some(detail)) => (StatusCode::OK, Json(detail)).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiError {
+                error: format!("Customer {} not found", phone),
+            }),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// Edit a customer's name, language, tags, marketing opt-in or notes
+async fn update_customer(
+    State(state): State<AppState>,
+    Path(phone): Path<String>,
+    Json(update): Json<CustomerUpdate>,
+) -> impl IntoResponse {
+    // An empty language clears it; anything else has to be one we speak
+    if let Some(ref code) = update.language
+        && !code.is_empty()
+        && state.config.get().i18n.language(code).is_none()
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError {
+                error: format!("Unknown language '{}'", code),
+            }),
+        )
+            .into_response();
+    }
+
+    match state.store.update_customer(&phone, &update) {
+        Ok(true) => {
+            let customer = state.store.get_customer(&phone).ok().flatten();
+            (StatusCode::OK, Json(serde_json::to_value(customer).unwrap())).into_response()
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiError {
+                error: format!("Customer {} not found", phone),
+            }),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
 async fn get_stats(State(state): State<AppState>) -> impl IntoResponse {
     match state.store.get_stats() {
         Ok(stats) => (StatusCode::OK, Json(serde_json::to_value(stats).unwrap())).into_response(),
@@ -227,6 +688,297 @@ async fn get_stats(State(state): State<AppState>) -> impl IntoResponse {
     }
 }
 
+/// Resolve `?date=` (default: today at the business) for booking endpoints.
+fn booking_date<'a>(
+    config: &'a HiveConfig,
+    params: &BookingsQuery,
+) -> Result<(&'a crate::config::BookingConfig, NaiveDate), (StatusCode, Json<ApiError>)> {
+    let Some(ref cfg) = config.bookings else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiError {
+                error: "Bookings are not enabled".to_string(),
+            }),
+        ));
+    };
+    let date = match params.date.as_deref() {
+        Some(d) => NaiveDate::parse_from_str(d, crate::bookings::DATE_FORMAT).map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    error: format!("Invalid date '{}', expected YYYY-MM-DD", d),
+                }),
+            )
+        })?,
+        None => cfg.now().date(),
+    };
+    Ok((cfg, date))
+}
+
+async fn list_bookings(
+    State(state): State<AppState>,
+    Query(params): Query<BookingsQuery>,
+) -> impl IntoResponse {
+    let config = state.config.get();
+    let date = match booking_date(&config, &params) {
+        Ok((_, date)) => date,
+        Err(e) => return e.into_response(),
+    };
+
+    match state.store.list_bookings_on(date) {
+        Ok(bookings) => (StatusCode::OK, Json(serde_json::to_value(bookings).unwrap())).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// A day's calendar: opening hours, how many places are taken in each slot,
+/// and the bookings themselves.
+async fn booking_calendar(
+    State(state): State<AppState>,
+    Query(params): Query<BookingsQuery>,
+) -> impl IntoResponse {
+    let config = state.config.get();
+    let (cfg, date) = match booking_date(&config, &params) {
+        Ok(resolved) => resolved,
+        Err(e) => return e.into_response(),
+    };
+
+    let bookings = match state.store.list_bookings_on(date) {
+        Ok(bookings) => bookings,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    error: e.to_string(),
+                }),
+            )
+                .into_response();
+        }
+    };
+
+    let hours = cfg.hours.for_day(date.weekday());
+    let mut slots = Vec::new();
+    if let Some((open, close)) = hours {
+        let step = chrono::Duration::minutes(cfg.slot_minutes as i64);
+        let mut start = date.and_time(open);
+        while start < date.and_time(close) {
+            let booked = crate::bookings::max_concurrent(&bookings, start, start + step);
+            slots.push(serde_json::json!({
+                "time": start.format("%H:%M").to_string(),
+                "booked": booked,
+                "available": (cfg.capacity as usize).saturating_sub(booked),
+            }));
+            start += step;
+        }
+    }
+
+    Json(serde_json::json!({
+        "date": date.format(crate::bookings::DATE_FORMAT).to_string(),
+        "open": hours.map(|(open, _)| open.format("%H:%M").to_string()),
+        "close": hours.map(|(_, close)| close.format("%H:%M").to_string()),
+        "capacity": cfg.capacity,
+        "slots": slots,
+        "bookings": bookings,
+    }))
+    .into_response()
+}
+
+async fn cancel_booking(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let booking = match state.store.get_booking(id) {
+        Ok(Some(booking)) => booking,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ApiError {
+                    error: format!("Booking {} not found", id),
+                }),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    error: e.to_string(),
+                }),
+            )
+                .into_response();
+        }
+    };
+
+    match state.store.cancel_booking(id) {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(ApiError {
+                    error: format!("Booking {} is already cancelled", id),
+                }),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    error: e.to_string(),
+                }),
+            )
+                .into_response();
+        }
+    }
+
+    // Let the customer know
+    if let Some(ref transport) = state.transport {
+        let msg = format!(
+            "❌ Your booking #{} ({}, {}) has been cancelled by {}.\nReply 1 to book another time.",
+            booking.id,
+            booking.service,
+            booking.start_at,
+            state.config.get().business.name
+        );
+        if let Err(e) = transport.send_to_phone(&booking.customer_phone, &msg).await {
+            log::error!("Failed to notify customer {}: {}", booking.customer_phone, e);
+        }
+    }
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "id": id, "status": "cancelled" })),
+    )
+        .into_response()
+}
+
+async fn list_stock(State(state): State<AppState>) -> impl IntoResponse {
+    let stock = match state.store.list_stock() {
+        Ok(stock) => stock,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    error: e.to_string(),
+                }),
+            )
+                .into_response();
+        }
+    };
+
+    let threshold = state.config.get().inventory.low_stock_threshold as i64;
+    let items: Vec<_> = stock
+        .iter()
+        .map(|s| {
+            serde_json::json!({
+                "item_name": s.item_name,
+                "quantity": s.quantity,
+                "low": s.quantity <= threshold,
+                "sold_out": s.quantity <= 0,
+                "updated_at": s.updated_at,
+            })
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "low_stock_threshold": threshold,
+            "items": items,
+        })),
+    )
+        .into_response()
+}
+
+async fn update_stock(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(req): Json<UpdateStockRequest>,
+) -> impl IntoResponse {
+    let name = match state.store.find_menu_item(&name) {
+        Ok(Some(item)) => item.name,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ApiError {
+                    error: format!("Menu item '{}' not found", name),
+                }),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError {
+                    error: e.to_string(),
+                }),
+            )
+                .into_response();
+        }
+    };
+
+    let result = match (req.quantity, req.adjust) {
+        (Some(quantity), None) => state.store.set_stock(&name, quantity).map(|_| Some(quantity.max(0))),
+        (None, Some(delta)) => state.store.adjust_stock(&name, delta),
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError {
+                    error: "Provide exactly one of 'quantity' or 'adjust'".to_string(),
+                }),
+            )
+                .into_response();
+        }
+    };
+
+    match result {
+        Ok(Some(quantity)) => {
+            log::info!("📦 Stock for {} set to {}", name, quantity);
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "item_name": name, "quantity": quantity })),
+            )
+                .into_response()
+        }
+        Ok(None) => (
+            StatusCode::CONFLICT,
+            Json(ApiError {
+                error: format!("'{}' isn't stock-tracked; set a quantity first", name),
+            }),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                error: e.to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+async fn reload_config(State(state): State<AppState>) -> impl IntoResponse {
+    match state.config.reload(&state.store) {
+        Ok(summary) => {
+            log::info!("🔄 Config reloaded from dashboard — changed: {:?}", summary.changed);
+            (StatusCode::OK, Json(serde_json::to_value(summary).unwrap())).into_response()
+        }
+        Err(e) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ApiError {
+                error: format!("{:#}", e),
+            }),
+        )
+            .into_response(),
+    }
+}
+
 /// M-Pesa webhook handler for payment callbacks
 async fn mpesa_callback(
     State(state): State<AppState>,
@@ -234,13 +986,8 @@ async fn mpesa_callback(
 ) -> impl IntoResponse {
     log::info!("📥 M-Pesa callback received");
     
-    // Get WhatsApp client (may be None if bot not connected yet)
-    let wa_client = {
-        let client_lock = state.wa_client.read().await;
-        client_lock.clone()
-    };
-    
-    match process_callback(callback, &state.store, &state.config, wa_client).await {
+    let refunds = state.refund_provider.as_deref();
+    match process_callback(callback, &state.store, &state.config.get(), state.transport.clone(), refunds).await {
         Ok(result) => {
             log::info!("✅ {}", result.message);
             (StatusCode::OK, Json(serde_json::json!({
@@ -258,6 +1005,41 @@ async fn mpesa_callback(
     }
 }
 
+/// PayStack webhook (payment results), signed with the secret key
+async fn paystack_webhook(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> impl IntoResponse {
+    let config = state.config.get();
+    let Some(ref paystack_cfg) = config.payments.paystack else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let signature = headers
+        .get(paystack::SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !paystack::verify_signature(&paystack_cfg.secret_key, &body, signature) {
+        log::warn!("⚠️ PayStack webhook with a bad signature rejected");
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let event: PaystackEvent = match serde_json::from_slice(&body) {
+        Ok(event) => event,
+        Err(e) => {
+            log::warn!("⚠️ Unreadable PayStack webhook: {}", e);
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+
+    log::info!("📥 PayStack webhook received: {}", event.event);
+    let refunds = state.refund_provider.as_deref();
+    match paystack::process_webhook(event, &state.store, &config, state.transport.clone(), refunds).await {
+        Ok(Some(result)) => log::info!("✅ {}", result.message),
+        Ok(None) => {}
+        Err(e) => log::error!("❌ PayStack webhook processing failed: {:#}", e),
+    }
+
+    // Anything but a 200 and PayStack sends it again
+    StatusCode::OK.into_response()
+}
+
 /// List all payments with optional filtering
 async fn list_payments(State(state): State<AppState>) -> impl IntoResponse {
     // For now, get all payments by querying each order
@@ -309,21 +1091,9 @@ async fn get_payment(
 /// Refund a completed payment
 async fn refund_payment(
     State(state): State<AppState>,
+    Extension(user): Extension<UserRecord>,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
-    // Check if B2C is configured
-    let b2c = match state.b2c_client.as_ref() {
-        Some(client) => client,
-        None => {
-            return (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(ApiError {
-                    error: "M-Pesa B2C (refunds) not configured. Contact admin.".to_string(),
-                }),
-            ).into_response();
-        }
-    };
-
     // Get payment
     let payment = match state.store.get_payment(&id) {
         Ok(Some(p)) => p,
@@ -349,60 +1119,45 @@ async fn refund_payment(
         })).into_response();
     }
 
-    // Create refund record
-    let refund_id = format!("REF-{}-{}", payment.order_id, chrono::Utc::now().timestamp());
-    if let Err(e) = state.store.create_refund(
-        &refund_id,
-        &payment.id,
-        payment.order_id,
-        payment.amount,
-        &payment.currency,
-        &payment.phone,
-        Some("Admin refund via dashboard"),
-        Some("dashboard"), // TODO: Get actual admin ID from auth
-    ) {
-        log::error!("Failed to create refund record: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiError {
-                error: format!("Failed to create refund record: {}", e),
-            }),
-        ).into_response();
-    }
-
-    // Initiate B2C refund
-    match b2c.refund_payment(payment.amount, &payment.phone, payment.order_id).await {
-        Ok(conversation_id) => {
-            // Update refund status to processing
-            if let Err(e) = state.store.update_refund_status(&refund_id, "processing", Some(&conversation_id)) {
-                log::error!("Failed to update refund status: {}", e);
-            }
-
-            log::info!("💸 Refund {} initiated for payment {}: ConversationID={}", refund_id, payment.id, conversation_id);
+    // Payments the provider can't refund (PayStack, or M-Pesa without B2C)
+    // are recorded as pending for the business to pay out by hand
+    match crate::payments::refunds::refund_payment(
+        &state.store,
+        state.refund_provider.as_deref(),
+        &payment,
+        "Admin refund via dashboard",
+        Some(&user.username),
+    )
+    .await
+    {
+        Ok(refund) => {
+            log::info!("💸 Refund {} ({}) requested by {}", refund.id, refund.status.as_str(), user.username);
+            let amount = format!("{}{:.2}", state.config.get().business.currency, payment.amount);
+            let message = match refund.status {
+                crate::store::RefundStatus::Pending => format!("Refund of {} to {} recorded to pay out by hand", amount, payment.phone),
+                _ => format!("Refund of {} initiated to {}", amount, payment.phone),
+            };
             (StatusCode::OK, Json(serde_json::json!({
                 "success": true,
-                "refund_id": refund_id,
-                "conversation_id": conversation_id,
-                "message": format!("Refund of {}{} initiated to {}", 
-                                  state.config.business.currency, 
-                                  payment.amount, 
-                                  payment.phone)
+                "refund_id": refund.id,
+                "status": refund.status.as_str(),
+                "conversation_id": refund.conversation_id,
+                "message": message,
             }))).into_response()
         }
-        Err(e) => {
-            // Update refund status to failed
-            if let Err(update_err) = state.store.update_refund_status(&refund_id, "failed", None) {
-                log::error!("Failed to update refund status: {}", update_err);
-            }
-
-            log::error!("❌ Refund {} failed for payment {}: {}", refund_id, payment.id, e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiError {
-                    error: format!("Refund failed: {}", e),
-                }),
-            ).into_response()
-        }
+        // Don't pay the same payment back twice
+        Err(e) if e.downcast_ref::<RefundInProgress>().is_some() => (
+            StatusCode::CONFLICT,
+            Json(ApiError {
+                error: "This payment has already been refunded".to_string(),
+            }),
+        ).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError {
+                error: format!("{:#}", e),
+            }),
+        ).into_response(),
     }
 }
 
@@ -440,50 +1195,19 @@ async fn get_refund(
     }
 }
 
-/// M-Pesa B2C callback handler (refund confirmations)
+/// M-Pesa B2C callback handler (refund results and queue timeouts)
 async fn mpesa_b2c_callback(
     State(state): State<AppState>,
-    Json(callback): Json<serde_json::Value>,
+    Json(callback): Json<B2CCallback>,
 ) -> impl IntoResponse {
     log::info!("📥 M-Pesa B2C callback received: {:?}", callback);
-    
-    // Extract conversation ID and result
-    let conversation_id = callback["Result"]["ConversationID"].as_str();
-    let result_code = callback["Result"]["ResultCode"].as_i64();
-    
-    if let (Some(conv_id), Some(code)) = (conversation_id, result_code) {
-        // Find refund by conversation ID
-        let refunds = match state.store.list_refunds(None) {
-            Ok(r) => r,
-            Err(e) => {
-                log::error!("Failed to list refunds: {}", e);
-                return (StatusCode::OK, Json(serde_json::json!({
-                    "ResultCode": 1,
-                    "ResultDesc": "Internal error"
-                }))).into_response();
-            }
-        };
-        
-        let refund = refunds.iter().find(|r| {
-            r.conversation_id.as_deref() == Some(conv_id)
-        });
-        
-        if let Some(refund) = refund {
-            let new_status = if code == 0 { "completed" } else { "failed" };
-            
-            if let Err(e) = state.store.update_refund_status(&refund.id, new_status, Some(conv_id)) {
-                log::error!("Failed to update refund status: {}", e);
-            } else {
-                log::info!("✅ Refund {} {} (ConversationID={})", 
-                          refund.id, 
-                          if code == 0 { "completed" } else { "failed" }, 
-                          conv_id);
-            }
-        } else {
-            log::warn!("⚠️ Refund not found for ConversationID: {}", conv_id);
-        }
+
+    let config = state.config.get();
+    if let Err(e) = process_b2c_callback(&callback, &state.store, &config, state.transport.clone()).await {
+        log::error!("Failed to process B2C callback: {:#}", e);
     }
-    
+
+    // Always acknowledge, or M-Pesa keeps retrying
     (StatusCode::OK, Json(serde_json::json!({
         "ResultCode": 0,
         "ResultDesc": "Accepted"
@@ -583,9 +1307,9 @@ async fn export_ledger(State(state): State<AppState>) -> impl IntoResponse {
     let report = serde_json::json!({
         "generated_at": chrono::Utc::now().to_rfc3339(),
         "business": {
-            "name": state.config.business.name,
-            "currency": state.config.business.currency,
-            "phone": state.config.business.phone,
+            "name": state.config.get().business.name,
+            "currency": state.config.get().business.currency,
+            "phone": state.config.get().business.phone,
         },
         "summary": {
             "total_revenue": stats.total_revenue,
@@ -662,7 +1386,7 @@ async fn export_ledger(State(state): State<AppState>) -> impl IntoResponse {
         ("Content-Type", "application/json"),
         ("Content-Disposition", &format!(
             "attachment; filename=\"{}-ledger-{}.json\"",
-            state.config.business.name.replace(" ", "-").to_lowercase(),
+            state.config.get().business.name.replace(" ", "-").to_lowercase(),
             chrono::Utc::now().format("%Y%m%d")
         )),
     ];
@@ -750,7 +1474,7 @@ async fn payment_analytics(State(state): State<AppState>) -> impl IntoResponse {
             },
         },
         "insights": {
-            "avg_order_value": format!("{}{:.2}", state.config.business.currency, avg_order_value),
+            "avg_order_value": format!("{}{:.2}", state.config.get().business.currency, avg_order_value),
             "peak_hours": peak_hours,
             "total_transactions": all_payments.len(),
             "successful_transactions": completed_payments.len(),
@@ -797,8 +1521,16 @@ async fn reconciliation_report(State(state): State<AppState>) -> impl IntoRespon
     // Identify discrepancies
     let mut issues = Vec::new();
     
-    // Check for stuck payments (processing for >24h)
-    // TODO: Add timestamp comparison when we have it
+    // Check for stuck payments (processing for >24h), which even the
+    // reconciliation poller couldn't settle
+    let stuck_payments = state.store.get_stale_payments(24 * 60).map(|p| p.len()).unwrap_or_default();
+    if stuck_payments > 0 {
+        issues.push(serde_json::json!({
+            "severity": "warning",
+            "issue": format!("{} payments processing for over 24 hours", stuck_payments),
+            "action": "Check them in the M-Pesa portal and confirm or cancel the orders by hand",
+        }));
+    }
     
     // Check for orders without payments
     let orders_without_payment = stats.total_orders - stats.total_payments;
@@ -0,0 +1,429 @@
+//! Appointment booking handler.
+//!
+//! Used instead of the order cart when `bookings:` is configured:
+//! 1. User picks a service from the menu
+//! 2. User picks a day with free slots
+//! 3. User picks a start time; the booking is saved and admins notified
+//!
+//! Customers manage bookings with "My Bookings", `RESCHEDULE <id>` and
+//! `CANCEL <id>`.
+
+use super::{HandlerResult, MessageContext, MessageHandler};
+use crate::bookings::{self, DATE_FORMAT};
+use crate::bot::conversation::{BookingDraft, ConversationState};
+use crate::config::{BookingConfig, HiveConfig, MessageTemplates};
+use crate::i18n::{Language, TranslationKey};
+use crate::store::{BookingRecord, Store};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, NaiveTime};
+
+pub struct BookingHandler;
+
+#[async_trait]
+impl MessageHandler for BookingHandler {
+    fn matches(&self, _text: &str, state: &ConversationState) -> bool {
+        matches!(
+            state,
+            ConversationState::ViewingMenu
+                | ConversationState::ViewingCategory(_)
+                | ConversationState::PickingBookingDate(_)
+                | ConversationState::PickingBookingSlot(_)
+        )
+    }
+
+    async fn handle(
+        &self,
+        config: &HiveConfig,
+        ctx: &MessageContext,
+        state: &mut ConversationState,
+        store: &Store,
+    ) -> Result<HandlerResult> {
+        let Some(ref cfg) = config.bookings else {
+            return Ok(HandlerResult::NoReply);
+        };
+        let text = ctx.text.trim();
+
+        match state.clone() {
+            ConversationState::ViewingMenu | ConversationState::ViewingCategory(_) => {
+                handle_service_selection(config, cfg, ctx.lang, state, store, text)
+            }
+            ConversationState::PickingBookingDate(draft) => {
+                handle_date_choice(config, cfg, ctx.lang, state, store, draft, text)
+            }
+            ConversationState::PickingBookingSlot(draft) => {
+                handle_slot_choice(config, cfg, ctx, state, store, draft, text).await
+            }
+            _ => Ok(HandlerResult::NoReply),
+        }
+    }
+}
+
+/// Parse a 1-based choice from a numbered list.
+fn pick<'a>(options: &'a [String], text: &str) -> Option<&'a String> {
+    match text.parse::<usize>() {
+        Ok(n) if n >= 1 => options.get(n - 1),
+        _ => None,
+    }
+}
+
+/// Handle a service number typed while viewing the menu.
+fn handle_service_selection(
+    config: &HiveConfig,
+    cfg: &BookingConfig,
+    lang: Language,
+    state: &mut ConversationState,
+    store: &Store,
+    text: &str,
+) -> Result<HandlerResult> {
+    let available = super::menu::listed_menu(config, store, state);
+    let item = match text.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= available.len() => &available[n - 1],
+        _ => {
+            return Ok(HandlerResult::Reply(config.i18n.render(
+                lang,
+                TranslationKey::ServiceRange,
+                &[("max", &available.len().to_string())],
+            )));
+        }
+    };
+
+    let draft = BookingDraft {
+        service: item.name.clone(),
+        price: item.price,
+        duration_minutes: cfg.duration_for(item),
+        reschedule_id: None,
+        date: None,
+        options: Vec::new(),
+    };
+    offer_days(config, cfg, lang, state, store, draft)
+}
+
+/// Show the days with free slots and move to `PickingBookingDate`.
+fn offer_days(
+    config: &HiveConfig,
+    cfg: &BookingConfig,
+    lang: Language,
+    state: &mut ConversationState,
+    store: &Store,
+    mut draft: BookingDraft,
+) -> Result<HandlerResult> {
+    let days = bookings::available_days(cfg, store, draft.duration_minutes, cfg.now(), draft.reschedule_id)?;
+
+    if days.is_empty() {
+        *state = ConversationState::Idle;
+        return Ok(HandlerResult::Reply(config.i18n.render(
+            lang,
+            TranslationKey::NoFreeAppointments,
+            &[("service", &draft.service), ("days", &cfg.days_ahead.to_string())],
+        )));
+    }
+
+    let mut lines = vec![format!(
+        "{}\n",
+        config.i18n.render(
+            lang,
+            TranslationKey::ChooseDay,
+            &[("service", &draft.service), ("minutes", &draft.duration_minutes.to_string())],
+        )
+    )];
+    for (i, day) in days.iter().enumerate() {
+        lines.push(format!("{}. {}", i + 1, config.i18n.format_date(lang, *day)));
+    }
+    lines.push(format!("\n{}", config.i18n.text(lang, TranslationKey::GoBackHint)));
+
+    draft.date = None;
+    draft.options = days.iter().map(|d| d.format(DATE_FORMAT).to_string()).collect();
+    *state = ConversationState::PickingBookingDate(draft);
+
+    Ok(HandlerResult::Reply(lines.join("\n")))
+}
+
+/// Show the free times on `date` and move to `PickingBookingSlot`.
+fn offer_slots(
+    config: &HiveConfig,
+    cfg: &BookingConfig,
+    lang: Language,
+    state: &mut ConversationState,
+    store: &Store,
+    mut draft: BookingDraft,
+    date: NaiveDate,
+) -> Result<HandlerResult> {
+    let day_bookings = store.list_bookings_on(date)?;
+    let slots = bookings::available_slots(
+        cfg,
+        &day_bookings,
+        date,
+        draft.duration_minutes,
+        cfg.now(),
+        draft.reschedule_id,
+    );
+
+    if slots.is_empty() {
+        return offer_days(config, cfg, lang, state, store, draft);
+    }
+
+    let date_label = config.i18n.format_date(lang, date);
+    let mut lines = vec![format!(
+        "{}\n",
+        config.i18n.render(lang, TranslationKey::ChooseTime, &[("date", &date_label)])
+    )];
+    for (i, slot) in slots.iter().enumerate() {
+        lines.push(format!("{}. {}", i + 1, slot.format("%H:%M")));
+    }
+    lines.push(format!("\n{}", config.i18n.text(lang, TranslationKey::GoBackHint)));
+
+    draft.date = Some(date.format(DATE_FORMAT).to_string());
+    draft.options = slots.iter().map(|t| t.format("%H:%M").to_string()).collect();
+    *state = ConversationState::PickingBookingSlot(draft);
+
+    Ok(HandlerResult::Reply(lines.join("\n")))
+}
+
+fn handle_date_choice(
+    config: &HiveConfig,
+    cfg: &BookingConfig,
+    lang: Language,
+    state: &mut ConversationState,
+    store: &Store,
+    draft: BookingDraft,
+    text: &str,
+) -> Result<HandlerResult> {
+    let Some(date) = pick(&draft.options, text).and_then(|d| NaiveDate::parse_from_str(d, DATE_FORMAT).ok())
+    else {
+        return Ok(HandlerResult::Reply(config.i18n.render(
+            lang,
+            TranslationKey::DayRange,
+            &[("max", &draft.options.len().to_string())],
+        )));
+    };
+
+    offer_slots(config, cfg, lang, state, store, draft, date)
+}
+
+async fn handle_slot_choice(
+    config: &HiveConfig,
+    cfg: &BookingConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    draft: BookingDraft,
+    text: &str,
+) -> Result<HandlerResult> {
+    let date = draft
+        .date
+        .as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d, DATE_FORMAT).ok());
+    let lang = ctx.lang;
+    let Some(date) = date else {
+        return offer_days(config, cfg, lang, state, store, draft);
+    };
+    let Some(time) = pick(&draft.options, text).and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok())
+    else {
+        return Ok(HandlerResult::Reply(config.i18n.render(
+            lang,
+            TranslationKey::TimeRange,
+            &[("max", &draft.options.len().to_string())],
+        )));
+    };
+
+    let start = date.and_time(time);
+    let end = start + Duration::minutes(draft.duration_minutes as i64);
+
+    // The slots were offered when the customer asked, which may have been
+    // hours ago; the time has to still be far enough ahead
+    let booking_id = if start < bookings::earliest_start(cfg, cfg.now()) {
+        Err(TranslationKey::SlotPassed)
+    } else {
+        match draft.reschedule_id {
+            Some(id) => store.reschedule_booking(id, start, end, cfg.capacity)?.then_some(id),
+            None => store.create_booking(&ctx.sender, &draft.service, draft.price, start, end, cfg.capacity)?,
+        }
+        // Someone else took the slot since we offered it
+        .ok_or(TranslationKey::SlotTaken)
+    };
+
+    let booking_id = match booking_id {
+        Ok(id) => id,
+        Err(key) => {
+            let reply = offer_slots(config, cfg, lang, state, store, draft, date)?;
+            return Ok(match reply {
+                HandlerResult::Reply(text) => {
+                    HandlerResult::Reply(format!("{}\n\n{}", config.i18n.text(lang, key), text))
+                }
+                other => other,
+            });
+        }
+    };
+
+    *state = ConversationState::Idle;
+
+    // Admins get English dates, the customer their own language
+    let date_str = config.i18n.format_date(Language::English, date);
+    let time_str = time.format("%H:%M").to_string();
+    let id_str = booking_id.to_string();
+
+    let admin_msg = if draft.reschedule_id.is_some() {
+        format!(
+            "🔁 Booking #{} ({}) moved to {} at {}\n👤 {}",
+            booking_id, draft.service, date_str, time_str, ctx.sender
+        )
+    } else {
+        MessageTemplates::render(
+            &config.messages.booking_received_admin,
+            &[
+                ("id", &id_str),
+                ("service", &draft.service),
+                ("date", &date_str),
+                ("time", &time_str),
+                ("phone", &ctx.sender),
+            ],
+        )
+    };
+    notify_admins(config, ctx, &admin_msg).await;
+
+    log::info!(
+        "📅 Booking #{} for {} — {} {} {}",
+        booking_id,
+        ctx.sender,
+        draft.service,
+        date,
+        time_str
+    );
+
+    Ok(HandlerResult::Reply(MessageTemplates::render(
+        &config.messages_in(lang).booking_confirmed,
+        &[
+            ("id", &id_str),
+            ("service", &draft.service),
+            ("date", &config.i18n.format_date(lang, date)),
+            ("time", &time_str),
+            ("phone", &ctx.sender),
+        ],
+    )))
+}
+
+/// Handle "My Bookings" — list the customer's upcoming appointments.
+pub async fn handle_my_bookings(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    store: &Store,
+) -> Result<HandlerResult> {
+    let Some(ref cfg) = config.bookings else {
+        return Ok(HandlerResult::NoReply);
+    };
+    let upcoming = store.get_customer_bookings(&ctx.sender, cfg.now())?;
+
+    let lang = ctx.lang;
+    if upcoming.is_empty() {
+        return Ok(HandlerResult::Reply(config.i18n.text(lang, TranslationKey::NoUpcomingBookings)));
+    }
+
+    let mut lines = vec![format!("{}\n", config.i18n.text(lang, TranslationKey::UpcomingBookings))];
+    for booking in &upcoming {
+        lines.push(format!("#{} — {} — {}", booking.id, booking.service, describe_time(config, booking, lang)));
+    }
+    lines.push(format!("\n{}", config.i18n.text(lang, TranslationKey::ManageBookingsHint)));
+
+    Ok(HandlerResult::Reply(lines.join("\n")))
+}
+
+/// Handle `CANCEL <id>` and `RESCHEDULE <id>`. Returns `None` if `text`
+/// isn't a booking command.
+pub async fn handle_booking_command(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    text: &str,
+) -> Result<Option<HandlerResult>> {
+    let Some(ref cfg) = config.bookings else {
+        return Ok(None);
+    };
+    let upper = text.to_uppercase();
+    let (reschedule, id) = if let Some(rest) = upper.strip_prefix("CANCEL ") {
+        (false, rest.trim().trim_start_matches('#').parse::<i64>())
+    } else if let Some(rest) = upper.strip_prefix("RESCHEDULE ") {
+        (true, rest.trim().trim_start_matches('#').parse::<i64>())
+    } else {
+        return Ok(None);
+    };
+    let Ok(id) = id else {
+        return Ok(None);
+    };
+
+    // Customers may only change their own upcoming bookings
+    let booking = store
+        .get_booking(id)?
+        .filter(|b| b.customer_phone == ctx.sender)
+        .filter(|b| b.status == crate::store::BookingStatus::Confirmed)
+        .filter(|b| b.start().is_some_and(|start| start > cfg.now()));
+    let Some(booking) = booking else {
+        return Ok(Some(HandlerResult::Reply(config.i18n.render(
+            ctx.lang,
+            TranslationKey::NoSuchBooking,
+            &[("id", &id.to_string())],
+        ))));
+    };
+
+    if reschedule {
+        let duration = match (booking.start(), booking.end()) {
+            (Some(start), Some(end)) => (end - start).num_minutes() as u32,
+            _ => cfg.default_duration_minutes,
+        };
+        let draft = BookingDraft {
+            service: booking.service.clone(),
+            price: booking.price,
+            duration_minutes: duration,
+            reschedule_id: Some(booking.id),
+            date: None,
+            options: Vec::new(),
+        };
+        return offer_days(config, cfg, ctx.lang, state, store, draft).map(Some);
+    }
+
+    store.cancel_booking(booking.id)?;
+    *state = ConversationState::Idle;
+
+    let admin_msg = format!(
+        "❌ Booking #{} ({}, {}) cancelled by {}",
+        booking.id,
+        booking.service,
+        describe_time(config, &booking, Language::English),
+        ctx.sender
+    );
+    notify_admins(config, ctx, &admin_msg).await;
+
+    Ok(Some(HandlerResult::Reply(config.i18n.render(
+        ctx.lang,
+        TranslationKey::BookingCancelled,
+        &[
+            ("id", &booking.id.to_string()),
+            ("service", &booking.service),
+            ("when", &describe_time(config, &booking, ctx.lang)),
+        ],
+    ))))
+}
+
+/// "Mon 2 Mar at 10:00"
+fn describe_time(config: &HiveConfig, booking: &BookingRecord, lang: Language) -> String {
+    match booking.start() {
+        Some(start) => config.i18n.render(
+            lang,
+            TranslationKey::DateAtTime,
+            &[
+                ("date", &config.i18n.format_date(lang, start.date())),
+                ("time", &start.format("%H:%M").to_string()),
+            ],
+        ),
+        None => booking.start_at.clone(),
+    }
+}
+
+async fn notify_admins(config: &HiveConfig, ctx: &MessageContext, msg: &str) {
+    for admin_number in &config.admin_numbers {
+        if let Err(e) = ctx.transport.send_to_phone(admin_number, msg).await {
+            log::error!("Failed to notify admin {}: {}", admin_number, e);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Declarative flow handler.
+//!
+//! Runs the questionnaire-style flows defined under `flows:` in config.yaml:
+//! 1. An idle user sends one of the flow's triggers
+//! 2. Each step's prompt is sent and the answer validated against its input type
+//! 3. Answers are collected in `ConversationState::Flow { vars }`
+//! 4. On completion the user gets the `complete` message and admins a summary
+
+use super::{HandlerResult, MessageContext, MessageHandler};
+use crate::bot::conversation::ConversationState;
+use crate::config::{FlowConfig, FlowInput, FlowStep, HiveConfig, MessageTemplates, FLOW_END};
+use crate::i18n::TranslationKey;
+use crate::store::Store;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+
+pub struct FlowHandler;
+
+#[async_trait]
+impl MessageHandler for FlowHandler {
+    fn matches(&self, _text: &str, state: &ConversationState) -> bool {
+        matches!(state, ConversationState::Flow { .. })
+    }
+
+    async fn handle(
+        &self,
+        config: &HiveConfig,
+        ctx: &MessageContext,
+        state: &mut ConversationState,
+        _store: &Store,
+    ) -> Result<HandlerResult> {
+        let ConversationState::Flow { flow, step, vars } = state else {
+            return Ok(HandlerResult::NoReply);
+        };
+
+        // The config may have changed since this conversation started
+        let Some((flow_cfg, step_cfg)) = config
+            .flow(flow)
+            .and_then(|f| f.step(step).map(|s| (f, s)))
+        else {
+            *state = ConversationState::Idle;
+            return Ok(HandlerResult::Reply(config.welcome(ctx.lang)));
+        };
+
+        let (answer, chosen_next) = match parse_answer(config, step_cfg, ctx) {
+            Ok(parsed) => parsed,
+            Err(hint) => {
+                let error = step_cfg.error.clone().unwrap_or(hint);
+                return Ok(HandlerResult::Reply(format!(
+                    "{}\n\n{}\n\n{}",
+                    error,
+                    render_prompt(step_cfg, vars),
+                    config.i18n.text(ctx.lang, TranslationKey::CancelHint)
+                )));
+            }
+        };
+        vars.insert(step_cfg.id.clone(), answer);
+
+        let next_id = chosen_next
+            .or_else(|| step_cfg.next.clone())
+            .or_else(|| flow_cfg.following_step(&step_cfg.id).map(|s| s.id.clone()))
+            .unwrap_or_else(|| FLOW_END.to_string());
+
+        match flow_cfg.step(&next_id) {
+            Some(next_step) => {
+                let prompt = render_prompt(next_step, vars);
+                *step = next_id;
+                Ok(HandlerResult::Reply(prompt))
+            }
+            None => {
+                let vars = std::mem::take(vars);
+                *state = ConversationState::Idle;
+                complete_flow(config, ctx, flow_cfg, &vars).await
+            }
+        }
+    }
+}
+
+/// Start a flow: move into its first step and return that step's prompt.
+pub fn start_flow(flow: &FlowConfig, state: &mut ConversationState) -> HandlerResult {
+    let first = &flow.steps[0];
+    let vars = BTreeMap::new();
+    let prompt = render_prompt(first, &vars);
+    *state = ConversationState::Flow {
+        flow: flow.name.clone(),
+        step: first.id.clone(),
+        vars,
+    };
+    HandlerResult::Reply(prompt)
+}
+
+/// Render a step's prompt, listing numbered options for choice steps.
+fn render_prompt(step: &FlowStep, vars: &BTreeMap<String, String>) -> String {
+    let mut prompt = render_vars(&step.prompt, vars);
+    if step.input == FlowInput::Choice {
+        for (i, option) in step.options.iter().enumerate() {
+            prompt.push_str(&format!("\n{}. {}", i + 1, option.label));
+        }
+    }
+    prompt
+}
+
+fn render_vars(template: &str, vars: &BTreeMap<String, String>) -> String {
+    let pairs: Vec<(&str, &str)> = vars.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    MessageTemplates::render(template, &pairs)
+}
+
+/// Validate an answer for a step.
+///
+/// Returns the value to store and, for choice steps, the option's `next`
+/// override. On failure returns a hint describing what was expected.
+fn parse_answer(config: &HiveConfig, step: &FlowStep, ctx: &MessageContext) -> Result<(String, Option<String>), String> {
+    let text = ctx.text.trim();
+    let lang = ctx.lang;
+
+    match step.input {
+        FlowInput::Text => {
+            let len = text.chars().count() as f64;
+            if text.is_empty() {
+                return Err(config.i18n.text(lang, TranslationKey::TypeReply));
+            }
+            if let Some(min) = step.min
+                && len < min
+            {
+                return Err(config.i18n.render(lang, TranslationKey::MinLength, &[("min", &min.to_string())]));
+            }
+            if let Some(max) = step.max
+                && len > max
+            {
+                return Err(config.i18n.render(lang, TranslationKey::MaxLength, &[("max", &max.to_string())]));
+            }
+            Ok((text.to_string(), None))
+        }
+        FlowInput::Number => {
+            // "NaN" and "inf" parse as f64 but would slip past min/max
+            let value: f64 = text
+                .parse()
+                .ok()
+                .filter(|v: &f64| v.is_finite())
+                .ok_or_else(|| config.i18n.text(lang, TranslationKey::NumberExpected))?;
+            if let Some(min) = step.min
+                && value < min
+            {
+                return Err(config.i18n.render(lang, TranslationKey::NumberMin, &[("min", &min.to_string())]));
+            }
+            if let Some(max) = step.max
+                && value > max
+            {
+                return Err(config.i18n.render(lang, TranslationKey::NumberMax, &[("max", &max.to_string())]));
+            }
+            Ok((text.to_string(), None))
+        }
+        FlowInput::Location => {
+            // Accept a shared location, or a typed address
+            let location = if ctx.has_location {
+                ctx.location_text.clone().unwrap_or_default()
+            } else {
+                text.to_string()
+            };
+            if location.trim().is_empty() {
+                return Err(config.i18n.text(lang, TranslationKey::LocationExpected));
+            }
+            Ok((location, None))
+        }
+        FlowInput::Choice => {
+            let option = match text.parse::<usize>() {
+                Ok(n) if n >= 1 => step.options.get(n - 1),
+                _ => step
+                    .options
+                    .iter()
+                    .find(|o| o.label.eq_ignore_ascii_case(text)),
+            };
+            match option {
+                Some(option) => Ok((option.label.clone(), option.next.clone())),
+                None => Err(config.i18n.render(
+                    lang,
+                    TranslationKey::NumberRange,
+                    &[("max", &step.options.len().to_string())],
+                )),
+            }
+        }
+    }
+}
+
+/// Finish a flow: notify admins and confirm to the user.
+async fn complete_flow(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    flow: &FlowConfig,
+    vars: &BTreeMap<String, String>,
+) -> Result<HandlerResult> {
+    if flow.notify_admin {
+        let answers = flow
+            .steps
+            .iter()
+            .filter_map(|s| vars.get(&s.id).map(|v| format!("• {}: {}", s.id, v)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let admin_msg = format!(
+            "📝 New {} from +{}\n\n{}",
+            flow.title(),
+            crate::transport::phone_digits(&ctx.sender),
+            answers
+        );
+
+        for admin_number in &config.admin_numbers {
+            if let Err(e) = ctx.transport.send_to_phone(admin_number, &admin_msg).await {
+                log::error!("Failed to notify admin {}: {}", admin_number, e);
+            }
+        }
+    }
+
+    log::info!("📝 Flow '{}' completed by {}", flow.name, ctx.sender);
+
+    Ok(HandlerResult::Reply(render_vars(&flow.complete, vars)))
+}
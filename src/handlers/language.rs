@@ -0,0 +1,95 @@
+//! Language picker.
+//!
+//! `LANGUAGE` (or `LUGHA`, `IDIOMA`, …) lists the languages the business
+//! offers, as a list message where the transport supports one. The
+//! customer's pick is saved on their customer record and every reply after
+//! that is sent in it.
+
+use super::{HandlerResult, MessageContext};
+use crate::bot::conversation::ConversationState;
+use crate::config::HiveConfig;
+use crate::i18n::{Language, TranslationKey};
+use crate::store::{CustomerUpdate, Store};
+use crate::transport::{Choice, ListMessage};
+use anyhow::Result;
+
+/// Commands that open the picker, in each supported language.
+const LANGUAGE_COMMANDS: &[&str] = &["language", "lang", "lugha", "taal", "idioma", "langue", "भाषा", "🌐"];
+
+/// Whether `text` asks for the language picker.
+pub fn is_language_command(text: &str) -> bool {
+    let text = text.trim().to_lowercase();
+    LANGUAGE_COMMANDS.contains(&text.as_str())
+}
+
+/// Show the languages on offer and move to `ChoosingLanguage`.
+pub fn handle_language_command(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+) -> HandlerResult {
+    *state = ConversationState::ChoosingLanguage;
+    HandlerResult::List(language_list(config, ctx.lang))
+}
+
+/// Save the language a customer picked by number, code or name, and greet
+/// them in it. Returns `None` if `text` isn't a choice, so the message can
+/// be routed normally.
+pub fn handle_language_choice(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    text: &str,
+) -> Result<Option<HandlerResult>> {
+    let offered = config.offered_languages();
+    let chosen = match text.parse::<usize>() {
+        Ok(n) => match n.checked_sub(1).and_then(|i| offered.get(i)) {
+            Some(lang) => *lang,
+            None => {
+                let mut list = language_list(config, ctx.lang);
+                list.text = format!("{}\n\n{}", config.i18n.text(ctx.lang, TranslationKey::InvalidChoice), list.text);
+                return Ok(Some(HandlerResult::List(list)));
+            }
+        },
+        Err(_) => {
+            let text = text.to_lowercase();
+            match offered
+                .iter()
+                .find(|l| l.code() == text || config.i18n.language_name(**l).to_lowercase() == text)
+            {
+                Some(lang) => *lang,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let update = CustomerUpdate {
+        language: Some(chosen.code().to_string()),
+        ..Default::default()
+    };
+    store.update_customer(&ctx.sender, &update)?;
+    *state = ConversationState::Idle;
+    log::info!("🌐 {} now speaks {}", ctx.sender, chosen.code());
+
+    Ok(Some(HandlerResult::Reply(format!(
+        "{}\n\n{}",
+        config.i18n.render(chosen, TranslationKey::LanguageSet, &[("language", config.i18n.language_name(chosen))]),
+        config.welcome(chosen)
+    ))))
+}
+
+/// The numbered list of languages, introduced in `lang`.
+fn language_list(config: &HiveConfig, lang: Language) -> ListMessage {
+    ListMessage {
+        text: format!("🌐 {}", config.i18n.text(lang, TranslationKey::ChooseLanguage)),
+        button: config.i18n.text(lang, TranslationKey::ChooseButton),
+        rows: config
+            .offered_languages()
+            .iter()
+            .enumerate()
+            .map(|(i, language)| Choice::new((i + 1).to_string(), config.i18n.language_name(*language)))
+            .collect(),
+        footer: Some(config.i18n.text(lang, TranslationKey::GoBackHint)),
+    }
+}
@@ -1,12 +1,18 @@
 //! Menu display handler.
 //!
-//! Formats the menu from config and presents it to the user.
-//! Transitions the conversation to `ViewingMenu` state.
+//! Formats the live menu (from the store) and presents it to the user.
+//! Transitions the conversation to `ViewingMenu` state; menus with
+//! categories list the categories first (as a list message) and open one as
+//! `ViewingCategory`. Items with a photo are sent as images ahead of the
+//! numbered item list. Also handles the admin commands that edit the menu:
+//! ITEMS, PRICE, HIDE and SHOW.
 
 use super::{HandlerResult, MessageContext, MessageHandler};
 use crate::bot::conversation::ConversationState;
-use crate::config::HiveConfig;
-use crate::store::Store;
+use crate::config::{HiveConfig, MenuItem, OTHER_CATEGORY};
+use crate::i18n::{Language, TranslationKey};
+use crate::store::{MenuItemRecord, MenuItemUpdate, Store};
+use crate::transport::{Choice, ImageMessage, ListMessage, MessageKind, OutgoingMessage};
 use anyhow::Result;
 use async_trait::async_trait;
 
@@ -22,65 +28,226 @@ impl MessageHandler for MenuHandler {
     async fn handle(
         &self,
         config: &HiveConfig,
-        _ctx: &MessageContext,
+        ctx: &MessageContext,
         state: &mut ConversationState,
-        _store: &Store,
+        store: &Store,
     ) -> Result<HandlerResult> {
-        let available = config.available_menu();
+        let lang = ctx.lang;
+        let available = config.available_menu(store);
 
         if available.is_empty() {
-            return Ok(HandlerResult::Reply(
-                "😔 Sorry, our menu is currently empty. Check back later!".to_string(),
-            ));
+            return Ok(HandlerResult::Reply(config.i18n.text(lang, TranslationKey::MenuEmpty)));
         }
 
-        let currency = &config.business.currency;
-        let mut lines = vec![format!("📋 *{} Menu*\n", config.business.name)];
-
-        for (i, item) in available.iter().enumerate() {
-            let emoji = item.emoji.as_deref().unwrap_or("•");
-            let desc = item
-                .description
-                .as_deref()
-                .map(|d| format!("\n   _{}_", d))
-                .unwrap_or_default();
+        let categories = menu_categories(&available);
+        let title = config.i18n.render(lang, TranslationKey::MenuTitle, &[("business", &config.business.name)]);
+        let mut lines = vec![format!("{}\n", title)];
 
-            lines.push(format!(
-                "{}. {} *{}* — {}{:.2}{}",
-                i + 1,
-                emoji,
-                item.name,
-                currency,
-                item.price,
-                desc
-            ));
+        if categories.is_empty() {
+            lines.extend(item_lines(config, &available, lang));
+        } else {
+            // The categories go in the list; the menu title in its text
+            lines[0] = title;
         }
 
         // Add delivery fee info if configured
+        let currency = &config.business.currency;
         if let Some(ref delivery) = config.delivery {
             if delivery.fee > 0.0 {
+                let fee = format!("{}{:.2}", currency, delivery.fee);
                 lines.push(format!(
-                    "\n🚗 Delivery fee: {}{:.2}",
-                    currency, delivery.fee
+                    "\n{}",
+                    config.i18n.render(lang, TranslationKey::DeliveryFee, &[("fee", &fee)])
                 ));
             }
-            lines.push(format!("⏱ Estimated: {}", delivery.estimate_string()));
+            lines.push(config.i18n.render(
+                lang,
+                TranslationKey::DeliveryEstimate,
+                &[("estimate", &delivery.estimate_in(&config.i18n, lang))],
+            ));
         }
 
         lines.push("\n━━━━━━━━━━━━━━━━━━━".to_string());
-        lines.push("Reply with item number(s) to order".to_string());
-        lines.push("e.g. *1* or *1,3,5*".to_string());
-        lines.push("Reply *0* to go back".to_string());
-
         *state = ConversationState::ViewingMenu;
 
-        Ok(HandlerResult::Reply(lines.join("\n")))
+        if !categories.is_empty() {
+            lines.push(config.i18n.text(lang, TranslationKey::PickCategory));
+            let rows = categories
+                .iter()
+                .enumerate()
+                .map(|(i, category)| {
+                    let count = available.iter().filter(|m| m.category() == category).count();
+                    Choice::new(
+                        (i + 1).to_string(),
+                        format!("{} ({})", category_label(config, category, lang), count),
+                    )
+                })
+                .collect();
+            return Ok(HandlerResult::List(ListMessage {
+                text: lines.join("\n"),
+                button: config.i18n.text(lang, TranslationKey::ChooseButton),
+                rows,
+                footer: Some(config.i18n.text(lang, TranslationKey::GoBackHint)),
+            }));
+        }
+
+        if config.bookings.is_some() {
+            lines.push(config.i18n.text(lang, TranslationKey::PickService));
+        } else {
+            lines.push(config.i18n.text(lang, TranslationKey::PickItems));
+        }
+        lines.push(config.i18n.text(lang, TranslationKey::GoBackHint));
+
+        Ok(with_photos(config, ctx, &available, lines.join("\n")))
+    }
+}
+
+/// Categories in menu order, or none if no item sets a category.
+/// Items without one are grouped under `OTHER_CATEGORY`, listed last.
+pub fn menu_categories(items: &[MenuItem]) -> Vec<String> {
+    if items.iter().all(|m| m.category.is_none()) {
+        return Vec::new();
     }
+    let mut categories: Vec<String> = Vec::new();
+    for item in items.iter().filter(|m| m.category.is_some()) {
+        if !categories.iter().any(|c| c == item.category()) {
+            categories.push(item.category().to_string());
+        }
+    }
+    if items.iter().any(|m| m.category.is_none()) {
+        categories.push(OTHER_CATEGORY.to_string());
+    }
+    categories
+}
+
+/// A category's name as shown to customers; the catch-all category is
+/// translated.
+fn category_label(config: &HiveConfig, category: &str, lang: Language) -> String {
+    if category == OTHER_CATEGORY {
+        config.i18n.text(lang, TranslationKey::OtherCategory)
+    } else {
+        category.to_string()
+    }
+}
+
+/// The items the customer is currently looking at, in the order numbered:
+/// one category when browsing by category, otherwise the whole menu.
+pub fn listed_menu(config: &HiveConfig, store: &Store, state: &ConversationState) -> Vec<MenuItem> {
+    let available = config.available_menu(store);
+    match state {
+        ConversationState::ViewingCategory(category) => available
+            .into_iter()
+            .filter(|m| m.category() == category)
+            .collect(),
+        _ => available,
+    }
+}
+
+/// Open a category when a customer picks one from the category list.
+/// Returns `None` if the menu has no categories.
+pub fn handle_category_choice(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    text: &str,
+) -> Result<Option<HandlerResult>> {
+    let lang = ctx.lang;
+    let available = config.available_menu(store);
+    let categories = menu_categories(&available);
+    if categories.is_empty() {
+        return Ok(None);
+    }
+
+    let chosen = match text.parse::<usize>() {
+        Ok(n) => n.checked_sub(1).and_then(|i| categories.get(i)),
+        Err(_) => categories
+            .iter()
+            .find(|c| c.eq_ignore_ascii_case(text) || category_label(config, c, lang).eq_ignore_ascii_case(text)),
+    };
+    let Some(category) = chosen else {
+        if text.parse::<usize>().is_ok() {
+            let max = categories.len().to_string();
+            return Ok(Some(HandlerResult::Reply(config.i18n.render(
+                lang,
+                TranslationKey::CategoryRange,
+                &[("max", &max)],
+            ))));
+        }
+        return Ok(None);
+    };
+
+    let items: Vec<MenuItem> = available
+        .into_iter()
+        .filter(|m| m.category() == category)
+        .collect();
+    let mut lines = vec![format!("📋 *{}*\n", category_label(config, category, lang))];
+    lines.extend(item_lines(config, &items, lang));
+    lines.push("\n━━━━━━━━━━━━━━━━━━━".to_string());
+    if config.bookings.is_some() {
+        lines.push(config.i18n.text(lang, TranslationKey::PickService));
+    } else {
+        lines.push(config.i18n.text(lang, TranslationKey::PickItems));
+    }
+    lines.push(config.i18n.text(lang, TranslationKey::AllCategories));
+
+    *state = ConversationState::ViewingCategory(category.clone());
+    Ok(Some(with_photos(config, ctx, &items, lines.join("\n"))))
+}
+
+/// `menu` preceded by the photos of the `items` that have one, each
+/// captioned with its menu line. Without image support the captions would
+/// only repeat the menu, so then it's just the menu.
+fn with_photos(config: &HiveConfig, ctx: &MessageContext, items: &[MenuItem], menu: String) -> HandlerResult {
+    if !ctx.transport.supports(MessageKind::Image) {
+        return HandlerResult::Reply(menu);
+    }
+    let mut messages: Vec<OutgoingMessage> = items
+        .iter()
+        .zip(item_lines(config, items, ctx.lang))
+        .filter_map(|(item, caption)| {
+            let path = item.image.as_ref()?;
+            Some(OutgoingMessage::Image(ImageMessage {
+                path: path.into(),
+                caption: Some(caption),
+            }))
+        })
+        .collect();
+    if messages.is_empty() {
+        return HandlerResult::Reply(menu);
+    }
+    messages.push(OutgoingMessage::Text(menu));
+    HandlerResult::Messages(messages)
+}
+
+/// Numbered menu lines with descriptions.
+fn item_lines(config: &HiveConfig, items: &[MenuItem], lang: Language) -> Vec<String> {
+    let currency = &config.business.currency;
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let emoji = item.emoji.as_deref().unwrap_or("•");
+            let desc = config
+                .i18n
+                .item_description(lang, &item.name, item.description.as_deref())
+                .map(|d| format!("\n   _{}_", d))
+                .unwrap_or_default();
+            format!(
+                "{}. {} *{}* — {}{}",
+                i + 1,
+                emoji,
+                config.i18n.item_name(lang, &item.name),
+                item.price_label_in(currency, &config.i18n, lang),
+                desc
+            )
+        })
+        .collect()
 }
 
 /// Format a compact menu summary (used in order confirmations, etc.)
-pub fn format_menu_compact(config: &HiveConfig) -> String {
-    let available = config.available_menu();
+pub fn format_menu_compact(config: &HiveConfig, store: &Store, lang: Language) -> String {
+    let available = config.available_menu(store);
     let currency = &config.business.currency;
 
     available
@@ -88,8 +255,115 @@ pub fn format_menu_compact(config: &HiveConfig) -> String {
         .enumerate()
         .map(|(i, item)| {
             let emoji = item.emoji.as_deref().unwrap_or("•");
-            format!("{}. {} {} — {}{:.2}", i + 1, emoji, item.name, currency, item.price)
+            format!(
+                "{}. {} {} — {}",
+                i + 1,
+                emoji,
+                config.i18n.item_name(lang, &item.name),
+                item.price_label_in(currency, &config.i18n, lang)
+            )
         })
         .collect::<Vec<_>>()
         .join("\n")
 }
+
+/// Handle an admin menu-editing command. Returns `None` if `text` isn't one.
+///
+/// Items are referred to by their number in the ITEMS list or by name:
+/// - ITEMS — list every item, including hidden ones
+/// - PRICE <item> <amount> — change an item's price
+/// - HIDE <item> / SHOW <item> — take an item off or put it back on the menu
+pub fn handle_admin_menu_command(config: &HiveConfig, store: &Store, text: &str) -> Result<Option<HandlerResult>> {
+    let (command, rest) = text.split_once(' ').unwrap_or((text, ""));
+    let rest = rest.trim();
+
+    match command.to_uppercase().as_str() {
+        "ITEMS" if rest.is_empty() => Ok(Some(list_items(config, store)?)),
+        "PRICE" => {
+            let Some((item_ref, amount)) = rest.rsplit_once(' ') else {
+                return Ok(Some(HandlerResult::Reply(
+                    "Usage: PRICE <item> <amount>\nExample: PRICE 2 45".to_string(),
+                )));
+            };
+            let Ok(price) = amount.trim().parse::<f64>() else {
+                return Ok(Some(HandlerResult::Reply(format!("❌ '{}' isn't a valid price.", amount))));
+            };
+            if price < 0.0 {
+                return Ok(Some(HandlerResult::Reply("❌ Price cannot be negative.".to_string())));
+            }
+            let Some(item) = resolve_item(store, item_ref.trim())? else {
+                return Ok(Some(item_not_found(item_ref)));
+            };
+            let update = MenuItemUpdate {
+                price: Some(price),
+                ..Default::default()
+            };
+            store.update_menu_item(item.id, &update)?;
+            log::info!("📋 Price of {} changed to {:.2}", item.name, price);
+            Ok(Some(HandlerResult::Reply(format!(
+                "✅ {} now costs {}{:.2} (was {}{:.2}).",
+                item.name, config.business.currency, price, config.business.currency, item.price
+            ))))
+        }
+        "HIDE" | "SHOW" if !rest.is_empty() => {
+            let Some(item) = resolve_item(store, rest)? else {
+                return Ok(Some(item_not_found(rest)));
+            };
+            let available = command.eq_ignore_ascii_case("SHOW");
+            let update = MenuItemUpdate {
+                available: Some(available),
+                ..Default::default()
+            };
+            store.update_menu_item(item.id, &update)?;
+            log::info!("📋 {} is now {}", item.name, if available { "shown" } else { "hidden" });
+            Ok(Some(HandlerResult::Reply(if available {
+                format!("✅ {} is back on the menu.", item.name)
+            } else {
+                format!("🙈 {} is hidden from the menu. Reply SHOW {} to bring it back.", item.name, rest)
+            })))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Find an item by its ITEMS number or by name.
+fn resolve_item(store: &Store, item_ref: &str) -> Result<Option<MenuItemRecord>> {
+    if let Ok(n) = item_ref.parse::<usize>() {
+        let items = store.list_menu_items()?;
+        return Ok(n.checked_sub(1).and_then(|i| items.into_iter().nth(i)));
+    }
+    store.find_menu_item(item_ref)
+}
+
+fn item_not_found(item_ref: &str) -> HandlerResult {
+    HandlerResult::Reply(format!(
+        "❌ No menu item '{}'. Reply ITEMS to see the list.",
+        item_ref.trim()
+    ))
+}
+
+/// Admin view of the whole menu, with hidden and sold-out markers.
+fn list_items(config: &HiveConfig, store: &Store) -> Result<HandlerResult> {
+    let items = store.list_menu_items()?;
+    if items.is_empty() {
+        return Ok(HandlerResult::Reply("📋 The menu is empty.".to_string()));
+    }
+
+    let currency = &config.business.currency;
+    let mut lines = vec!["📋 *Menu Items:*\n".to_string()];
+    for (i, item) in items.iter().enumerate() {
+        let mut line = format!("{}. {} — {}{:.2}", i + 1, item.name, currency, item.price);
+        if !item.available {
+            line.push_str(" 🙈 hidden");
+        }
+        match item.stock {
+            Some(q) if q <= 0 => line.push_str(" 🚫 sold out"),
+            Some(q) => line.push_str(&format!(" 📦 {} left", q)),
+            None => {}
+        }
+        lines.push(line);
+    }
+    lines.push("\nPRICE <n> <amount> • HIDE <n> • SHOW <n>".to_string());
+
+    Ok(HandlerResult::Reply(lines.join("\n")))
+}
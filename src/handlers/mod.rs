@@ -4,19 +4,25 @@
 //! interaction. The router tries handlers in priority order and dispatches
 //! to the first one that matches.
 
+pub mod booking;
+pub mod flow;
+pub mod language;
 pub mod menu;
+pub mod my_orders;
 pub mod order;
 pub mod voucher;
 
 use crate::bot::conversation::ConversationState;
 use crate::config::HiveConfig;
-use crate::payments::PaymentProvider;
+use crate::i18n::{Language, TranslationKey};
+use crate::payments::{PaymentProviders, RefundProvider};
 use crate::store::Store;
+use crate::transport::{
+    ButtonsMessage, DocumentMessage, ImageMessage, ListMessage, LocationPin, MessagingTransport, OutgoingMessage,
+};
 use anyhow::Result;
 use async_trait::async_trait;
 use std::sync::Arc;
-use wacore_binary::jid::Jid;
-use whatsapp_rust::client::Client;
 
 /// Context passed to handlers for each incoming message.
 pub struct MessageContext {
@@ -32,26 +38,60 @@ pub struct MessageContext {
     pub has_location: bool,
     /// Extracted location text (address or coordinates)
     pub location_text: Option<String>,
-    /// The raw protobuf message
-    pub raw_message: Box<waproto::whatsapp::Message>,
-    /// WhatsApp client for sending replies
-    pub wa_client: Arc<Client>,
-    /// Chat JID to reply to
-    pub chat_jid: Jid,
-    /// Payment provider (if configured)
-    pub payment_provider: Option<Arc<dyn PaymentProvider>>,
+    /// Transport for sending out-of-band messages (admin alerts, payment prompts)
+    pub transport: Arc<dyn MessagingTransport>,
+    /// Chat address to reply to
+    pub chat: String,
+    /// Payment providers customers can pay with (none if payments are off)
+    pub payment_providers: PaymentProviders,
+    /// Refund provider for customer cancellations (if configured)
+    pub refund_provider: Option<Arc<dyn RefundProvider>>,
+    /// Language to reply in
+    pub lang: Language,
 }
 
 /// Result of handling a message.
+///
+/// Anything but text is sent as its text fallback where the transport
+/// can't send it (see `transport::send_message`).
 pub enum HandlerResult {
     /// Send a single text reply.
     Reply(String),
     /// Send multiple text replies in sequence.
     MultiReply(Vec<String>),
+    /// Send an image, e.g. a menu item's photo.
+    Image(ImageMessage),
+    /// Send a file, e.g. a PDF receipt.
+    Document(DocumentMessage),
+    /// Send a message with quick-reply buttons.
+    Buttons(ButtonsMessage),
+    /// Send a message with a list to pick from.
+    List(ListMessage),
+    /// Send a location pin.
+    Location(LocationPin),
+    /// Send messages of any kind in sequence.
+    Messages(Vec<OutgoingMessage>),
     /// No reply needed (already handled or ignored).
     NoReply,
 }
 
+impl HandlerResult {
+    /// The messages to send, in order.
+    pub fn into_messages(self) -> Vec<OutgoingMessage> {
+        match self {
+            HandlerResult::Reply(text) => vec![OutgoingMessage::Text(text)],
+            HandlerResult::MultiReply(texts) => texts.into_iter().map(OutgoingMessage::Text).collect(),
+            HandlerResult::Image(image) => vec![OutgoingMessage::Image(image)],
+            HandlerResult::Document(document) => vec![OutgoingMessage::Document(document)],
+            HandlerResult::Buttons(buttons) => vec![OutgoingMessage::Buttons(buttons)],
+            HandlerResult::List(list) => vec![OutgoingMessage::List(list)],
+            HandlerResult::Location(pin) => vec![OutgoingMessage::Location(pin)],
+            HandlerResult::Messages(messages) => messages,
+            HandlerResult::NoReply => Vec::new(),
+        }
+    }
+}
+
 /// Trait for message handlers.
 #[async_trait]
 pub trait MessageHandler: Send + Sync {
@@ -94,9 +134,22 @@ pub async fn route_message(
         ConversationState::AwaitingLocation(_) => {
             return order::OrderHandler.handle(config, ctx, state, store).await;
         }
-        ConversationState::ViewingMenu => {
-            // If they type a number, treat it as adding an item
-            if text.parse::<usize>().is_ok() || text.eq_ignore_ascii_case("order") {
+        ConversationState::ChoosingItemOptions(_) => {
+            return order::OrderHandler.handle(config, ctx, state, store).await;
+        }
+        ConversationState::ViewingMenu | ConversationState::ViewingCategory(_) => {
+            // A menu with categories lists categories first: pick one to see its items
+            if matches!(state, ConversationState::ViewingMenu)
+                && let Some(result) = menu::handle_category_choice(config, ctx, state, store, text)?
+            {
+                return Ok(result);
+            }
+            // With bookings enabled, a service number starts the date picker
+            if config.bookings.is_some() && text.parse::<usize>().is_ok() {
+                return booking::BookingHandler.handle(config, ctx, state, store).await;
+            }
+            // If they type item number(s), treat it as adding items
+            if !order::parse_item_selections(text).is_empty() || text.eq_ignore_ascii_case("order") {
                 return order::OrderHandler.handle(config, ctx, state, store).await;
             }
             // Otherwise show menu again or route normally
@@ -104,37 +157,101 @@ pub async fn route_message(
         ConversationState::RedeemingVoucher => {
             return voucher::VoucherHandler.handle(config, ctx, state, store).await;
         }
+        ConversationState::PickingBookingDate(_) | ConversationState::PickingBookingSlot(_) => {
+            return booking::BookingHandler.handle(config, ctx, state, store).await;
+        }
+        ConversationState::Flow { .. } => {
+            return flow::FlowHandler.handle(config, ctx, state, store).await;
+        }
+        ConversationState::ViewingOrders(_) => {
+            if let Some(result) = my_orders::handle_order_choice(config, ctx, state, store, text)? {
+                return Ok(result);
+            }
+            // Not an order number: leave My Orders and route normally
+            *state = ConversationState::Idle;
+        }
+        ConversationState::ManagingOrder(_)
+        | ConversationState::ConfirmingOrderCancel(_)
+        | ConversationState::ChangingOrderLocation(_) => {
+            return my_orders::handle_manage_order(config, ctx, state, store, text).await;
+        }
+        ConversationState::ChoosingPaymentMethod(_) => {
+            return order::handle_payment_choice(config, ctx, state, store, text).await;
+        }
+        ConversationState::ChoosingLanguage => {
+            if let Some(result) = language::handle_language_choice(config, ctx, state, store, text)? {
+                return Ok(result);
+            }
+            // Not a language: leave the picker and route normally
+            *state = ConversationState::Idle;
+        }
         _ => {}
     }
 
+    // CANCEL <id> / RESCHEDULE <id> for bookings
+    if let Some(result) = booking::handle_booking_command(config, ctx, state, store, text).await? {
+        return Ok(result);
+    }
+
+    // REORDER / REORDER <id> refills the cart from a past order
+    if config.bookings.is_none()
+        && let Some(result) = order::handle_reorder_command(config, ctx, state, store, text)?
+    {
+        return Ok(result);
+    }
+
+    // RECEIPT <id> sends a PDF receipt
+    if let Some(result) = my_orders::handle_receipt_command(config, ctx, store, text)? {
+        return Ok(result);
+    }
+
+    // Config-defined flows take precedence over the built-in menu options
+    if let Some(flow_cfg) = config.flow_for_trigger(text) {
+        return Ok(flow::start_flow(flow_cfg, state));
+    }
+
+    // LANGUAGE opens the language picker
+    if language::is_language_command(text) {
+        return Ok(language::handle_language_command(config, ctx, state));
+    }
+
     // Text-based routing for idle state
     match text {
         // Main menu options
         "1" | "menu" => {
             return menu::MenuHandler.handle(config, ctx, state, store).await;
         }
-        "2" | "orders" | "my orders" => {
-            return handle_my_orders(config, ctx, store).await;
+        "2" | "orders" | "my orders" | "bookings" | "my bookings" => {
+            if config.bookings.is_some() {
+                return booking::handle_my_bookings(config, ctx, store).await;
+            }
+            return my_orders::handle_my_orders(config, ctx, state, store).await;
         }
         "3" | "voucher" | "redeem" => {
             *state = ConversationState::RedeemingVoucher;
-            return Ok(HandlerResult::Reply(
-                "🎟️ Enter your voucher code:".to_string(),
-            ));
+            return Ok(HandlerResult::Reply(config.i18n.text(ctx.lang, TranslationKey::EnterVoucherCode)));
         }
         "4" | "about" => {
-            let about = config
-                .business
-                .about
-                .as_deref()
-                .unwrap_or("Thanks for choosing us!");
-            return Ok(HandlerResult::Reply(about.to_string()));
+            let about = config.about(ctx.lang);
+            let Some(ref location) = config.business.location else {
+                return Ok(HandlerResult::Reply(about));
+            };
+            let pin = LocationPin {
+                latitude: location.latitude,
+                longitude: location.longitude,
+                name: Some(config.business.name.clone()),
+                address: location.address.clone(),
+            };
+            return Ok(HandlerResult::Messages(vec![
+                OutgoingMessage::Text(about),
+                OutgoingMessage::Location(pin),
+            ]));
         }
         _ => {}
     }
 
     // Default: show welcome message
-    Ok(HandlerResult::Reply(config.business.welcome.clone()))
+    Ok(HandlerResult::Reply(config.welcome(ctx.lang)))
 }
 
 /// Route an admin message. Checks for mode toggle, then dispatches based on state.
@@ -157,7 +274,9 @@ pub async fn route_admin_message(
              3. 🎟️ Create Voucher\n\n\
              Or type:\n\
              • DONE <id> — mark order delivered\n\
-             • VOUCHER <amount> — create voucher\n\n\
+             • VOUCHER <amount> — create voucher\n\
+             • ITEMS — list menu items\n\
+             • PRICE <n> <amount> / HIDE <n> / SHOW <n> — edit the menu\n\n\
              Type EXIT to return to customer view."
         )));
     }
@@ -165,7 +284,12 @@ pub async fn route_admin_message(
     // Toggle: "EXIT" leaves admin mode
     if matches!(state, ConversationState::AdminMode) && (text_upper == "EXIT" || text == "0") {
         *state = ConversationState::Idle;
-        return Ok(HandlerResult::Reply(config.business.welcome.clone()));
+        return Ok(HandlerResult::Reply(config.welcome(ctx.lang)));
+    }
+
+    // Menu edits work in and out of admin mode
+    if let Some(result) = menu::handle_admin_menu_command(config, store, text)? {
+        return Ok(result);
     }
 
     // If in admin mode, route numbers and commands to admin handlers
@@ -207,6 +331,9 @@ pub async fn route_admin_message(
              2 — Stats\n\
              3 — Create Voucher\n\
              DONE <id> — Mark delivered\n\
+             ITEMS — Menu items\n\
+             PRICE <n> <amount> — Change a price\n\
+             HIDE <n> / SHOW <n> — Hide or show an item\n\
              EXIT — Back to customer view"
                 .to_string(),
         ));
@@ -234,45 +361,6 @@ pub async fn route_admin_message(
     route_message(config, ctx, state, store).await
 }
 
-/// Handle "My Orders" — show recent orders for the customer.
-async fn handle_my_orders(
-    config: &HiveConfig,
-    ctx: &MessageContext,
-    store: &Store,
-) -> Result<HandlerResult> {
-    let orders = store.get_customer_orders(&ctx.sender, 5)?;
-
-    if orders.is_empty() {
-        return Ok(HandlerResult::Reply(
-            "📦 You don't have any orders yet.\n\nReply 1 to view our menu!".to_string(),
-        ));
-    }
-
-    let currency = &config.business.currency;
-    let mut lines = vec!["📦 *Your Recent Orders:*\n".to_string()];
-
-    for order in &orders {
-        let status_emoji = match order.status {
-            crate::store::OrderStatus::Pending => "⏳",
-            crate::store::OrderStatus::Confirmed => "✅",
-            crate::store::OrderStatus::Preparing => "🍳",
-            crate::store::OrderStatus::Delivering => "🚗",
-            crate::store::OrderStatus::Delivered => "🎉",
-            crate::store::OrderStatus::Cancelled => "❌",
-        };
-        lines.push(format!(
-            "{} Order #{} — {}{:.2} — {}",
-            status_emoji,
-            order.id,
-            currency,
-            order.total,
-            order.status.as_str()
-        ));
-    }
-
-    Ok(HandlerResult::Reply(lines.join("\n")))
-}
-
 /// Admin: mark an order as delivered.
 async fn handle_admin_done(
     config: &HiveConfig,
@@ -280,52 +368,45 @@ async fn handle_admin_done(
     store: &Store,
     order_id: i64,
 ) -> Result<HandlerResult> {
-    let order = store.get_order(order_id)?;
-    match order {
-        Some(order) => {
-            store.update_order_status(order_id, &crate::store::OrderStatus::Delivered)?;
-
-            // Format the delivery notification for the customer
-            let msg = crate::config::MessageTemplates::render(
-                &config.messages.order_delivered,
-                &[("id", &order_id.to_string())],
-            );
-
-            // Send delivery notification to customer via WhatsApp
-            let clean_number: String = order.customer_phone
-                .chars()
-                .filter(|c| c.is_ascii_digit())
-                .collect();
-            if !clean_number.is_empty() {
-                let customer_jid = wacore_binary::jid::Jid::pn(&clean_number);
-                let wa_msg = waproto::whatsapp::Message {
-                    extended_text_message: Some(Box::new(
-                        waproto::whatsapp::message::ExtendedTextMessage {
-                            text: Some(msg),
-                            ..Default::default()
-                        },
-                    )),
-                    ..Default::default()
-                };
-                if let Err(e) = ctx.wa_client.send_message(customer_jid, wa_msg).await {
-                    log::error!("Failed to notify customer {}: {}", order.customer_phone, e);
-                    return Ok(HandlerResult::Reply(format!(
-                        "✅ Order #{} marked as delivered.\n⚠️ Failed to notify customer: {}",
-                        order_id, e
-                    )));
-                }
+    let actor = format!("admin:{}", ctx.sender);
+    let order = match store.transition_order(order_id, &crate::store::OrderStatus::Delivered, &actor, None) {
+        Ok(Some(order)) => order,
+        Ok(None) => return Ok(HandlerResult::Reply(format!("❌ Order #{} not found.", order_id))),
+        Err(e) => match e.downcast_ref::<crate::store::InvalidTransition>() {
+            // Unpaid orders have to be confirmed before they can be delivered
+            Some(invalid) if invalid.from == crate::store::OrderStatus::Pending => {
+                return Ok(HandlerResult::Reply(format!(
+                    "❌ Order #{} can't be marked delivered from pending — confirm it first.",
+                    order_id
+                )));
             }
-
-            Ok(HandlerResult::Reply(format!(
-                "✅ Order #{} marked as delivered.\n📨 Customer {} has been notified.",
-                order_id, order.customer_phone
-            )))
-        }
-        None => Ok(HandlerResult::Reply(format!(
-            "❌ Order #{} not found.",
-            order_id
-        ))),
+            Some(invalid) => {
+                return Ok(HandlerResult::Reply(format!(
+                    "❌ Order #{} is already {}.",
+                    order_id,
+                    invalid.from.as_str()
+                )));
+            }
+            None => return Err(e),
+        },
+    };
+
+    // Send delivery notification to customer
+    let lang = config.customer_language(store, &order.customer_phone);
+    if let Some(msg) = order::order_status_message(config, lang, &order)
+        && let Err(e) = ctx.transport.send_to_phone(&order.customer_phone, &msg).await
+    {
+        log::error!("Failed to notify customer {}: {}", order.customer_phone, e);
+        return Ok(HandlerResult::Reply(format!(
+            "✅ Order #{} marked as delivered.\n⚠️ Failed to notify customer: {}",
+            order_id, e
+        )));
     }
+
+    Ok(HandlerResult::Reply(format!(
+        "✅ Order #{} marked as delivered.\n📨 Customer {} has been notified.",
+        order_id, order.customer_phone
+    )))
 }
 
 /// Admin: create a voucher.
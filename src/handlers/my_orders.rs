@@ -0,0 +1,462 @@
+//! "My Orders" — a customer's recent orders and what they can still change.
+//!
+//! Flow:
+//! 1. Customer opens My Orders and sees their last five orders with history
+//! 2. Replying with an order number opens that order
+//! 3. They can cancel it (while pending or confirmed) — a completed M-Pesa
+//!    payment is refunded automatically — or change the delivery address
+//!    (until it's out for delivery)
+//! 4. Admins are told about every change
+//!
+//! `RECEIPT <id>` sends a PDF receipt for one of the customer's orders.
+
+use super::{HandlerResult, MessageContext, order};
+use crate::bot::conversation::{ConversationState, OrderItem};
+use crate::config::HiveConfig;
+use crate::i18n::{self, Language, TranslationKey};
+use crate::payments::refunds;
+use crate::store::{InvalidTransition, OrderRecord, OrderStatus, Store};
+use crate::transport::DocumentMessage;
+use anyhow::Result;
+
+/// Show the customer's recent orders, each with its status history.
+pub async fn handle_my_orders(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+) -> Result<HandlerResult> {
+    let lang = ctx.lang;
+    let orders = store.get_customer_orders(&ctx.sender, 5)?;
+
+    if orders.is_empty() {
+        return Ok(HandlerResult::Reply(config.i18n.text(lang, TranslationKey::NoOrdersYet)));
+    }
+
+    let mut lines = vec![config.i18n.text(lang, TranslationKey::RecentOrders)];
+
+    for order in &orders {
+        lines.push(format!("\n{}", order_line(config, lang, order, TranslationKey::OrderLine)));
+        for event in store.order_events(order.id)? {
+            let label = match event.from_status {
+                None => config.i18n.text(lang, TranslationKey::OrderPlacedEvent),
+                Some(_) => status_label(config, &event.to_status, lang),
+            };
+            lines.push(format!(
+                "   {} {} · {}",
+                status_emoji(&event.to_status),
+                label,
+                local_time(config, &event.created_at, lang)
+            ));
+        }
+    }
+
+    lines.push(format!("\n{}", config.i18n.text(lang, TranslationKey::ReorderHint)));
+    lines.push(config.i18n.text(lang, TranslationKey::ReceiptHint));
+
+    let open: Vec<i64> = orders
+        .iter()
+        .filter(|o| can_cancel(&o.status) || can_change_location(&o.status))
+        .map(|o| o.id)
+        .collect();
+    if !open.is_empty() {
+        lines.push(config.i18n.text(lang, TranslationKey::ManageOrdersHint));
+        *state = ConversationState::ViewingOrders(open);
+    }
+
+    Ok(HandlerResult::Reply(lines.join("\n")))
+}
+
+/// Handle `RECEIPT <id>`: send a PDF receipt for one of the sender's
+/// orders. Returns `None` if `text` isn't a receipt command.
+pub fn handle_receipt_command(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    store: &Store,
+    text: &str,
+) -> Result<Option<HandlerResult>> {
+    let upper = text.to_uppercase();
+    let Some(rest) = upper.strip_prefix("RECEIPT ") else {
+        return Ok(None);
+    };
+    let Ok(order_id) = rest.trim().trim_start_matches('#').parse::<i64>() else {
+        return Ok(None);
+    };
+    let lang = ctx.lang;
+    let Some(order) = customer_order(ctx, store, order_id)? else {
+        return Ok(Some(no_such_order(config, lang, order_id)));
+    };
+
+    let title = config.i18n.render(lang, TranslationKey::ReceiptTitle, &[("id", &order.id.to_string())]);
+    let lines = receipt_lines(config, lang, &order);
+    let mut fallback = vec![format!("🧾 *{}*", config.business.name), format!("*{}*", title)];
+    fallback.extend(lines.iter().cloned());
+
+    Ok(Some(HandlerResult::Document(DocumentMessage {
+        data: crate::pdf::text_document(&config.business.name, &[vec![title.clone()], lines].concat()),
+        file_name: format!("receipt-{}.pdf", order.id),
+        mime_type: "application/pdf".to_string(),
+        caption: Some(title),
+        fallback: fallback.join("\n"),
+    })))
+}
+
+/// The body of a receipt: date, items, totals and status.
+fn receipt_lines(config: &HiveConfig, lang: Language, order: &OrderRecord) -> Vec<String> {
+    let currency = &config.business.currency;
+    let amount = |value: f64| format!("{}{:.2}", currency, value);
+    let items: Vec<OrderItem> = serde_json::from_str(&order.items_json).unwrap_or_default();
+
+    let mut lines = vec![local_time(config, &order.created_at, lang), String::new()];
+    for item in &items {
+        lines.push(order::in_language(config, lang, item).display(currency));
+    }
+    lines.push(String::new());
+    lines.push(config.i18n.render(lang, TranslationKey::Subtotal, &[("amount", &amount(order.subtotal))]));
+    if order.delivery_fee > 0.0 {
+        lines.push(config.i18n.render(lang, TranslationKey::DeliveryCharge, &[("amount", &amount(order.delivery_fee))]));
+    }
+    lines.push(config.i18n.render(lang, TranslationKey::Total, &[("amount", &amount(order.total))]));
+    lines.push(config.i18n.render(
+        lang,
+        TranslationKey::ReceiptStatus,
+        &[("status", &status_label(config, &order.status, lang))],
+    ));
+    lines
+}
+
+/// Open one of the orders listed in My Orders. Returns `None` if `text`
+/// isn't one of them, so the message can be routed normally.
+pub fn handle_order_choice(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    text: &str,
+) -> Result<Option<HandlerResult>> {
+    let ConversationState::ViewingOrders(ref open) = *state else {
+        return Ok(None);
+    };
+    let Ok(order_id) = text.trim_start_matches('#').parse::<i64>() else {
+        return Ok(None);
+    };
+    if !open.contains(&order_id) {
+        return Ok(None);
+    }
+    show_order_options(config, ctx, state, store, order_id).map(Some)
+}
+
+/// Handle a reply while managing, cancelling or re-addressing an order.
+pub async fn handle_manage_order(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    text: &str,
+) -> Result<HandlerResult> {
+    match state.clone() {
+        ConversationState::ManagingOrder(order_id) => {
+            handle_order_option(config, ctx, state, store, order_id, text)
+        }
+        ConversationState::ConfirmingOrderCancel(order_id) => {
+            handle_cancel_confirmation(config, ctx, state, store, order_id, text).await
+        }
+        ConversationState::ChangingOrderLocation(order_id) => {
+            handle_new_location(config, ctx, state, store, order_id, text).await
+        }
+        _ => Ok(HandlerResult::NoReply),
+    }
+}
+
+/// Customers may cancel until the kitchen starts on the order.
+fn can_cancel(status: &OrderStatus) -> bool {
+    matches!(status, OrderStatus::Pending | OrderStatus::Confirmed)
+}
+
+/// Customers may move an order until it's out for delivery.
+fn can_change_location(status: &OrderStatus) -> bool {
+    matches!(
+        status,
+        OrderStatus::Pending | OrderStatus::Confirmed | OrderStatus::Preparing
+    )
+}
+
+/// "✅ Order #3 — KES 45.00 — confirmed", from `key` (`OrderLine` or `OrderHeader`).
+fn order_line(config: &HiveConfig, lang: Language, order: &OrderRecord, key: TranslationKey) -> String {
+    config.i18n.render(
+        lang,
+        key,
+        &[
+            ("emoji", status_emoji(&order.status)),
+            ("id", &order.id.to_string()),
+            ("total", &format!("{}{:.2}", config.business.currency, order.total)),
+            ("status", &status_label(config, &order.status, lang).to_lowercase()),
+        ],
+    )
+}
+
+fn no_such_order(config: &HiveConfig, lang: Language, order_id: i64) -> HandlerResult {
+    HandlerResult::Reply(config.i18n.render(lang, TranslationKey::NoSuchOrder, &[("id", &order_id.to_string())]))
+}
+
+/// The sender's order `order_id`, if it is theirs.
+fn customer_order(ctx: &MessageContext, store: &Store, order_id: i64) -> Result<Option<OrderRecord>> {
+    Ok(store
+        .get_order(order_id)?
+        .filter(|o| o.customer_phone == ctx.sender))
+}
+
+fn show_order_options(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    order_id: i64,
+) -> Result<HandlerResult> {
+    let lang = ctx.lang;
+    let Some(order) = customer_order(ctx, store, order_id)? else {
+        *state = ConversationState::Idle;
+        return Ok(no_such_order(config, lang, order_id));
+    };
+    if !can_cancel(&order.status) && !can_change_location(&order.status) {
+        *state = ConversationState::Idle;
+        return Ok(HandlerResult::Reply(config.i18n.render(
+            lang,
+            TranslationKey::OrderLocked,
+            &[
+                ("emoji", status_emoji(&order.status)),
+                ("id", &order.id.to_string()),
+                ("status", &status_label(config, &order.status, lang).to_lowercase()),
+            ],
+        )));
+    }
+
+    let mut lines = vec![order_line(config, lang, &order, TranslationKey::OrderHeader)];
+    if let Some(ref location) = order.location {
+        lines.push(format!("📍 {}", location));
+    }
+    lines.push(String::new());
+    if can_cancel(&order.status) {
+        lines.push(format!("1. {}", config.i18n.text(lang, TranslationKey::CancelOrderOption)));
+    }
+    if can_change_location(&order.status) {
+        lines.push(format!("2. {}", config.i18n.text(lang, TranslationKey::ChangeAddressOption)));
+    }
+    lines.push(format!("\n0. {}", config.i18n.text(lang, TranslationKey::BackOption)));
+
+    *state = ConversationState::ManagingOrder(order.id);
+    Ok(HandlerResult::Reply(lines.join("\n")))
+}
+
+fn handle_order_option(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    order_id: i64,
+    text: &str,
+) -> Result<HandlerResult> {
+    let lang = ctx.lang;
+    let Some(order) = customer_order(ctx, store, order_id)? else {
+        *state = ConversationState::Idle;
+        return Ok(no_such_order(config, lang, order_id));
+    };
+    let id = order.id.to_string();
+
+    match text {
+        "1" if can_cancel(&order.status) => {
+            let mut prompt = config.i18n.render(lang, TranslationKey::ConfirmCancel, &[("id", &id)]);
+            let refund: f64 = refunds::refundable_payments(store, order.id)?
+                .iter()
+                .map(|p| p.amount)
+                .sum();
+            if refund > 0.0 {
+                let amount = format!("{}{:.2}", config.business.currency, refund);
+                prompt.push_str(&format!(
+                    "\n{}",
+                    config.i18n.render(lang, TranslationKey::RefundNotice, &[("amount", &amount)])
+                ));
+            }
+            prompt.push_str(&format!("\n\n{}", config.i18n.text(lang, TranslationKey::CancelOrKeep)));
+            *state = ConversationState::ConfirmingOrderCancel(order.id);
+            Ok(HandlerResult::Reply(prompt))
+        }
+        "2" if can_change_location(&order.status) => {
+            *state = ConversationState::ChangingOrderLocation(order.id);
+            Ok(HandlerResult::Reply(config.i18n.render(lang, TranslationKey::SendNewAddress, &[("id", &id)])))
+        }
+        // Status moved on, or not an option: show what's possible now
+        _ => show_order_options(config, ctx, state, store, order_id),
+    }
+}
+
+async fn handle_cancel_confirmation(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    order_id: i64,
+    text: &str,
+) -> Result<HandlerResult> {
+    let lang = ctx.lang;
+    let id = order_id.to_string();
+    if i18n::is_no(text) {
+        *state = ConversationState::Idle;
+        return Ok(HandlerResult::Reply(config.i18n.render(lang, TranslationKey::OrderUnchanged, &[("id", &id)])));
+    }
+    if !i18n::is_yes(text) {
+        return Ok(HandlerResult::Reply(config.i18n.render(lang, TranslationKey::CancelOrKeepOrder, &[("id", &id)])));
+    }
+    *state = ConversationState::Idle;
+
+    // The kitchen may have started on it since the customer asked
+    let order = match customer_order(ctx, store, order_id)? {
+        Some(order) if can_cancel(&order.status) => order,
+        Some(order) => {
+            return Ok(HandlerResult::Reply(config.i18n.render(
+                lang,
+                TranslationKey::TooLateToCancel,
+                &[("id", &id), ("status", &status_label(config, &order.status, lang).to_lowercase())],
+            )));
+        }
+        None => return Ok(no_such_order(config, lang, order_id)),
+    };
+
+    let actor = format!("customer:{}", ctx.sender);
+    let order = match store.transition_order(order.id, &OrderStatus::Cancelled, &actor, Some("Cancelled by customer")) {
+        Ok(Some(order)) => order,
+        Ok(None) => return Ok(no_such_order(config, lang, order_id)),
+        Err(e) => match e.downcast_ref::<InvalidTransition>() {
+            Some(invalid) => {
+                return Ok(HandlerResult::Reply(config.i18n.render(
+                    lang,
+                    TranslationKey::OrderAlreadyStatus,
+                    &[("id", &id), ("status", &status_label(config, &invalid.from, lang).to_lowercase())],
+                )));
+            }
+            None => return Err(e),
+        },
+    };
+
+    let currency = &config.business.currency;
+    let mut customer_lines = vec![order::order_status_message(config, lang, &order)
+        .unwrap_or_else(|| config.i18n.render(lang, TranslationKey::OrderCancelledNotice, &[("id", &id)]))];
+    let mut admin_lines = vec![format!(
+        "❌ Order #{} ({}{:.2}) was cancelled by {}",
+        order.id, currency, order.total, ctx.sender
+    )];
+
+    // Pay back whatever the customer already paid
+    for payment in refunds::refundable_payments(store, order.id)? {
+        let (customer_line, admin_line) = refunds::refund_and_describe(
+            store,
+            ctx.refund_provider.as_deref(),
+            &payment,
+            "Cancelled by customer",
+            config,
+            lang,
+        )
+        .await;
+        customer_lines.push(customer_line);
+        admin_lines.push(admin_line);
+    }
+
+    notify_admins(config, ctx, &admin_lines.join("\n")).await;
+    Ok(HandlerResult::Reply(customer_lines.join("\n")))
+}
+
+async fn handle_new_location(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    order_id: i64,
+    text: &str,
+) -> Result<HandlerResult> {
+    let lang = ctx.lang;
+    let location = match ctx.location_text {
+        Some(ref loc) => loc.clone(),
+        None if !text.is_empty() => text.to_string(),
+        None => return Ok(HandlerResult::Reply(config.i18n.text(lang, TranslationKey::SendAddress))),
+    };
+    *state = ConversationState::Idle;
+
+    let order = match customer_order(ctx, store, order_id)? {
+        Some(order) if can_change_location(&order.status) => order,
+        Some(order) => {
+            return Ok(HandlerResult::Reply(config.i18n.render(
+                lang,
+                TranslationKey::TooLateToChangeAddress,
+                &[
+                    ("id", &order.id.to_string()),
+                    ("status", &status_label(config, &order.status, lang).to_lowercase()),
+                ],
+            )));
+        }
+        None => return Ok(no_such_order(config, lang, order_id)),
+    };
+
+    store.set_order_location(order.id, &location)?;
+    let previous = order.location.as_deref().unwrap_or("none");
+    notify_admins(
+        config,
+        ctx,
+        &format!(
+            "📍 Order #{} has a new delivery address:\n{}\n(was: {})",
+            order.id, location, previous
+        ),
+    )
+    .await;
+
+    Ok(HandlerResult::Reply(config.i18n.render(
+        lang,
+        TranslationKey::NewAddressSaved,
+        &[("id", &order.id.to_string()), ("address", &location)],
+    )))
+}
+
+async fn notify_admins(config: &HiveConfig, ctx: &MessageContext, msg: &str) {
+    for admin_number in &config.admin_numbers {
+        if let Err(e) = ctx.transport.send_to_phone(admin_number, msg).await {
+            log::error!("Failed to notify admin {}: {}", admin_number, e);
+        }
+    }
+}
+
+fn status_emoji(status: &OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::Pending => "⏳",
+        OrderStatus::Confirmed => "✅",
+        OrderStatus::Preparing => "🍳",
+        OrderStatus::Delivering => "🚗",
+        OrderStatus::Delivered => "🎉",
+        OrderStatus::Cancelled => "❌",
+    }
+}
+
+fn status_label(config: &HiveConfig, status: &OrderStatus, lang: Language) -> String {
+    let key = match status {
+        OrderStatus::Pending => TranslationKey::StatusPending,
+        OrderStatus::Confirmed => TranslationKey::StatusConfirmed,
+        OrderStatus::Preparing => TranslationKey::StatusPreparing,
+        OrderStatus::Delivering => TranslationKey::StatusDelivering,
+        OrderStatus::Delivered => TranslationKey::StatusDelivered,
+        OrderStatus::Cancelled => TranslationKey::StatusCancelled,
+    };
+    config.i18n.text(lang, key)
+}
+
+/// A stored UTC timestamp as the business's local time, e.g. "2 Mar 14:05".
+/// Uses the bookings time zone when one is configured.
+fn local_time(config: &HiveConfig, timestamp: &str, lang: Language) -> String {
+    let Ok(utc) = chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S") else {
+        return timestamp.to_string();
+    };
+    let offset = config
+        .bookings
+        .as_ref()
+        .map(|b| b.offset())
+        .unwrap_or_else(|| chrono::FixedOffset::east_opt(0).unwrap());
+    let local = utc + offset;
+    format!("{} {}", config.i18n.format_short_date(lang, local.date()), local.format("%H:%M"))
+}
@@ -2,14 +2,25 @@
 //!
 //! Manages the full order lifecycle:
 //! 1. User selects items from menu (by number, supports "1,3,5" or "1")
+//!    and picks a variant and add-ons for items that have them
 //! 2. User reviews order summary and confirms
-//! 3. User sends delivery location
+//! 3. User sends delivery location (or reuses the one from their last order)
 //! 4. Order is saved, admin is notified
+//!
+//! Returning customers can skip step 1 with `REORDER`, which refills the
+//! cart from a previous order.
+//!
+//! After that the business moves the order along (see
+//! `OrderStatus::can_transition_to`) and the customer gets an update for
+//! each step from `order_status_message`.
 
-use super::{HandlerResult, MessageContext, MessageHandler};
-use crate::bot::conversation::{ConversationState, Order, OrderItem};
-use crate::config::{HiveConfig, MessageTemplates};
-use crate::store::Store;
+use super::{menu, HandlerResult, MessageContext, MessageHandler};
+use crate::bot::conversation::{ConversationState, ItemChoice, Order, OrderItem};
+use crate::config::{HiveConfig, MenuAddon, MenuItem, MessageTemplates};
+use crate::i18n::{self, Language, TranslationKey};
+use crate::store::{OrderRecord, OrderStatus, OutOfStock, Store};
+use crate::payments::PaymentProvider;
+use crate::transport::{ButtonsMessage, Choice, ListMessage};
 use anyhow::Result;
 use async_trait::async_trait;
 
@@ -21,7 +32,9 @@ impl MessageHandler for OrderHandler {
         matches!(
             state,
             ConversationState::ViewingMenu
+                | ConversationState::ViewingCategory(_)
                 | ConversationState::BuildingOrder(_)
+                | ConversationState::ChoosingItemOptions(_)
                 | ConversationState::ConfirmingOrder(_)
                 | ConversationState::AwaitingLocation(_)
         )
@@ -37,11 +50,14 @@ impl MessageHandler for OrderHandler {
         let text = ctx.text.trim();
 
         match state.clone() {
-            ConversationState::ViewingMenu => {
-                handle_item_selection(config, ctx, state, text)
+            ConversationState::ViewingMenu | ConversationState::ViewingCategory(_) => {
+                handle_item_selection(config, ctx, state, text, store)
+            }
+            ConversationState::ChoosingItemOptions(choice) => {
+                handle_item_options(config, ctx.lang, state, choice, text, store)
             }
             ConversationState::BuildingOrder(cart) => {
-                handle_building_order(config, ctx, state, &cart, text)
+                handle_building_order(config, ctx, state, &cart, text, store)
             }
             ConversationState::ConfirmingOrder(order) => {
                 handle_order_confirmation(config, ctx, state, order, text, store)
@@ -55,7 +71,7 @@ impl MessageHandler for OrderHandler {
 }
 
 /// Parse item selections like "1", "1,3,5", "1 3 5", or "2x1" (2 of item 1).
-fn parse_item_selections(text: &str) -> Vec<(usize, u32)> {
+pub(crate) fn parse_item_selections(text: &str) -> Vec<(usize, u32)> {
     let mut selections = Vec::new();
 
     // Split by comma, space, or newline
@@ -86,170 +102,508 @@ fn parse_item_selections(text: &str) -> Vec<(usize, u32)> {
 /// Handle item selection from the menu.
 fn handle_item_selection(
     config: &HiveConfig,
-    _ctx: &MessageContext,
+    ctx: &MessageContext,
     state: &mut ConversationState,
     text: &str,
+    store: &Store,
 ) -> Result<HandlerResult> {
-    let available = config.available_menu();
+    let available = menu::listed_menu(config, store, state);
     let selections = parse_item_selections(text);
 
     if selections.is_empty() {
-        return Ok(HandlerResult::Reply(
-            "Please reply with item number(s) to order.\ne.g. *1* or *1,3,5* or *2x1* (2 of item 1)\n\nReply *0* to go back."
-                .to_string(),
-        ));
+        return Ok(HandlerResult::Reply(config.i18n.text(ctx.lang, TranslationKey::ItemSelectionHelp)));
     }
 
-    let mut cart = Vec::new();
+    let mut picks = Vec::new();
     let mut invalid = Vec::new();
 
     for (idx, qty) in &selections {
         if *idx > 0 && *idx <= available.len() {
-            let item = &available[idx - 1];
-            cart.push(OrderItem {
-                name: item.name.clone(),
-                price: item.price,
-                quantity: *qty,
-                emoji: item.emoji.clone(),
-            });
+            picks.push((available[idx - 1].clone(), *qty));
         } else {
             invalid.push(idx.to_string());
         }
     }
 
-    if cart.is_empty() {
-        return Ok(HandlerResult::Reply(format!(
-            "❌ Invalid item number(s): {}. Please check the menu and try again.",
-            invalid.join(", ")
+    if picks.is_empty() {
+        return Ok(HandlerResult::Reply(config.i18n.render(
+            ctx.lang,
+            TranslationKey::InvalidItems,
+            &[("items", &invalid.join(", "))],
         )));
     }
 
+    add_picks(config, ctx.lang, state, store, Vec::new(), picks, &invalid)
+}
+
+/// Check a cart against stock levels. Returns a reply for the customer if
+/// an item doesn't have enough left.
+fn check_stock(config: &HiveConfig, store: &Store, cart: &[OrderItem], lang: Language) -> Result<Option<String>> {
+    for item in cart {
+        let wanted: u32 = cart
+            .iter()
+            .filter(|i| i.name == item.name)
+            .map(|i| i.quantity)
+            .sum();
+        if let Some(left) = store.get_stock(&item.name)?
+            && left < wanted as i64
+        {
+            return Ok(Some(config.i18n.render(
+                lang,
+                TranslationKey::OnlyLeft,
+                &[("left", &left.max(0).to_string()), ("item", config.i18n.item_name(lang, &item.name))],
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// `item` with its name in `lang`, for showing to the customer.
+pub fn in_language(config: &HiveConfig, lang: Language, item: &OrderItem) -> OrderItem {
+    OrderItem {
+        name: config.i18n.item_name(lang, &item.name).to_string(),
+        ..item.clone()
+    }
+}
+
+/// Add an item to the cart, merging it with an identical line if there is one.
+fn add_to_cart(cart: &mut Vec<OrderItem>, item: OrderItem) {
+    match cart.iter_mut().find(|i| i.same_choice(&item)) {
+        Some(existing) => existing.quantity += item.quantity,
+        None => cart.push(item),
+    }
+}
+
+/// Add picked menu items to `cart`. Items with variants or add-ons are
+/// queued and the customer is asked about them one at a time; otherwise
+/// the order summary is shown.
+fn add_picks(
+    config: &HiveConfig,
+    lang: Language,
+    state: &mut ConversationState,
+    store: &Store,
+    mut cart: Vec<OrderItem>,
+    picks: Vec<(MenuItem, u32)>,
+    invalid: &[String],
+) -> Result<HandlerResult> {
+    // Check stock up front so nobody picks options for something sold out
+    let mut wanted = cart.clone();
+    wanted.extend(picks.iter().map(|(item, qty)| OrderItem::from_menu(item, *qty)));
+    if let Some(reply) = check_stock(config, store, &wanted, lang)? {
+        return Ok(HandlerResult::Reply(reply));
+    }
+
+    let mut pending = Vec::new();
+    for (item, qty) in picks {
+        if item.has_options() {
+            pending.push((item.name.clone(), qty));
+        } else {
+            add_to_cart(&mut cart, OrderItem::from_menu(&item, qty));
+        }
+    }
+
+    if pending.is_empty() {
+        return Ok(confirm_cart(config, lang, state, cart, invalid));
+    }
+
+    let choice = ItemChoice {
+        cart,
+        pending,
+        variant: None,
+    };
+    next_option_prompt(config, lang, state, store, choice)
+}
+
+/// Ask about the next pending item, or show the summary once none are left.
+fn next_option_prompt(
+    config: &HiveConfig,
+    lang: Language,
+    state: &mut ConversationState,
+    store: &Store,
+    mut choice: ItemChoice,
+) -> Result<HandlerResult> {
+    while let Some((name, _)) = choice.pending.first() {
+        // The item may have been removed from the menu in the meantime
+        let Some(item) = store.find_menu_item(name)?.map(|r| r.to_menu_item()) else {
+            choice.pending.remove(0);
+            choice.variant = None;
+            continue;
+        };
+
+        let currency = &config.business.currency;
+        let emoji = item.emoji.as_deref().unwrap_or("•");
+        let mut lines = Vec::new();
+        let vars = [("emoji", emoji), ("item", config.i18n.item_name(lang, &item.name))];
+        if !item.variants.is_empty() && choice.variant.is_none() {
+            lines.push(format!("{}\n", config.i18n.render(lang, TranslationKey::ChooseVariant, &vars)));
+            for (i, variant) in item.variants.iter().enumerate() {
+                lines.push(format!("{}. {} — {}{:.2}", i + 1, variant.name, currency, variant.price));
+            }
+            lines.push(format!("\n{}", config.i18n.text(lang, TranslationKey::ReplyWithNumber)));
+        } else {
+            lines.push(format!("{}\n", config.i18n.render(lang, TranslationKey::ChooseAddons, &vars)));
+            for (i, addon) in item.addons.iter().enumerate() {
+                lines.push(format!("{}. {} +{}{:.2}", i + 1, addon.name, currency, addon.price));
+            }
+            lines.push(format!("\n{}", config.i18n.text(lang, TranslationKey::AddonsHint)));
+        }
+
+        *state = ConversationState::ChoosingItemOptions(choice);
+        return Ok(HandlerResult::Reply(lines.join("\n")));
+    }
+
+    Ok(confirm_cart(config, lang, state, choice.cart, &[]))
+}
+
+/// Handle a variant or add-on answer for the first pending item.
+fn handle_item_options(
+    config: &HiveConfig,
+    lang: Language,
+    state: &mut ConversationState,
+    mut choice: ItemChoice,
+    text: &str,
+    store: &Store,
+) -> Result<HandlerResult> {
+    let Some((name, quantity)) = choice.pending.first().cloned() else {
+        return Ok(confirm_cart(config, lang, state, choice.cart, &[]));
+    };
+    let Some(item) = store.find_menu_item(&name)?.map(|r| r.to_menu_item()) else {
+        return next_option_prompt(config, lang, state, store, choice);
+    };
+
+    // Variant step
+    if !item.variants.is_empty() && choice.variant.is_none() {
+        let variant = match text.parse::<usize>() {
+            Ok(n) => n.checked_sub(1).and_then(|i| item.variants.get(i)),
+            Err(_) => item.variants.iter().find(|v| v.name.eq_ignore_ascii_case(text)),
+        };
+        let Some(variant) = variant else {
+            return Ok(HandlerResult::Reply(config.i18n.render(
+                lang,
+                TranslationKey::NumberRange,
+                &[("max", &item.variants.len().to_string())],
+            )));
+        };
+        choice.variant = Some(variant.name.clone());
+        if !item.addons.is_empty() {
+            return next_option_prompt(config, lang, state, store, choice);
+        }
+        return finish_item(config, lang, state, store, choice, &item, quantity, Vec::new());
+    }
+
+    // Add-on step
+    let upper = text.to_uppercase();
+    let addons = if i18n::is_no(text) || matches!(upper.as_str(), "NONE" | "SKIP") {
+        Vec::new()
+    } else {
+        let picked: Vec<usize> = parse_item_selections(text).into_iter().map(|(idx, _)| idx).collect();
+        if picked.is_empty() || picked.iter().any(|idx| *idx > item.addons.len()) {
+            return Ok(HandlerResult::Reply(config.i18n.render(
+                lang,
+                TranslationKey::AddonRange,
+                &[("max", &item.addons.len().to_string())],
+            )));
+        }
+        let mut addons: Vec<usize> = Vec::new();
+        for idx in picked {
+            if !addons.contains(&idx) {
+                addons.push(idx);
+            }
+        }
+        addons
+    };
+    finish_item(config, lang, state, store, choice, &item, quantity, addons)
+}
+
+/// Put the first pending item in the cart with the chosen options, then move on.
+#[allow(clippy::too_many_arguments)]
+fn finish_item(
+    config: &HiveConfig,
+    lang: Language,
+    state: &mut ConversationState,
+    store: &Store,
+    mut choice: ItemChoice,
+    item: &MenuItem,
+    quantity: u32,
+    addons: Vec<usize>,
+) -> Result<HandlerResult> {
+    let variant = choice.variant.take();
+    let base = variant
+        .as_ref()
+        .and_then(|name| item.variants.iter().find(|v| &v.name == name))
+        .map(|v| v.price)
+        .unwrap_or(item.price);
+    let addons: Vec<&MenuAddon> = addons.iter().map(|idx| &item.addons[idx - 1]).collect();
+
+    let line = OrderItem {
+        price: base + addons.iter().map(|a| a.price).sum::<f64>(),
+        variant,
+        addons: addons.iter().map(|a| a.name.clone()).collect(),
+        ..OrderItem::from_menu(item, quantity)
+    };
+    add_to_cart(&mut choice.cart, line);
+    choice.pending.remove(0);
+
+    next_option_prompt(config, lang, state, store, choice)
+}
+
+/// Show the order summary, with confirm and cancel buttons, and move to
+/// `ConfirmingOrder`.
+fn confirm_cart(
+    config: &HiveConfig,
+    lang: Language,
+    state: &mut ConversationState,
+    cart: Vec<OrderItem>,
+    invalid: &[String],
+) -> HandlerResult {
     let currency = &config.business.currency;
-    let delivery_fee = config.delivery.as_ref().map(|d| d.fee).unwrap_or(0.0);
+    let delivery_fee = delivery_fee(config);
+    let order = Order::from_cart(cart, delivery_fee);
 
-    // Build order summary
-    let subtotal: f64 = cart.iter().map(|i| i.subtotal()).sum();
-    let total = subtotal + delivery_fee;
+    let amount = |value: f64| format!("{}{:.2}", currency, value);
 
-    let mut lines = vec!["🛒 *Your Order:*\n".to_string()];
-    for item in &cart {
-        lines.push(format!("  {}", item.display(currency)));
+    let mut lines = vec![format!("{}\n", config.i18n.text(lang, TranslationKey::YourOrder))];
+    for item in &order.items {
+        lines.push(format!("  {}", in_language(config, lang, item).display(currency)));
     }
-    lines.push(format!("\nSubtotal: {}{:.2}", currency, subtotal));
+    lines.push(format!(
+        "\n{}",
+        config.i18n.render(lang, TranslationKey::Subtotal, &[("amount", &amount(order.subtotal))])
+    ));
     if delivery_fee > 0.0 {
-        lines.push(format!("Delivery: {}{:.2}", currency, delivery_fee));
+        lines.push(config.i18n.render(lang, TranslationKey::DeliveryCharge, &[("amount", &amount(delivery_fee))]));
     }
-    lines.push(format!("*Total: {}{:.2}*", currency, total));
+    lines.push(config.i18n.render(lang, TranslationKey::Total, &[("amount", &amount(order.total))]));
     lines.push("\n━━━━━━━━━━━━━━━━━━━".to_string());
-    lines.push("Reply *YES* to confirm".to_string());
-    lines.push("Reply *ADD* + numbers to add more items".to_string());
-    lines.push("Reply *0* to cancel".to_string());
+    lines.push(config.i18n.text(lang, TranslationKey::ConfirmOrderHint));
+    lines.push(config.i18n.text(lang, TranslationKey::AddMoreHint));
+    lines.push(config.i18n.text(lang, TranslationKey::CancelHint));
 
     if !invalid.is_empty() {
         lines.push(format!(
-            "\n⚠️ Skipped invalid items: {}",
-            invalid.join(", ")
+            "\n{}",
+            config.i18n.render(lang, TranslationKey::SkippedItems, &[("items", &invalid.join(", "))])
         ));
     }
 
-    let order = Order::from_cart(cart, delivery_fee);
     *state = ConversationState::ConfirmingOrder(order);
 
-    Ok(HandlerResult::Reply(lines.join("\n")))
+    HandlerResult::Buttons(ButtonsMessage {
+        text: lines.join("\n"),
+        buttons: vec![
+            Choice::new("YES", config.i18n.text(lang, TranslationKey::ConfirmButton)),
+            Choice::new("0", config.i18n.text(lang, TranslationKey::CancelButton)),
+        ],
+    })
 }
 
 /// Handle modifications while building an order.
 fn handle_building_order(
     config: &HiveConfig,
-    _ctx: &MessageContext,
+    ctx: &MessageContext,
     state: &mut ConversationState,
     cart: &[OrderItem],
     text: &str,
+    store: &Store,
 ) -> Result<HandlerResult> {
-    let available = config.available_menu();
+    // A reordered cart can go straight to checkout
+    if i18n::is_yes(text) {
+        if let Some(reply) = check_stock(config, store, cart, ctx.lang)? {
+            return Ok(HandlerResult::Reply(reply));
+        }
+        let order = Order::from_cart(cart.to_vec(), delivery_fee(config));
+        return ask_for_location(config, ctx, state, store, order);
+    }
+
+    let available = config.available_menu(store);
 
     // If they type a number, add to cart
     let selections = parse_item_selections(text);
     if !selections.is_empty() {
-        let mut new_cart = cart.to_vec();
-        for (idx, qty) in &selections {
-            if *idx > 0 && *idx <= available.len() {
-                let item = &available[idx - 1];
-                // Check if already in cart, increase quantity
-                if let Some(existing) = new_cart.iter_mut().find(|i| i.name == item.name) {
-                    existing.quantity += qty;
-                } else {
-                    new_cart.push(OrderItem {
-                        name: item.name.clone(),
-                        price: item.price,
-                        quantity: *qty,
-                        emoji: item.emoji.clone(),
-                    });
-                }
-            }
-        }
-
-        let delivery_fee = config.delivery.as_ref().map(|d| d.fee).unwrap_or(0.0);
-        let order = Order::from_cart(new_cart, delivery_fee);
-        *state = ConversationState::ConfirmingOrder(order.clone());
-
-        let currency = &config.business.currency;
-        let mut lines = vec!["🛒 *Updated Order:*\n".to_string()];
-        for item in &order.items {
-            lines.push(format!("  {}", item.display(currency)));
-        }
-        lines.push(format!("\n*Total: {}{:.2}*", currency, order.total));
-        lines.push("\nReply *YES* to confirm or *0* to cancel".to_string());
-
-        return Ok(HandlerResult::Reply(lines.join("\n")));
+        let picks: Vec<(MenuItem, u32)> = selections
+            .iter()
+            .filter(|(idx, _)| *idx > 0 && *idx <= available.len())
+            .map(|(idx, qty)| (available[idx - 1].clone(), *qty))
+            .collect();
+        return add_picks(config, ctx.lang, state, store, cart.to_vec(), picks, &[]);
     }
 
-    Ok(HandlerResult::Reply(
-        "Reply with item numbers to add, or *0* to cancel.".to_string(),
-    ))
+    Ok(HandlerResult::Reply(config.i18n.text(ctx.lang, TranslationKey::CartHelp)))
 }
 
 /// Handle order confirmation (YES/NO).
 fn handle_order_confirmation(
     config: &HiveConfig,
-    _ctx: &MessageContext,
+    ctx: &MessageContext,
     state: &mut ConversationState,
     order: Order,
     text: &str,
-    _store: &Store,
+    store: &Store,
 ) -> Result<HandlerResult> {
-    let upper = text.to_uppercase();
-
-    if upper == "YES" || upper == "Y" || upper == "CONFIRM" {
-        // Move to location phase
-        *state = ConversationState::AwaitingLocation(order);
-
-        let delivery_msg = if config.delivery.is_some() {
-            "📍 Great! Now send your *delivery address* or share your *location*."
-        } else {
-            "📍 Please send your address for the order."
-        };
-
-        return Ok(HandlerResult::Reply(delivery_msg.to_string()));
+    if i18n::is_yes(text) {
+        return ask_for_location(config, ctx, state, store, order);
     }
 
-    if upper.starts_with("ADD") {
+    if text.to_uppercase().starts_with("ADD") {
         // Go back to adding items
         let cart = order.items;
         *state = ConversationState::BuildingOrder(cart);
-        return Ok(HandlerResult::Reply(
-            "📋 Send item number(s) to add to your order:".to_string(),
-        ));
+        return Ok(HandlerResult::Reply(config.i18n.render(
+            ctx.lang,
+            TranslationKey::AddItemsPrompt,
+            &[("menu", &menu::format_menu_compact(config, store, ctx.lang))],
+        )));
     }
 
     // Show summary again
-    let currency = &config.business.currency;
-    Ok(HandlerResult::Reply(format!(
-        "🛒 Your order total: {}{:.2}\n\nReply *YES* to confirm or *0* to cancel.",
-        currency, order.total
+    let total = format!("{}{:.2}", config.business.currency, order.total);
+    Ok(HandlerResult::Reply(config.i18n.render(
+        ctx.lang,
+        TranslationKey::OrderTotalPrompt,
+        &[("total", &total)],
     )))
 }
 
+/// Move to the location phase, offering the address from the customer's
+/// last order if there is one.
+fn ask_for_location(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    order: Order,
+) -> Result<HandlerResult> {
+    *state = ConversationState::AwaitingLocation(order);
+
+    let mut prompt = if config.delivery.is_some() {
+        config.i18n.text(ctx.lang, TranslationKey::AskDeliveryLocation)
+    } else {
+        config.i18n.text(ctx.lang, TranslationKey::AskAddress)
+    };
+    if let Some(last) = store.last_delivery_location(&ctx.sender)? {
+        prompt.push_str(&format!(
+            "\n\n{}",
+            config.i18n.render(ctx.lang, TranslationKey::UseLastAddress, &[("address", &last)])
+        ));
+    }
+
+    Ok(HandlerResult::Reply(prompt))
+}
+
+fn delivery_fee(config: &HiveConfig) -> f64 {
+    config.delivery.as_ref().map(|d| d.fee).unwrap_or(0.0)
+}
+
+/// Handle `REORDER` (the customer's last order) and `REORDER <id>`: refill
+/// the cart from that order at today's prices. Returns `None` if `text`
+/// isn't a reorder command.
+pub fn handle_reorder_command(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    text: &str,
+) -> Result<Option<HandlerResult>> {
+    let upper = text.to_uppercase();
+    let previous = if upper == "REORDER" || upper == "ORDER AGAIN" {
+        store.get_customer_orders(&ctx.sender, 1)?.into_iter().next()
+    } else if let Some(rest) = upper.strip_prefix("REORDER ") {
+        let Ok(id) = rest.trim().trim_start_matches('#').parse::<i64>() else {
+            return Ok(None);
+        };
+        // Customers may only reorder their own orders
+        store.get_order(id)?.filter(|o| o.customer_phone == ctx.sender)
+    } else {
+        return Ok(None);
+    };
+    let Some(previous) = previous else {
+        return Ok(Some(HandlerResult::Reply(config.i18n.text(ctx.lang, TranslationKey::NoPreviousOrder))));
+    };
+
+    let items: Vec<OrderItem> = serde_json::from_str(&previous.items_json).unwrap_or_default();
+    let (cart, gone) = rebuild_cart(&config.available_menu(store), items);
+    if cart.is_empty() {
+        return Ok(Some(HandlerResult::Reply(config.i18n.render(
+            ctx.lang,
+            TranslationKey::NothingToReorder,
+            &[("id", &previous.id.to_string())],
+        ))));
+    }
+    if let Some(reply) = check_stock(config, store, &cart, ctx.lang)? {
+        return Ok(Some(HandlerResult::Reply(reply)));
+    }
+
+    let currency = &config.business.currency;
+    let order = Order::from_cart(cart, delivery_fee(config));
+    let lang = ctx.lang;
+    let mut lines = vec![format!(
+        "{}\n",
+        config.i18n.render(lang, TranslationKey::ReorderTitle, &[("id", &previous.id.to_string())])
+    )];
+    for item in &order.items {
+        lines.push(format!("  {}", in_language(config, lang, item).display(currency)));
+    }
+    let total = format!("{}{:.2}", currency, order.total);
+    lines.push(format!("\n{}", config.i18n.render(lang, TranslationKey::Total, &[("amount", &total)])));
+    if !gone.is_empty() {
+        let names: Vec<String> = gone.iter().map(|i| in_language(config, lang, i).label()).collect();
+        lines.push(format!(
+            "\n{}",
+            config.i18n.render(lang, TranslationKey::NoLongerAvailable, &[("items", &names.join(", "))])
+        ));
+    }
+    lines.push("\n━━━━━━━━━━━━━━━━━━━".to_string());
+    lines.push(config.i18n.text(lang, TranslationKey::ReorderConfirmHint));
+    lines.push(config.i18n.render(
+        lang,
+        TranslationKey::ReorderAddHint,
+        &[("menu", &menu::format_menu_compact(config, store, lang))],
+    ));
+    lines.push(config.i18n.text(lang, TranslationKey::CancelHint));
+
+    *state = ConversationState::BuildingOrder(order.items);
+    Ok(Some(HandlerResult::Reply(lines.join("\n"))))
+}
+
+/// Re-price the lines of a past order against the current menu. Lines whose
+/// item, variant or add-ons are no longer available come back separately.
+fn rebuild_cart(available: &[MenuItem], items: Vec<OrderItem>) -> (Vec<OrderItem>, Vec<OrderItem>) {
+    let mut cart = Vec::new();
+    let mut gone = Vec::new();
+    for line in items {
+        let Some(item) = available.iter().find(|m| m.name == line.name) else {
+            gone.push(line);
+            continue;
+        };
+        let base = match line.variant {
+            Some(ref name) => item.variants.iter().find(|v| &v.name == name).map(|v| v.price),
+            // The item has gained variants since: the customer needs to pick one
+            None if !item.variants.is_empty() => None,
+            None => Some(item.price),
+        };
+        let addons: Option<Vec<&MenuAddon>> = line
+            .addons
+            .iter()
+            .map(|name| item.addons.iter().find(|a| &a.name == name))
+            .collect();
+        match (base, addons) {
+            (Some(base), Some(addons)) => {
+                let price = base + addons.iter().map(|a| a.price).sum::<f64>();
+                add_to_cart(
+                    &mut cart,
+                    OrderItem {
+                        price,
+                        emoji: item.emoji.clone(),
+                        ..line
+                    },
+                );
+            }
+            _ => gone.push(line),
+        }
+    }
+    (cart, gone)
+}
+
 /// Handle location input for a confirmed order.
 async fn handle_location_input(
     config: &HiveConfig,
@@ -262,136 +616,231 @@ async fn handle_location_input(
     // Accept location from location message or text
     let location = if let Some(ref loc) = ctx.location_text {
         loc.clone()
+    } else if text.eq_ignore_ascii_case("same") {
+        match store.last_delivery_location(&ctx.sender)? {
+            Some(last) => last,
+            None => return Ok(HandlerResult::Reply(config.i18n.text(ctx.lang, TranslationKey::NoAddressYet))),
+        }
     } else if !text.is_empty() {
         text.to_string()
     } else {
-        return Ok(HandlerResult::Reply(
-            "📍 Please send your delivery address or share your location.".to_string(),
-        ));
+        return Ok(HandlerResult::Reply(config.i18n.text(ctx.lang, TranslationKey::SendAddress)));
     };
 
     order.location = Some(location.clone());
 
     // Save order to database
     let items_json = serde_json::to_string(&order.items)?;
-    let order_id = store.create_order(
+    let order_id = match store.create_order(
         &ctx.sender,
         &items_json,
         order.subtotal,
         order.delivery_fee,
         order.total,
         None,
-    )?;
+    ) {
+        Ok(id) => id,
+        Err(e) => match e.downcast_ref::<OutOfStock>() {
+            // Sold out while the customer was checking out
+            Some(out) => {
+                *state = ConversationState::Idle;
+                return Ok(HandlerResult::Reply(config.i18n.render(
+                    ctx.lang,
+                    TranslationKey::SoldOutAtCheckout,
+                    &[("item", config.i18n.item_name(ctx.lang, &out.item)), ("left", &out.available.to_string())],
+                )));
+            }
+            None => return Err(e),
+        },
+    };
+
+    notify_low_stock(config, ctx, store, &order.items).await;
 
     // Set location and confirm
     store.set_order_location(order_id, &location)?;
 
-    // Initiate payment if M-Pesa is configured
-    if let Some(ref payment_provider) = ctx.payment_provider {
-        
-        log::info!("💰 Initiating M-Pesa payment for order #{} — {}{:.2}", 
-                  order_id, config.business.currency, order.total);
-        
-        let payment_id = format!("PAY-{}-{}", order_id, chrono::Utc::now().timestamp());
-        
-        // Create payment record
-        store.create_payment(
-            &payment_id,
-            order_id,
-            order.total,
-            &config.business.currency,
-            "mpesa",
-            &ctx.sender,
-            &format!("Order #{}", order_id),
-        )?;
-        
-        // Initiate STK Push
-        match payment_provider.initiate_payment(
-            order.total,
-            &config.business.currency,
-            &ctx.sender,
-            &format!("Order-{}", order_id),
-        ).await {
-            Ok(checkout_request_id) => {
-                // Update payment with provider reference
-                store.update_payment_status(&payment_id, "processing", Some(&checkout_request_id))?;
-                
-                log::info!("✅ M-Pesa STK Push sent to {} — CheckoutRequestID: {}", 
-                          ctx.sender, checkout_request_id);
-                
-                // Send payment prompt to customer
-                let payment_msg = format!(
-                    "💰 *Payment Request Sent*\n\n\
-                     Check your phone for the M-Pesa payment prompt.\n\
-                     Amount: {}{:.2}\n\n\
-                     ⏱️ Please complete payment within 2 minutes.\n\n\
-                     We'll confirm your order once payment is received.",
-                    config.business.currency, order.total
-                );
-                
-                let payment_jid = ctx.chat_jid.clone();
-                let payment_wa_msg = waproto::whatsapp::Message {
-                    extended_text_message: Some(Box::new(
-                        waproto::whatsapp::message::ExtendedTextMessage {
-                            text: Some(payment_msg),
-                            ..Default::default()
-                        },
-                    )),
-                    ..Default::default()
-                };
-                
-                if let Err(e) = ctx.wa_client.send_message(payment_jid, payment_wa_msg).await {
-                    log::error!("Failed to send payment prompt: {}", e);
-                }
-                
-                // Reset state
-                *state = ConversationState::Idle;
-                
-                // Return no reply since we already sent the payment prompt
-                return Ok(HandlerResult::NoReply);
+    // With one payment provider checkout goes straight to it; with several
+    // the customer picks one first
+    let providers = ctx.payment_providers.all();
+    if providers.len() > 1 {
+        *state = ConversationState::ChoosingPaymentMethod(order_id);
+        return Ok(HandlerResult::List(payment_method_list(config, ctx, order_id, order.total)));
+    }
+    if let Some(provider) = providers.first()
+        && let Some(result) = start_payment(config, ctx, state, store, provider.as_ref(), order_id, order.total).await?
+    {
+        return Ok(result);
+    }
+
+    confirm_unpaid_order(config, ctx, state, store, order_id, &order, &location).await
+}
+
+/// Pay for the order the customer is choosing a payment method for, with
+/// the provider they picked by number or name.
+pub async fn handle_payment_choice(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    text: &str,
+) -> Result<HandlerResult> {
+    let ConversationState::ChoosingPaymentMethod(order_id) = *state else {
+        return Ok(HandlerResult::NoReply);
+    };
+    let Some(record) = store.get_order(order_id)? else {
+        *state = ConversationState::Idle;
+        return Ok(HandlerResult::NoReply);
+    };
+
+    let providers = ctx.payment_providers.all();
+    let chosen = match text.parse::<usize>() {
+        Ok(n) => n.checked_sub(1).and_then(|i| providers.get(i)),
+        Err(_) => providers.iter().find(|p| {
+            let method = p.method();
+            text.eq_ignore_ascii_case(method.as_str()) || text.eq_ignore_ascii_case(&method.to_string())
+        }),
+    };
+    let Some(provider) = chosen else {
+        let mut list = payment_method_list(config, ctx, order_id, record.total);
+        list.text = format!("{}\n\n{}", config.i18n.text(ctx.lang, TranslationKey::InvalidChoice), list.text);
+        return Ok(HandlerResult::List(list));
+    };
+
+    if let Some(result) = start_payment(config, ctx, state, store, provider.as_ref(), order_id, record.total).await? {
+        return Ok(result);
+    }
+
+    let order = Order {
+        id: Some(order_id),
+        items: serde_json::from_str(&record.items_json)?,
+        subtotal: record.subtotal,
+        delivery_fee: record.delivery_fee,
+        total: record.total,
+        location: record.location.clone(),
+        voucher_discount: 0.0,
+    };
+    let location = record.location.unwrap_or_default();
+    confirm_unpaid_order(config, ctx, state, store, order_id, &order, &location).await
+}
+
+/// The payment methods on offer for an order.
+fn payment_method_list(config: &HiveConfig, ctx: &MessageContext, order_id: i64, total: f64) -> ListMessage {
+    ListMessage {
+        text: config.i18n.render(
+            ctx.lang,
+            TranslationKey::ChoosePaymentMethod,
+            &[
+                ("id", &order_id.to_string()),
+                ("total", &format!("{}{:.2}", config.business.currency, total)),
+            ],
+        ),
+        button: config.i18n.text(ctx.lang, TranslationKey::ChooseButton),
+        rows: ctx
+            .payment_providers
+            .all()
+            .iter()
+            .enumerate()
+            .map(|(i, provider)| Choice::new((i + 1).to_string(), provider.method().to_string()))
+            .collect(),
+        footer: None,
+    }
+}
+
+/// Ask `provider` for payment of an order and tell the customer how to pay.
+///
+/// Returns `None` if the provider couldn't start the payment: the customer
+/// has been told, and the order goes ahead as a cash order.
+async fn start_payment(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    provider: &dyn PaymentProvider,
+    order_id: i64,
+    total: f64,
+) -> Result<Option<HandlerResult>> {
+    let method = provider.method();
+    log::info!("💰 Initiating {} payment for order #{} — {}{:.2}", method, order_id, config.business.currency, total);
+
+    let payment_id = format!("PAY-{}-{}", order_id, chrono::Utc::now().timestamp());
+    store.create_payment(
+        &payment_id,
+        order_id,
+        total,
+        &config.business.currency,
+        method.as_str(),
+        &ctx.sender,
+        &format!("Order #{}", order_id),
+    )?;
+
+    let amount = format!("{}{:.2}", config.business.currency, total);
+    match provider
+        .initiate_payment(total, &config.business.currency, &ctx.sender, &format!("Order-{}", order_id))
+        .await
+    {
+        Ok(request) => {
+            store.update_payment_status(&payment_id, "processing", Some(&request.provider_ref))?;
+            log::info!("✅ {} payment requested from {} — {}", method, ctx.sender, request.provider_ref);
+
+            // M-Pesa prompts the customer's phone; other providers send a link
+            let payment_msg = match request.checkout_url {
+                Some(ref url) => config.i18n.render(
+                    ctx.lang,
+                    TranslationKey::PaymentLinkSent,
+                    &[("amount", &amount), ("id", &order_id.to_string()), ("url", url)],
+                ),
+                None => config.i18n.render(ctx.lang, TranslationKey::PaymentRequestSent, &[("amount", &amount)]),
+            };
+            if let Err(e) = ctx.transport.send_text(&ctx.chat, &payment_msg).await {
+                log::error!("Failed to send payment prompt: {}", e);
             }
-            Err(e) => {
-                // Payment initiation failed — mark as failed and continue with cash order
-                store.update_payment_status(&payment_id, "failed", None)?;
-                log::error!("❌ M-Pesa payment failed for order #{}: {}", order_id, e);
-                
-                // Send error message and fall back to cash
-                let error_msg = format!(
-                    "⚠️ Payment request failed: {}\n\n\
-                     Your order has been placed for cash payment.\n\
-                     Order #{} — Total: {}{:.2}",
-                    e, order_id, config.business.currency, order.total
-                );
-                
-                let error_jid = ctx.chat_jid.clone();
-                let error_wa_msg = waproto::whatsapp::Message {
-                    extended_text_message: Some(Box::new(
-                        waproto::whatsapp::message::ExtendedTextMessage {
-                            text: Some(error_msg),
-                            ..Default::default()
-                        },
-                    )),
-                    ..Default::default()
-                };
-                
-                if let Err(send_err) = ctx.wa_client.send_message(error_jid, error_wa_msg).await {
-                    log::error!("Failed to send error message: {}", send_err);
-                }
-                
-                // Continue with normal order flow (fall through)
+
+            // No reply needed: the payment prompt has been sent
+            *state = ConversationState::Idle;
+            Ok(Some(HandlerResult::NoReply))
+        }
+        Err(e) => {
+            // Mark the payment failed and continue as a cash order
+            store.update_payment_status(&payment_id, "failed", None)?;
+            log::error!("❌ {} payment failed for order #{}: {}", method, order_id, e);
+
+            let error_msg = config.i18n.render(
+                ctx.lang,
+                TranslationKey::PaymentRequestFailed,
+                &[("error", &e.to_string()), ("id", &order_id.to_string()), ("total", &amount)],
+            );
+            if let Err(send_err) = ctx.transport.send_text(&ctx.chat, &error_msg).await {
+                log::error!("Failed to send error message: {}", send_err);
             }
+            Ok(None)
         }
     }
+}
+
+/// Confirm an order that isn't being paid for up front (cash on delivery)
+/// and tell the customer and admins.
+async fn confirm_unpaid_order(
+    config: &HiveConfig,
+    ctx: &MessageContext,
+    state: &mut ConversationState,
+    store: &Store,
+    order_id: i64,
+    order: &Order,
+    location: &str,
+) -> Result<HandlerResult> {
+    // Cash orders are confirmed straight away; paid ones once the payment is in
+    store.transition_order(
+        order_id,
+        &OrderStatus::Confirmed,
+        &format!("customer:{}", ctx.sender),
+        Some("Cash on delivery"),
+    )?;
 
     // Build confirmation message for customer
-    let estimate = config
-        .delivery
-        .as_ref()
-        .map(|d| d.estimate_string())
-        .unwrap_or_else(|| "30-45 minutes".to_string());
+    let estimate = delivery_estimate(config, ctx.lang);
 
     let customer_msg = MessageTemplates::render(
-        &config.messages.order_confirmed,
+        &config.messages_in(ctx.lang).order_confirmed,
         &[
             ("id", &order_id.to_string()),
             ("estimate", &estimate),
@@ -408,29 +857,16 @@ async fn handle_location_input(
             ("items", &items_display),
             ("currency", currency),
             ("total", &format!("{:.2}", order.total)),
-            ("location", &location),
+            ("location", location),
         ],
     );
 
-    // Send admin notification via WhatsApp
+    // Send admin notification
     for admin_number in &config.admin_numbers {
-        let clean_number: String = admin_number.chars().filter(|c| c.is_ascii_digit()).collect();
-        if !clean_number.is_empty() {
-            let admin_jid = wacore_binary::jid::Jid::pn(&clean_number);
-            let admin_wa_msg = waproto::whatsapp::Message {
-                extended_text_message: Some(Box::new(
-                    waproto::whatsapp::message::ExtendedTextMessage {
-                        text: Some(admin_msg.clone()),
-                        ..Default::default()
-                    },
-                )),
-                ..Default::default()
-            };
-            if let Err(e) = ctx.wa_client.send_message(admin_jid, admin_wa_msg).await {
-                log::error!("Failed to notify admin {}: {}", admin_number, e);
-            } else {
-                log::info!("📢 Notified admin {} about order #{}", admin_number, order_id);
-            }
+        if let Err(e) = ctx.transport.send_to_phone(admin_number, &admin_msg).await {
+            log::error!("Failed to notify admin {}: {}", admin_number, e);
+        } else {
+            log::info!("📢 Notified admin {} about order #{}", admin_number, order_id);
         }
     }
 
@@ -449,6 +885,72 @@ async fn handle_location_input(
     Ok(HandlerResult::Reply(customer_msg))
 }
 
+/// Alert admins about items whose stock just fell to the low-stock threshold.
+async fn notify_low_stock(config: &HiveConfig, ctx: &MessageContext, store: &Store, items: &[OrderItem]) {
+    let threshold = config.inventory.low_stock_threshold as i64;
+    for item in items {
+        let left = match store.get_stock(&item.name) {
+            Ok(Some(left)) => left,
+            Ok(None) => continue,
+            Err(e) => {
+                log::error!("Failed to read stock for {}: {}", item.name, e);
+                continue;
+            }
+        };
+        // Alert on the order that crosses the threshold, and again when it sells out
+        let crossed = left <= threshold && left + item.quantity as i64 > threshold;
+        if !crossed && left > 0 {
+            continue;
+        }
+
+        let msg = if left == 0 {
+            format!("🚫 *{}* is now sold out and hidden from the menu.", item.name)
+        } else {
+            format!("⚠️ Low stock: only {} left of *{}*.", left, item.name)
+        };
+        for admin_number in &config.admin_numbers {
+            if let Err(e) = ctx.transport.send_to_phone(admin_number, &msg).await {
+                log::error!("Failed to notify admin {}: {}", admin_number, e);
+            }
+        }
+    }
+}
+
+/// Delivery time estimate shown to customers.
+fn delivery_estimate(config: &HiveConfig, lang: Language) -> String {
+    config
+        .delivery
+        .as_ref()
+        .map(|d| d.estimate_in(&config.i18n, lang))
+        .unwrap_or_else(|| config.i18n.render(lang, TranslationKey::Minutes, &[("minutes", "30-45")]))
+}
+
+/// The customer's update, in `lang`, for an order that has just moved to its
+/// current status, or `None` if there's nothing to send (or the message is off).
+pub fn order_status_message(config: &HiveConfig, lang: Language, order: &OrderRecord) -> Option<String> {
+    let messages = config.messages_in(lang);
+    let template = match order.status {
+        OrderStatus::Pending => return None,
+        OrderStatus::Confirmed => &messages.order_accepted,
+        OrderStatus::Preparing => &messages.order_preparing,
+        OrderStatus::Delivering => &messages.order_delivering,
+        OrderStatus::Delivered => &messages.order_delivered,
+        OrderStatus::Cancelled => &messages.order_cancelled,
+    };
+    if template.is_empty() {
+        return None;
+    }
+    Some(MessageTemplates::render(
+        template,
+        &[
+            ("id", &order.id.to_string()),
+            ("currency", &config.business.currency),
+            ("total", &format!("{:.2}", order.total)),
+            ("estimate", &delivery_estimate(config, lang)),
+        ],
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -482,4 +984,43 @@ mod tests {
         let selections = parse_item_selections("abc");
         assert!(selections.is_empty());
     }
+
+    #[test]
+    fn test_rebuild_cart_uses_current_menu() {
+        let menu: Vec<MenuItem> = serde_yaml::from_str(
+            r#"
+- { name: "Kota", price: 40 }
+- name: "Pizza"
+  price: 10
+  variants: [{ name: "Large", price: 13 }]
+  addons: [{ name: "Olives", price: 1 }]
+"#,
+        )
+        .unwrap();
+        let line = |name: &str, variant: Option<&str>, addons: &[&str]| OrderItem {
+            name: name.to_string(),
+            price: 1.0,
+            quantity: 2,
+            emoji: None,
+            variant: variant.map(str::to_string),
+            addons: addons.iter().map(|a| a.to_string()).collect(),
+        };
+
+        let (cart, gone) = rebuild_cart(
+            &menu,
+            vec![
+                line("Kota", None, &[]),
+                line("Pizza", Some("Large"), &["Olives"]),
+                line("Pizza", Some("Small"), &[]),
+                line("Pizza", Some("Large"), &["Anchovies"]),
+                line("Gatsby", None, &[]),
+            ],
+        );
+        assert_eq!(cart.len(), 2);
+        assert_eq!(cart[0].price, 40.0);
+        assert_eq!(cart[1].price, 14.0);
+        assert_eq!(cart[1].quantity, 2);
+        let gone: Vec<String> = gone.iter().map(|i| i.label()).collect();
+        assert_eq!(gone, vec!["Pizza (Small)", "Pizza (Large) + Anchovies", "Gatsby"]);
+    }
 }
@@ -7,6 +7,7 @@
 use super::{HandlerResult, MessageContext, MessageHandler};
 use crate::bot::conversation::ConversationState;
 use crate::config::{HiveConfig, MessageTemplates};
+use crate::i18n::{TranslationKey};
 use crate::store::Store;
 use anyhow::Result;
 use async_trait::async_trait;
@@ -27,11 +28,10 @@ impl MessageHandler for VoucherHandler {
         store: &Store,
     ) -> Result<HandlerResult> {
         let code = ctx.text.trim().to_uppercase();
+        let messages = config.messages_in(ctx.lang);
 
         if code.is_empty() {
-            return Ok(HandlerResult::Reply(
-                "🎟️ Enter your voucher code:".to_string(),
-            ));
+            return Ok(HandlerResult::Reply(config.i18n.text(ctx.lang, TranslationKey::EnterVoucherCode)));
         }
 
         // Try to redeem the voucher
@@ -39,7 +39,7 @@ impl MessageHandler for VoucherHandler {
             Some(amount) => {
                 let currency = &config.business.currency;
                 let msg = MessageTemplates::render(
-                    &config.messages.voucher_redeemed,
+                    &messages.voucher_redeemed,
                     &[
                         ("code", &code),
                         ("currency", currency),
@@ -53,22 +53,24 @@ impl MessageHandler for VoucherHandler {
                 Ok(HandlerResult::Reply(msg))
             }
             None => {
-                let msg = config.messages.voucher_invalid.clone();
+                let msg = messages.voucher_invalid;
 
                 // Check if the voucher exists but was already redeemed
                 if let Some(voucher) = store.get_voucher(&code)? {
                     if voucher.redeemed_by.is_some() {
                         *state = ConversationState::Idle;
-                        return Ok(HandlerResult::Reply(
-                            "❌ This voucher has already been redeemed.".to_string(),
-                        ));
+                        return Ok(HandlerResult::Reply(config.i18n.text(
+                            ctx.lang,
+                            TranslationKey::VoucherAlreadyRedeemed,
+                        )));
                     }
                 }
 
                 // Stay in voucher state for retry
                 Ok(HandlerResult::Reply(format!(
-                    "{}\n\nTry again or reply *0* to go back.",
-                    msg
+                    "{}\n\n{}",
+                    msg,
+                    config.i18n.text(ctx.lang, TranslationKey::VoucherTryAgain)
                 )))
             }
         }
@@ -0,0 +1,214 @@
+//! Guessing a customer's language from their first message.
+//!
+//! Deliberately simple: a message in Devanagari is Hindi; otherwise each
+//! candidate language scores a point per word that appears in its list of
+//! greetings and common words. When the best score is shared, the country
+//! code of the sender's phone number breaks the tie. A message with no
+//! known words ("1", "menu", "hi") gives no answer, and the customer keeps
+//! the business's default language.
+
+use super::Language;
+
+/// Greetings and common words, lowercase. Words used the same way in every
+/// language ("hi", "hello", "ok", "menu") are left out on purpose.
+const WORDS: &[(Language, &[&str])] = &[
+    (
+        Language::English,
+        &[
+            "good", "morning", "afternoon", "evening", "please", "thanks", "thank", "you", "want", "would", "like",
+            "order", "the", "and", "what", "how", "can", "have", "my", "is", "are", "do", "food", "some", "yes",
+            "there",
+        ],
+    ),
+    (
+        Language::Swahili,
+        &[
+            "habari", "jambo", "hujambo", "mambo", "sasa", "vipi", "niaje", "shikamoo", "karibu", "asante",
+            "tafadhali", "nataka", "naomba", "nina", "chakula", "ndiyo", "ndio", "hapana", "sawa", "poa", "na",
+            "ya", "wa", "za", "kwa", "ni", "nini", "gani", "leo", "bei", "rafiki", "yako", "yangu",
+        ],
+    ),
+    (
+        Language::Afrikaans,
+        &[
+            "hallo", "goeie", "môre", "more", "middag", "naand", "dankie", "asseblief", "ek", "wil", "graag",
+            "die", "en", "het", "nie", "jy", "julle", "ons", "kos", "bestel", "baie", "hoe", "gaan", "dit", "wat",
+            "is",
+        ],
+    ),
+    (
+        Language::Portuguese,
+        &[
+            "olá", "ola", "oi", "bom", "boa", "dia", "tarde", "noite", "obrigado", "obrigada", "favor", "quero",
+            "gostaria", "eu", "você", "voce", "não", "nao", "sim", "comida", "pedido", "tudo", "bem", "que", "um",
+            "uma", "com", "para", "de", "por",
+        ],
+    ),
+    (
+        Language::Hindi,
+        &[
+            "namaste", "namaskar", "kya", "hai", "hain", "mujhe", "chahiye", "aap", "kaise", "dhanyavad",
+            "dhanyawad", "shukriya", "haan", "nahi", "nahin", "khana", "mera", "meri", "accha", "acha", "ji",
+        ],
+    ),
+    (
+        Language::Spanish,
+        &[
+            "hola", "buenos", "buenas", "días", "dias", "tardes", "noches", "gracias", "favor", "quiero",
+            "quisiera", "yo", "usted", "sí", "comida", "pedido", "qué", "que", "cómo", "como", "el", "la", "los",
+            "las", "un", "una", "con", "para", "de", "por",
+        ],
+    ),
+    (
+        Language::French,
+        &[
+            "bonjour", "bonsoir", "salut", "merci", "plaît", "plait", "je", "veux", "voudrais", "oui", "non",
+            "le", "la", "les", "un", "une", "et", "avec", "pour", "commande", "commander", "est", "ça", "ca",
+            "vous", "de",
+        ],
+    ),
+];
+
+/// Languages spoken in a country, most likely first, by calling code.
+const COUNTRY_LANGUAGES: &[(&str, &[Language])] = &[
+    ("1", &[Language::English, Language::Spanish, Language::French]),
+    ("27", &[Language::English, Language::Afrikaans]),
+    ("32", &[Language::French]),
+    ("33", &[Language::French]),
+    ("34", &[Language::Spanish]),
+    ("44", &[Language::English]),
+    ("51", &[Language::Spanish]),
+    ("52", &[Language::Spanish]),
+    ("53", &[Language::Spanish]),
+    ("54", &[Language::Spanish]),
+    ("55", &[Language::Portuguese]),
+    ("56", &[Language::Spanish]),
+    ("57", &[Language::Spanish]),
+    ("58", &[Language::Spanish]),
+    ("61", &[Language::English]),
+    ("91", &[Language::Hindi, Language::English]),
+    ("221", &[Language::French]),
+    ("223", &[Language::French]),
+    ("224", &[Language::French]),
+    ("225", &[Language::French]),
+    ("226", &[Language::French]),
+    ("227", &[Language::French]),
+    ("228", &[Language::French]),
+    ("229", &[Language::French]),
+    ("233", &[Language::English]),
+    ("234", &[Language::English]),
+    ("237", &[Language::French, Language::English]),
+    ("238", &[Language::Portuguese]),
+    ("241", &[Language::French]),
+    ("242", &[Language::French]),
+    ("243", &[Language::French, Language::Swahili]),
+    ("244", &[Language::Portuguese]),
+    ("245", &[Language::Portuguese]),
+    ("250", &[Language::English, Language::French, Language::Swahili]),
+    ("254", &[Language::Swahili, Language::English]),
+    ("255", &[Language::Swahili, Language::English]),
+    ("256", &[Language::English, Language::Swahili]),
+    ("258", &[Language::Portuguese]),
+    ("260", &[Language::English]),
+    ("263", &[Language::English]),
+    ("264", &[Language::English, Language::Afrikaans]),
+    ("351", &[Language::Portuguese]),
+    ("353", &[Language::English]),
+    ("509", &[Language::French]),
+    ("591", &[Language::Spanish]),
+    ("593", &[Language::Spanish]),
+    ("595", &[Language::Spanish]),
+    ("598", &[Language::Spanish]),
+];
+
+/// Guess which of `candidates` `text` is written in. `phone` (digits, a
+/// `+` number or a WhatsApp JID) breaks ties. Returns `None` when there
+/// isn't enough to go on.
+pub fn detect_language(text: &str, phone: &str, candidates: &[Language]) -> Option<Language> {
+    if text.chars().any(is_devanagari) {
+        return candidates.contains(&Language::Hindi).then_some(Language::Hindi);
+    }
+
+    let text = text.to_lowercase();
+    let words: Vec<&str> = text.split(|c: char| !c.is_alphabetic()).filter(|w| !w.is_empty()).collect();
+
+    let mut best: Vec<Language> = Vec::new();
+    let mut best_score = 0;
+    for &(lang, known) in WORDS {
+        if !candidates.contains(&lang) {
+            continue;
+        }
+        let score = words.iter().filter(|w| known.contains(w)).count();
+        if score > best_score {
+            best_score = score;
+            best = vec![lang];
+        } else if score == best_score && score > 0 {
+            best.push(lang);
+        }
+    }
+
+    match best.as_slice() {
+        [] => None,
+        [lang] => Some(*lang),
+        tied => country_languages(phone)
+            .iter()
+            .find(|lang| tied.contains(lang))
+            .copied(),
+    }
+}
+
+fn is_devanagari(c: char) -> bool {
+    ('\u{0900}'..='\u{097F}').contains(&c)
+}
+
+/// Languages of the country `phone` is registered in, most likely first.
+fn country_languages(phone: &str) -> &'static [Language] {
+    let number = phone.split('@').next().unwrap_or_default();
+    let digits: String = number.chars().filter(|c| c.is_ascii_digit()).collect();
+    // Calling codes are prefix-free, so the first match is the only one
+    COUNTRY_LANGUAGES
+        .iter()
+        .find(|(code, _)| digits.starts_with(code))
+        .map(|(_, langs)| *langs)
+        .unwrap_or(&[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        let all = &Language::ALL;
+        assert_eq!(detect_language("Habari, nataka chakula", "254700000001", all), Some(Language::Swahili));
+        assert_eq!(detect_language("Olá, bom dia!", "5511999999999", all), Some(Language::Portuguese));
+        assert_eq!(detect_language("Bonjour, je voudrais commander", "+33 6 12 34 56 78", all), Some(Language::French));
+        assert_eq!(detect_language("Goeie môre, ek wil graag bestel", "27820000000", all), Some(Language::Afrikaans));
+        assert_eq!(detect_language("Hola, buenas tardes", "34600000000", all), Some(Language::Spanish));
+        assert_eq!(detect_language("Good morning, can I order?", "254700000001", all), Some(Language::English));
+        assert_eq!(detect_language("नमस्ते", "919800000000", all), Some(Language::Hindi));
+        assert_eq!(detect_language("namaste ji", "919800000000", all), Some(Language::Hindi));
+
+        // Nothing to go on
+        assert_eq!(detect_language("1", "254700000001", all), None);
+        assert_eq!(detect_language("hi", "254700000001", all), None);
+        assert_eq!(detect_language("menu", "5511999999999", all), None);
+
+        // Only the languages on offer
+        let offered = [Language::English, Language::Swahili];
+        assert_eq!(detect_language("Olá, bom dia!", "5511999999999", &offered), None);
+        assert_eq!(detect_language("नमस्ते", "919800000000", &offered), None);
+    }
+
+    #[test]
+    fn test_phone_prefix_breaks_ties() {
+        let all = &Language::ALL;
+        // "por favor" is both Spanish and Portuguese
+        assert_eq!(detect_language("por favor", "5511999999999", all), Some(Language::Portuguese));
+        assert_eq!(detect_language("por favor", "5215512345678@s.whatsapp.net", all), Some(Language::Spanish));
+        // A country that speaks neither leaves it undecided
+        assert_eq!(detect_language("por favor", "254700000001", all), None);
+        // "is" is English and Afrikaans
+        assert_eq!(detect_language("is", "27820000000", all), Some(Language::English));
+    }
+}
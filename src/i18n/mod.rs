@@ -1,13 +1,32 @@
 //! Multi-language support for Hive bots.
 //!
 //! Provides translations for common bot messages in multiple languages.
-//! Auto-detects user language from first message or allows manual selection.
+//! Each customer is served in the language they picked with the LANGUAGE
+//! command, or the business's `business.language` until they do. A new
+//! customer's first message is also used to guess their language (see
+//! `detect_language`). Keys without a translation fall back to English.
+//!
+//! Translations may contain `{placeholders}`, filled in by `render`.
+//!
+//! A project can ship `locales/<code>.yaml` files that override built-in
+//! strings, translate its `messages:` and menu, or add a language Hive
+//! doesn't know (see `Locale`). `HiveConfig::load` reads them into
+//! `HiveConfig::i18n`, which is what handlers translate with.
+
+mod detect;
+
+pub use detect::detect_language;
 
+use crate::config::MessageTemplates;
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex};
 
 /// Supported languages
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Language {
     English,
     Swahili,    // Kenya, Tanzania, Uganda
@@ -16,9 +35,22 @@ pub enum Language {
     Hindi,      // India
     Spanish,    // Latin America
     French,     // West Africa
+    /// A language added by a project's `locales/<code>.yaml`, by code
+    Custom(&'static str),
 }
 
 impl Language {
+    /// Every supported language, in the order the language picker lists them.
+    pub const ALL: [Language; 7] = [
+        Language::English,
+        Language::Swahili,
+        Language::Afrikaans,
+        Language::Portuguese,
+        Language::Hindi,
+        Language::Spanish,
+        Language::French,
+    ];
+
     /// Parse language from ISO code (e.g., "en", "sw", "af")
     pub fn from_code(code: &str) -> Option<Self> {
         match code.to_lowercase().as_str() {
@@ -43,6 +75,7 @@ impl Language {
             Language::Hindi => "hi",
             Language::Spanish => "es",
             Language::French => "fr",
+            Language::Custom(code) => code,
         }
     }
 
@@ -56,12 +89,36 @@ impl Language {
             Language::Hindi => "हिन्दी",
             Language::Spanish => "Español",
             Language::French => "Français",
+            // The locale file has the real name: see `Translations::language_name`
+            Language::Custom(code) => code,
         }
     }
+
+    /// The language for a locale file's `code`: a built-in one, or a new one.
+    fn for_locale(code: &str) -> Self {
+        static CODES: LazyLock<Mutex<HashSet<&'static str>>> = LazyLock::new(Default::default);
+
+        if let Some(lang) = Language::from_code(code) {
+            return lang;
+        }
+        // Codes are leaked once each so `Language` can stay `Copy`
+        let mut codes = CODES.lock().unwrap();
+        let code = match codes.get(code) {
+            Some(code) => *code,
+            None => {
+                let code: &'static str = Box::leak(code.to_string().into_boxed_str());
+                codes.insert(code);
+                code
+            }
+        };
+        Language::Custom(code)
+    }
 }
 
-/// Translation key for common bot messages
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Translation key for customer-facing bot messages. Locale files use the
+/// snake_case name, e.g. `welcome_menu`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TranslationKey {
     Welcome,
     ViewMenu,
@@ -75,11 +132,216 @@ pub enum TranslationKey {
     OrderPlaced,
     ThankYou,
     ChooseLanguage,
+
+    // Main menu
+    WelcomeMenu,
+    AboutDefault,
+    EnterVoucherCode,
+    GoBackHint,
+    CancelHint,
+    LanguageSet,
+
+    // Buttons and lists
+    ChooseButton,
+    ConfirmButton,
+    CancelButton,
+
+    // Dates and times
+    Weekdays,
+    Months,
+    DateFormat,
+    ShortDateFormat,
+    DateAtTime,
+    Minutes,
+
+    // Menu
+    MenuTitle,
+    OtherCategory,
+    PriceFrom,
+    DeliveryFee,
+    DeliveryEstimate,
+    PickCategory,
+    PickService,
+    PickItems,
+    AllCategories,
+    CategoryRange,
+
+    // Ordering
+    ItemSelectionHelp,
+    InvalidItems,
+    OnlyLeft,
+    ChooseVariant,
+    ReplyWithNumber,
+    ChooseAddons,
+    AddonsHint,
+    NumberRange,
+    AddonRange,
+    YourOrder,
+    Subtotal,
+    DeliveryCharge,
+    Total,
+    ConfirmOrderHint,
+    AddMoreHint,
+    SkippedItems,
+    CartHelp,
+    AddItemsPrompt,
+    OrderTotalPrompt,
+    AskDeliveryLocation,
+    AskAddress,
+    UseLastAddress,
+    NoAddressYet,
+    SendAddress,
+    SoldOutAtCheckout,
+    PaymentRequestSent,
+    PaymentRequestFailed,
+    NoPreviousOrder,
+    NothingToReorder,
+    ReorderTitle,
+    NoLongerAvailable,
+    ReorderConfirmHint,
+    ReorderAddHint,
+
+    // Default `messages:` templates sent to customers
+    OrderConfirmedMessage,
+    OrderAcceptedMessage,
+    OrderPreparingMessage,
+    OrderDeliveringMessage,
+    OrderDeliveredMessage,
+    OrderCancelledMessage,
+    VoucherRedeemedMessage,
+    VoucherInvalidMessage,
+    BookingConfirmedMessage,
+
+    // Payments
+    PaymentReceived,
+    PaymentConfirmed,
+    ChoosePaymentMethod,
+    PaymentLinkSent,
+    PaymentFailed,
+
+    // Vouchers
+    VoucherAlreadyRedeemed,
+    VoucherTryAgain,
+
+    // My Orders
+    NoOrdersYet,
+    RecentOrders,
+    OrderLine,
+    OrderHeader,
+    OrderPlacedEvent,
+    ReorderHint,
+    ManageOrdersHint,
+    NoSuchOrder,
+    ReceiptHint,
+    ReceiptTitle,
+    ReceiptStatus,
+    OrderLocked,
+    CancelOrderOption,
+    ChangeAddressOption,
+    BackOption,
+    ConfirmCancel,
+    RefundNotice,
+    CancelOrKeep,
+    CancelOrKeepOrder,
+    SendNewAddress,
+    OrderUnchanged,
+    TooLateToCancel,
+    TooLateToChangeAddress,
+    OrderAlreadyStatus,
+    OrderCancelledNotice,
+    RefundSoon,
+    RefundSent,
+    RefundCompleted,
+    PaidAfterCancel,
+    NewAddressSaved,
+    StatusPending,
+    StatusConfirmed,
+    StatusPreparing,
+    StatusDelivering,
+    StatusDelivered,
+    StatusCancelled,
+
+    // Flows
+    TypeReply,
+    MinLength,
+    MaxLength,
+    NumberExpected,
+    NumberMin,
+    NumberMax,
+    LocationExpected,
+
+    // Bookings
+    ServiceRange,
+    NoFreeAppointments,
+    ChooseDay,
+    ChooseTime,
+    DayRange,
+    TimeRange,
+    SlotTaken,
+    SlotPassed,
+    NoUpcomingBookings,
+    UpcomingBookings,
+    ManageBookingsHint,
+    NoSuchBooking,
+    BookingCancelled,
+}
+
+impl TranslationKey {
+    /// The key's name in locale files, e.g. `welcome_menu`.
+    pub fn name(&self) -> String {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(name)) => name,
+            _ => format!("{:?}", self),
+        }
+    }
+}
+
+/// A `locales/<code>.yaml` file.
+///
+/// ```yaml
+/// name: isiZulu                 # required for a language Hive doesn't know
+/// strings:
+///   welcome_menu: "Sawubona! ..."
+/// messages:                     # customer `messages:`, plus welcome and about
+///   order_confirmed: "✅ Ioda #{id} iqinisekisiwe!"
+/// menu:
+///   Kota:
+///     name: "Ikota"
+///     description: "..."
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Locale {
+    /// Name of the language in itself, shown by the language picker
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Overrides for built-in strings
+    #[serde(default)]
+    pub strings: HashMap<TranslationKey, String>,
+    /// Translations of the config's customer messages, by template name
+    #[serde(default)]
+    pub messages: HashMap<String, String>,
+    /// Translations of menu items, by the item's name in config.yaml
+    #[serde(default)]
+    pub menu: HashMap<String, ItemTranslation>,
+}
+
+/// A menu item's name and description in one language.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ItemTranslation {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
 }
 
 /// Translation provider
+#[derive(Debug, PartialEq)]
 pub struct Translations {
     data: HashMap<(Language, TranslationKey), String>,
+    /// Locale files loaded on top of the built-in strings
+    locales: HashMap<Language, Locale>,
 }
 
 impl Translations {
@@ -96,10 +358,140 @@ impl Translations {
         data.insert((Language::English, TranslationKey::OrderConfirmed), "✅ Order confirmed!".to_string());
         data.insert((Language::English, TranslationKey::OrderDelivered), "🎉 Order delivered! Enjoy!".to_string());
         data.insert((Language::English, TranslationKey::InvalidChoice), "❌ Invalid choice. Please try again.".to_string());
-        data.insert((Language::English, TranslationKey::MenuEmpty), "No items available right now.".to_string());
+        data.insert((Language::English, TranslationKey::MenuEmpty), "😔 Sorry, our menu is currently empty. Check back later!".to_string());
         data.insert((Language::English, TranslationKey::OrderPlaced), "Your order has been placed!".to_string());
         data.insert((Language::English, TranslationKey::ThankYou), "Thank you! 😊".to_string());
         data.insert((Language::English, TranslationKey::ChooseLanguage), "Choose your language / Chagua lugha / Kies jou taal".to_string());
+        data.insert((Language::English, TranslationKey::WelcomeMenu), "Welcome! Reply with a number:\n1. 📋 View Menu\n2. 📦 My Orders\n3. 🎟️ Redeem Voucher\n4. ℹ️ About Us".to_string());
+        data.insert((Language::English, TranslationKey::AboutDefault), "Thanks for choosing us!".to_string());
+        data.insert((Language::English, TranslationKey::EnterVoucherCode), "🎟️ Enter your voucher code:".to_string());
+        data.insert((Language::English, TranslationKey::GoBackHint), "Reply *0* to go back".to_string());
+        data.insert((Language::English, TranslationKey::CancelHint), "Reply *0* to cancel".to_string());
+        data.insert((Language::English, TranslationKey::LanguageSet), "✅ Language set to {language}.".to_string());
+        data.insert((Language::English, TranslationKey::ChooseButton), "📋 Choose".to_string());
+        data.insert((Language::English, TranslationKey::ConfirmButton), "✅ Confirm".to_string());
+        data.insert((Language::English, TranslationKey::CancelButton), "❌ Cancel".to_string());
+        data.insert((Language::English, TranslationKey::Weekdays), "Mon,Tue,Wed,Thu,Fri,Sat,Sun".to_string());
+        data.insert((Language::English, TranslationKey::Months), "Jan,Feb,Mar,Apr,May,Jun,Jul,Aug,Sep,Oct,Nov,Dec".to_string());
+        data.insert((Language::English, TranslationKey::DateFormat), "{weekday} {day} {month}".to_string());
+        data.insert((Language::English, TranslationKey::ShortDateFormat), "{day} {month}".to_string());
+        data.insert((Language::English, TranslationKey::DateAtTime), "{date} at {time}".to_string());
+        data.insert((Language::English, TranslationKey::Minutes), "{minutes} minutes".to_string());
+        data.insert((Language::English, TranslationKey::MenuTitle), "📋 *{business} Menu*".to_string());
+        data.insert((Language::English, TranslationKey::OtherCategory), "Other".to_string());
+        data.insert((Language::English, TranslationKey::PriceFrom), "from {price}".to_string());
+        data.insert((Language::English, TranslationKey::DeliveryFee), "🚗 Delivery fee: {fee}".to_string());
+        data.insert((Language::English, TranslationKey::DeliveryEstimate), "⏱ Estimated: {estimate}".to_string());
+        data.insert((Language::English, TranslationKey::PickCategory), "Reply with a category number to see its items".to_string());
+        data.insert((Language::English, TranslationKey::PickService), "Reply with a service number to book".to_string());
+        data.insert((Language::English, TranslationKey::PickItems), "Reply with item number(s) to order\ne.g. *1* or *1,3,5*".to_string());
+        data.insert((Language::English, TranslationKey::AllCategories), "Reply *menu* for all categories".to_string());
+        data.insert((Language::English, TranslationKey::CategoryRange), "Please reply with a category number from 1 to {max}.".to_string());
+        data.insert((Language::English, TranslationKey::ItemSelectionHelp), "Please reply with item number(s) to order.\ne.g. *1* or *1,3,5* or *2x1* (2 of item 1)\n\nReply *0* to go back.".to_string());
+        data.insert((Language::English, TranslationKey::InvalidItems), "❌ Invalid item number(s): {items}. Please check the menu and try again.".to_string());
+        data.insert((Language::English, TranslationKey::OnlyLeft), "😕 Only {left} left of {item}. Please choose a smaller quantity.".to_string());
+        data.insert((Language::English, TranslationKey::ChooseVariant), "{emoji} *{item}* — which one would you like?".to_string());
+        data.insert((Language::English, TranslationKey::ReplyWithNumber), "Reply with a number.".to_string());
+        data.insert((Language::English, TranslationKey::ChooseAddons), "{emoji} Any extras for *{item}*?".to_string());
+        data.insert((Language::English, TranslationKey::AddonsHint), "Reply with number(s), e.g. *1* or *1,2*, or *NO* for none.".to_string());
+        data.insert((Language::English, TranslationKey::NumberRange), "❌ Please reply with a number from 1 to {max}.".to_string());
+        data.insert((Language::English, TranslationKey::AddonRange), "❌ Please reply with extra number(s) from 1 to {max}, or *NO* for none.".to_string());
+        data.insert((Language::English, TranslationKey::YourOrder), "🛒 *Your Order:*".to_string());
+        data.insert((Language::English, TranslationKey::Subtotal), "Subtotal: {amount}".to_string());
+        data.insert((Language::English, TranslationKey::DeliveryCharge), "Delivery: {amount}".to_string());
+        data.insert((Language::English, TranslationKey::Total), "*Total: {amount}*".to_string());
+        data.insert((Language::English, TranslationKey::ConfirmOrderHint), "Reply *YES* to confirm".to_string());
+        data.insert((Language::English, TranslationKey::AddMoreHint), "Reply *ADD* + numbers to add more items".to_string());
+        data.insert((Language::English, TranslationKey::SkippedItems), "⚠️ Skipped invalid items: {items}".to_string());
+        data.insert((Language::English, TranslationKey::CartHelp), "Reply with item numbers to add, *YES* to check out, or *0* to cancel.".to_string());
+        data.insert((Language::English, TranslationKey::AddItemsPrompt), "📋 Send item number(s) to add to your order:\n\n{menu}".to_string());
+        data.insert((Language::English, TranslationKey::OrderTotalPrompt), "🛒 Your order total: {total}\n\nReply *YES* to confirm or *0* to cancel.".to_string());
+        data.insert((Language::English, TranslationKey::AskDeliveryLocation), "📍 Great! Now send your *delivery address* or share your *location*.".to_string());
+        data.insert((Language::English, TranslationKey::AskAddress), "📍 Please send your address for the order.".to_string());
+        data.insert((Language::English, TranslationKey::UseLastAddress), "Or reply *SAME* to use your last address:\n📍 {address}".to_string());
+        data.insert((Language::English, TranslationKey::NoAddressYet), "📍 We don't have an address for you yet — please send your delivery address or share your location.".to_string());
+        data.insert((Language::English, TranslationKey::SendAddress), "📍 Please send your delivery address or share your location.".to_string());
+        data.insert((Language::English, TranslationKey::SoldOutAtCheckout), "😕 Sorry, {item} just sold out (only {left} left) and your order wasn't placed.\n\nReply *menu* to order again.".to_string());
+        data.insert((Language::English, TranslationKey::PaymentRequestSent), "💰 *Payment Request Sent*\n\nCheck your phone for the M-Pesa payment prompt.\nAmount: {amount}\n\n⏱️ Please complete payment within 2 minutes.\n\nWe'll confirm your order once payment is received.".to_string());
+        data.insert((Language::English, TranslationKey::PaymentRequestFailed), "⚠️ Payment request failed: {error}\n\nYour order has been placed for cash payment.\nOrder #{id} — Total: {total}".to_string());
+        data.insert((Language::English, TranslationKey::NoPreviousOrder), "📦 No previous order found.\n\nReply 1 to view our menu!".to_string());
+        data.insert((Language::English, TranslationKey::NothingToReorder), "😕 Nothing from order #{id} is on the menu any more.\n\nReply 1 to view our menu!".to_string());
+        data.insert((Language::English, TranslationKey::ReorderTitle), "🔁 *Order #{id} again:*".to_string());
+        data.insert((Language::English, TranslationKey::NoLongerAvailable), "⚠️ No longer available: {items}".to_string());
+        data.insert((Language::English, TranslationKey::ReorderConfirmHint), "Reply *YES* to order this".to_string());
+        data.insert((Language::English, TranslationKey::ReorderAddHint), "Reply with item number(s) to add more:\n{menu}".to_string());
+        data.insert((Language::English, TranslationKey::OrderConfirmedMessage), "✅ Order #{id} confirmed!\n📍 Send your location or address\n⏱ Estimated delivery: {estimate}".to_string());
+        data.insert((Language::English, TranslationKey::OrderAcceptedMessage), "✅ Order #{id} has been accepted — we'll let you know when it's being prepared.".to_string());
+        data.insert((Language::English, TranslationKey::OrderPreparingMessage), "🍳 Order #{id} is being prepared.".to_string());
+        data.insert((Language::English, TranslationKey::OrderDeliveringMessage), "🚗 Order #{id} is on its way!\n⏱ Estimated delivery: {estimate}".to_string());
+        data.insert((Language::English, TranslationKey::OrderDeliveredMessage), "🎉 Order #{id} has been delivered! Enjoy your meal!\nRate us: ⭐⭐⭐⭐⭐".to_string());
+        data.insert((Language::English, TranslationKey::OrderCancelledMessage), "❌ Order #{id} has been cancelled.\nReply 1 to order again.".to_string());
+        data.insert((Language::English, TranslationKey::VoucherRedeemedMessage), "✅ Voucher {code} redeemed! {currency}{amount} off your next order.".to_string());
+        data.insert((Language::English, TranslationKey::VoucherInvalidMessage), "❌ That voucher code is invalid or already used.".to_string());
+        data.insert((Language::English, TranslationKey::BookingConfirmedMessage), "✅ Booking #{id} confirmed!\n{service}\n📅 {date} at {time}\n\nReply RESCHEDULE {id} or CANCEL {id} if your plans change.".to_string());
+        data.insert((Language::English, TranslationKey::PaymentReceived), "💰 Payment of {amount} received — M-Pesa receipt {receipt}.".to_string());
+        data.insert((Language::English, TranslationKey::PaymentConfirmed), "💰 Payment of {amount} received.".to_string());
+        data.insert((Language::English, TranslationKey::ChoosePaymentMethod), "💳 How would you like to pay {total} for order #{id}?".to_string());
+        data.insert((Language::English, TranslationKey::PaymentLinkSent), "💳 *Pay Online*\n\nPay {amount} for order #{id} here:\n{url}\n\nWe'll confirm your order once payment is received.".to_string());
+        data.insert((Language::English, TranslationKey::PaymentFailed), "⚠️ Your {method} payment for order #{id} didn't go through, so the order isn't confirmed yet.\n\nReply 2 to see your orders.".to_string());
+        data.insert((Language::English, TranslationKey::VoucherAlreadyRedeemed), "❌ This voucher has already been redeemed.".to_string());
+        data.insert((Language::English, TranslationKey::VoucherTryAgain), "Try again or reply *0* to go back.".to_string());
+        data.insert((Language::English, TranslationKey::NoOrdersYet), "📦 You don't have any orders yet.\n\nReply 1 to view our menu!".to_string());
+        data.insert((Language::English, TranslationKey::RecentOrders), "📦 *Your Recent Orders:*".to_string());
+        data.insert((Language::English, TranslationKey::OrderLine), "{emoji} Order #{id} — {total} — {status}".to_string());
+        data.insert((Language::English, TranslationKey::OrderHeader), "{emoji} *Order #{id}* — {total} — {status}".to_string());
+        data.insert((Language::English, TranslationKey::OrderPlacedEvent), "Placed".to_string());
+        data.insert((Language::English, TranslationKey::ReorderHint), "🔁 Reply *REORDER* to order your last one again, or *REORDER <number>* for another.".to_string());
+        data.insert((Language::English, TranslationKey::ManageOrdersHint), "Reply with an order number to cancel it or change the delivery address.".to_string());
+        data.insert((Language::English, TranslationKey::NoSuchOrder), "❌ You don't have an order #{id}.".to_string());
+        data.insert((Language::English, TranslationKey::ReceiptHint), "🧾 Reply *RECEIPT <number>* for a receipt.".to_string());
+        data.insert((Language::English, TranslationKey::ReceiptTitle), "Receipt for order #{id}".to_string());
+        data.insert((Language::English, TranslationKey::ReceiptStatus), "Status: {status}".to_string());
+        data.insert((Language::English, TranslationKey::OrderLocked), "{emoji} Order #{id} is {status} and can't be changed any more.".to_string());
+        data.insert((Language::English, TranslationKey::CancelOrderOption), "❌ Cancel order".to_string());
+        data.insert((Language::English, TranslationKey::ChangeAddressOption), "📍 Change delivery address".to_string());
+        data.insert((Language::English, TranslationKey::BackOption), "Back".to_string());
+        data.insert((Language::English, TranslationKey::ConfirmCancel), "❓ Cancel order #{id}?".to_string());
+        data.insert((Language::English, TranslationKey::RefundNotice), "💸 Your payment of {amount} will be refunded.".to_string());
+        data.insert((Language::English, TranslationKey::CancelOrKeep), "Reply *YES* to cancel or *NO* to keep it.".to_string());
+        data.insert((Language::English, TranslationKey::CancelOrKeepOrder), "Reply *YES* to cancel order #{id} or *NO* to keep it.".to_string());
+        data.insert((Language::English, TranslationKey::SendNewAddress), "📍 Send the new delivery address for order #{id} or share your location.".to_string());
+        data.insert((Language::English, TranslationKey::OrderUnchanged), "👍 Order #{id} is unchanged.".to_string());
+        data.insert((Language::English, TranslationKey::TooLateToCancel), "❌ Order #{id} is already {status} — please contact us to cancel it.".to_string());
+        data.insert((Language::English, TranslationKey::TooLateToChangeAddress), "❌ Order #{id} is already {status} — please contact us to change the address.".to_string());
+        data.insert((Language::English, TranslationKey::OrderAlreadyStatus), "❌ Order #{id} is already {status}.".to_string());
+        data.insert((Language::English, TranslationKey::OrderCancelledNotice), "❌ Order #{id} has been cancelled.".to_string());
+        data.insert((Language::English, TranslationKey::RefundSoon), "💸 We'll refund your payment of {amount} shortly.".to_string());
+        data.insert((Language::English, TranslationKey::RefundSent), "💸 Your payment of {amount} is being refunded to {phone}.".to_string());
+        data.insert((Language::English, TranslationKey::RefundCompleted), "✅ Your refund of {amount} for order #{id} has been sent — M-Pesa receipt {receipt}.".to_string());
+        data.insert((Language::English, TranslationKey::PaidAfterCancel), "💰 We received {amount} for order #{id}, but the order had already been cancelled.".to_string());
+        data.insert((Language::English, TranslationKey::NewAddressSaved), "✅ Order #{id} will be delivered to:\n📍 {address}".to_string());
+        data.insert((Language::English, TranslationKey::StatusPending), "Awaiting payment".to_string());
+        data.insert((Language::English, TranslationKey::StatusConfirmed), "Confirmed".to_string());
+        data.insert((Language::English, TranslationKey::StatusPreparing), "Being prepared".to_string());
+        data.insert((Language::English, TranslationKey::StatusDelivering), "Out for delivery".to_string());
+        data.insert((Language::English, TranslationKey::StatusDelivered), "Delivered".to_string());
+        data.insert((Language::English, TranslationKey::StatusCancelled), "Cancelled".to_string());
+        data.insert((Language::English, TranslationKey::TypeReply), "❌ Please type a reply.".to_string());
+        data.insert((Language::English, TranslationKey::MinLength), "❌ Please enter at least {min} characters.".to_string());
+        data.insert((Language::English, TranslationKey::MaxLength), "❌ Please keep it under {max} characters.".to_string());
+        data.insert((Language::English, TranslationKey::NumberExpected), "❌ Please reply with a number.".to_string());
+        data.insert((Language::English, TranslationKey::NumberMin), "❌ Please enter a number of at least {min}.".to_string());
+        data.insert((Language::English, TranslationKey::NumberMax), "❌ Please enter a number no greater than {max}.".to_string());
+        data.insert((Language::English, TranslationKey::LocationExpected), "📍 Please share your location or type an address.".to_string());
+        data.insert((Language::English, TranslationKey::ServiceRange), "Please reply with a service number from 1 to {max}.\n\nReply *0* to go back.".to_string());
+        data.insert((Language::English, TranslationKey::NoFreeAppointments), "😔 Sorry, there are no free appointments for {service} in the next {days} days.".to_string());
+        data.insert((Language::English, TranslationKey::ChooseDay), "📅 *{service}* ({minutes} min)\n\nWhich day suits you?".to_string());
+        data.insert((Language::English, TranslationKey::ChooseTime), "🕐 *{date}* — pick a time:".to_string());
+        data.insert((Language::English, TranslationKey::DayRange), "Please reply with a day number from 1 to {max}.".to_string());
+        data.insert((Language::English, TranslationKey::TimeRange), "Please reply with a time number from 1 to {max}.".to_string());
+        data.insert((Language::English, TranslationKey::SlotTaken), "😔 Sorry, that time was just taken.".to_string());
+        data.insert((Language::English, TranslationKey::SlotPassed), "⏰ Sorry, that time is no longer available.".to_string());
+        data.insert((Language::English, TranslationKey::NoUpcomingBookings), "📅 You don't have any upcoming bookings.\n\nReply 1 to see our services!".to_string());
+        data.insert((Language::English, TranslationKey::UpcomingBookings), "📅 *Your Upcoming Bookings:*".to_string());
+        data.insert((Language::English, TranslationKey::ManageBookingsHint), "Reply RESCHEDULE <id> or CANCEL <id> to change a booking.".to_string());
+        data.insert((Language::English, TranslationKey::NoSuchBooking), "❌ You don't have an upcoming booking #{id}.".to_string());
+        data.insert((Language::English, TranslationKey::BookingCancelled), "✅ Booking #{id} ({service}, {when}) has been cancelled.".to_string());
 
         // Swahili
         data.insert((Language::Swahili, TranslationKey::Welcome), "Karibu! 👋".to_string());
@@ -110,10 +502,140 @@ impl Translations {
         data.insert((Language::Swahili, TranslationKey::OrderConfirmed), "✅ Agizo limethibitishwa!".to_string());
         data.insert((Language::Swahili, TranslationKey::OrderDelivered), "🎉 Agizo limefikishwa! Furahia!".to_string());
         data.insert((Language::Swahili, TranslationKey::InvalidChoice), "❌ Chaguo batili. Tafadhali jaribu tena.".to_string());
-        data.insert((Language::Swahili, TranslationKey::MenuEmpty), "Hakuna vitu vinavyopatikana sasa hivi.".to_string());
+        data.insert((Language::Swahili, TranslationKey::MenuEmpty), "😔 Samahani, menyu yetu haina kitu kwa sasa. Tafadhali angalia tena baadaye!".to_string());
         data.insert((Language::Swahili, TranslationKey::OrderPlaced), "Agizo lako limewekwa!".to_string());
         data.insert((Language::Swahili, TranslationKey::ThankYou), "Asante! 😊".to_string());
         data.insert((Language::Swahili, TranslationKey::ChooseLanguage), "Chagua lugha yako".to_string());
+        data.insert((Language::Swahili, TranslationKey::WelcomeMenu), "Karibu! Jibu na namba:\n1. 📋 Angalia Menyu\n2. 📦 Maagizo Yangu\n3. 🎟️ Tumia Vocha\n4. ℹ️ Kuhusu Sisi".to_string());
+        data.insert((Language::Swahili, TranslationKey::AboutDefault), "Asante kwa kutuchagua!".to_string());
+        data.insert((Language::Swahili, TranslationKey::EnterVoucherCode), "🎟️ Weka namba ya vocha yako:".to_string());
+        data.insert((Language::Swahili, TranslationKey::GoBackHint), "Jibu *0* kurudi nyuma".to_string());
+        data.insert((Language::Swahili, TranslationKey::CancelHint), "Jibu *0* kughairi".to_string());
+        data.insert((Language::Swahili, TranslationKey::LanguageSet), "✅ Lugha imebadilishwa kuwa {language}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::ChooseButton), "📋 Chagua".to_string());
+        data.insert((Language::Swahili, TranslationKey::ConfirmButton), "✅ Thibitisha".to_string());
+        data.insert((Language::Swahili, TranslationKey::CancelButton), "❌ Ghairi".to_string());
+        data.insert((Language::Swahili, TranslationKey::Weekdays), "Jumatatu,Jumanne,Jumatano,Alhamisi,Ijumaa,Jumamosi,Jumapili".to_string());
+        data.insert((Language::Swahili, TranslationKey::Months), "Jan,Feb,Mac,Apr,Mei,Jun,Jul,Ago,Sep,Okt,Nov,Des".to_string());
+        data.insert((Language::Swahili, TranslationKey::DateFormat), "{weekday} {day} {month}".to_string());
+        data.insert((Language::Swahili, TranslationKey::ShortDateFormat), "{day} {month}".to_string());
+        data.insert((Language::Swahili, TranslationKey::DateAtTime), "{date} saa {time}".to_string());
+        data.insert((Language::Swahili, TranslationKey::Minutes), "dakika {minutes}".to_string());
+        data.insert((Language::Swahili, TranslationKey::MenuTitle), "📋 *Menyu ya {business}*".to_string());
+        data.insert((Language::Swahili, TranslationKey::OtherCategory), "Vingine".to_string());
+        data.insert((Language::Swahili, TranslationKey::PriceFrom), "kuanzia {price}".to_string());
+        data.insert((Language::Swahili, TranslationKey::DeliveryFee), "🚗 Ada ya usafirishaji: {fee}".to_string());
+        data.insert((Language::Swahili, TranslationKey::DeliveryEstimate), "⏱ Muda unaokadiriwa: {estimate}".to_string());
+        data.insert((Language::Swahili, TranslationKey::PickCategory), "Jibu na namba ya kundi kuona bidhaa zake".to_string());
+        data.insert((Language::Swahili, TranslationKey::PickService), "Jibu na namba ya huduma kuweka miadi".to_string());
+        data.insert((Language::Swahili, TranslationKey::PickItems), "Jibu na namba za bidhaa kuagiza\nmfano *1* au *1,3,5*".to_string());
+        data.insert((Language::Swahili, TranslationKey::AllCategories), "Jibu *menu* kuona makundi yote".to_string());
+        data.insert((Language::Swahili, TranslationKey::CategoryRange), "Tafadhali jibu na namba ya kundi kuanzia 1 hadi {max}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::ItemSelectionHelp), "Tafadhali jibu na namba za bidhaa kuagiza.\nmfano *1* au *1,3,5* au *2x1* (2 za bidhaa 1)\n\nJibu *0* kurudi nyuma.".to_string());
+        data.insert((Language::Swahili, TranslationKey::InvalidItems), "❌ Namba batili za bidhaa: {items}. Tafadhali angalia menyu kisha ujaribu tena.".to_string());
+        data.insert((Language::Swahili, TranslationKey::OnlyLeft), "😕 Zimebaki {left} tu za {item}. Tafadhali chagua idadi ndogo zaidi.".to_string());
+        data.insert((Language::Swahili, TranslationKey::ChooseVariant), "{emoji} *{item}* — ungependa ipi?".to_string());
+        data.insert((Language::Swahili, TranslationKey::ReplyWithNumber), "Jibu na namba.".to_string());
+        data.insert((Language::Swahili, TranslationKey::ChooseAddons), "{emoji} Ungependa nyongeza kwa *{item}*?".to_string());
+        data.insert((Language::Swahili, TranslationKey::AddonsHint), "Jibu na namba, mfano *1* au *1,2*, au *HAPANA* kama hutaki.".to_string());
+        data.insert((Language::Swahili, TranslationKey::NumberRange), "❌ Tafadhali jibu na namba kuanzia 1 hadi {max}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::AddonRange), "❌ Tafadhali jibu na namba za nyongeza kuanzia 1 hadi {max}, au *HAPANA* kama hutaki.".to_string());
+        data.insert((Language::Swahili, TranslationKey::YourOrder), "🛒 *Agizo Lako:*".to_string());
+        data.insert((Language::Swahili, TranslationKey::Subtotal), "Jumla ndogo: {amount}".to_string());
+        data.insert((Language::Swahili, TranslationKey::DeliveryCharge), "Usafirishaji: {amount}".to_string());
+        data.insert((Language::Swahili, TranslationKey::Total), "*Jumla: {amount}*".to_string());
+        data.insert((Language::Swahili, TranslationKey::ConfirmOrderHint), "Jibu *NDIYO* kuthibitisha".to_string());
+        data.insert((Language::Swahili, TranslationKey::AddMoreHint), "Jibu *ADD* + namba kuongeza bidhaa zaidi".to_string());
+        data.insert((Language::Swahili, TranslationKey::SkippedItems), "⚠️ Bidhaa batili zimerukwa: {items}".to_string());
+        data.insert((Language::Swahili, TranslationKey::CartHelp), "Jibu na namba za bidhaa kuongeza, *NDIYO* kuendelea na malipo, au *0* kughairi.".to_string());
+        data.insert((Language::Swahili, TranslationKey::AddItemsPrompt), "📋 Tuma namba za bidhaa kuongeza kwenye agizo lako:\n\n{menu}".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderTotalPrompt), "🛒 Jumla ya agizo lako: {total}\n\nJibu *NDIYO* kuthibitisha au *0* kughairi.".to_string());
+        data.insert((Language::Swahili, TranslationKey::AskDeliveryLocation), "📍 Vizuri! Sasa tuma *anwani ya kupelekewa* au shiriki *mahali ulipo*.".to_string());
+        data.insert((Language::Swahili, TranslationKey::AskAddress), "📍 Tafadhali tuma anwani yako kwa agizo hili.".to_string());
+        data.insert((Language::Swahili, TranslationKey::UseLastAddress), "Au jibu *SAME* kutumia anwani yako ya mwisho:\n📍 {address}".to_string());
+        data.insert((Language::Swahili, TranslationKey::NoAddressYet), "📍 Bado hatuna anwani yako — tafadhali tuma anwani ya kupelekewa au shiriki mahali ulipo.".to_string());
+        data.insert((Language::Swahili, TranslationKey::SendAddress), "📍 Tafadhali tuma anwani ya kupelekewa au shiriki mahali ulipo.".to_string());
+        data.insert((Language::Swahili, TranslationKey::SoldOutAtCheckout), "😕 Samahani, {item} imeisha sasa hivi (zimebaki {left} tu) na agizo lako halijawekwa.\n\nJibu *menu* kuagiza tena.".to_string());
+        data.insert((Language::Swahili, TranslationKey::PaymentRequestSent), "💰 *Ombi la Malipo Limetumwa*\n\nAngalia simu yako kwa ombi la malipo la M-Pesa.\nKiasi: {amount}\n\n⏱️ Tafadhali kamilisha malipo ndani ya dakika 2.\n\nTutathibitisha agizo lako tukipokea malipo.".to_string());
+        data.insert((Language::Swahili, TranslationKey::PaymentRequestFailed), "⚠️ Ombi la malipo halikufaulu: {error}\n\nAgizo lako limewekwa kwa malipo ya pesa taslimu.\nAgizo #{id} — Jumla: {total}".to_string());
+        data.insert((Language::Swahili, TranslationKey::NoPreviousOrder), "📦 Hakuna agizo la awali.\n\nJibu 1 kuona menyu yetu!".to_string());
+        data.insert((Language::Swahili, TranslationKey::NothingToReorder), "😕 Hakuna kitu cha agizo #{id} kilichobaki kwenye menyu.\n\nJibu 1 kuona menyu yetu!".to_string());
+        data.insert((Language::Swahili, TranslationKey::ReorderTitle), "🔁 *Agizo #{id} tena:*".to_string());
+        data.insert((Language::Swahili, TranslationKey::NoLongerAvailable), "⚠️ Hazipatikani tena: {items}".to_string());
+        data.insert((Language::Swahili, TranslationKey::ReorderConfirmHint), "Jibu *NDIYO* kuagiza hivi".to_string());
+        data.insert((Language::Swahili, TranslationKey::ReorderAddHint), "Jibu na namba za bidhaa kuongeza zaidi:\n{menu}".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderConfirmedMessage), "✅ Agizo #{id} limethibitishwa!\n📍 Tuma mahali ulipo au anwani yako\n⏱ Muda wa kufika: {estimate}".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderAcceptedMessage), "✅ Agizo #{id} limekubaliwa — tutakujulisha likianza kuandaliwa.".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderPreparingMessage), "🍳 Agizo #{id} linaandaliwa.".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderDeliveringMessage), "🚗 Agizo #{id} liko njiani!\n⏱ Muda wa kufika: {estimate}".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderDeliveredMessage), "🎉 Agizo #{id} limefikishwa! Furahia chakula chako!\nTupe alama: ⭐⭐⭐⭐⭐".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderCancelledMessage), "❌ Agizo #{id} limeghairiwa.\nJibu 1 kuagiza tena.".to_string());
+        data.insert((Language::Swahili, TranslationKey::VoucherRedeemedMessage), "✅ Vocha {code} imetumika! Punguzo la {currency}{amount} kwenye agizo lako lijalo.".to_string());
+        data.insert((Language::Swahili, TranslationKey::VoucherInvalidMessage), "❌ Namba hiyo ya vocha si sahihi au imeshatumika.".to_string());
+        data.insert((Language::Swahili, TranslationKey::BookingConfirmedMessage), "✅ Miadi #{id} imethibitishwa!\n{service}\n📅 {date} saa {time}\n\nJibu RESCHEDULE {id} au CANCEL {id} mipango yako ikibadilika.".to_string());
+        data.insert((Language::Swahili, TranslationKey::PaymentReceived), "💰 Malipo ya {amount} yamepokelewa — risiti ya M-Pesa {receipt}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::PaymentConfirmed), "💰 Malipo ya {amount} yamepokelewa.".to_string());
+        data.insert((Language::Swahili, TranslationKey::ChoosePaymentMethod), "💳 Ungependa kulipaje {total} kwa agizo #{id}?".to_string());
+        data.insert((Language::Swahili, TranslationKey::PaymentLinkSent), "💳 *Lipa Mtandaoni*\n\nLipa {amount} kwa agizo #{id} hapa:\n{url}\n\nTutathibitisha agizo lako tukipokea malipo.".to_string());
+        data.insert((Language::Swahili, TranslationKey::PaymentFailed), "⚠️ Malipo yako ya {method} kwa agizo #{id} hayakufaulu, kwa hiyo agizo bado halijathibitishwa.\n\nJibu 2 kuona maagizo yako.".to_string());
+        data.insert((Language::Swahili, TranslationKey::VoucherAlreadyRedeemed), "❌ Vocha hii imeshatumika.".to_string());
+        data.insert((Language::Swahili, TranslationKey::VoucherTryAgain), "Jaribu tena au jibu *0* kurudi nyuma.".to_string());
+        data.insert((Language::Swahili, TranslationKey::NoOrdersYet), "📦 Bado huna maagizo yoyote.\n\nJibu 1 kuona menyu yetu!".to_string());
+        data.insert((Language::Swahili, TranslationKey::RecentOrders), "📦 *Maagizo Yako ya Karibuni:*".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderLine), "{emoji} Agizo #{id} — {total} — {status}".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderHeader), "{emoji} *Agizo #{id}* — {total} — {status}".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderPlacedEvent), "Limewekwa".to_string());
+        data.insert((Language::Swahili, TranslationKey::ReorderHint), "🔁 Jibu *REORDER* kuagiza agizo lako la mwisho tena, au *REORDER <namba>* kwa jingine.".to_string());
+        data.insert((Language::Swahili, TranslationKey::ManageOrdersHint), "Jibu na namba ya agizo kulighairi au kubadilisha anwani ya kupelekewa.".to_string());
+        data.insert((Language::Swahili, TranslationKey::NoSuchOrder), "❌ Huna agizo #{id}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::ReceiptHint), "🧾 Jibu *RECEIPT <namba>* kupata risiti.".to_string());
+        data.insert((Language::Swahili, TranslationKey::ReceiptTitle), "Risiti ya agizo #{id}".to_string());
+        data.insert((Language::Swahili, TranslationKey::ReceiptStatus), "Hali: {status}".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderLocked), "{emoji} Agizo #{id}: {status} — haliwezi kubadilishwa tena.".to_string());
+        data.insert((Language::Swahili, TranslationKey::CancelOrderOption), "❌ Ghairi agizo".to_string());
+        data.insert((Language::Swahili, TranslationKey::ChangeAddressOption), "📍 Badilisha anwani ya kupelekewa".to_string());
+        data.insert((Language::Swahili, TranslationKey::BackOption), "Rudi".to_string());
+        data.insert((Language::Swahili, TranslationKey::ConfirmCancel), "❓ Ughairi agizo #{id}?".to_string());
+        data.insert((Language::Swahili, TranslationKey::RefundNotice), "💸 Malipo yako ya {amount} yatarejeshwa.".to_string());
+        data.insert((Language::Swahili, TranslationKey::CancelOrKeep), "Jibu *NDIYO* kughairi au *HAPANA* kuliacha.".to_string());
+        data.insert((Language::Swahili, TranslationKey::CancelOrKeepOrder), "Jibu *NDIYO* kughairi agizo #{id} au *HAPANA* kuliacha.".to_string());
+        data.insert((Language::Swahili, TranslationKey::SendNewAddress), "📍 Tuma anwani mpya ya kupelekewa agizo #{id} au shiriki mahali ulipo.".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderUnchanged), "👍 Agizo #{id} halijabadilishwa.".to_string());
+        data.insert((Language::Swahili, TranslationKey::TooLateToCancel), "❌ Agizo #{id} tayari: {status} — tafadhali wasiliana nasi kulighairi.".to_string());
+        data.insert((Language::Swahili, TranslationKey::TooLateToChangeAddress), "❌ Agizo #{id} tayari: {status} — tafadhali wasiliana nasi kubadilisha anwani.".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderAlreadyStatus), "❌ Agizo #{id} tayari: {status}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::OrderCancelledNotice), "❌ Agizo #{id} limeghairiwa.".to_string());
+        data.insert((Language::Swahili, TranslationKey::RefundSoon), "💸 Tutarejesha malipo yako ya {amount} hivi karibuni.".to_string());
+        data.insert((Language::Swahili, TranslationKey::RefundSent), "💸 Malipo yako ya {amount} yanarejeshwa kwa {phone}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::RefundCompleted), "✅ Marejesho yako ya {amount} kwa oda #{id} yametumwa — risiti ya M-Pesa {receipt}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::PaidAfterCancel), "💰 Tumepokea {amount} kwa oda #{id}, lakini oda hiyo ilikuwa imeshaghairiwa.".to_string());
+        data.insert((Language::Swahili, TranslationKey::NewAddressSaved), "✅ Agizo #{id} litapelekwa:\n📍 {address}".to_string());
+        data.insert((Language::Swahili, TranslationKey::StatusPending), "Linasubiri malipo".to_string());
+        data.insert((Language::Swahili, TranslationKey::StatusConfirmed), "Limethibitishwa".to_string());
+        data.insert((Language::Swahili, TranslationKey::StatusPreparing), "Linaandaliwa".to_string());
+        data.insert((Language::Swahili, TranslationKey::StatusDelivering), "Liko njiani".to_string());
+        data.insert((Language::Swahili, TranslationKey::StatusDelivered), "Limefikishwa".to_string());
+        data.insert((Language::Swahili, TranslationKey::StatusCancelled), "Limeghairiwa".to_string());
+        data.insert((Language::Swahili, TranslationKey::TypeReply), "❌ Tafadhali andika jibu.".to_string());
+        data.insert((Language::Swahili, TranslationKey::MinLength), "❌ Tafadhali andika angalau herufi {min}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::MaxLength), "❌ Tafadhali usizidi herufi {max}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::NumberExpected), "❌ Tafadhali jibu na namba.".to_string());
+        data.insert((Language::Swahili, TranslationKey::NumberMin), "❌ Tafadhali andika namba isiyopungua {min}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::NumberMax), "❌ Tafadhali andika namba isiyozidi {max}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::LocationExpected), "📍 Tafadhali shiriki mahali ulipo au andika anwani.".to_string());
+        data.insert((Language::Swahili, TranslationKey::ServiceRange), "Tafadhali jibu na namba ya huduma kuanzia 1 hadi {max}.\n\nJibu *0* kurudi nyuma.".to_string());
+        data.insert((Language::Swahili, TranslationKey::NoFreeAppointments), "😔 Samahani, hakuna nafasi za {service} katika siku {days} zijazo.".to_string());
+        data.insert((Language::Swahili, TranslationKey::ChooseDay), "📅 *{service}* (dakika {minutes})\n\nSiku gani inakufaa?".to_string());
+        data.insert((Language::Swahili, TranslationKey::ChooseTime), "🕐 *{date}* — chagua saa:".to_string());
+        data.insert((Language::Swahili, TranslationKey::DayRange), "Tafadhali jibu na namba ya siku kuanzia 1 hadi {max}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::TimeRange), "Tafadhali jibu na namba ya saa kuanzia 1 hadi {max}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::SlotTaken), "😔 Samahani, muda huo umechukuliwa sasa hivi.".to_string());
+        data.insert((Language::Swahili, TranslationKey::SlotPassed), "⏰ Samahani, muda huo haupatikani tena.".to_string());
+        data.insert((Language::Swahili, TranslationKey::NoUpcomingBookings), "📅 Huna miadi yoyote ijayo.\n\nJibu 1 kuona huduma zetu!".to_string());
+        data.insert((Language::Swahili, TranslationKey::UpcomingBookings), "📅 *Miadi Yako Ijayo:*".to_string());
+        data.insert((Language::Swahili, TranslationKey::ManageBookingsHint), "Jibu RESCHEDULE <namba> au CANCEL <namba> kubadilisha miadi.".to_string());
+        data.insert((Language::Swahili, TranslationKey::NoSuchBooking), "❌ Huna miadi ijayo #{id}.".to_string());
+        data.insert((Language::Swahili, TranslationKey::BookingCancelled), "✅ Miadi #{id} ({service}, {when}) imeghairiwa.".to_string());
 
         // Afrikaans
         data.insert((Language::Afrikaans, TranslationKey::Welcome), "Welkom! 👋".to_string());
@@ -128,6 +650,9 @@ impl Translations {
         data.insert((Language::Afrikaans, TranslationKey::OrderPlaced), "Jou bestelling is geplaas!".to_string());
         data.insert((Language::Afrikaans, TranslationKey::ThankYou), "Dankie! 😊".to_string());
         data.insert((Language::Afrikaans, TranslationKey::ChooseLanguage), "Kies jou taal".to_string());
+        data.insert((Language::Afrikaans, TranslationKey::ChooseButton), "📋 Kies".to_string());
+        data.insert((Language::Afrikaans, TranslationKey::ConfirmButton), "✅ Bevestig".to_string());
+        data.insert((Language::Afrikaans, TranslationKey::CancelButton), "❌ Kanselleer".to_string());
 
         // Portuguese
         data.insert((Language::Portuguese, TranslationKey::Welcome), "Bem-vindo! 👋".to_string());
@@ -138,10 +663,140 @@ impl Translations {
         data.insert((Language::Portuguese, TranslationKey::OrderConfirmed), "✅ Pedido confirmado!".to_string());
         data.insert((Language::Portuguese, TranslationKey::OrderDelivered), "🎉 Pedido entregue! Aproveite!".to_string());
         data.insert((Language::Portuguese, TranslationKey::InvalidChoice), "❌ Escolha inválida. Por favor, tente novamente.".to_string());
-        data.insert((Language::Portuguese, TranslationKey::MenuEmpty), "Nenhum item disponível no momento.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::MenuEmpty), "😔 Desculpe, o nosso menu está vazio de momento. Volte mais tarde!".to_string());
         data.insert((Language::Portuguese, TranslationKey::OrderPlaced), "Seu pedido foi feito!".to_string());
         data.insert((Language::Portuguese, TranslationKey::ThankYou), "Obrigado! 😊".to_string());
         data.insert((Language::Portuguese, TranslationKey::ChooseLanguage), "Escolha seu idioma".to_string());
+        data.insert((Language::Portuguese, TranslationKey::WelcomeMenu), "Bem-vindo! Responda com um número:\n1. 📋 Ver Menu\n2. 📦 Os Meus Pedidos\n3. 🎟️ Usar Voucher\n4. ℹ️ Sobre Nós".to_string());
+        data.insert((Language::Portuguese, TranslationKey::AboutDefault), "Obrigado por nos escolher!".to_string());
+        data.insert((Language::Portuguese, TranslationKey::EnterVoucherCode), "🎟️ Introduza o código do seu voucher:".to_string());
+        data.insert((Language::Portuguese, TranslationKey::GoBackHint), "Responda *0* para voltar".to_string());
+        data.insert((Language::Portuguese, TranslationKey::CancelHint), "Responda *0* para cancelar".to_string());
+        data.insert((Language::Portuguese, TranslationKey::LanguageSet), "✅ Idioma alterado para {language}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ChooseButton), "📋 Escolher".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ConfirmButton), "✅ Confirmar".to_string());
+        data.insert((Language::Portuguese, TranslationKey::CancelButton), "❌ Cancelar".to_string());
+        data.insert((Language::Portuguese, TranslationKey::Weekdays), "seg,ter,qua,qui,sex,sáb,dom".to_string());
+        data.insert((Language::Portuguese, TranslationKey::Months), "jan,fev,mar,abr,mai,jun,jul,ago,set,out,nov,dez".to_string());
+        data.insert((Language::Portuguese, TranslationKey::DateFormat), "{weekday}, {day} {month}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ShortDateFormat), "{day} {month}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::DateAtTime), "{date} às {time}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::Minutes), "{minutes} minutos".to_string());
+        data.insert((Language::Portuguese, TranslationKey::MenuTitle), "📋 *Menu — {business}*".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OtherCategory), "Outros".to_string());
+        data.insert((Language::Portuguese, TranslationKey::PriceFrom), "a partir de {price}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::DeliveryFee), "🚗 Taxa de entrega: {fee}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::DeliveryEstimate), "⏱ Tempo estimado: {estimate}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::PickCategory), "Responda com o número de uma categoria para ver os artigos".to_string());
+        data.insert((Language::Portuguese, TranslationKey::PickService), "Responda com o número de um serviço para marcar".to_string());
+        data.insert((Language::Portuguese, TranslationKey::PickItems), "Responda com o(s) número(s) dos artigos para encomendar\nex. *1* ou *1,3,5*".to_string());
+        data.insert((Language::Portuguese, TranslationKey::AllCategories), "Responda *menu* para ver todas as categorias".to_string());
+        data.insert((Language::Portuguese, TranslationKey::CategoryRange), "Por favor, responda com um número de categoria de 1 a {max}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ItemSelectionHelp), "Por favor, responda com o(s) número(s) dos artigos a encomendar.\nex. *1* ou *1,3,5* ou *2x1* (2 do artigo 1)\n\nResponda *0* para voltar.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::InvalidItems), "❌ Número(s) de artigo inválido(s): {items}. Verifique o menu e tente novamente.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OnlyLeft), "😕 Só restam {left} de {item}. Por favor, escolha uma quantidade menor.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ChooseVariant), "{emoji} *{item}* — qual prefere?".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ReplyWithNumber), "Responda com um número.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ChooseAddons), "{emoji} Quer extras para *{item}*?".to_string());
+        data.insert((Language::Portuguese, TranslationKey::AddonsHint), "Responda com o(s) número(s), ex. *1* ou *1,2*, ou *NÃO* para nenhum.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NumberRange), "❌ Por favor, responda com um número de 1 a {max}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::AddonRange), "❌ Por favor, responda com o(s) número(s) dos extras de 1 a {max}, ou *NÃO* para nenhum.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::YourOrder), "🛒 *O Seu Pedido:*".to_string());
+        data.insert((Language::Portuguese, TranslationKey::Subtotal), "Subtotal: {amount}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::DeliveryCharge), "Entrega: {amount}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::Total), "*Total: {amount}*".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ConfirmOrderHint), "Responda *SIM* para confirmar".to_string());
+        data.insert((Language::Portuguese, TranslationKey::AddMoreHint), "Responda *ADD* + números para adicionar mais artigos".to_string());
+        data.insert((Language::Portuguese, TranslationKey::SkippedItems), "⚠️ Artigos inválidos ignorados: {items}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::CartHelp), "Responda com números de artigos para adicionar, *SIM* para finalizar, ou *0* para cancelar.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::AddItemsPrompt), "📋 Envie o(s) número(s) dos artigos a adicionar ao pedido:\n\n{menu}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderTotalPrompt), "🛒 Total do seu pedido: {total}\n\nResponda *SIM* para confirmar ou *0* para cancelar.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::AskDeliveryLocation), "📍 Ótimo! Agora envie a sua *morada de entrega* ou partilhe a sua *localização*.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::AskAddress), "📍 Por favor, envie a sua morada para o pedido.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::UseLastAddress), "Ou responda *SAME* para usar a sua última morada:\n📍 {address}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NoAddressYet), "📍 Ainda não temos a sua morada — envie a sua morada de entrega ou partilhe a sua localização.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::SendAddress), "📍 Por favor, envie a sua morada de entrega ou partilhe a sua localização.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::SoldOutAtCheckout), "😕 Desculpe, {item} acabou de esgotar (só restam {left}) e o seu pedido não foi feito.\n\nResponda *menu* para encomendar novamente.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::PaymentRequestSent), "💰 *Pedido de Pagamento Enviado*\n\nVeja no seu telemóvel o pedido de pagamento M-Pesa.\nValor: {amount}\n\n⏱️ Por favor, conclua o pagamento dentro de 2 minutos.\n\nConfirmamos o seu pedido assim que recebermos o pagamento.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::PaymentRequestFailed), "⚠️ O pedido de pagamento falhou: {error}\n\nO seu pedido foi registado para pagamento em dinheiro.\nPedido #{id} — Total: {total}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NoPreviousOrder), "📦 Não encontrámos nenhum pedido anterior.\n\nResponda 1 para ver o nosso menu!".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NothingToReorder), "😕 Nada do pedido #{id} continua no menu.\n\nResponda 1 para ver o nosso menu!".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ReorderTitle), "🔁 *Repetir o pedido #{id}:*".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NoLongerAvailable), "⚠️ Já não disponível: {items}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ReorderConfirmHint), "Responda *SIM* para encomendar".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ReorderAddHint), "Responda com o(s) número(s) dos artigos para adicionar mais:\n{menu}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderConfirmedMessage), "✅ Pedido #{id} confirmado!\n📍 Envie a sua localização ou morada\n⏱ Entrega estimada: {estimate}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderAcceptedMessage), "✅ O pedido #{id} foi aceite — avisamos quando estiver a ser preparado.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderPreparingMessage), "🍳 O pedido #{id} está a ser preparado.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderDeliveringMessage), "🚗 O pedido #{id} está a caminho!\n⏱ Entrega estimada: {estimate}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderDeliveredMessage), "🎉 O pedido #{id} foi entregue! Bom apetite!\nAvalie-nos: ⭐⭐⭐⭐⭐".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderCancelledMessage), "❌ O pedido #{id} foi cancelado.\nResponda 1 para encomendar novamente.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::VoucherRedeemedMessage), "✅ Voucher {code} resgatado! {currency}{amount} de desconto no seu próximo pedido.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::VoucherInvalidMessage), "❌ Esse código de voucher é inválido ou já foi usado.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::BookingConfirmedMessage), "✅ Marcação #{id} confirmada!\n{service}\n📅 {date} às {time}\n\nResponda RESCHEDULE {id} ou CANCEL {id} se os seus planos mudarem.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::PaymentReceived), "💰 Pagamento de {amount} recebido — recibo M-Pesa {receipt}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::PaymentConfirmed), "💰 Pagamento de {amount} recebido.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ChoosePaymentMethod), "💳 Como gostaria de pagar {total} pelo pedido #{id}?".to_string());
+        data.insert((Language::Portuguese, TranslationKey::PaymentLinkSent), "💳 *Pagar Online*\n\nPague {amount} pelo pedido #{id} aqui:\n{url}\n\nConfirmamos o seu pedido assim que recebermos o pagamento.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::PaymentFailed), "⚠️ O seu pagamento {method} do pedido #{id} não foi concluído, por isso o pedido ainda não está confirmado.\n\nResponda 2 para ver os seus pedidos.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::VoucherAlreadyRedeemed), "❌ Este voucher já foi resgatado.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::VoucherTryAgain), "Tente novamente ou responda *0* para voltar.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NoOrdersYet), "📦 Ainda não tem pedidos.\n\nResponda 1 para ver o nosso menu!".to_string());
+        data.insert((Language::Portuguese, TranslationKey::RecentOrders), "📦 *Os Seus Pedidos Recentes:*".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderLine), "{emoji} Pedido #{id} — {total} — {status}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderHeader), "{emoji} *Pedido #{id}* — {total} — {status}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderPlacedEvent), "Feito".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ReorderHint), "🔁 Responda *REORDER* para repetir o último pedido, ou *REORDER <número>* para outro.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ManageOrdersHint), "Responda com o número de um pedido para o cancelar ou mudar a morada de entrega.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NoSuchOrder), "❌ Não tem nenhum pedido #{id}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ReceiptHint), "🧾 Responda *RECEIPT <número>* para receber o recibo.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ReceiptTitle), "Recibo do pedido #{id}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ReceiptStatus), "Estado: {status}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderLocked), "{emoji} O pedido #{id} está {status} e já não pode ser alterado.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::CancelOrderOption), "❌ Cancelar pedido".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ChangeAddressOption), "📍 Mudar a morada de entrega".to_string());
+        data.insert((Language::Portuguese, TranslationKey::BackOption), "Voltar".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ConfirmCancel), "❓ Cancelar o pedido #{id}?".to_string());
+        data.insert((Language::Portuguese, TranslationKey::RefundNotice), "💸 O seu pagamento de {amount} será reembolsado.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::CancelOrKeep), "Responda *SIM* para cancelar ou *NÃO* para o manter.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::CancelOrKeepOrder), "Responda *SIM* para cancelar o pedido #{id} ou *NÃO* para o manter.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::SendNewAddress), "📍 Envie a nova morada de entrega do pedido #{id} ou partilhe a sua localização.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderUnchanged), "👍 O pedido #{id} não foi alterado.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::TooLateToCancel), "❌ O pedido #{id} já está {status} — contacte-nos para o cancelar.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::TooLateToChangeAddress), "❌ O pedido #{id} já está {status} — contacte-nos para mudar a morada.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderAlreadyStatus), "❌ O pedido #{id} já está {status}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::OrderCancelledNotice), "❌ O pedido #{id} foi cancelado.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::RefundSoon), "💸 Vamos reembolsar o seu pagamento de {amount} em breve.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::RefundSent), "💸 O seu pagamento de {amount} está a ser reembolsado para {phone}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::RefundCompleted), "✅ O seu reembolso de {amount} da encomenda #{id} foi enviado — recibo M-Pesa {receipt}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::PaidAfterCancel), "💰 Recebemos {amount} pela encomenda #{id}, mas a encomenda já tinha sido cancelada.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NewAddressSaved), "✅ O pedido #{id} será entregue em:\n📍 {address}".to_string());
+        data.insert((Language::Portuguese, TranslationKey::StatusPending), "A aguardar pagamento".to_string());
+        data.insert((Language::Portuguese, TranslationKey::StatusConfirmed), "Confirmado".to_string());
+        data.insert((Language::Portuguese, TranslationKey::StatusPreparing), "Em preparação".to_string());
+        data.insert((Language::Portuguese, TranslationKey::StatusDelivering), "A caminho".to_string());
+        data.insert((Language::Portuguese, TranslationKey::StatusDelivered), "Entregue".to_string());
+        data.insert((Language::Portuguese, TranslationKey::StatusCancelled), "Cancelado".to_string());
+        data.insert((Language::Portuguese, TranslationKey::TypeReply), "❌ Por favor, escreva uma resposta.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::MinLength), "❌ Por favor, escreva pelo menos {min} caracteres.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::MaxLength), "❌ Por favor, use menos de {max} caracteres.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NumberExpected), "❌ Por favor, responda com um número.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NumberMin), "❌ Por favor, indique um número de pelo menos {min}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NumberMax), "❌ Por favor, indique um número não superior a {max}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::LocationExpected), "📍 Por favor, partilhe a sua localização ou escreva uma morada.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ServiceRange), "Por favor, responda com um número de serviço de 1 a {max}.\n\nResponda *0* para voltar.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NoFreeAppointments), "😔 Desculpe, não há horários livres para {service} nos próximos {days} dias.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ChooseDay), "📅 *{service}* ({minutes} min)\n\nQue dia lhe convém?".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ChooseTime), "🕐 *{date}* — escolha uma hora:".to_string());
+        data.insert((Language::Portuguese, TranslationKey::DayRange), "Por favor, responda com um número de dia de 1 a {max}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::TimeRange), "Por favor, responda com um número de hora de 1 a {max}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::SlotTaken), "😔 Desculpe, esse horário acabou de ser ocupado.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::SlotPassed), "⏰ Desculpe, esse horário já não está disponível.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NoUpcomingBookings), "📅 Não tem marcações futuras.\n\nResponda 1 para ver os nossos serviços!".to_string());
+        data.insert((Language::Portuguese, TranslationKey::UpcomingBookings), "📅 *As Suas Próximas Marcações:*".to_string());
+        data.insert((Language::Portuguese, TranslationKey::ManageBookingsHint), "Responda RESCHEDULE <número> ou CANCEL <número> para alterar uma marcação.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::NoSuchBooking), "❌ Não tem nenhuma marcação futura #{id}.".to_string());
+        data.insert((Language::Portuguese, TranslationKey::BookingCancelled), "✅ A marcação #{id} ({service}, {when}) foi cancelada.".to_string());
 
         // Hindi
         data.insert((Language::Hindi, TranslationKey::Welcome), "स्वागत है! 👋".to_string());
@@ -156,6 +811,9 @@ impl Translations {
         data.insert((Language::Hindi, TranslationKey::OrderPlaced), "आपका ऑर्डर दिया गया है!".to_string());
         data.insert((Language::Hindi, TranslationKey::ThankYou), "धन्यवाद! 😊".to_string());
         data.insert((Language::Hindi, TranslationKey::ChooseLanguage), "अपनी भाषा चुनें".to_string());
+        data.insert((Language::Hindi, TranslationKey::ChooseButton), "📋 चुनें".to_string());
+        data.insert((Language::Hindi, TranslationKey::ConfirmButton), "✅ पुष्टि करें".to_string());
+        data.insert((Language::Hindi, TranslationKey::CancelButton), "❌ रद्द करें".to_string());
 
         // Spanish
         data.insert((Language::Spanish, TranslationKey::Welcome), "¡Bienvenido! 👋".to_string());
@@ -170,6 +828,9 @@ impl Translations {
         data.insert((Language::Spanish, TranslationKey::OrderPlaced), "¡Tu pedido ha sido realizado!".to_string());
         data.insert((Language::Spanish, TranslationKey::ThankYou), "¡Gracias! 😊".to_string());
         data.insert((Language::Spanish, TranslationKey::ChooseLanguage), "Elige tu idioma".to_string());
+        data.insert((Language::Spanish, TranslationKey::ChooseButton), "📋 Elegir".to_string());
+        data.insert((Language::Spanish, TranslationKey::ConfirmButton), "✅ Confirmar".to_string());
+        data.insert((Language::Spanish, TranslationKey::CancelButton), "❌ Cancelar".to_string());
 
         // French
         data.insert((Language::French, TranslationKey::Welcome), "Bienvenue! 👋".to_string());
@@ -184,8 +845,144 @@ impl Translations {
         data.insert((Language::French, TranslationKey::OrderPlaced), "Votre commande a été passée!".to_string());
         data.insert((Language::French, TranslationKey::ThankYou), "Merci! 😊".to_string());
         data.insert((Language::French, TranslationKey::ChooseLanguage), "Choisissez votre langue".to_string());
+        data.insert((Language::French, TranslationKey::ChooseButton), "📋 Choisir".to_string());
+        data.insert((Language::French, TranslationKey::ConfirmButton), "✅ Confirmer".to_string());
+        data.insert((Language::French, TranslationKey::CancelButton), "❌ Annuler".to_string());
+
+        Self {
+            data,
+            locales: HashMap::new(),
+        }
+    }
+
+    /// The built-in translations plus any `locales/*.yaml` in `project_dir`.
+    pub fn load(project_dir: &Path) -> Result<Self> {
+        let mut translations = Self::new();
+        let dir = project_dir.join("locales");
+        if !dir.is_dir() {
+            return Ok(translations);
+        }
+
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(&dir).with_context(|| format!("Could not read {}", dir.display()))? {
+            let path = entry?.path();
+            if matches!(path.extension().and_then(|e| e.to_str()), Some("yaml" | "yml")) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        for path in paths {
+            let code = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_lowercase();
+            if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                anyhow::bail!("{}: the file name must be a language code, e.g. sw.yaml", path.display());
+            }
+            let contents =
+                std::fs::read_to_string(&path).with_context(|| format!("Could not read {}", path.display()))?;
+            let locale: Locale = serde_yaml::from_str(&contents)
+                .with_context(|| format!("Invalid locale file {}", path.display()))?;
+
+            let lang = Language::for_locale(&code);
+            if matches!(lang, Language::Custom(_)) && locale.name.is_none() {
+                anyhow::bail!("{}: `name` is required for a new language", path.display());
+            }
+            if translations.locales.contains_key(&lang) {
+                anyhow::bail!("{}: there is already a locale file for '{}'", path.display(), code);
+            }
+            translations.add_locale(lang, locale);
+        }
+        Ok(translations)
+    }
+
+    /// Layer `locale` over the translations for `lang`.
+    pub fn add_locale(&mut self, lang: Language, mut locale: Locale) {
+        for (key, text) in locale.strings.drain() {
+            self.data.insert((lang, key), text);
+        }
+        self.locales.insert(lang, locale);
+    }
+
+    /// The locale file loaded for `lang`, if there is one.
+    pub fn locale(&self, lang: Language) -> Option<&Locale> {
+        self.locales.get(&lang)
+    }
+
+    /// The language with ISO `code`, built in or from a locale file.
+    pub fn language(&self, code: &str) -> Option<Language> {
+        let code = code.to_lowercase();
+        Language::from_code(&code).or_else(|| self.locales.keys().find(|l| l.code() == code).copied())
+    }
+
+    /// Every language: the built-in ones, then those from locale files.
+    pub fn languages(&self) -> Vec<Language> {
+        let mut custom: Vec<Language> = self
+            .locales
+            .keys()
+            .filter(|l| matches!(l, Language::Custom(_)))
+            .copied()
+            .collect();
+        custom.sort_by_key(|l| l.code());
+        Language::ALL.into_iter().chain(custom).collect()
+    }
+
+    /// Name of `lang` in itself, as its locale file spells it if it has one.
+    pub fn language_name(&self, lang: Language) -> &str {
+        self.locale(lang)
+            .and_then(|l| l.name.as_deref())
+            .unwrap_or_else(|| lang.native_name())
+    }
+
+    /// A customer message (`order_confirmed`, `welcome`, …) from `lang`'s locale file.
+    pub fn message(&self, lang: Language, name: &str) -> Option<&str> {
+        self.locale(lang)?.messages.get(name).map(|s| s.as_str())
+    }
+
+    /// A menu item's name in `lang`.
+    pub fn item_name<'a>(&'a self, lang: Language, name: &'a str) -> &'a str {
+        self.locale(lang)
+            .and_then(|l| l.menu.get(name))
+            .and_then(|t| t.name.as_deref())
+            .unwrap_or(name)
+    }
 
-        Self { data }
+    /// A menu item's description in `lang`, or `description` if it has no translation.
+    pub fn item_description<'a>(&'a self, lang: Language, name: &str, description: Option<&'a str>) -> Option<&'a str> {
+        self.locale(lang)
+            .and_then(|l| l.menu.get(name))
+            .and_then(|t| t.description.as_deref())
+            .or(description)
+    }
+
+    /// Keys with an English string but none in `lang`, by name.
+    pub fn missing(&self, lang: Language) -> Vec<TranslationKey> {
+        let mut missing: Vec<TranslationKey> = self
+            .data
+            .keys()
+            .filter(|(l, key)| *l == Language::English && !self.data.contains_key(&(lang, *key)))
+            .map(|(_, key)| *key)
+            .collect();
+        missing.sort_by_key(|k| k.name());
+        missing
+    }
+
+    /// Keys whose `lang` string has different `{placeholders}` from the English one.
+    pub fn placeholder_mismatches(&self, lang: Language) -> Vec<TranslationKey> {
+        let mut mismatched: Vec<TranslationKey> = self
+            .data
+            .iter()
+            .filter(|((l, _), _)| *l == lang && lang != Language::English)
+            .filter(|((_, key), text)| {
+                self.get(Language::English, *key)
+                    .is_some_and(|english| placeholders(english) != placeholders(text))
+            })
+            .map(|((_, key), _)| *key)
+            .collect();
+        mismatched.sort_by_key(|k| k.name());
+        mismatched
     }
 
     /// Get translation for a key in a specific language
@@ -199,6 +996,79 @@ impl Translations {
             .or_else(|| self.get(Language::English, key))
             .unwrap_or("[missing translation]")
     }
+
+    /// Get a translation with its `{placeholders}` filled in from `vars`
+    pub fn render(&self, lang: Language, key: TranslationKey, vars: &[(&str, &str)]) -> String {
+        MessageTemplates::render(self.get_or_fallback(lang, key), vars)
+    }
+
+    /// Translate `key` into `lang`.
+    pub fn text(&self, lang: Language, key: TranslationKey) -> String {
+        self.get_or_fallback(lang, key).to_string()
+    }
+
+    /// A date for customers, e.g. "Mon 2 Mar" or "Jumatatu 2 Mac".
+    pub fn format_date(&self, lang: Language, date: NaiveDate) -> String {
+        let weekday = self.date_name(lang, TranslationKey::Weekdays, date.weekday().num_days_from_monday());
+        let month = self.date_name(lang, TranslationKey::Months, date.month0());
+        self.render(
+            lang,
+            TranslationKey::DateFormat,
+            &[("weekday", &weekday), ("day", &date.day().to_string()), ("month", &month)],
+        )
+    }
+
+    /// A date without the weekday, e.g. "2 Mar".
+    pub fn format_short_date(&self, lang: Language, date: NaiveDate) -> String {
+        let month = self.date_name(lang, TranslationKey::Months, date.month0());
+        self.render(
+            lang,
+            TranslationKey::ShortDateFormat,
+            &[("day", &date.day().to_string()), ("month", &month)],
+        )
+    }
+
+    /// The `index`th name in a comma-separated list of weekday or month names.
+    fn date_name(&self, lang: Language, key: TranslationKey, index: u32) -> String {
+        self.get_or_fallback(lang, key)
+            .split(',')
+            .nth(index as usize)
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    }
+}
+
+static BUILT_IN: LazyLock<Arc<Translations>> = LazyLock::new(|| Arc::new(Translations::new()));
+
+/// The built-in translations, for configs with no locale files.
+pub fn built_in() -> Arc<Translations> {
+    BUILT_IN.clone()
+}
+
+/// The `{placeholder}` names in `text`.
+fn placeholders(text: &str) -> HashSet<&str> {
+    text.split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}'))
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Whether `text` means yes in any supported language.
+pub fn is_yes(text: &str) -> bool {
+    matches!(
+        text.trim().to_uppercase().as_str(),
+        "YES" | "Y" | "CONFIRM" | "NDIYO" | "NDIO" | "SIM" | "JA" | "SÍ" | "SI" | "OUI" | "हाँ" | "हां"
+    )
+}
+
+/// Whether `text` means no in any supported language.
+pub fn is_no(text: &str) -> bool {
+    matches!(
+        text.trim().to_uppercase().as_str(),
+        "NO" | "N" | "HAPANA" | "NÃO" | "NAO" | "NEE" | "NON" | "नहीं"
+    )
 }
 
 impl Default for Translations {
@@ -225,10 +1095,96 @@ mod tests {
         assert_eq!(t.get_or_fallback(Language::Portuguese, TranslationKey::OrderConfirmed), "✅ Pedido confirmado!");
     }
 
+    #[test]
+    fn test_render_with_fallback() {
+        let t = Translations::new();
+        let vars = [("id", "7")];
+        assert_eq!(
+            t.render(Language::Swahili, TranslationKey::OrderPreparingMessage, &vars),
+            "🍳 Agizo #7 linaandaliwa."
+        );
+        assert_eq!(
+            t.render(Language::Portuguese, TranslationKey::OrderPreparingMessage, &vars),
+            "🍳 O pedido #7 está a ser preparado."
+        );
+        // Not translated into Hindi yet
+        assert_eq!(
+            t.render(Language::Hindi, TranslationKey::OrderPreparingMessage, &vars),
+            "🍳 Order #7 is being prepared."
+        );
+    }
+
+    #[test]
+    fn test_format_date() {
+        let t = Translations::new();
+        let date = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        assert_eq!(t.format_date(Language::English, date), "Mon 2 Mar");
+        assert_eq!(t.format_date(Language::Swahili, date), "Jumatatu 2 Mac");
+        assert_eq!(t.format_date(Language::Portuguese, date), "seg, 2 mar");
+        assert_eq!(t.format_short_date(Language::English, date), "2 Mar");
+    }
+
+    #[test]
+    fn test_yes_and_no() {
+        assert!(is_yes("yes") && is_yes("Ndiyo") && is_yes("sim"));
+        assert!(is_no("no") && is_no("hapana") && is_no("não"));
+        assert!(!is_yes("1") && !is_no("yes"));
+    }
+
     #[test]
     fn test_language_codes() {
         assert_eq!(Language::from_code("sw"), Some(Language::Swahili));
         assert_eq!(Language::from_code("en"), Some(Language::English));
         assert_eq!(Language::Swahili.code(), "sw");
     }
+
+    #[test]
+    fn test_load_locale_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("locales")).unwrap();
+        std::fs::write(
+            dir.path().join("locales/sw.yaml"),
+            "strings:\n  thank_you: \"Asante sana!\"\nmenu:\n  Kota:\n    name: \"Kota ya Nyama\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("locales/zu.yaml"),
+            "name: isiZulu\nstrings:\n  thank_you: \"Ngiyabonga!\"\n  order_preparing_message: \"Ioda #{id} iyalungiswa.\"\n",
+        )
+        .unwrap();
+
+        let t = Translations::load(dir.path()).unwrap();
+        assert_eq!(t.get_or_fallback(Language::Swahili, TranslationKey::ThankYou), "Asante sana!");
+        assert_eq!(t.get_or_fallback(Language::Swahili, TranslationKey::Welcome), "Karibu! 👋");
+        assert_eq!(t.item_name(Language::Swahili, "Kota"), "Kota ya Nyama");
+        assert_eq!(t.item_name(Language::Swahili, "Gatsby"), "Gatsby");
+
+        let zulu = t.language("zu").unwrap();
+        assert_eq!(zulu.code(), "zu");
+        assert_eq!(t.language_name(zulu), "isiZulu");
+        assert_eq!(t.languages().last(), Some(&zulu));
+        assert_eq!(t.get_or_fallback(zulu, TranslationKey::ThankYou), "Ngiyabonga!");
+        // Falls back to English until translated
+        assert_eq!(t.get_or_fallback(zulu, TranslationKey::Welcome), "Welcome! 👋");
+        assert!(t.missing(zulu).contains(&TranslationKey::Welcome));
+        assert!(!t.missing(zulu).contains(&TranslationKey::ThankYou));
+        assert!(t.missing(Language::English).is_empty());
+        assert!(t.placeholder_mismatches(zulu).is_empty());
+
+        // A new language needs a name
+        std::fs::write(dir.path().join("locales/yo.yaml"), "strings: {}\n").unwrap();
+        assert!(Translations::load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_placeholder_mismatches() {
+        let mut t = Translations::new();
+        let mut locale = Locale::default();
+        locale
+            .strings
+            .insert(TranslationKey::NoSuchOrder, "❌ Huna agizo #{order}.".to_string());
+        t.add_locale(Language::Swahili, locale);
+        assert_eq!(t.placeholder_mismatches(Language::Swahili), vec![TranslationKey::NoSuchOrder]);
+        assert_eq!(TranslationKey::NoSuchOrder.name(), "no_such_order");
+    }
 }
@@ -2,6 +2,7 @@
 //!
 //! Library crate exposing modules for integration tests and examples.
 
+pub mod bookings;
 pub mod bot;
 pub mod config;
 pub mod dashboard;
@@ -9,5 +10,7 @@ pub mod handlers;
 pub mod i18n;
 pub mod network;
 pub mod payments;
+pub mod pdf;
 pub mod store;
+pub mod transport;
 pub mod vouchers;
@@ -1,11 +1,18 @@
 #![allow(dead_code)]
 //! Hive — WhatsApp Bot Framework for Reality Network
 //!
-//! CLI entry point with three commands:
+//! CLI entry point with four main commands:
 //! - `hive init <path>` — scaffold a new bot project
 //! - `hive run <path>` — start bot + optional dashboard
 //! - `hive dashboard <path>` — start only the dashboard
+//! - `hive chat <path>` — talk to the bot from the terminal
+//!
+//! Plus `hive db status|migrate <path>` for schema maintenance,
+//! `hive user ...` for dashboard logins, `hive i18n check <path>` for
+//! the project's translations, `hive mpesa credential <path>` for B2C
+//! security credentials and `hive mpesa-sim` for testing payments offline.
 
+mod bookings;
 mod bot;
 mod config;
 mod dashboard;
@@ -13,13 +20,15 @@ mod handlers;
 mod i18n;
 pub mod network;
 mod payments;
+mod pdf;
 mod store;
+mod transport;
 mod vouchers;
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use log::info;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(
@@ -62,11 +71,141 @@ enum Commands {
         /// Path to the bot project directory (containing config.yaml)
         path: PathBuf,
     },
+    /// Chat with the bot in the terminal (no WhatsApp needed)
+    Chat {
+        /// Path to the bot project directory (containing config.yaml)
+        path: PathBuf,
+        /// Phone number to chat as
+        #[arg(long, default_value = "+15550000001")]
+        phone: String,
+        /// Start with admin identity
+        #[arg(long)]
+        admin: bool,
+    },
+    /// Inspect or migrate the bot's database schema
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+    /// Manage dashboard logins and API keys
+    User {
+        #[command(subcommand)]
+        command: UserCommands,
+    },
+    /// Check the project's translations
+    I18n {
+        #[command(subcommand)]
+        command: I18nCommands,
+    },
+    /// Set up M-Pesa
+    Mpesa {
+        #[command(subcommand)]
+        command: MpesaCommands,
+    },
+    /// Run a fake M-Pesa API for testing payments offline
+    MpesaSim {
+        /// Port to listen on (set payments.mpesa.base_url to http://localhost:<port>)
+        #[arg(long, default_value_t = 8090)]
+        port: u16,
+        /// How every payment ends: success, failure or timeout
+        #[arg(long, default_value = "success")]
+        outcome: payments::mpesa_sim::SimOutcome,
+        /// Seconds before a payment settles, like a customer entering their PIN
+        #[arg(long, default_value_t = 5)]
+        delay: u64,
+        /// Send callbacks to this bot (e.g. http://localhost:8080) instead of the host in the callback URL
+        #[arg(long)]
+        bot_url: Option<String>,
+        /// Don't send callbacks, so results only show up in status queries
+        #[arg(long)]
+        no_callbacks: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum UserCommands {
+    /// Add a dashboard user (prompts for the password)
+    Add {
+        /// Path to the bot project directory (containing config.yaml)
+        path: PathBuf,
+        username: String,
+        /// owner, staff or accountant
+        #[arg(long, default_value = "staff")]
+        role: String,
+    },
+    /// List dashboard users
+    List {
+        /// Path to the bot project directory (containing config.yaml)
+        path: PathBuf,
+    },
+    /// Set a new password (prompts for it) and sign out the user's sessions
+    Password {
+        /// Path to the bot project directory (containing config.yaml)
+        path: PathBuf,
+        username: String,
+    },
+    /// Remove a user and their API keys
+    Remove {
+        /// Path to the bot project directory (containing config.yaml)
+        path: PathBuf,
+        username: String,
+    },
+    /// Create an API key that acts as the given user
+    ApiKey {
+        /// Path to the bot project directory (containing config.yaml)
+        path: PathBuf,
+        username: String,
+        /// Label to recognise the key by
+        #[arg(long)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Show the current schema version and pending migrations
+    Status {
+        /// Path to the bot project directory (containing config.yaml)
+        path: PathBuf,
+    },
+    /// Apply pending migrations
+    Migrate {
+        /// Path to the bot project directory (containing config.yaml)
+        path: PathBuf,
+        /// List pending migrations without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum I18nCommands {
+    /// Report missing and broken translations for each language
+    Check {
+        /// Path to the bot project directory (containing config.yaml)
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum MpesaCommands {
+    /// Encrypt the B2C initiator password (prompts for it) into payments.mpesa_b2c.security_credential
+    Credential {
+        /// Path to the bot project directory (containing config.yaml)
+        path: PathBuf,
+        /// Safaricom's sandbox or production certificate, relative to the
+        /// project directory (default: the only .cer, .crt or .pem file there)
+        #[arg(long)]
+        cert: Option<PathBuf>,
+    },
 }
 
 /// Default config template embedded at compile time
 const DEFAULT_CONFIG: &str = include_str!("../templates/default.yaml");
 
+/// How often `hive run` / `hive dashboard` check config.yaml for changes
+const CONFIG_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
 #[tokio::main]
 async fn main() -> Result<()> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
@@ -79,6 +218,27 @@ async fn main() -> Result<()> {
         Commands::Templates => cmd_templates()?,
         Commands::Run { path, phone } => cmd_run(&path, phone).await?,
         Commands::Dashboard { path } => cmd_dashboard(&path).await?,
+        Commands::Chat { path, phone, admin } => cmd_chat(&path, phone, admin).await?,
+        Commands::Db { command } => match command {
+            DbCommands::Status { path } => cmd_db_status(&path)?,
+            DbCommands::Migrate { path, dry_run } => cmd_db_migrate(&path, dry_run)?,
+        },
+        Commands::User { command } => cmd_user(command)?,
+        Commands::I18n { command } => match command {
+            I18nCommands::Check { path } => cmd_i18n_check(&path)?,
+        },
+        Commands::Mpesa { command } => match command {
+            MpesaCommands::Credential { path, cert } => cmd_mpesa_credential(&path, cert.as_deref())?,
+        },
+        Commands::MpesaSim { port, outcome, delay, bot_url, no_callbacks } => {
+            cmd_mpesa_sim(port, payments::mpesa_sim::SimConfig {
+                outcome,
+                delay: std::time::Duration::from_secs(delay),
+                bot_url,
+                send_callbacks: !no_callbacks,
+            })
+            .await?
+        }
     }
 
     Ok(())
@@ -323,17 +483,32 @@ async fn cmd_run(path: &PathBuf, phone: Option<String>) -> Result<()> {
     std::fs::create_dir_all(db_path.parent().unwrap())?;
     let store = store::Store::new(db_path.to_str().unwrap())
         .with_context(|| "Failed to initialize database")?;
+    store.seed_menu(&config.menu)?;
+
+    // WhatsApp transport, shared with the dashboard for webhook notifications
+    let mut whatsapp = transport::WhatsAppTransport::new(path).with_interactive(config.whatsapp.interactive);
+    if let Some(phone) = phone {
+        whatsapp = whatsapp.with_phone_number(phone);
+    }
+    let transport: std::sync::Arc<dyn transport::MessagingTransport> = std::sync::Arc::new(whatsapp);
+
+    // Pick up config.yaml edits without dropping the WhatsApp session
+    let live_config = config::live::LiveConfig::new(config.clone(), path);
+    tokio::spawn(live_config.clone().watch(store.clone(), CONFIG_POLL_INTERVAL));
 
-    // Create shared WhatsApp client (populated after bot connects)
-    let wa_client_shared = std::sync::Arc::new(tokio::sync::RwLock::new(None));
+    // One refund provider for the bot and the dashboard
+    let refund_provider = payments::refunds::provider_from_config(&config);
 
     // Start dashboard in background if enabled
     let dashboard_handle = if config.dashboard.enabled {
-        let dashboard_config = config.clone();
+        let dashboard_config = live_config.clone();
         let dashboard_store = store.clone();
-        let dashboard_client = wa_client_shared.clone();
+        let dashboard_transport = transport.clone();
+        let dashboard_refunds = refund_provider.clone();
         Some(tokio::spawn(async move {
-            if let Err(e) = dashboard::run_dashboard(dashboard_config, dashboard_store, dashboard_client).await {
+            if let Err(e) =
+                dashboard::run_dashboard(dashboard_config, dashboard_store, Some(dashboard_transport), dashboard_refunds).await
+            {
                 log::error!("Dashboard error: {}", e);
             }
         }))
@@ -341,12 +516,8 @@ async fn cmd_run(path: &PathBuf, phone: Option<String>) -> Result<()> {
         None
     };
 
-    // Start the WhatsApp bot
-    let mut engine = bot::BotEngine::new(config, store, path.clone()).await?;
-    if let Some(phone) = phone {
-        engine = engine.with_phone_number(phone);
-    }
-    engine = engine.with_wa_client_shared(wa_client_shared);
+    // Start the bot
+    let mut engine = bot::BotEngine::new(live_config, store, path.clone(), transport, refund_provider).await?;
     engine.run().await?;
 
     // Wait for dashboard if it was started
@@ -365,14 +536,361 @@ async fn cmd_dashboard(path: &PathBuf) -> Result<()> {
     let db_path = path.join("data").join("hive.db");
     let store = store::Store::new(db_path.to_str().unwrap())
         .with_context(|| "Failed to initialize database")?;
+    store.seed_menu(&config.menu)?;
 
     info!(
         "🐝 Starting Hive dashboard for \"{}\" on port {}",
         config.business.name, config.dashboard.port
     );
 
-    // Dashboard-only mode: no WhatsApp client (webhooks won't send notifications)
-    let wa_client_shared = std::sync::Arc::new(tokio::sync::RwLock::new(None));
+    // Dashboard-only mode: no transport (webhooks won't send notifications)
+    let refund_provider = payments::refunds::provider_from_config(&config);
+    let live_config = config::live::LiveConfig::new(config, path);
+    tokio::spawn(live_config.clone().watch(store.clone(), CONFIG_POLL_INTERVAL));
+    dashboard::run_dashboard(live_config, store, None, refund_provider).await
+}
+
+/// `hive mpesa-sim` — serve a fake Daraja API until interrupted
+/// `hive mpesa credential <path>` — encrypt the B2C initiator password into config.yaml
+fn cmd_mpesa_credential(path: &Path, cert: Option<&Path>) -> Result<()> {
+    use payments::credential;
+
+    let cert_path = credential::find_certificate(path, cert)?;
+    let certificate =
+        std::fs::read(&cert_path).with_context(|| format!("Failed to read {}", cert_path.display()))?;
+    let config_path = path.join("config.yaml");
+    let config = std::fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read {}", config_path.display()))?;
+
+    println!("🔐 Encrypting the B2C initiator password with {}", cert_path.display());
+    let password = read_new_password()?;
+    let security_credential = credential::security_credential(&password, &certificate)?;
+    std::fs::write(&config_path, credential::set_security_credential(&config, &security_credential)?)?;
+    println!("✅ Wrote payments.mpesa_b2c.security_credential to {}", config_path.display());
+    Ok(())
+}
+
+async fn cmd_mpesa_sim(port: u16, config: payments::mpesa_sim::SimConfig) -> Result<()> {
+    println!("🧪 M-Pesa simulator on http://localhost:{}", port);
+    println!("   Every payment: {:?}, after {}s", config.outcome, config.delay.as_secs());
+    match (&config.bot_url, config.send_callbacks) {
+        (_, false) => println!("   Callbacks: off (status queries only)"),
+        (Some(url), true) => println!("   Callbacks: to {}", url),
+        (None, true) => println!("   Callbacks: to each request's callback URL"),
+    }
+    println!("\n   In config.yaml:\n     payments:\n       mpesa:\n         base_url: \"http://localhost:{}\"\n", port);
+    payments::mpesa_sim::serve(port, config).await
+}
+
+/// `hive db status <path>` — show schema version and pending migrations
+fn cmd_db_status(path: &PathBuf) -> Result<()> {
+    let db_path = path.join("data").join("hive.db");
+    if !db_path.exists() {
+        println!("No database at {} yet — it will be created on first run.", db_path.display());
+        return Ok(());
+    }
+
+    let store = store::Store::open_read_only(db_path.to_str().unwrap())?;
+    let status = store.schema_status()?;
 
-    dashboard::run_dashboard(config, store, wa_client_shared).await
+    println!("🗄️  {}", db_path.display());
+    println!(
+        "   Schema version: {} (latest: {})\n",
+        status.current_version, status.latest_version
+    );
+    for m in &status.applied {
+        println!("   ✅ {:>3}  {:<28} {}", m.version, m.name, m.applied_at);
+    }
+    for (version, name) in &status.pending {
+        println!("   ⏳ {:>3}  {}", version, name);
+    }
+    if status.pending.is_empty() {
+        println!("\n   Up to date.");
+    } else {
+        println!("\n   {} pending — run: hive db migrate {}", status.pending.len(), path.display());
+    }
+    Ok(())
+}
+
+/// `hive db migrate <path>` — apply (or list) pending migrations
+fn cmd_db_migrate(path: &PathBuf, dry_run: bool) -> Result<()> {
+    let db_path = path.join("data").join("hive.db");
+    // A dry run only looks, so it must not create the database either
+    let store = if dry_run {
+        if !db_path.exists() {
+            anyhow::bail!("No database at {}", db_path.display());
+        }
+        store::Store::open_read_only(db_path.to_str().unwrap())
+    } else {
+        std::fs::create_dir_all(db_path.parent().unwrap())?;
+        store::Store::open(db_path.to_str().unwrap())
+    }
+    .with_context(|| "Failed to open database")?;
+    let status = store.schema_status()?;
+
+    if status.pending.is_empty() {
+        println!("✅ Schema is up to date (version {}).", status.current_version);
+        return Ok(());
+    }
+
+    if dry_run {
+        println!("Would apply {} migration(s):", status.pending.len());
+        for (version, name) in &status.pending {
+            println!("   {:>3}  {}", version, name);
+        }
+        return Ok(());
+    }
+
+    let applied = store.migrate()?;
+    println!(
+        "✅ Applied {} migration(s), schema is now at version {}.",
+        applied.len(),
+        status.latest_version
+    );
+    Ok(())
+}
+
+/// `hive i18n check <path>` — report missing keys per language
+///
+/// Covers the languages the bot offers plus any with a locale file. Missing
+/// keys fall back to English, so they are only reported; a translation that
+/// drops a `{placeholder}` or a locale menu entry for an item that isn't on
+/// the menu is an error.
+fn cmd_i18n_check(path: &Path) -> Result<()> {
+    let config = config::HiveConfig::load(path)?;
+    let i18n = &config.i18n;
+
+    let mut languages = config.offered_languages();
+    for lang in i18n.languages() {
+        if i18n.locale(lang).is_some() && !languages.contains(&lang) {
+            languages.push(lang);
+        }
+    }
+
+    let mut errors = 0;
+    for lang in languages {
+        let file = match i18n.locale(lang) {
+            Some(_) => format!(" — locales/{}.yaml", lang.code()),
+            None => String::new(),
+        };
+        println!("🌐 {} ({}){}", i18n.language_name(lang), lang.code(), file);
+
+        let missing = i18n.missing(lang);
+        if missing.is_empty() {
+            println!("   ✅ Nothing missing");
+        } else {
+            println!("   ⚠️  {} missing (English is used):", missing.len());
+            for key in &missing {
+                println!("      {}", key.name());
+            }
+        }
+        for key in i18n.placeholder_mismatches(lang) {
+            println!("   ❌ {}: placeholders differ from English", key.name());
+            errors += 1;
+        }
+        if let Some(locale) = i18n.locale(lang) {
+            let mut unknown: Vec<&String> = locale
+                .menu
+                .keys()
+                .filter(|name| !config.menu.iter().any(|item| &item.name == *name))
+                .collect();
+            unknown.sort();
+            for name in unknown {
+                println!("   ❌ menu: no item named '{}'", name);
+                errors += 1;
+            }
+        }
+        println!();
+    }
+
+    if errors > 0 {
+        anyhow::bail!("{} translation error(s)", errors);
+    }
+    println!("✅ Translations OK");
+    Ok(())
+}
+
+/// `hive user ...` — manage dashboard logins
+fn cmd_user(command: UserCommands) -> Result<()> {
+    use store::{TOKEN_API_KEY, UserRole};
+
+    let open = |path: &PathBuf| -> Result<store::Store> {
+        let db_path = path.join("data").join("hive.db");
+        std::fs::create_dir_all(db_path.parent().unwrap())?;
+        store::Store::new(db_path.to_str().unwrap()).with_context(|| "Failed to open database")
+    };
+
+    match command {
+        UserCommands::Add { path, username, role } => {
+            let Some(role) = UserRole::parse(&role) else {
+                anyhow::bail!("Unknown role '{}' (use owner, staff or accountant)", role);
+            };
+            let store = open(&path)?;
+            let password = read_new_password()?;
+            dashboard::auth::validate_credentials(&username, &password)?;
+            let user = store.create_user(&username, &dashboard::auth::hash_password(&password)?, role)?;
+            println!("✅ Added {} ({}). They can now sign in to the dashboard.", user.username, user.role.as_str());
+        }
+        UserCommands::List { path } => {
+            let users = open(&path)?.list_users()?;
+            if users.is_empty() {
+                println!("No dashboard users yet — add one with: hive user add {} <username> --role owner", path.display());
+            }
+            for user in users {
+                println!(
+                    "   {:<20} {:<11} last login: {}",
+                    user.username,
+                    user.role.as_str(),
+                    user.last_login_at.as_deref().unwrap_or("never")
+                );
+            }
+        }
+        UserCommands::Password { path, username } => {
+            let store = open(&path)?;
+            let password = read_new_password()?;
+            dashboard::auth::validate_credentials(&username, &password)?;
+            if !store.set_user_password(&username, &dashboard::auth::hash_password(&password)?)? {
+                anyhow::bail!("No user named '{}'", username);
+            }
+            println!("✅ Password changed for {}.", username);
+        }
+        UserCommands::Remove { path, username } => {
+            if !open(&path)?.delete_user(&username)? {
+                anyhow::bail!("No user named '{}'", username);
+            }
+            println!("✅ Removed {}.", username);
+        }
+        UserCommands::ApiKey { path, username, name } => {
+            let store = open(&path)?;
+            let Some((user, _)) = store.user_credentials(&username)? else {
+                anyhow::bail!("No user named '{}'", username);
+            };
+            let key = dashboard::auth::new_token();
+            let hash = dashboard::auth::token_hash(&key);
+            store.create_token(user.id, &hash, TOKEN_API_KEY, name.as_deref(), None)?;
+            println!("🔑 API key for {} ({}):\n\n   {}\n", user.username, user.role.as_str(), key);
+            println!("   Send it as \"Authorization: Bearer <key>\". It won't be shown again.");
+        }
+    }
+    Ok(())
+}
+
+/// Ask for a new password twice on stdin.
+fn read_new_password() -> Result<String> {
+    use std::io::{self, Write};
+
+    let ask = |prompt: &str| -> Result<String> {
+        print!("{}", prompt);
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        Ok(input.trim_end_matches(['\r', '\n']).to_string())
+    };
+    let password = ask("Password: ")?;
+    if ask("Repeat password: ")? != password {
+        anyhow::bail!("Passwords don't match");
+    }
+    Ok(password)
+}
+
+/// `hive chat <path>` — local REPL against the project's config and store
+async fn cmd_chat(path: &PathBuf, phone: String, admin: bool) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let config = config::HiveConfig::load(path)
+        .with_context(|| format!("Failed to load config from {}", path.display()))?;
+
+    let db_path = path.join("data").join("hive.db");
+    std::fs::create_dir_all(db_path.parent().unwrap())?;
+    let store = store::Store::new(db_path.to_str().unwrap())
+        .with_context(|| "Failed to initialize database")?;
+    store.seed_menu(&config.menu)?;
+
+    let mut sim = bot::simulator::ChatSimulator::with_store(config, store);
+    let mut phone = phone;
+    if admin {
+        set_chat_admin(&mut sim, &phone, true);
+    }
+
+    println!("🐝 Chatting with \"{}\" as {}", sim.config().business.name, phone);
+    println!("   Payments are disabled in chat mode. Type /help for commands.\n");
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        let is_admin = sim.config().is_admin(&phone);
+        let mut stdout = tokio::io::stdout();
+        stdout
+            .write_all(format!("{}{} > ", phone, if is_admin { " (admin)" } else { "" }).as_bytes())
+            .await?;
+        stdout.flush().await?;
+
+        let Some(line) = lines.next_line().await? else {
+            break;
+        };
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+
+        let result = if let Some(command) = input.strip_prefix('/') {
+            let (command, arg) = command.split_once(' ').unwrap_or((command, ""));
+            match command {
+                "quit" | "exit" => break,
+                "help" => {
+                    println!("  /as <phone>        chat as another phone number");
+                    println!("  /admin             toggle admin identity for the current phone");
+                    println!("  /location <text>   share a location");
+                    println!("  /state             show conversation state");
+                    println!("  /quit              leave the chat");
+                    continue;
+                }
+                "as" if !arg.trim().is_empty() => {
+                    phone = arg.trim().to_string();
+                    println!("  Now chatting as {}", phone);
+                    continue;
+                }
+                "admin" => {
+                    set_chat_admin(&mut sim, &phone, !is_admin);
+                    println!("  Admin identity {}", if is_admin { "off" } else { "on" });
+                    continue;
+                }
+                "state" => {
+                    println!("  {}", sim.state(&phone)?.to_json());
+                    continue;
+                }
+                "location" if !arg.trim().is_empty() => sim.send_location(&phone, arg.trim()).await,
+                _ => {
+                    println!("  Unknown command. Type /help for commands.");
+                    continue;
+                }
+            }
+        } else {
+            sim.send(&phone, input).await
+        };
+
+        match result {
+            Ok(outbox) => {
+                for msg in outbox {
+                    if msg.to == phone {
+                        println!("\n🐝 {}\n", msg.text.replace('\n', "\n   "));
+                    } else {
+                        println!("\n📤 to {}:\n   {}\n", msg.to, msg.text.replace('\n', "\n   "));
+                    }
+                }
+            }
+            Err(e) => println!("  ❌ Error: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Add or remove `phone` from the simulator's (in-memory) admin numbers.
+fn set_chat_admin(sim: &mut bot::simulator::ChatSimulator, phone: &str, admin: bool) {
+    let digits = transport::phone_digits(phone);
+    let admins = &mut sim.config_mut().admin_numbers;
+    admins.retain(|n| transport::phone_digits(n) != digits);
+    if admin {
+        admins.push(phone.to_string());
+    }
 }
@@ -19,8 +19,11 @@ pub struct B2CConfig {
     pub shortcode: String,
     pub initiator_name: String,
     pub security_credential: String,
-    pub callback_url: String,
+    pub result_url: String,
+    pub timeout_url: String,
     pub sandbox: bool,
+    /// Daraja API to use instead of Safaricom's (e.g. `hive mpesa-sim`)
+    pub base_url: Option<String>,
 }
 
 pub struct B2CClient {
@@ -81,6 +84,33 @@ struct B2CResponse {
     error_message: Option<String>,
 }
 
+/// Result of a B2C payout, posted to the `ResultURL` (or the
+/// `QueueTimeOutURL` when it timed out).
+#[derive(Debug, Deserialize)]
+pub struct B2CCallback {
+    #[serde(rename = "Result")]
+    pub result: B2CResult,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct B2CResult {
+    #[serde(rename = "ResultCode")]
+    pub result_code: i64,
+    #[serde(rename = "ResultDesc")]
+    pub result_desc: String,
+    #[serde(rename = "ConversationID")]
+    pub conversation_id: String,
+    /// The M-Pesa receipt for the payout
+    #[serde(rename = "TransactionID", default)]
+    pub transaction_id: Option<String>,
+}
+
+impl B2CResult {
+    pub fn is_successful(&self) -> bool {
+        self.result_code == 0
+    }
+}
+
 /// B2C transaction type
 #[derive(Debug, Clone, Copy)]
 pub enum B2CTransactionType {
@@ -111,8 +141,31 @@ impl B2CClient {
         }
     }
 
+    /// A client for `payments.mpesa_b2c`, sharing the `mpesa` section's app
+    /// keys and environment where it doesn't set its own. `None` when B2C
+    /// isn't configured.
+    pub fn from_config(payments: &crate::config::PaymentConfig) -> Option<Self> {
+        let b2c = payments.mpesa_b2c.as_ref()?;
+        let mpesa = payments.mpesa.as_ref();
+        let consumer_key = b2c.consumer_key.clone().or_else(|| mpesa.map(|m| m.consumer_key.clone()))?;
+        let consumer_secret = b2c.consumer_secret.clone().or_else(|| mpesa.map(|m| m.consumer_secret.clone()))?;
+        Some(Self::new(B2CConfig {
+            consumer_key,
+            consumer_secret,
+            shortcode: b2c.shortcode.clone(),
+            initiator_name: b2c.initiator_name.clone(),
+            security_credential: b2c.security_credential.clone(),
+            result_url: b2c.result_url.clone(),
+            timeout_url: b2c.timeout_url.clone().unwrap_or_else(|| b2c.result_url.clone()),
+            sandbox: b2c.sandbox.or(mpesa.map(|m| m.sandbox)).unwrap_or(false),
+            base_url: b2c.base_url.clone().or_else(|| mpesa.and_then(|m| m.base_url.clone())),
+        }))
+    }
+
     fn base_url(&self) -> &str {
-        if self.config.sandbox {
+        if let Some(ref url) = self.config.base_url {
+            url.trim_end_matches('/')
+        } else if self.config.sandbox {
             MPESA_SANDBOX_URL
         } else {
             MPESA_PRODUCTION_URL
@@ -208,8 +261,8 @@ impl B2CClient {
             party_a: self.config.shortcode.clone(),
             party_b: phone_formatted.clone(),
             remarks: remarks.to_string(),
-            queue_timeout_url: self.config.callback_url.clone(),
-            result_url: self.config.callback_url.clone(),
+            queue_timeout_url: self.config.timeout_url.clone(),
+            result_url: self.config.result_url.clone(),
             occasion: occasion.to_string(),
         };
 
@@ -282,8 +335,10 @@ mod tests {
             shortcode: "600000".to_string(),
             initiator_name: "test".to_string(),
             security_credential: "test".to_string(),
-            callback_url: "https://example.com/callback".to_string(),
+            result_url: "https://example.com/callback".to_string(),
+            timeout_url: "https://example.com/callback".to_string(),
             sandbox: true,
+            base_url: None,
         };
         let client = B2CClient::new(config);
         
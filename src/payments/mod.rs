@@ -4,14 +4,25 @@
 //! - M-Pesa (Kenya) - Mobile money via Safaricom
 //! - PayStack (Nigeria, Ghana, South Africa) - Card payments
 //! - Stripe (International) - Coming soon
+//!
+//! Every provider configured under `payments` is enabled at once (see
+//! `PaymentProviders`); with more than one, customers pick at checkout.
 
 pub mod b2c;
+pub mod credential;
 pub mod mpesa;
+pub mod mpesa_sim;
+pub mod paystack;
+pub mod reconcile;
+pub mod refunds;
+pub mod registry;
 pub mod types;
 pub mod webhook;
 
 pub use b2c::{B2CClient, B2CConfig, B2CTransactionType};
 pub use mpesa::MpesaClient;
+pub use refunds::RefundProvider;
+pub use registry::PaymentProviders;
 pub use types::{Payment, PaymentMethod, PaymentStatus};
 pub use webhook::{MpesaCallback, PaymentCallbackResult, process_callback};
 
@@ -20,6 +31,9 @@ use anyhow::Result;
 /// Payment provider trait
 #[async_trait::async_trait]
 pub trait PaymentProvider: Send + Sync {
+    /// The payment method this provider takes
+    fn method(&self) -> PaymentMethod;
+
     /// Initiate a payment request
     async fn initiate_payment(
         &self,
@@ -27,8 +41,42 @@ pub trait PaymentProvider: Send + Sync {
         currency: &str,
         phone: &str,
         reference: &str,
-    ) -> Result<String>;
+    ) -> Result<PaymentRequest>;
 
     /// Check payment status
-    async fn check_status(&self, payment_id: &str) -> Result<PaymentStatus>;
+    async fn check_status(&self, payment_id: &str) -> Result<PaymentCheck>;
+}
+
+/// What a provider's status query says about a payment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentCheck {
+    pub status: PaymentStatus,
+    /// What the customer was charged, for providers whose query says
+    /// (PayStack's verify does, M-Pesa's STK Push Query doesn't)
+    pub charged: Option<Charge>,
+}
+
+impl From<PaymentStatus> for PaymentCheck {
+    fn from(status: PaymentStatus) -> Self {
+        Self { status, charged: None }
+    }
+}
+
+/// An amount a provider charged, in the currency's main unit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Charge {
+    pub amount: f64,
+    /// ISO currency code, e.g. `NGN`
+    pub currency: String,
+}
+
+/// A payment a provider has started.
+#[derive(Debug, Clone)]
+pub struct PaymentRequest {
+    /// The provider's reference for the payment (M-Pesa's CheckoutRequestID,
+    /// PayStack's transaction reference)
+    pub provider_ref: String,
+    /// Page where the customer pays, for providers that take payment on the
+    /// web rather than on the customer's phone
+    pub checkout_url: Option<String>,
 }
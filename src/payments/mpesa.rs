@@ -1,11 +1,12 @@
 //! M-Pesa payment integration (Safaricom Kenya)
 //!
-//! Implements STK Push (Lipa na M-Pesa Online) for customer payments.
+//! Implements STK Push (Lipa na M-Pesa Online) for customer payments, and
+//! the STK Push Query for checking on one whose callback never arrived.
 
-use super::types::PaymentStatus;
-use super::PaymentProvider;
+use super::types::{PaymentMethod, PaymentStatus};
+use super::{PaymentCheck, PaymentProvider, PaymentRequest};
 use anyhow::{Result, Context, bail};
-use log::{info, warn};
+use log::info;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -22,6 +23,8 @@ pub struct MpesaConfig {
     pub passkey: String,
     pub callback_url: String,
     pub sandbox: bool,
+    /// Daraja API to use instead of Safaricom's (e.g. `hive mpesa-sim`)
+    pub base_url: Option<String>,
 }
 
 pub struct MpesaClient {
@@ -86,6 +89,59 @@ struct StkPushResponse {
     error_message: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+struct StkQueryRequest {
+    #[serde(rename = "BusinessShortCode")]
+    business_short_code: String,
+    #[serde(rename = "Password")]
+    password: String,
+    #[serde(rename = "Timestamp")]
+    timestamp: String,
+    #[serde(rename = "CheckoutRequestID")]
+    checkout_request_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct StkQueryResponse {
+    /// "0" once paid; Safaricom sends it as a string, but be lenient
+    #[serde(rename = "ResultCode")]
+    result_code: Option<serde_json::Value>,
+    #[serde(rename = "ResultDesc")]
+    result_desc: Option<String>,
+    #[serde(rename = "errorCode")]
+    error_code: Option<String>,
+    #[serde(rename = "errorMessage")]
+    error_message: Option<String>,
+}
+
+/// Query error code while the customer hasn't answered the prompt yet.
+const STILL_PROCESSING: &str = "500.001.1001";
+
+/// STK Push result code when the customer dismissed the prompt.
+const CANCELLED_BY_USER: &str = "1032";
+
+impl StkQueryResponse {
+    /// What the query says about the payment.
+    fn status(&self) -> Result<PaymentStatus> {
+        if let Some(ref code) = self.error_code {
+            if code == STILL_PROCESSING {
+                return Ok(PaymentStatus::Processing);
+            }
+            bail!("M-Pesa error {}: {}", code, self.error_message.as_deref().unwrap_or_default());
+        }
+        let code = match self.result_code {
+            Some(serde_json::Value::String(ref code)) => code.clone(),
+            Some(ref code) => code.to_string(),
+            None => bail!("No ResultCode in STK Push query response"),
+        };
+        Ok(match code.as_str() {
+            "0" => PaymentStatus::Completed,
+            CANCELLED_BY_USER => PaymentStatus::Cancelled,
+            _ => PaymentStatus::Failed,
+        })
+    }
+}
+
 impl MpesaClient {
     pub fn new(config: MpesaConfig) -> Self {
         Self {
@@ -96,7 +152,9 @@ impl MpesaClient {
     }
 
     fn base_url(&self) -> &str {
-        if self.config.sandbox {
+        if let Some(ref url) = self.config.base_url {
+            url.trim_end_matches('/')
+        } else if self.config.sandbox {
             MPESA_SANDBOX_URL
         } else {
             MPESA_PRODUCTION_URL
@@ -183,13 +241,18 @@ impl MpesaClient {
 
 #[async_trait::async_trait]
 impl PaymentProvider for MpesaClient {
+    fn method(&self) -> PaymentMethod {
+        PaymentMethod::MPesa
+    }
+
+    /// Send an STK Push; the customer approves it on their phone.
     async fn initiate_payment(
         &self,
         amount: f64,
         _currency: &str,
         phone: &str,
         reference: &str,
-    ) -> Result<String> {
+    ) -> Result<PaymentRequest> {
         let access_token = self.get_access_token().await?;
         let phone_formatted = self.format_phone(phone);
         
@@ -248,16 +311,68 @@ impl PaymentProvider for MpesaClient {
             response_body.customer_message.unwrap_or_default()
         );
 
-        Ok(checkout_request_id)
+        Ok(PaymentRequest {
+            provider_ref: checkout_request_id,
+            checkout_url: None,
+        })
     }
 
-    async fn check_status(&self, payment_id: &str) -> Result<PaymentStatus> {
-        // M-Pesa status check requires the CheckoutRequestID
-        // In a real implementation, you'd query the STK Push status endpoint
-        // For now, we rely on the callback webhook to update payment status
-        warn!("M-Pesa check_status not implemented yet, use webhook callbacks");
-        Ok(PaymentStatus::Pending)
+    /// Ask M-Pesa how an STK Push went. `payment_id` is its CheckoutRequestID.
+    async fn check_status(&self, payment_id: &str) -> Result<PaymentCheck> {
+        let access_token = self.get_access_token().await?;
+        let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S").to_string();
+        let request = StkQueryRequest {
+            business_short_code: self.config.shortcode.clone(),
+            password: self.generate_password(&timestamp),
+            timestamp,
+            checkout_request_id: payment_id.to_string(),
+        };
+
+        let url = format!("{}/mpesa/stkpushquery/v1/query", self.base_url());
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", access_token))
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send STK Push query")?;
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let query: StkQueryResponse = serde_json::from_str(&body)
+            .with_context(|| format!("Failed to parse STK Push query response ({}): {}", status, body))?;
+        let payment_status = query.status()?;
+        info!(
+            "🔎 M-Pesa STK Push {} is {}: {}",
+            payment_id,
+            payment_status,
+            query.result_desc.unwrap_or_default()
+        );
+        Ok(payment_status.into())
     }
 }
 
 // Need to add base64 and chrono dependencies
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(json: &str) -> Result<PaymentStatus> {
+        serde_json::from_str::<StkQueryResponse>(json).unwrap().status()
+    }
+
+    #[test]
+    fn test_stk_query_status() {
+        let paid = r#"{"ResponseCode":"0","ResponseDescription":"The service request has been accepted successfully",
+            "MerchantRequestID":"22205-34066-1","CheckoutRequestID":"ws_CO_13012021093521236557",
+            "ResultCode":"0","ResultDesc":"The service request is processed successfully."}"#;
+        assert_eq!(status(paid).unwrap(), PaymentStatus::Completed);
+        assert_eq!(status(r#"{"ResultCode":"1032","ResultDesc":"Request cancelled by user"}"#).unwrap(), PaymentStatus::Cancelled);
+        assert_eq!(status(r#"{"ResultCode":1037,"ResultDesc":"DS timeout user cannot be reached"}"#).unwrap(), PaymentStatus::Failed);
+        let waiting = r#"{"requestId":"","errorCode":"500.001.1001","errorMessage":"The transaction is being processed"}"#;
+        assert_eq!(status(waiting).unwrap(), PaymentStatus::Processing);
+        assert!(status(r#"{"errorCode":"404.001.03","errorMessage":"Invalid Access Token"}"#).is_err());
+    }
+}
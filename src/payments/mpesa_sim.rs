@@ -0,0 +1,407 @@
+//! A fake M-Pesa Daraja API for developing and testing payments offline.
+//!
+//! `hive mpesa-sim` serves the endpoints Hive uses — OAuth, STK Push, STK
+//! Push Query and B2C payment requests — and after a short delay sends the
+//! result callback Safaricom would. Point the bot at it with
+//! `payments.mpesa.base_url: http://localhost:8090`.
+//!
+//! Every payment ends the same way, set by `SimConfig::outcome`:
+//! - `success`: paid (STK result 0, B2C result 0)
+//! - `failure`: the customer cancelled the prompt (1032); for B2C, the
+//!   initiator was rejected (2001)
+//! - `timeout`: the customer never answered (1037); B2C requests time out
+//!   in the queue and only the timeout URL is called
+//!
+//! With `send_callbacks` off the result is only visible to status queries,
+//! which is how the reconciliation poller finds it.
+
+use anyhow::Result;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How simulated payments end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimOutcome {
+    Success,
+    Failure,
+    Timeout,
+}
+
+impl SimOutcome {
+    /// STK Push result code and description.
+    fn stk_result(self) -> (i64, &'static str) {
+        match self {
+            SimOutcome::Success => (0, "The service request is processed successfully."),
+            SimOutcome::Failure => (1032, "Request cancelled by user"),
+            SimOutcome::Timeout => (1037, "DS timeout user cannot be reached"),
+        }
+    }
+}
+
+impl std::str::FromStr for SimOutcome {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "success" => Ok(SimOutcome::Success),
+            "failure" => Ok(SimOutcome::Failure),
+            "timeout" => Ok(SimOutcome::Timeout),
+            other => anyhow::bail!("unknown outcome '{}' (expected success, failure or timeout)", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    pub outcome: SimOutcome,
+    /// Wait before settling, like a customer entering their PIN
+    pub delay: Duration,
+    /// Send callbacks to this origin (e.g. `http://localhost:8080`) instead
+    /// of the host in each request's callback URL, keeping its path
+    pub bot_url: Option<String>,
+    pub send_callbacks: bool,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            outcome: SimOutcome::Success,
+            delay: Duration::from_secs(5),
+            bot_url: None,
+            send_callbacks: true,
+        }
+    }
+}
+
+struct SimState {
+    config: SimConfig,
+    client: reqwest::Client,
+    counter: AtomicU64,
+    /// STK Pushes by CheckoutRequestID, and whether they've settled yet
+    stk_pushes: Mutex<HashMap<String, bool>>,
+}
+
+/// The simulator's routes, for serving or embedding in a test.
+pub fn router(config: SimConfig) -> Router {
+    let state = Arc::new(SimState {
+        config,
+        client: reqwest::Client::new(),
+        counter: AtomicU64::new(0),
+        stk_pushes: Mutex::new(HashMap::new()),
+    });
+    Router::new()
+        .route("/oauth/v1/generate", get(generate_token))
+        .route("/mpesa/stkpush/v1/processrequest", post(stk_push))
+        .route("/mpesa/stkpushquery/v1/query", post(stk_query))
+        .route("/mpesa/b2c/v1/paymentrequest", post(b2c_payment))
+        .with_state(state)
+}
+
+/// Serve the simulator on `port` until the process is stopped.
+pub async fn serve(port: u16, config: SimConfig) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    axum::serve(listener, router(config)).await?;
+    Ok(())
+}
+
+type SharedState = State<Arc<SimState>>;
+
+fn error(status: StatusCode, code: &str, message: &str) -> Response {
+    (status, Json(json!({ "requestId": "", "errorCode": code, "errorMessage": message }))).into_response()
+}
+
+fn has_auth(headers: &HeaderMap, scheme: &str) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with(scheme))
+}
+
+/// A field of a Daraja request body, as a string.
+fn field(body: &Value, name: &str) -> String {
+    match &body[name] {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+impl SimState {
+    fn next_id(&self) -> u64 {
+        self.counter.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Where to send a callback meant for `url`.
+    fn callback_target(&self, url: &str) -> String {
+        let Some(ref bot_url) = self.config.bot_url else {
+            return url.to_string();
+        };
+        let path = reqwest::Url::parse(url)
+            .map(|u| format!("{}{}", u.path(), u.query().map(|q| format!("?{}", q)).unwrap_or_default()))
+            .unwrap_or_else(|_| url.to_string());
+        format!("{}{}", bot_url.trim_end_matches('/'), path)
+    }
+
+    /// After the delay, send `body` to `url` (unless callbacks are off).
+    fn send_later(self: &Arc<Self>, url: String, body: Value, then: impl FnOnce(&SimState) + Send + 'static) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(state.config.delay).await;
+            then(&state);
+            if !state.config.send_callbacks || url.is_empty() {
+                return;
+            }
+            let target = state.callback_target(&url);
+            match state.client.post(&target).json(&body).send().await {
+                Ok(response) => log::info!("📤 Callback to {}: {}", target, response.status()),
+                Err(e) => log::warn!("⚠️ Callback to {} failed: {}", target, e),
+            }
+        });
+    }
+}
+
+async fn generate_token(State(state): SharedState, headers: HeaderMap) -> Response {
+    if !has_auth(&headers, "Basic ") {
+        return error(StatusCode::BAD_REQUEST, "400.008.01", "Invalid Authentication passed");
+    }
+    Json(json!({ "access_token": format!("SIM-TOKEN-{}", state.next_id()), "expires_in": "3599" })).into_response()
+}
+
+async fn stk_push(State(state): SharedState, headers: HeaderMap, Json(body): Json<Value>) -> Response {
+    if !has_auth(&headers, "Bearer ") {
+        return error(StatusCode::UNAUTHORIZED, "404.001.03", "Invalid Access Token");
+    }
+    let n = state.next_id();
+    let merchant_request_id = format!("SIM-{}", n);
+    let checkout_request_id = format!("ws_CO_SIM_{}", n);
+    state.stk_pushes.lock().unwrap().insert(checkout_request_id.clone(), false);
+    log::info!(
+        "📲 STK Push {} for {} to {} ({})",
+        checkout_request_id,
+        field(&body, "Amount"),
+        field(&body, "PhoneNumber"),
+        field(&body, "AccountReference")
+    );
+
+    let (code, desc) = state.config.outcome.stk_result();
+    let mut callback = json!({
+        "MerchantRequestID": merchant_request_id,
+        "CheckoutRequestID": checkout_request_id,
+        "ResultCode": code,
+        "ResultDesc": desc,
+    });
+    if code == 0 {
+        let amount: f64 = field(&body, "Amount").parse().unwrap_or_default();
+        let phone: u64 = field(&body, "PhoneNumber").parse().unwrap_or_default();
+        callback["CallbackMetadata"] = json!({
+            "Item": [
+                { "Name": "Amount", "Value": amount },
+                { "Name": "MpesaReceiptNumber", "Value": format!("SIM{:07}", n) },
+                { "Name": "TransactionDate", "Value": chrono::Utc::now().format("%Y%m%d%H%M%S").to_string().parse::<u64>().unwrap_or_default() },
+                { "Name": "PhoneNumber", "Value": phone },
+            ]
+        });
+    }
+    let id = checkout_request_id.clone();
+    state.send_later(
+        field(&body, "CallBackURL"),
+        json!({ "Body": { "stkCallback": callback } }),
+        move |state| {
+            state.stk_pushes.lock().unwrap().insert(id, true);
+        },
+    );
+
+    Json(json!({
+        "MerchantRequestID": merchant_request_id,
+        "CheckoutRequestID": checkout_request_id,
+        "ResponseCode": "0",
+        "ResponseDescription": "Success. Request accepted for processing",
+        "CustomerMessage": "Success. Request accepted for processing",
+    }))
+    .into_response()
+}
+
+async fn stk_query(State(state): SharedState, headers: HeaderMap, Json(body): Json<Value>) -> Response {
+    if !has_auth(&headers, "Bearer ") {
+        return error(StatusCode::UNAUTHORIZED, "404.001.03", "Invalid Access Token");
+    }
+    let checkout_request_id = field(&body, "CheckoutRequestID");
+    let settled = state.stk_pushes.lock().unwrap().get(&checkout_request_id).copied();
+    match settled {
+        None => error(StatusCode::BAD_REQUEST, "400.002.02", "Bad Request - Invalid CheckoutRequestID"),
+        Some(false) => error(StatusCode::INTERNAL_SERVER_ERROR, "500.001.1001", "The transaction is being processed"),
+        Some(true) => {
+            let (code, desc) = state.config.outcome.stk_result();
+            Json(json!({
+                "ResponseCode": "0",
+                "ResponseDescription": "The service request has been accepted successfully",
+                "CheckoutRequestID": checkout_request_id,
+                "ResultCode": code.to_string(),
+                "ResultDesc": desc,
+            }))
+            .into_response()
+        }
+    }
+}
+
+async fn b2c_payment(State(state): SharedState, headers: HeaderMap, Json(body): Json<Value>) -> Response {
+    if !has_auth(&headers, "Bearer ") {
+        return error(StatusCode::UNAUTHORIZED, "404.001.03", "Invalid Access Token");
+    }
+    let n = state.next_id();
+    let conversation_id = format!("AG_SIM_{}", n);
+    let originator_conversation_id = format!("SIM-B2C-{}", n);
+    log::info!("💸 B2C payment {} of {} to {}", conversation_id, field(&body, "Amount"), field(&body, "PartyB"));
+
+    let (url, result) = match state.config.outcome {
+        SimOutcome::Success => (
+            field(&body, "ResultURL"),
+            json!({
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": originator_conversation_id,
+                "ConversationID": conversation_id,
+                "TransactionID": format!("SIMB2C{:05}", n),
+                "ResultParameters": { "ResultParameter": [
+                    { "Key": "TransactionAmount", "Value": field(&body, "Amount").parse::<f64>().unwrap_or_default() },
+                    { "Key": "TransactionReceipt", "Value": format!("SIMB2C{:05}", n) },
+                    { "Key": "ReceiverPartyPublicName", "Value": format!("{} - Simulated Customer", field(&body, "PartyB")) },
+                ]},
+            }),
+        ),
+        SimOutcome::Failure => (
+            field(&body, "ResultURL"),
+            json!({
+                "ResultType": 0,
+                "ResultCode": 2001,
+                "ResultDesc": "The initiator information is invalid.",
+                "OriginatorConversationID": originator_conversation_id,
+                "ConversationID": conversation_id,
+                "TransactionID": format!("SIMB2C{:05}", n),
+            }),
+        ),
+        SimOutcome::Timeout => (
+            field(&body, "QueueTimeOutURL"),
+            json!({
+                "ResultType": 1,
+                "ResultCode": 1,
+                "ResultDesc": "The request timed out in the queue.",
+                "OriginatorConversationID": originator_conversation_id,
+                "ConversationID": conversation_id,
+            }),
+        ),
+    };
+    state.send_later(url, json!({ "Result": result }), |_| {});
+
+    Json(json!({
+        "ConversationID": conversation_id,
+        "OriginatorConversationID": originator_conversation_id,
+        "ResponseCode": "0",
+        "ResponseDescription": "Accept the service request successfully.",
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payments::b2c::{B2CClient, B2CConfig};
+    use crate::payments::mpesa::{MpesaClient, MpesaConfig};
+    use crate::payments::{MpesaCallback, PaymentProvider, PaymentStatus};
+    use tokio::sync::mpsc;
+
+    /// Start `router` on a free local port and return its URL.
+    async fn start(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        url
+    }
+
+    /// A stand-in bot that passes on every callback it receives.
+    async fn start_bot() -> (String, mpsc::UnboundedReceiver<(String, Value)>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let router = Router::new().fallback(move |uri: axum::http::Uri, Json(body): Json<Value>| async move {
+            tx.send((uri.path().to_string(), body)).unwrap();
+            Json(json!({ "ResultCode": 0 }))
+        });
+        (start(router).await, rx)
+    }
+
+    fn mpesa_config(base_url: &str) -> MpesaConfig {
+        MpesaConfig {
+            consumer_key: "key".to_string(),
+            consumer_secret: "secret".to_string(),
+            shortcode: "174379".to_string(),
+            passkey: "passkey".to_string(),
+            callback_url: "https://bot.example.com/api/mpesa/callback".to_string(),
+            sandbox: true,
+            base_url: Some(base_url.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stk_push_and_b2c_round_trip() {
+        let (bot_url, mut callbacks) = start_bot().await;
+        let sim_url = start(router(SimConfig {
+            delay: Duration::ZERO,
+            bot_url: Some(bot_url),
+            ..Default::default()
+        }))
+        .await;
+
+        let mpesa = MpesaClient::new(mpesa_config(&sim_url));
+        let checkout_request_id = mpesa.initiate_payment(45.0, "KES", "0712345678", "Order-1").await.unwrap().provider_ref;
+        let (path, body) = callbacks.recv().await.unwrap();
+        assert_eq!(path, "/api/mpesa/callback");
+        let callback: MpesaCallback = serde_json::from_value(body).unwrap();
+        let stk = callback.body.stk_callback;
+        assert_eq!(stk.checkout_request_id, checkout_request_id);
+        assert_eq!(stk.parse_payment_details().unwrap().amount, 45.0);
+        assert_eq!(mpesa.check_status(&checkout_request_id).await.unwrap().status, PaymentStatus::Completed);
+
+        let b2c = B2CClient::new(B2CConfig {
+            consumer_key: "key".to_string(),
+            consumer_secret: "secret".to_string(),
+            shortcode: "600000".to_string(),
+            initiator_name: "testapi".to_string(),
+            security_credential: "credential".to_string(),
+            result_url: "https://bot.example.com/api/mpesa/b2c/callback".to_string(),
+            timeout_url: "https://bot.example.com/api/mpesa/b2c/timeout".to_string(),
+            sandbox: true,
+            base_url: Some(sim_url.clone()),
+        });
+        let conversation_id = b2c.refund_payment(45.0, "0712345678", 1).await.unwrap();
+        let (path, body) = callbacks.recv().await.unwrap();
+        assert_eq!(path, "/api/mpesa/b2c/callback");
+        assert_eq!(body["Result"]["ConversationID"], conversation_id.as_str());
+        assert_eq!(body["Result"]["ResultCode"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_without_callbacks() {
+        let sim_url = start(router(SimConfig {
+            outcome: SimOutcome::Timeout,
+            delay: Duration::from_millis(200),
+            send_callbacks: false,
+            ..Default::default()
+        }))
+        .await;
+
+        let mpesa = MpesaClient::new(mpesa_config(&sim_url));
+        let checkout_request_id = mpesa.initiate_payment(45.0, "KES", "0712345678", "Order-1").await.unwrap().provider_ref;
+        assert_eq!(mpesa.check_status(&checkout_request_id).await.unwrap().status, PaymentStatus::Processing);
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(mpesa.check_status(&checkout_request_id).await.unwrap().status, PaymentStatus::Failed);
+        assert!(mpesa.check_status("ws_CO_unknown").await.is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! PayStack payment integration (Nigeria, Ghana, South Africa, Kenya)
+//!
+//! Payments are PayStack transactions: `initialize` returns a checkout page
+//! that the customer opens from WhatsApp and pays on by card, bank or mobile
+//! money. PayStack reports the result to `/api/paystack/webhook`, signing
+//! each event with the secret key, and `verify` answers status queries for
+//! payments whose webhook never arrived.
+
+use super::types::{PaymentMethod, PaymentStatus};
+use super::webhook::{PaymentCallbackResult, PaymentOutcome, check_charge, settle_payment};
+use super::refunds::RefundProvider;
+use super::{Charge, PaymentCheck, PaymentProvider, PaymentRequest};
+use crate::config::PaystackConfig;
+use crate::store::Store;
+use crate::transport::MessagingTransport;
+use anyhow::{Context, Result, bail};
+use hmac::{Hmac, Mac};
+use log::info;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::Sha512;
+use std::sync::Arc;
+
+const PAYSTACK_URL: &str = "https://api.paystack.co";
+
+/// Header PayStack signs webhooks in
+pub const SIGNATURE_HEADER: &str = "x-paystack-signature";
+
+pub struct PaystackClient {
+    config: PaystackConfig,
+    /// ISO code payments are made in
+    currency: String,
+    client: Client,
+}
+
+#[derive(Debug, Serialize)]
+struct InitializeRequest {
+    email: String,
+    /// In the currency's subunit (kobo, pesewas, cents)
+    amount: u64,
+    currency: String,
+    reference: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    callback_url: Option<String>,
+    metadata: serde_json::Value,
+}
+
+/// PayStack's response envelope.
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    status: bool,
+    message: String,
+    data: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InitializeData {
+    authorization_url: String,
+    reference: String,
+}
+
+/// A PayStack webhook event.
+#[derive(Debug, Deserialize)]
+pub struct PaystackEvent {
+    /// e.g. `charge.success`
+    pub event: String,
+    pub data: PaystackTransaction,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PaystackTransaction {
+    pub reference: String,
+    /// In the currency's subunit
+    pub amount: u64,
+    pub currency: String,
+    /// `success`, `failed`, `abandoned`, `ongoing`, `pending`, `reversed`, …
+    pub status: String,
+    #[serde(default)]
+    pub gateway_response: Option<String>,
+}
+
+impl PaystackTransaction {
+    /// Payment status for PayStack's transaction status. Abandoned checkouts
+    /// can still be paid, so they're still processing until the reconciler
+    /// expires them.
+    pub fn payment_status(&self) -> PaymentStatus {
+        match self.status.as_str() {
+            "success" => PaymentStatus::Completed,
+            "failed" => PaymentStatus::Failed,
+            "reversed" => PaymentStatus::Cancelled,
+            _ => PaymentStatus::Processing,
+        }
+    }
+
+    /// What the customer was charged.
+    pub fn charge(&self) -> Charge {
+        Charge {
+            amount: self.amount as f64 / 100.0,
+            currency: self.currency.clone(),
+        }
+    }
+}
+
+impl PaystackClient {
+    /// A client charging in `payments.paystack.currency`, or `business_currency`
+    /// when that's unset.
+    pub fn new(config: PaystackConfig, business_currency: &str) -> Result<Self> {
+        let currency = config
+            .currency_code(business_currency)
+            .context("payments.paystack.currency must be a currency code like NGN")?;
+        Ok(Self {
+            config,
+            currency,
+            client: Client::new(),
+        })
+    }
+
+    fn base_url(&self) -> &str {
+        self.config.base_url.as_deref().unwrap_or(PAYSTACK_URL).trim_end_matches('/')
+    }
+
+    /// The placeholder email PayStack gets for a customer.
+    fn email(&self, phone: &str) -> String {
+        let digits: String = phone.chars().filter(|c| c.is_ascii_digit()).collect();
+        format!("{}@{}", digits, self.config.email_domain)
+    }
+}
+
+#[async_trait::async_trait]
+impl PaymentProvider for PaystackClient {
+    fn method(&self) -> PaymentMethod {
+        PaymentMethod::PayStack
+    }
+
+    /// Initialize a transaction; the customer pays on its checkout page.
+    async fn initiate_payment(
+        &self,
+        amount: f64,
+        _currency: &str,
+        phone: &str,
+        reference: &str,
+    ) -> Result<PaymentRequest> {
+        // References must be unique across retries of the same order
+        let request = InitializeRequest {
+            email: self.email(phone),
+            amount: (amount * 100.0).round() as u64,
+            currency: self.currency.clone(),
+            reference: format!("{}-{}", reference, chrono::Utc::now().timestamp_millis()),
+            callback_url: self.config.callback_url.clone(),
+            metadata: serde_json::json!({ "phone": phone, "order": reference }),
+        };
+
+        info!("Initializing PayStack transaction {} for {} {:.2}", request.reference, request.currency, amount);
+        let response = self
+            .client
+            .post(format!("{}/transaction/initialize", self.base_url()))
+            .bearer_auth(&self.config.secret_key)
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send PayStack initialize request")?;
+
+        let status = response.status();
+        let body: ApiResponse<InitializeData> = response
+            .json()
+            .await
+            .context("Failed to parse PayStack initialize response")?;
+        let data = match body.data {
+            Some(data) if body.status && status.is_success() => data,
+            _ => bail!("PayStack error ({}): {}", status, body.message),
+        };
+
+        info!("✅ PayStack transaction initialized: {}", data.reference);
+        Ok(PaymentRequest {
+            provider_ref: data.reference,
+            checkout_url: Some(data.authorization_url),
+        })
+    }
+
+    /// Ask PayStack how a transaction went. `payment_id` is its reference.
+    async fn check_status(&self, payment_id: &str) -> Result<PaymentCheck> {
+        let response = self
+            .client
+            .get(format!("{}/transaction/verify/{}", self.base_url(), payment_id))
+            .bearer_auth(&self.config.secret_key)
+            .send()
+            .await
+            .context("Failed to send PayStack verify request")?;
+
+        let status = response.status();
+        let body: ApiResponse<PaystackTransaction> = response
+            .json()
+            .await
+            .context("Failed to parse PayStack verify response")?;
+        match body.data {
+            Some(transaction) if body.status && status.is_success() => Ok(PaymentCheck {
+                status: transaction.payment_status(),
+                charged: Some(transaction.charge()),
+            }),
+            _ => bail!("PayStack error ({}): {}", status, body.message),
+        }
+    }
+}
+
+/// Whether `signature` (the `x-paystack-signature` header) is the
+/// HMAC-SHA512 of `body` under `secret_key`.
+pub fn verify_signature(secret_key: &str, body: &[u8], signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature.trim()) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha512>::new_from_slice(secret_key.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Settle the payment a verified webhook event is about. Only `charge.success`
+/// settles anything; other events return `None`.
+pub async fn process_webhook(
+    event: PaystackEvent,
+    store: &Store,
+    config: &crate::config::HiveConfig,
+    transport: Option<Arc<dyn MessagingTransport>>,
+    refund_provider: Option<&dyn RefundProvider>,
+) -> Result<Option<PaymentCallbackResult>> {
+    if event.event != "charge.success" {
+        info!("PayStack event {} for {} ignored", event.event, event.data.reference);
+        return Ok(None);
+    }
+
+    let transaction = event.data;
+    let payment = store
+        .get_payment_by_provider_ref(&transaction.reference)?
+        .with_context(|| format!("No payment for PayStack reference {}", transaction.reference))?;
+    if payment.method != PaymentMethod::PayStack {
+        bail!("Payment {} isn't a PayStack payment", payment.id);
+    }
+
+    // The amount and currency come from the checkout we initialized, but
+    // check anyway
+    let charge = transaction.charge();
+    check_charge(&payment, &charge, config)?;
+
+    let outcome = PaymentOutcome::Paid {
+        amount: charge.amount,
+        receipt: Some(transaction.reference),
+    };
+    settle_payment(payment, outcome, store, config, transport, refund_provider).await.map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_signature() {
+        let body = br#"{"event":"charge.success","data":{"reference":"Order-1-1700000000000","amount":450000,"currency":"NGN","status":"success"}}"#;
+        let mut mac = Hmac::<Sha512>::new_from_slice(b"sk_test_secret").unwrap();
+        mac.update(body);
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        assert!(verify_signature("sk_test_secret", body, &signature));
+        assert!(!verify_signature("sk_test_other", body, &signature));
+        assert!(!verify_signature("sk_test_secret", &body[1..], &signature));
+        assert!(!verify_signature("sk_test_secret", body, "not hex"));
+
+        let event: PaystackEvent = serde_json::from_slice(body).unwrap();
+        assert_eq!(event.data.amount, 450000);
+        assert_eq!(event.data.payment_status(), PaymentStatus::Completed);
+    }
+}
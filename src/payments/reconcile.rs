@@ -0,0 +1,130 @@
+//! Reconciling payments whose callback never arrived.
+//!
+//! Providers report how a payment went by calling the bot: M-Pesa at
+//! `/api/mpesa/callback`, PayStack at `/api/paystack/webhook`. If that call
+//! never reaches the bot (it was offline, the tunnel was down) the payment
+//! would stay `processing` forever. The poller asks each provider about its
+//! payments that have been processing for longer than
+//! `payments.reconcile_after_minutes` and settles them just as the callback
+//! would have. Payments the provider still calls processing after
+//! `payments.expire_after_minutes` (a checkout the customer walked away
+//! from) are given up on and marked cancelled.
+
+use super::types::PaymentStatus;
+use super::webhook::{PaymentOutcome, check_charge, settle_payment};
+use super::refunds::RefundProvider;
+use super::{PaymentProvider, PaymentProviders};
+use crate::config::HiveConfig;
+use crate::config::live::LiveConfig;
+use crate::store::Store;
+use crate::transport::MessagingTransport;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Reconcile stale payments every `interval`, with the config current at
+/// each check. Runs until the task is dropped.
+pub async fn run(
+    config: LiveConfig,
+    store: Store,
+    transport: Arc<dyn MessagingTransport>,
+    providers: PaymentProviders,
+    refund_provider: Option<Arc<dyn RefundProvider>>,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let config = config.get();
+        let min_age_minutes = config.payments.reconcile_after_minutes;
+        if min_age_minutes == 0 {
+            continue;
+        }
+        let expire_after_minutes = Some(config.payments.expire_after_minutes).filter(|&minutes| minutes > 0);
+        for provider in providers.all() {
+            let refunds = refund_provider.as_deref();
+            if let Err(e) = reconcile_payments(
+                &config,
+                &store,
+                &transport,
+                provider.as_ref(),
+                refunds,
+                min_age_minutes,
+                expire_after_minutes,
+            )
+            .await
+            {
+                log::error!("❌ {} payment reconciliation failed: {:#}", provider.method(), e);
+            }
+        }
+    }
+}
+
+/// Check each of `provider`'s payments that has been processing for at
+/// least `min_age_minutes` and settle the ones that have finished, or that
+/// started at least `expire_after_minutes` ago and never will. Returns how
+/// many were settled.
+pub async fn reconcile_payments(
+    config: &HiveConfig,
+    store: &Store,
+    transport: &Arc<dyn MessagingTransport>,
+    provider: &dyn PaymentProvider,
+    refund_provider: Option<&dyn RefundProvider>,
+    min_age_minutes: u32,
+    expire_after_minutes: Option<u32>,
+) -> Result<usize> {
+    let mut settled = 0;
+    for payment in store.get_stale_payments(min_age_minutes)? {
+        if payment.method != provider.method() {
+            continue;
+        }
+        let Some(provider_ref) = payment.provider_ref.clone() else {
+            continue;
+        };
+
+        // One payment the provider can't tell us about shouldn't hold up the rest
+        let check = match provider.check_status(&provider_ref).await {
+            Ok(check) => check,
+            Err(e) => {
+                log::warn!("⚠️ Couldn't check payment {} ({}): {:#}", payment.id, provider_ref, e);
+                continue;
+            }
+        };
+        let status = check.status;
+        let outcome = match status {
+            // Held to the same amount and currency checks as a webhook
+            PaymentStatus::Completed => match check.charged {
+                Some(ref charged) if check_charge(&payment, charged, config).is_err() => continue,
+                charged => PaymentOutcome::Paid {
+                    amount: charged.map_or(payment.amount, |c| c.amount),
+                    receipt: None,
+                },
+            },
+            PaymentStatus::Failed | PaymentStatus::Cancelled => PaymentOutcome::Failed {
+                reason: format!("status query: {}", status),
+            },
+            PaymentStatus::Pending | PaymentStatus::Processing => match expire_after_minutes {
+                Some(minutes) if minutes_since(&payment.created_at).is_some_and(|age| age >= i64::from(minutes)) => {
+                    PaymentOutcome::Expired { minutes }
+                }
+                _ => continue,
+            },
+        };
+
+        match outcome {
+            PaymentOutcome::Expired { .. } => {
+                log::info!("⌛ Payment {} for order #{} expired while {}", payment.id, payment.order_id, status)
+            }
+            _ => log::info!("🔎 Payment {} for order #{} reconciled: {}", payment.id, payment.order_id, status),
+        }
+        settle_payment(payment, outcome, store, config, Some(transport.clone()), refund_provider).await?;
+        settled += 1;
+    }
+    Ok(settled)
+}
+
+/// Minutes since `timestamp`, a UTC time as SQLite's `datetime('now')` writes it.
+fn minutes_since(timestamp: &str) -> Option<i64> {
+    let then = chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S").ok()?;
+    Some((chrono::Utc::now().naive_utc() - then).num_minutes())
+}
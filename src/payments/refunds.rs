@@ -0,0 +1,242 @@
+//! Refunds of completed payments.
+//!
+//! A refund is recorded in the `refunds` table before any money moves, so one
+//! that fails — or that has no provider to go through — still shows up on the
+//! dashboard for the business to pay out by hand.
+
+use super::b2c::B2CCallback;
+use super::{B2CClient, Payment, PaymentMethod, PaymentStatus};
+use crate::config::HiveConfig;
+use crate::i18n::{Language, TranslationKey};
+use crate::store::{RefundRecord, RefundStatus, Store};
+use crate::transport::MessagingTransport;
+use anyhow::{Context, Result};
+use std::sync::Arc;
+
+/// Refund provider trait
+#[async_trait::async_trait]
+pub trait RefundProvider: Send + Sync {
+    /// The payment method whose payments this provider can refund
+    fn method(&self) -> PaymentMethod;
+
+    /// Send `amount` back to `phone` for `order_id`. Returns the provider's
+    /// reference for the payout (the B2C ConversationID for M-Pesa).
+    async fn refund_payment(&self, amount: f64, phone: &str, order_id: i64) -> Result<String>;
+}
+
+#[async_trait::async_trait]
+impl RefundProvider for B2CClient {
+    fn method(&self) -> PaymentMethod {
+        PaymentMethod::MPesa
+    }
+
+    async fn refund_payment(&self, amount: f64, phone: &str, order_id: i64) -> Result<String> {
+        B2CClient::refund_payment(self, amount, phone, order_id).await
+    }
+}
+
+/// The provider customer refunds go out through: M-Pesa B2C when payments
+/// are enabled and it's configured. Without one, refunds are recorded as
+/// pending for the business to pay out by hand.
+pub fn provider_from_config(config: &HiveConfig) -> Option<Arc<dyn RefundProvider>> {
+    if !config.payments.enabled {
+        return None;
+    }
+    B2CClient::from_config(&config.payments).map(|client| {
+        log::info!("💸 M-Pesa B2C refunds enabled");
+        Arc::new(client) as Arc<dyn RefundProvider>
+    })
+}
+
+/// Completed payments for an order that don't already have a refund under way.
+pub fn refundable_payments(store: &Store, order_id: i64) -> Result<Vec<Payment>> {
+    let mut payments = Vec::new();
+    for payment in store.get_order_payments(order_id)? {
+        if payment.status == PaymentStatus::Completed && !has_active_refund(store, &payment.id)? {
+            payments.push(payment);
+        }
+    }
+    Ok(payments)
+}
+
+/// Whether a payment has a refund that hasn't failed or been cancelled.
+pub fn has_active_refund(store: &Store, payment_id: &str) -> Result<bool> {
+    Ok(store
+        .get_payment_refunds(payment_id)?
+        .iter()
+        .any(|r| !matches!(r.status, RefundStatus::Failed | RefundStatus::Cancelled)))
+}
+
+/// Record a refund of `payment` and, when there is a `provider` for its
+/// payment method, start the payout.
+///
+/// Returns the stored refund: `processing` once the provider has accepted it,
+/// or `pending` when there is no provider for it and someone has to pay it
+/// out by hand. If the provider rejects it the refund is marked `failed` and the
+/// error is returned. A payment that already has a refund under way fails
+/// with [`RefundInProgress`](crate::store::RefundInProgress).
+pub async fn refund_payment(
+    store: &Store,
+    provider: Option<&dyn RefundProvider>,
+    payment: &Payment,
+    reason: &str,
+    admin_id: Option<&str>,
+) -> Result<RefundRecord> {
+    // An order can have several payments refunded in the same second
+    let refund_id = format!("REF-{}-{}", payment.order_id, uuid::Uuid::new_v4().simple());
+    store
+        .create_refund(
+            &refund_id,
+            &payment.id,
+            payment.order_id,
+            payment.amount,
+            &payment.currency,
+            &payment.phone,
+            Some(reason),
+            admin_id,
+        )
+        .context("Failed to create refund record")?;
+
+    if let Some(provider) = provider.filter(|p| p.method() == payment.method) {
+        match provider
+            .refund_payment(payment.amount, &payment.phone, payment.order_id)
+            .await
+        {
+            Ok(conversation_id) => {
+                store.update_refund_status(&refund_id, "processing", Some(&conversation_id))?;
+                log::info!(
+                    "💸 Refund {} initiated for payment {}: ConversationID={}",
+                    refund_id,
+                    payment.id,
+                    conversation_id
+                );
+            }
+            Err(e) => {
+                store.update_refund_status(&refund_id, "failed", None)?;
+                log::error!("❌ Refund {} failed for payment {}: {}", refund_id, payment.id, e);
+                return Err(e.context(format!("Refund {} failed", refund_id)));
+            }
+        }
+    }
+
+    store
+        .get_refund(&refund_id)?
+        .context("Refund record disappeared")
+}
+
+/// Refund `payment` as [`refund_payment`] does, leaving a payout that can't be
+/// started for the admins to make by hand.
+///
+/// Returns the line to send the customer (in `lang`) and the line for the
+/// admins.
+pub async fn refund_and_describe(
+    store: &Store,
+    provider: Option<&dyn RefundProvider>,
+    payment: &Payment,
+    reason: &str,
+    config: &HiveConfig,
+    lang: Language,
+) -> (String, String) {
+    let currency = &config.business.currency;
+    let amount = format!("{}{:.2}", currency, payment.amount);
+    match refund_payment(store, provider, payment, reason, None).await {
+        Ok(refund) if refund.status == RefundStatus::Pending => (
+            config.i18n.render(lang, TranslationKey::RefundSoon, &[("amount", &amount)]),
+            format!(
+                "💸 Refund {} of {} to {} needs to be paid out by hand.",
+                refund.id, amount, payment.phone
+            ),
+        ),
+        Ok(refund) => (
+            config.i18n.render(
+                lang,
+                TranslationKey::RefundSent,
+                &[("amount", &amount), ("phone", &payment.phone)],
+            ),
+            format!("💸 Refund {} of {} sent to {}.", refund.id, amount, payment.phone),
+        ),
+        Err(e) => {
+            log::error!("Refund for order #{} failed: {:#}", payment.order_id, e);
+            (
+                config.i18n.render(lang, TranslationKey::RefundSoon, &[("amount", &amount)]),
+                format!(
+                    "⚠️ Refund of {} to {} failed ({:#}) — please pay it out by hand.",
+                    amount, payment.phone, e
+                ),
+            )
+        }
+    }
+}
+
+/// Settle a refund from M-Pesa's B2C result.
+///
+/// A successful payout completes the refund and tells the customer; a failed
+/// or timed-out one marks it `failed` and asks the admins to pay it out by
+/// hand. Results for refunds that have already finished are ignored, so a
+/// retried callback doesn't notify anyone twice.
+pub async fn process_b2c_callback(
+    callback: &B2CCallback,
+    store: &Store,
+    config: &HiveConfig,
+    transport: Option<Arc<dyn MessagingTransport>>,
+) -> Result<RefundRecord> {
+    let result = &callback.result;
+    let refund = store
+        .get_refund_by_conversation_id(&result.conversation_id)?
+        .with_context(|| format!("No refund for ConversationID {}", result.conversation_id))?;
+
+    if matches!(refund.status, RefundStatus::Completed | RefundStatus::Failed) {
+        log::info!("⚠️ Refund {} already {} (idempotent retry)", refund.id, refund.status.as_str());
+        return Ok(refund);
+    }
+
+    let amount_text = format!("{}{:.2}", config.business.currency, refund.amount);
+    if result.is_successful() {
+        store.update_refund_status(&refund.id, "completed", None)?;
+        log::info!("✅ Refund {} completed (ConversationID={})", refund.id, result.conversation_id);
+
+        if let Some(transport) = transport {
+            let lang = config.customer_language(store, &refund.phone);
+            let msg = config.i18n.render(
+                lang,
+                TranslationKey::RefundCompleted,
+                &[
+                    ("amount", &amount_text),
+                    ("id", &refund.order_id.to_string()),
+                    ("receipt", result.transaction_id.as_deref().unwrap_or("-")),
+                ],
+            );
+            if let Err(e) = transport.send_to_phone(&refund.phone, &msg).await {
+                log::error!("Failed to notify {} about refund: {}", refund.phone, e);
+            }
+        }
+    } else {
+        store.update_refund_status(&refund.id, "failed", None)?;
+        log::error!(
+            "❌ Refund {} failed (ConversationID={}): {} {}",
+            refund.id,
+            result.conversation_id,
+            result.result_code,
+            result.result_desc
+        );
+
+        if let Some(transport) = transport {
+            let notification = format!(
+                "⚠️ *Refund Failed*\n\n\
+                 Order #{}\n\
+                 Amount: {}\n\
+                 Customer: {}\n\
+                 M-Pesa said: {}\n\n\
+                 Please refund the customer by hand.",
+                refund.order_id, amount_text, refund.phone, result.result_desc
+            );
+            for admin_number in &config.admin_numbers {
+                if let Err(e) = transport.send_to_phone(admin_number, &notification).await {
+                    log::error!("Failed to notify admin {}: {}", admin_number, e);
+                }
+            }
+        }
+    }
+
+    store.get_refund(&refund.id)?.context("Refund record disappeared")
+}
@@ -0,0 +1,79 @@
+//! The payment providers a bot takes payments with.
+//!
+//! Each provider section under `payments` (`mpesa`, `paystack`) enables
+//! that provider. With one, checkout goes straight to it; with several,
+//! the customer picks one, in the order they're listed here.
+
+use super::mpesa::MpesaConfig;
+use super::paystack::PaystackClient;
+use super::{MpesaClient, PaymentMethod, PaymentProvider};
+use crate::config::HiveConfig;
+use log::{error, info};
+use std::sync::Arc;
+
+#[derive(Clone, Default)]
+pub struct PaymentProviders {
+    providers: Vec<Arc<dyn PaymentProvider>>,
+}
+
+impl PaymentProviders {
+    /// The providers configured in `config`; none unless `payments.enabled`.
+    pub fn from_config(config: &HiveConfig) -> Self {
+        let payments = &config.payments;
+        let mut providers = Self::default();
+        if !payments.enabled {
+            return providers;
+        }
+
+        if let Some(ref mpesa_cfg) = payments.mpesa {
+            info!("💰 M-Pesa payments enabled ({})", match mpesa_cfg.base_url {
+                Some(ref url) => url.as_str(),
+                None if mpesa_cfg.sandbox => "sandbox",
+                None => "production",
+            });
+            providers.add(Arc::new(MpesaClient::new(MpesaConfig {
+                consumer_key: mpesa_cfg.consumer_key.clone(),
+                consumer_secret: mpesa_cfg.consumer_secret.clone(),
+                shortcode: mpesa_cfg.shortcode.clone(),
+                passkey: mpesa_cfg.passkey.clone(),
+                callback_url: mpesa_cfg.callback_url.clone(),
+                sandbox: mpesa_cfg.sandbox,
+                base_url: mpesa_cfg.base_url.clone(),
+            })));
+        }
+
+        if let Some(ref paystack_cfg) = payments.paystack {
+            match PaystackClient::new(paystack_cfg.clone(), &config.business.currency) {
+                Ok(client) => {
+                    info!("💳 PayStack payments enabled");
+                    providers.add(Arc::new(client));
+                }
+                Err(e) => error!("❌ PayStack not enabled: {:#}", e),
+            }
+        }
+
+        if providers.is_empty() {
+            log::warn!("💰 Payments enabled but no provider configured");
+        }
+        providers
+    }
+
+    /// Offer `provider` too, after the ones already added.
+    pub fn add(&mut self, provider: Arc<dyn PaymentProvider>) {
+        self.providers.push(provider);
+    }
+
+    /// The provider for `method`, if it's enabled.
+    pub fn get(&self, method: &PaymentMethod) -> Option<&Arc<dyn PaymentProvider>> {
+        self.providers.iter().find(|p| &p.method() == method)
+    }
+
+    /// All enabled providers, in the order customers see them.
+    pub fn all(&self) -> &[Arc<dyn PaymentProvider>] {
+        &self.providers
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+}
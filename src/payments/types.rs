@@ -40,6 +40,41 @@ pub enum PaymentStatus {
     Cancelled,
 }
 
+impl PaymentMethod {
+    /// The method as stored in the `payments` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentMethod::MPesa => "mpesa",
+            PaymentMethod::PayStack => "paystack",
+            PaymentMethod::Stripe => "stripe",
+            PaymentMethod::Cash => "cash",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "mpesa" => Some(PaymentMethod::MPesa),
+            "paystack" => Some(PaymentMethod::PayStack),
+            "stripe" => Some(PaymentMethod::Stripe),
+            "cash" => Some(PaymentMethod::Cash),
+            _ => None,
+        }
+    }
+}
+
+impl PaymentStatus {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(PaymentStatus::Pending),
+            "processing" => Some(PaymentStatus::Processing),
+            "completed" => Some(PaymentStatus::Completed),
+            "failed" => Some(PaymentStatus::Failed),
+            "cancelled" => Some(PaymentStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
 impl std::fmt::Display for PaymentStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1,7 +1,11 @@
 //! M-Pesa webhook handler for payment callbacks
 //!
-//! Receives payment confirmations from Safaricom and updates order status.
+//! Receives payment confirmations from Safaricom, updates order status and
+//! tells the customer (in their language) and the admins. `settle_payment`
+//! is shared with the reconciliation poller (see `reconcile`).
 
+use super::refunds::{self, RefundProvider};
+use crate::i18n::TranslationKey;
 use anyhow::Result;
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
@@ -109,7 +113,8 @@ pub async fn process_callback(
     callback: MpesaCallback,
     store: &crate::store::Store,
     config: &crate::config::HiveConfig,
-    wa_client: Option<std::sync::Arc<whatsapp_rust::client::Client>>,
+    transport: Option<std::sync::Arc<dyn crate::transport::MessagingTransport>>,
+    refund_provider: Option<&dyn RefundProvider>,
 ) -> Result<PaymentCallbackResult> {
     let stk = callback.body.stk_callback;
     let checkout_request_id = &stk.checkout_request_id;
@@ -121,6 +126,83 @@ pub async fn process_callback(
     let payment = store.get_payment_by_provider_ref(checkout_request_id)?
         .ok_or_else(|| anyhow::anyhow!("Payment not found for CheckoutRequestID: {}", checkout_request_id))?;
 
+    let outcome = if stk.is_successful() {
+        let details = stk.parse_payment_details()?;
+        info!("✅ M-Pesa payment successful: Receipt={}, Amount={}, Phone={}", 
+              details.mpesa_receipt_number, details.amount, details.phone_number);
+        PaymentOutcome::Paid {
+            amount: details.amount,
+            receipt: Some(details.mpesa_receipt_number),
+        }
+    } else {
+        warn!("❌ M-Pesa payment failed: ResultCode={}, ResultDesc={}", 
+              stk.result_code, stk.result_desc);
+        PaymentOutcome::Failed { reason: stk.result_desc }
+    };
+
+    settle_payment(payment, outcome, store, config, transport, refund_provider).await
+}
+
+/// How a payment ended, from the provider's callback or a status query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaymentOutcome {
+    /// Paid; `receipt` is the M-Pesa receipt number or PayStack reference,
+    /// which status queries don't give
+    Paid { amount: f64, receipt: Option<String> },
+    Failed { reason: String },
+    /// Still processing `minutes` after it started; given up on as cancelled
+    Expired { minutes: u32 },
+}
+
+/// Fail unless `charge` is what `payment` asked for: the same amount, in the
+/// currency the payment's provider charges in. Webhooks and status queries
+/// both check before an order is confirmed.
+pub fn check_charge(
+    payment: &super::types::Payment,
+    charge: &super::Charge,
+    config: &crate::config::HiveConfig,
+) -> Result<()> {
+    let currency = match payment.method {
+        super::types::PaymentMethod::PayStack => {
+            config.payments.paystack.as_ref().and_then(|p| p.currency_code(&payment.currency))
+        }
+        _ => Some(payment.currency.trim().to_ascii_uppercase()),
+    };
+    if currency.as_deref() != Some(charge.currency.to_ascii_uppercase().as_str()) {
+        warn!(
+            "⚠️ {} charged in {} for payment {} in {} — not confirming order #{}",
+            payment.method,
+            charge.currency,
+            payment.id,
+            currency.as_deref().unwrap_or(&payment.currency),
+            payment.order_id
+        );
+        anyhow::bail!("Currency mismatch for payment {}", payment.id);
+    }
+    if (charge.amount - payment.amount).abs() >= 0.01 {
+        warn!(
+            "⚠️ {} charged {} {:.2} for payment {} of {:.2} — not confirming order #{}",
+            payment.method, charge.currency, charge.amount, payment.id, payment.amount, payment.order_id
+        );
+        anyhow::bail!("Amount mismatch for payment {}", payment.id);
+    }
+    Ok(())
+}
+
+/// Record how `payment` ended: mark it completed or failed, confirm a paid
+/// order and tell the customer and admins.
+///
+/// A payment that completes after its order was cancelled (the customer
+/// cancelled while the prompt was still open) is refunded through
+/// `refund_provider`, or left for the admins to pay back by hand.
+pub async fn settle_payment(
+    payment: super::types::Payment,
+    outcome: PaymentOutcome,
+    store: &crate::store::Store,
+    config: &crate::config::HiveConfig,
+    transport: Option<std::sync::Arc<dyn crate::transport::MessagingTransport>>,
+    refund_provider: Option<&dyn RefundProvider>,
+) -> Result<PaymentCallbackResult> {
     // Check idempotency - if already processed, return early
     if matches!(payment.status, super::types::PaymentStatus::Completed) {
         info!("⚠️ Payment {} already completed (idempotent retry)", payment.id);
@@ -132,61 +214,97 @@ pub async fn process_callback(
         });
     }
 
-    if stk.is_successful() {
-        // Payment successful
-        let details = stk.parse_payment_details()?;
-        
-        info!("✅ M-Pesa payment successful: Receipt={}, Amount={}, Phone={}", 
-              details.mpesa_receipt_number, details.amount, details.phone_number);
-        
-        // Update payment status to completed
-        store.update_payment_status(
-            &payment.id,
-            "completed",
-            Some(&stk.checkout_request_id),
-        )?;
-        
-        // Update order status to confirmed
-        store.update_order_status(payment.order_id, &crate::store::OrderStatus::Confirmed)?;
-        
-        info!("💰 Payment {} completed — Order #{} confirmed", payment.id, payment.order_id);
-        
-        // Notify admin(s) via WhatsApp
-        if let Some(client) = wa_client {
-            let order = store.get_order(payment.order_id)?;
-            if let Some(order) = order {
-                for admin_number in &config.admin_numbers {
-                    let clean_number: String = admin_number.chars().filter(|c| c.is_ascii_digit()).collect();
-                    if !clean_number.is_empty() {
-                        let admin_jid = wacore_binary::jid::Jid::pn(&clean_number);
-                        
-                        let notification = format!(
-                            "💰 *Payment Received*\n\n\
-                             Order #{}\n\
-                             Amount: {}{:.2}\n\
-                             Receipt: {}\n\
-                             Customer: {}\n\
-                             Location: {}\n\n\
-                             ✅ Order confirmed and ready to prepare!",
+    match outcome {
+        PaymentOutcome::Paid { amount, receipt } => {
+            // Update payment status to completed
+            store.update_payment_status(&payment.id, "completed", payment.provider_ref.as_deref())?;
+
+            // Paid orders are confirmed; one cancelled in the meantime stays
+            // cancelled and the payment goes back
+            let note = match receipt {
+                Some(ref receipt) => format!("{} receipt {}", payment.method, receipt),
+                None => format!("{} payment confirmed by status query", payment.method),
+            };
+            let confirmed = match store.transition_order(
+                payment.order_id,
+                &crate::store::OrderStatus::Confirmed,
+                payment.method.as_str(),
+                Some(&note),
+            ) {
+                Ok(order) => {
+                    info!("💰 Payment {} completed — Order #{} confirmed", payment.id, payment.order_id);
+                    order
+                }
+                Err(e) => match e.downcast_ref::<crate::store::InvalidTransition>() {
+                    Some(invalid) if invalid.from == crate::store::OrderStatus::Cancelled => {
+                        warn!(
+                            "💰 Payment {} completed but order #{} was cancelled — refunding",
+                            payment.id, payment.order_id
+                        );
+                        return refund_paid_after_cancel(payment, amount, receipt, store, config, transport, refund_provider)
+                            .await;
+                    }
+                    Some(invalid) => {
+                        warn!(
+                            "💰 Payment {} completed but order #{} is already {}",
+                            payment.id,
                             payment.order_id,
-                            config.business.currency,
-                            details.amount,
-                            details.mpesa_receipt_number,
-                            order.customer_phone,
-                            order.location.as_deref().unwrap_or("No location")
+                            invalid.from.as_str()
                         );
-                        
-                        let admin_msg = waproto::whatsapp::Message {
-                            extended_text_message: Some(Box::new(
-                                waproto::whatsapp::message::ExtendedTextMessage {
-                                    text: Some(notification),
-                                    ..Default::default()
-                                },
-                            )),
-                            ..Default::default()
-                        };
-                        
-                        if let Err(e) = client.send_message(admin_jid, admin_msg).await {
+                        None
+                    }
+                    None => return Err(e),
+                },
+            };
+
+            let amount_text = format!("{}{:.2}", config.business.currency, amount);
+
+            // Tell the customer their order is on
+            if let (Some(transport), Some(order)) = (&transport, &confirmed) {
+                let lang = config.customer_language(store, &order.customer_phone);
+                // Only M-Pesa receipts mean anything to the customer
+                let mut lines = vec![match receipt {
+                    Some(ref receipt) if payment.method == super::types::PaymentMethod::MPesa => config.i18n.render(
+                        lang,
+                        TranslationKey::PaymentReceived,
+                        &[("amount", &amount_text), ("receipt", receipt)],
+                    ),
+                    _ => config.i18n.render(lang, TranslationKey::PaymentConfirmed, &[("amount", &amount_text)]),
+                }];
+                lines.extend(crate::handlers::order::order_status_message(config, lang, order));
+                if let Err(e) = transport.send_to_phone(&order.customer_phone, &lines.join("\n\n")).await {
+                    log::error!("Failed to notify {} about payment: {}", order.customer_phone, e);
+                }
+            }
+
+            // Notify admin(s)
+            if let Some(transport) = transport {
+                let order = store.get_order(payment.order_id)?;
+                if let Some(order) = order {
+                    let status_line = match confirmed {
+                        Some(_) => "✅ Order confirmed and ready to prepare!".to_string(),
+                        None => format!("ℹ️ Order was already {}.", order.status.as_str()),
+                    };
+                    let notification = format!(
+                        "💰 *Payment Received*\n\n\
+                         Order #{}\n\
+                         Amount: {}\n\
+                         Paid with: {}\n\
+                         Receipt: {}\n\
+                         Customer: {}\n\
+                         Location: {}\n\n\
+                         {}",
+                        payment.order_id,
+                        amount_text,
+                        payment.method,
+                        receipt.as_deref().unwrap_or("(confirmed by status query)"),
+                        order.customer_phone,
+                        order.location.as_deref().unwrap_or("No location"),
+                        status_line
+                    );
+
+                    for admin_number in &config.admin_numbers {
+                        if let Err(e) = transport.send_to_phone(admin_number, &notification).await {
                             log::error!("Failed to notify admin {}: {}", admin_number, e);
                         } else {
                             log::info!("📢 Notified admin {} about payment for order #{}", admin_number, payment.order_id);
@@ -194,37 +312,114 @@ pub async fn process_callback(
                     }
                 }
             }
+
+            Ok(PaymentCallbackResult {
+                success: true,
+                message: format!("Payment completed: {}", receipt.as_deref().unwrap_or(&payment.id)),
+                order_id: payment.order_id,
+                receipt,
+            })
+        }
+        PaymentOutcome::Failed { reason } => {
+            fail_payment(payment, super::types::PaymentStatus::Failed, &reason, store, config, transport).await
+        }
+        PaymentOutcome::Expired { minutes } => {
+            let reason = format!("still processing after {} minutes", minutes);
+            fail_payment(payment, super::types::PaymentStatus::Cancelled, &reason, store, config, transport).await
         }
-        
-        Ok(PaymentCallbackResult {
-            success: true,
-            message: format!("Payment completed: {}", details.mpesa_receipt_number),
-            order_id: payment.order_id,
-            receipt: Some(details.mpesa_receipt_number),
-        })
-    } else {
-        // Payment failed
-        warn!("❌ M-Pesa payment failed: ResultCode={}, ResultDesc={}", 
-              stk.result_code, stk.result_desc);
-        
-        store.update_payment_status(
-            &payment.id,
-            "failed",
-            Some(&stk.checkout_request_id),
-        )?;
-        
-        // Optionally update order status to cancelled or leave as pending for cash
-        // For now, leave order as-is (customer can pay cash)
-        
-        Ok(PaymentCallbackResult {
-            success: false,
-            message: format!("Payment failed: {}", stk.result_desc),
-            order_id: payment.order_id,
-            receipt: None,
-        })
     }
 }
 
+/// Mark `payment` failed or cancelled and tell the customer it didn't go
+/// through. The order stays pending (unpaid); the business can confirm it
+/// for cash or cancel it from the dashboard.
+async fn fail_payment(
+    payment: super::types::Payment,
+    status: super::types::PaymentStatus,
+    reason: &str,
+    store: &crate::store::Store,
+    config: &crate::config::HiveConfig,
+    transport: Option<std::sync::Arc<dyn crate::transport::MessagingTransport>>,
+) -> Result<PaymentCallbackResult> {
+    store.update_payment_status(&payment.id, &status.to_string(), payment.provider_ref.as_deref())?;
+
+    if let Some(transport) = transport {
+        let lang = config.customer_language(store, &payment.phone);
+        let msg = config.i18n.render(
+            lang,
+            TranslationKey::PaymentFailed,
+            &[("id", &payment.order_id.to_string()), ("method", &payment.method.to_string())],
+        );
+        if let Err(e) = transport.send_to_phone(&payment.phone, &msg).await {
+            log::error!("Failed to notify {} about failed payment: {}", payment.phone, e);
+        }
+    }
+
+    Ok(PaymentCallbackResult {
+        success: false,
+        message: format!("Payment {}: {}", status, reason),
+        order_id: payment.order_id,
+        receipt: None,
+    })
+}
+
+/// Pay back a payment that completed after its order was cancelled, and tell
+/// the customer and admins why.
+async fn refund_paid_after_cancel(
+    payment: super::types::Payment,
+    amount: f64,
+    receipt: Option<String>,
+    store: &crate::store::Store,
+    config: &crate::config::HiveConfig,
+    transport: Option<std::sync::Arc<dyn crate::transport::MessagingTransport>>,
+    refund_provider: Option<&dyn RefundProvider>,
+) -> Result<PaymentCallbackResult> {
+    let amount_text = format!("{}{:.2}", config.business.currency, amount);
+    let lang = config.customer_language(store, &payment.phone);
+    let (customer_line, admin_line) =
+        refunds::refund_and_describe(store, refund_provider, &payment, "Paid after the order was cancelled", config, lang)
+            .await;
+
+    if let Some(transport) = transport {
+        let msg = format!(
+            "{}\n{}",
+            config.i18n.render(
+                lang,
+                TranslationKey::PaidAfterCancel,
+                &[("id", &payment.order_id.to_string()), ("amount", &amount_text)],
+            ),
+            customer_line
+        );
+        if let Err(e) = transport.send_to_phone(&payment.phone, &msg).await {
+            log::error!("Failed to notify {} about refund: {}", payment.phone, e);
+        }
+
+        let notification = format!(
+            "⚠️ *Paid After Cancel*\n\n\
+             Order #{} was cancelled, but {} was paid with {} (receipt {}) from {}.\n\
+             {}",
+            payment.order_id,
+            amount_text,
+            payment.method,
+            receipt.as_deref().unwrap_or("confirmed by status query"),
+            payment.phone,
+            admin_line
+        );
+        for admin_number in &config.admin_numbers {
+            if let Err(e) = transport.send_to_phone(admin_number, &notification).await {
+                log::error!("Failed to notify admin {}: {}", admin_number, e);
+            }
+        }
+    }
+
+    Ok(PaymentCallbackResult {
+        success: true,
+        message: format!("Payment completed after order #{} was cancelled — refunding", payment.order_id),
+        order_id: payment.order_id,
+        receipt,
+    })
+}
+
 /// Result of processing a payment callback
 #[derive(Debug, Clone, Serialize)]
 pub struct PaymentCallbackResult {
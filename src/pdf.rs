@@ -0,0 +1,169 @@
+//! A minimal PDF writer for receipts.
+//!
+//! Writes a single page of left-aligned text in Helvetica: a bold title and
+//! then one line per entry. The page is as tall as the text needs, like a
+//! till roll. The standard fonts only cover Latin-1 and some punctuation:
+//! currency symbols outside it are written as their ISO code (`₦` becomes
+//! `NGN`), letters from other scripts as `?` so the gap is visible, and
+//! emoji are left out.
+
+/// Page width in points (about 10 cm).
+const PAGE_WIDTH: f64 = 283.0;
+const MARGIN: f64 = 24.0;
+const TITLE_SIZE: f64 = 14.0;
+const TEXT_SIZE: f64 = 10.0;
+const LEADING: f64 = 14.0;
+
+/// A one-page PDF with `title` in bold above `lines`. Empty lines leave a gap.
+pub fn text_document(title: &str, lines: &[String]) -> Vec<u8> {
+    let height = 2.0 * MARGIN + TITLE_SIZE + LEADING * (lines.len() as f64 + 1.0);
+
+    let mut content = Vec::new();
+    content.extend_from_slice(b"BT\n");
+    content.extend_from_slice(format!("/F2 {} Tf\n", TITLE_SIZE).as_bytes());
+    content.extend_from_slice(format!("{} {} Td\n", MARGIN, height - MARGIN - TITLE_SIZE).as_bytes());
+    content.extend(string_literal(title));
+    content.extend_from_slice(b" Tj\n");
+    content.extend_from_slice(format!("/F1 {} Tf\n0 {} Td\n", TEXT_SIZE, -LEADING).as_bytes());
+    for line in lines {
+        content.extend_from_slice(format!("0 {} Td\n", -LEADING).as_bytes());
+        content.extend(string_literal(line));
+        content.extend_from_slice(b" Tj\n");
+    }
+    content.extend_from_slice(b"ET\n");
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents 6 0 R >>",
+            PAGE_WIDTH, height
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+    ];
+    let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+    stream.extend(content);
+    stream.extend_from_slice(b"\nendstream");
+    objects.push(stream);
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .as_bytes(),
+    );
+    pdf
+}
+
+/// `text` as a PDF string literal in WinAnsi bytes, without WhatsApp's
+/// `*bold*` markers. See the module docs for what happens to characters
+/// Helvetica can't show.
+fn string_literal(text: &str) -> Vec<u8> {
+    let mut bytes = vec![b'('];
+    let mut chars = text.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some(code) = currency_code(c) {
+            bytes.extend_from_slice(code.as_bytes());
+            if chars.peek().is_some_and(|next| !next.is_whitespace()) {
+                bytes.push(b' ');
+            }
+            continue;
+        }
+        match c {
+            '(' | ')' | '\\' => bytes.extend_from_slice(&[b'\\', c as u8]),
+            '*' => {}
+            ' '..='~' | '\u{A0}'..='\u{FF}' => bytes.push(c as u32 as u8),
+            '€' => bytes.push(0x80),
+            '‘' | '’' => bytes.push(b'\''),
+            '“' | '”' => bytes.push(b'"'),
+            '•' => bytes.push(0x95),
+            '–' => bytes.push(0x96),
+            '—' | '━' => bytes.push(0x97),
+            c if c.is_alphanumeric() => bytes.push(b'?'),
+            _ => {}
+        }
+    }
+    bytes.push(b')');
+    bytes
+}
+
+/// The ISO 4217 code for a currency symbol WinAnsi has no glyph for.
+fn currency_code(symbol: char) -> Option<&'static str> {
+    Some(match symbol {
+        '₦' => "NGN",
+        '₵' => "GHS",
+        '₹' => "INR",
+        '₱' => "PHP",
+        '₩' => "KRW",
+        '₪' => "ILS",
+        '₫' => "VND",
+        '₴' => "UAH",
+        '₺' => "TRY",
+        '₽' => "RUB",
+        '₸' => "KZT",
+        '₡' => "CRC",
+        '₲' => "PYG",
+        '฿' => "THB",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_document() {
+        let pdf = text_document("Mama's Kitchen", &["2x 🌯 Kota — R70.00".to_string(), "*Total: R70.00*".to_string()]);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        // Emoji and bold markers dropped, the dash in WinAnsi
+        assert!(pdf.windows(22).any(|w| w == b"(2x  Kota \x97 R70.00) Tj"));
+        assert!(text.contains("(Total: R70.00) Tj"));
+
+        // Every xref offset points at its object
+        let find = |needle: &[u8]| pdf.windows(needle.len()).position(|w| w == needle).unwrap();
+        let xref = find(b"\nxref\n") + 1;
+        assert!(text.ends_with(&format!("startxref\n{}\n%%EOF\n", xref)));
+        let table = String::from_utf8_lossy(&pdf[xref..]);
+        for (i, line) in table.lines().skip(3).take(6).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_currency_without_glyph() {
+        let lines = [
+            "2x Jollof — ₦4500.00".to_string(),
+            "*Total: ₦ 9000.00*".to_string(),
+            "धन्यवाद!".to_string(),
+        ];
+        let pdf = text_document("Mama Put", &lines);
+        let text = String::from_utf8_lossy(&pdf);
+        // The naira sign becomes its code rather than vanishing
+        assert!(pdf.windows(28).any(|w| w == b"(2x Jollof \x97 NGN 4500.00) Tj"));
+        assert!(text.contains("(Total: NGN 9000.00) Tj"));
+        // Devanagari can't be shown, but the line isn't silently blank
+        assert!(text.contains("(??????!) Tj"));
+    }
+}
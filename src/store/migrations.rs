@@ -0,0 +1,456 @@
+//! Versioned schema migrations.
+//!
+//! Each migration has a unique, increasing version and is applied at most
+//! once, inside its own transaction, in version order. Applied versions are
+//! recorded in the `schema_version` table.
+//!
+//! To change the schema, append a new `Migration` to `MIGRATIONS` — never
+//! edit one that has already shipped.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+/// A single schema migration.
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// All migrations, in order.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        // Uses IF NOT EXISTS so databases created before versioning are adopted as-is
+        sql: "
+            CREATE TABLE IF NOT EXISTS orders (
+                id              INTEGER PRIMARY KEY AUTOINCREMENT,
+                customer_phone  TEXT NOT NULL,
+                items_json      TEXT NOT NULL,
+                subtotal        REAL NOT NULL DEFAULT 0,
+                delivery_fee    REAL NOT NULL DEFAULT 0,
+                total           REAL NOT NULL,
+                status          TEXT NOT NULL DEFAULT 'pending',
+                location        TEXT,
+                voucher_code    TEXT,
+                created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+                updated_at      TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS vouchers (
+                id          INTEGER PRIMARY KEY AUTOINCREMENT,
+                code        TEXT NOT NULL UNIQUE,
+                amount      REAL NOT NULL,
+                redeemed_by TEXT,
+                created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+                redeemed_at TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS conversations (
+                phone       TEXT PRIMARY KEY,
+                state_json  TEXT NOT NULL DEFAULT '\"Idle\"',
+                updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS payments (
+                id              TEXT PRIMARY KEY,
+                order_id        INTEGER NOT NULL,
+                amount          REAL NOT NULL,
+                currency        TEXT NOT NULL DEFAULT 'KES',
+                method          TEXT NOT NULL,
+                status          TEXT NOT NULL DEFAULT 'pending',
+                phone           TEXT NOT NULL,
+                reference       TEXT NOT NULL,
+                provider_ref    TEXT,
+                created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+                updated_at      TEXT NOT NULL DEFAULT (datetime('now')),
+                FOREIGN KEY (order_id) REFERENCES orders(id)
+            );
+
+            CREATE TABLE IF NOT EXISTS refunds (
+                id                  TEXT PRIMARY KEY,
+                payment_id          TEXT NOT NULL,
+                order_id            INTEGER NOT NULL,
+                amount              REAL NOT NULL,
+                currency            TEXT NOT NULL DEFAULT 'KES',
+                phone               TEXT NOT NULL,
+                reason              TEXT,
+                conversation_id     TEXT,
+                status              TEXT NOT NULL DEFAULT 'pending',
+                admin_id            TEXT,
+                created_at          TEXT NOT NULL DEFAULT (datetime('now')),
+                completed_at        TEXT,
+                FOREIGN KEY (payment_id) REFERENCES payments(id),
+                FOREIGN KEY (order_id) REFERENCES orders(id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_orders_status ON orders(status);
+            CREATE INDEX IF NOT EXISTS idx_orders_phone ON orders(customer_phone);
+            CREATE INDEX IF NOT EXISTS idx_vouchers_code ON vouchers(code);
+            CREATE INDEX IF NOT EXISTS idx_payments_order ON payments(order_id);
+            CREATE INDEX IF NOT EXISTS idx_payments_status ON payments(status);
+            CREATE INDEX IF NOT EXISTS idx_refunds_payment ON refunds(payment_id);
+            CREATE INDEX IF NOT EXISTS idx_refunds_status ON refunds(status);
+        ",
+    },
+    Migration {
+        version: 2,
+        name: "bookings",
+        sql: "
+            CREATE TABLE bookings (
+                id              INTEGER PRIMARY KEY AUTOINCREMENT,
+                customer_phone  TEXT NOT NULL,
+                service         TEXT NOT NULL,
+                price           REAL NOT NULL DEFAULT 0,
+                start_at        TEXT NOT NULL,
+                end_at          TEXT NOT NULL,
+                status          TEXT NOT NULL DEFAULT 'confirmed',
+                created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+                updated_at      TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX idx_bookings_start ON bookings(start_at);
+            CREATE INDEX idx_bookings_phone ON bookings(customer_phone);
+        ",
+    },
+    Migration {
+        version: 3,
+        name: "stock",
+        sql: "
+            CREATE TABLE stock (
+                item_name   TEXT PRIMARY KEY,
+                quantity    INTEGER NOT NULL,
+                updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+        ",
+    },
+    Migration {
+        version: 4,
+        name: "menu_items",
+        sql: "
+            CREATE TABLE menu_items (
+                id                INTEGER PRIMARY KEY AUTOINCREMENT,
+                name              TEXT NOT NULL UNIQUE,
+                price             REAL NOT NULL,
+                emoji             TEXT,
+                description       TEXT,
+                available         INTEGER NOT NULL DEFAULT 1,
+                duration_minutes  INTEGER,
+                position          INTEGER NOT NULL DEFAULT 0,
+                created_at        TEXT NOT NULL DEFAULT (datetime('now')),
+                updated_at        TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+        ",
+    },
+    Migration {
+        version: 5,
+        name: "menu_options",
+        sql: "
+            ALTER TABLE menu_items ADD COLUMN category TEXT;
+            ALTER TABLE menu_items ADD COLUMN variants_json TEXT NOT NULL DEFAULT '[]';
+            ALTER TABLE menu_items ADD COLUMN addons_json TEXT NOT NULL DEFAULT '[]';
+        ",
+    },
+    Migration {
+        version: 6,
+        name: "dashboard_users",
+        sql: "
+            CREATE TABLE dashboard_users (
+                id              INTEGER PRIMARY KEY AUTOINCREMENT,
+                username        TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                password_hash   TEXT NOT NULL,
+                role            TEXT NOT NULL,
+                created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+                last_login_at   TEXT
+            );
+
+            CREATE TABLE dashboard_tokens (
+                id              INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id         INTEGER NOT NULL,
+                token_hash      TEXT NOT NULL UNIQUE,
+                kind            TEXT NOT NULL,
+                name            TEXT,
+                created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+                expires_at      TEXT,
+                last_used_at    TEXT,
+                FOREIGN KEY (user_id) REFERENCES dashboard_users(id)
+            );
+        ",
+    },
+    Migration {
+        version: 7,
+        name: "order_events",
+        sql: "
+            CREATE TABLE order_events (
+                id              INTEGER PRIMARY KEY AUTOINCREMENT,
+                order_id        INTEGER NOT NULL,
+                from_status     TEXT,
+                to_status       TEXT NOT NULL,
+                actor           TEXT NOT NULL,
+                note            TEXT,
+                created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+                FOREIGN KEY (order_id) REFERENCES orders(id)
+            );
+            CREATE INDEX idx_order_events_order ON order_events(order_id);
+
+            -- Orders placed before history was kept: record when they were
+            -- placed and the status they had reached, as far as we know
+            INSERT INTO order_events (order_id, from_status, to_status, actor, created_at)
+            SELECT id, NULL, 'pending', 'customer:' || customer_phone, created_at FROM orders;
+            INSERT INTO order_events (order_id, from_status, to_status, actor, note, created_at)
+            SELECT id, 'pending', status, 'unknown', 'Recorded before order history was kept', updated_at
+            FROM orders WHERE status != 'pending';
+        ",
+    },
+    Migration {
+        version: 8,
+        name: "customers",
+        sql: "
+            CREATE TABLE customers (
+                phone               TEXT PRIMARY KEY,
+                name                TEXT,
+                language            TEXT,
+                addresses_json      TEXT NOT NULL DEFAULT '[]',
+                tags_json           TEXT NOT NULL DEFAULT '[]',
+                marketing_opt_in    INTEGER NOT NULL DEFAULT 0,
+                marketing_opt_in_at TEXT,
+                notes               TEXT,
+                first_seen_at       TEXT NOT NULL DEFAULT (datetime('now')),
+                last_seen_at        TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            -- Everyone who has chatted or ordered so far
+            INSERT INTO customers (phone, first_seen_at, last_seen_at)
+            SELECT phone, MIN(seen), MAX(seen) FROM (
+                SELECT phone, updated_at AS seen FROM conversations
+                UNION ALL
+                SELECT customer_phone, created_at FROM orders
+            ) GROUP BY phone;
+            UPDATE customers SET addresses_json = json_array(
+                (SELECT location FROM orders
+                 WHERE customer_phone = customers.phone AND location IS NOT NULL AND location != ''
+                 ORDER BY created_at DESC, id DESC LIMIT 1)
+            )
+            WHERE EXISTS (
+                SELECT 1 FROM orders
+                WHERE customer_phone = customers.phone AND location IS NOT NULL AND location != ''
+            );
+        ",
+    },
+    Migration {
+        version: 9,
+        name: "menu_item_images",
+        sql: "ALTER TABLE menu_items ADD COLUMN image TEXT;",
+    },
+];
+
+/// A migration recorded in `schema_version`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub applied_at: String,
+}
+
+/// Schema version summary for `hive db status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaStatus {
+    pub current_version: i64,
+    pub latest_version: i64,
+    pub applied: Vec<AppliedMigration>,
+    pub pending: Vec<(i64, String)>,
+}
+
+/// Latest schema version known to this binary.
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+fn has_version_table(conn: &Connection) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+fn ensure_version_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version     INTEGER PRIMARY KEY,
+            name        TEXT NOT NULL,
+            applied_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );",
+    )?;
+    Ok(())
+}
+
+/// Highest applied version (0 for a fresh or pre-versioning database).
+/// Doesn't write to the database.
+pub fn current_version(conn: &Connection) -> Result<i64> {
+    if !has_version_table(conn)? {
+        return Ok(0);
+    }
+    let version: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(version)
+}
+
+/// Migrations that have not been applied yet.
+pub fn pending(conn: &Connection) -> Result<Vec<&'static Migration>> {
+    pending_from(conn, MIGRATIONS)
+}
+
+fn pending_from<'a>(conn: &Connection, migrations: &'a [Migration]) -> Result<Vec<&'a Migration>> {
+    let current = current_version(conn)?;
+    Ok(migrations.iter().filter(|m| m.version > current).collect())
+}
+
+/// Apply all pending migrations. Returns the migrations that were applied.
+pub fn migrate(conn: &mut Connection) -> Result<Vec<&'static Migration>> {
+    apply(conn, MIGRATIONS)
+}
+
+fn apply<'a>(conn: &mut Connection, migrations: &'a [Migration]) -> Result<Vec<&'a Migration>> {
+    ensure_version_table(conn)?;
+    let current = current_version(conn)?;
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+    if current > latest {
+        anyhow::bail!(
+            "Database schema version {} is newer than this binary supports ({}). Upgrade hive.",
+            current,
+            latest
+        );
+    }
+
+    let pending = pending_from(conn, migrations)?;
+    for migration in &pending {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration.sql).with_context(|| {
+            format!("Migration {} ({}) failed", migration.version, migration.name)
+        })?;
+        tx.execute(
+            "INSERT INTO schema_version (version, name) VALUES (?1, ?2)",
+            params![migration.version, migration.name],
+        )?;
+        tx.commit()?;
+        log::info!("🗄️  Applied migration {} ({})", migration.version, migration.name);
+    }
+
+    Ok(pending)
+}
+
+/// Applied and pending migrations. Doesn't write to the database, so it
+/// works on a read-only connection.
+pub fn status(conn: &Connection) -> Result<SchemaStatus> {
+    let mut applied = Vec::new();
+    if has_version_table(conn)? {
+        let mut stmt = conn.prepare(
+            "SELECT version, name, applied_at FROM schema_version ORDER BY version",
+        )?;
+        applied = stmt
+            .query_map([], |row| {
+                Ok(AppliedMigration {
+                    version: row.get(0)?,
+                    name: row.get(1)?,
+                    applied_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+
+    Ok(SchemaStatus {
+        current_version: current_version(conn)?,
+        latest_version: latest_version(),
+        applied,
+        pending: pending(conn)?
+            .into_iter()
+            .map(|m| (m.version, m.name.to_string()))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_are_strictly_increasing() {
+        for pair in MIGRATIONS.windows(2) {
+            assert!(pair[0].version < pair[1].version);
+        }
+    }
+
+    #[test]
+    fn test_migrate_fresh_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let applied = migrate(&mut conn).unwrap();
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+        // Second run is a no-op
+        assert!(migrate(&mut conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_adopts_pre_versioning_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY AUTOINCREMENT, customer_phone TEXT NOT NULL,
+             items_json TEXT NOT NULL, subtotal REAL NOT NULL DEFAULT 0, delivery_fee REAL NOT NULL DEFAULT 0,
+             total REAL NOT NULL, status TEXT NOT NULL DEFAULT 'pending', location TEXT, voucher_code TEXT,
+             created_at TEXT NOT NULL DEFAULT (datetime('now')), updated_at TEXT NOT NULL DEFAULT (datetime('now')));
+             INSERT INTO orders (customer_phone, items_json, total) VALUES ('+1', '[]', 5.0);",
+        )
+        .unwrap();
+
+        migrate(&mut conn).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM orders", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn test_status_does_not_write() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE orders (id INTEGER PRIMARY KEY);").unwrap();
+
+        let status = status(&conn).unwrap();
+        assert_eq!(status.current_version, 0);
+        assert!(status.applied.is_empty());
+        assert_eq!(status.pending.len(), MIGRATIONS.len());
+        assert!(!has_version_table(&conn).unwrap());
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let migrations = [
+            Migration { version: 1, name: "ok", sql: "CREATE TABLE a (id INTEGER);" },
+            Migration { version: 2, name: "broken", sql: "CREATE TABLE b (id INTEGER); NOT VALID SQL;" },
+        ];
+
+        assert!(apply(&mut conn, &migrations).is_err());
+        assert_eq!(current_version(&conn).unwrap(), 1);
+        let b_exists: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sqlite_master WHERE name = 'b'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(b_exists, 0);
+    }
+
+    #[test]
+    fn test_rejects_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        ensure_version_table(&conn).unwrap();
+        conn.execute("INSERT INTO schema_version (version, name) VALUES (999, 'future')", [])
+            .unwrap();
+        assert!(migrate(&mut conn).is_err());
+    }
+}
@@ -3,10 +3,16 @@
 //! Manages persistent state for orders, vouchers, menu items, and conversation
 //! state. Uses rusqlite with a simple synchronous API (wrapped in `Arc` for sharing).
 
+pub mod migrations;
+
 use anyhow::{Context, Result};
-use crate::payments::{Payment, PaymentStatus};
-use rusqlite::{params, Connection};
+use crate::bookings;
+use crate::config::{MenuAddon, MenuItem, MenuVariant};
+use crate::payments::{Payment, PaymentMethod, PaymentStatus};
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 
 /// Thread-safe handle to the SQLite database.
@@ -55,19 +61,145 @@ impl OrderStatus {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn parse(s: &str) -> Option<Self> {
         match s {
-            "pending" => Self::Pending,
-            "confirmed" => Self::Confirmed,
-            "preparing" => Self::Preparing,
-            "delivering" => Self::Delivering,
-            "delivered" => Self::Delivered,
-            "cancelled" => Self::Cancelled,
-            _ => Self::Pending,
+            "pending" => Some(Self::Pending),
+            "confirmed" => Some(Self::Confirmed),
+            "preparing" => Some(Self::Preparing),
+            "delivering" => Some(Self::Delivering),
+            "delivered" => Some(Self::Delivered),
+            "cancelled" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+
+    /// Read a status column, failing on anything that isn't a known status
+    /// rather than guessing.
+    fn from_column(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<Self> {
+        let raw: String = row.get(idx)?;
+        Self::parse(&raw).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(
+                idx,
+                rusqlite::types::Type::Text,
+                format!("unknown order status '{}'", raw).into(),
+            )
+        })
+    }
+
+    /// Whether an order may move from this status to `next`.
+    ///
+    /// Orders only move forward through Confirmed → Preparing → Delivering →
+    /// Delivered, possibly skipping steps (a collection order is never out
+    /// for delivery); a Pending (unpaid) order has to be confirmed first.
+    /// Anything not yet delivered can be cancelled. Delivered and Cancelled
+    /// are final.
+    pub fn can_transition_to(&self, next: &OrderStatus) -> bool {
+        match (self, next) {
+            (Self::Delivered | Self::Cancelled, _) => false,
+            (_, Self::Cancelled) => true,
+            (Self::Pending, next) => *next == Self::Confirmed,
+            (current, next) => next.stage() > current.stage(),
+        }
+    }
+
+    fn stage(&self) -> u8 {
+        match self {
+            Self::Pending => 0,
+            Self::Confirmed => 1,
+            Self::Preparing => 2,
+            Self::Delivering => 3,
+            Self::Delivered => 4,
+            Self::Cancelled => 5,
         }
     }
 }
 
+/// Returned by `transition_order` when an order can't move to the
+/// requested status.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTransition {
+    pub from: OrderStatus,
+    pub to: OrderStatus,
+}
+
+impl std::fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Order is {} and can't be marked {}", self.from.as_str(), self.to.as_str())
+    }
+}
+
+impl std::error::Error for InvalidTransition {}
+
+/// One step in an order's history. `from_status` is `None` for the event
+/// that created the order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderEvent {
+    pub id: i64,
+    pub order_id: i64,
+    pub from_status: Option<OrderStatus>,
+    pub to_status: OrderStatus,
+    /// Who made the change, e.g. "customer:+254…", "dashboard:amina", "mpesa"
+    pub actor: String,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+const ORDER_COLUMNS: &str = "id, customer_phone, items_json, subtotal, delivery_fee, total, status, location, \
+     voucher_code, created_at, updated_at";
+
+fn order_from_row(row: &rusqlite::Row) -> rusqlite::Result<OrderRecord> {
+    Ok(OrderRecord {
+        id: row.get(0)?,
+        customer_phone: row.get(1)?,
+        items_json: row.get(2)?,
+        subtotal: row.get(3)?,
+        delivery_fee: row.get(4)?,
+        total: row.get(5)?,
+        status: OrderStatus::from_column(row, 6)?,
+        location: row.get(7)?,
+        voucher_code: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+const PAYMENT_COLUMNS: &str =
+    "id, order_id, amount, currency, method, status, phone, reference, provider_ref, created_at, updated_at";
+
+fn payment_from_row(row: &rusqlite::Row) -> rusqlite::Result<Payment> {
+    Ok(Payment {
+        id: row.get(0)?,
+        order_id: row.get(1)?,
+        amount: row.get(2)?,
+        currency: row.get(3)?,
+        method: parse_column(row, 4, "payment method", PaymentMethod::parse)?,
+        status: parse_column(row, 5, "payment status", PaymentStatus::parse)?,
+        phone: row.get(6)?,
+        reference: row.get(7)?,
+        provider_ref: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+/// Read a text column through `parse`, failing on a value it doesn't know
+/// rather than guessing.
+fn parse_column<T>(
+    row: &rusqlite::Row,
+    idx: usize,
+    what: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> rusqlite::Result<T> {
+    let raw: String = row.get(idx)?;
+    parse(&raw).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            idx,
+            rusqlite::types::Type::Text,
+            format!("unknown {} '{}'", what, raw).into(),
+        )
+    })
+}
+
 /// Stored voucher record.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VoucherRecord {
@@ -117,16 +249,401 @@ impl RefundStatus {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(Self::Pending),
+            "processing" => Some(Self::Processing),
+            "completed" => Some(Self::Completed),
+            "failed" => Some(Self::Failed),
+            "cancelled" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+const REFUND_COLUMNS: &str = "id, payment_id, order_id, amount, currency, phone, reason, conversation_id, status, admin_id, \
+     created_at, completed_at";
+
+fn refund_from_row(row: &rusqlite::Row) -> rusqlite::Result<RefundRecord> {
+    Ok(RefundRecord {
+        id: row.get(0)?,
+        payment_id: row.get(1)?,
+        order_id: row.get(2)?,
+        amount: row.get(3)?,
+        currency: row.get(4)?,
+        phone: row.get(5)?,
+        reason: row.get(6)?,
+        conversation_id: row.get(7)?,
+        status: parse_column(row, 8, "refund status", RefundStatus::parse)?,
+        admin_id: row.get(9)?,
+        created_at: row.get(10)?,
+        completed_at: row.get(11)?,
+    })
+}
+
+/// Returned by `create_refund` when the payment already has a refund that
+/// hasn't failed or been cancelled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefundInProgress {
+    pub payment_id: String,
+    pub refund_id: String,
+}
+
+impl std::fmt::Display for RefundInProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Payment {} is already being refunded ({})", self.payment_id, self.refund_id)
+    }
+}
+
+impl std::error::Error for RefundInProgress {}
+
+/// Stock level for a menu item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockRecord {
+    pub item_name: String,
+    pub quantity: i64,
+    pub updated_at: String,
+}
+
+/// Returned by `create_order` when an item doesn't have enough stock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutOfStock {
+    pub item: String,
+    pub available: i64,
+}
+
+impl std::fmt::Display for OutOfStock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Only {} left of {}", self.available, self.item)
+    }
+}
+
+impl std::error::Error for OutOfStock {}
+
+/// Stored menu item, with its current stock level if stock-tracked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MenuItemRecord {
+    pub id: i64,
+    pub name: String,
+    pub price: f64,
+    pub emoji: Option<String>,
+    pub description: Option<String>,
+    pub available: bool,
+    pub duration_minutes: Option<u32>,
+    pub position: i64,
+    pub stock: Option<i64>,
+    pub category: Option<String>,
+    pub variants: Vec<MenuVariant>,
+    pub addons: Vec<MenuAddon>,
+    pub image: Option<String>,
+    pub updated_at: String,
+}
+
+impl MenuItemRecord {
+    /// The item as handlers see it.
+    pub fn to_menu_item(&self) -> MenuItem {
+        MenuItem {
+            name: self.name.clone(),
+            price: self.price,
+            description: self.description.clone(),
+            emoji: self.emoji.clone(),
+            available: self.available,
+            duration_minutes: self.duration_minutes,
+            stock: None,
+            category: self.category.clone(),
+            variants: self.variants.clone(),
+            addons: self.addons.clone(),
+            image: self.image.clone(),
+        }
+    }
+}
+
+/// Changes to a menu item; `None` fields are left as they are.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MenuItemUpdate {
+    pub name: Option<String>,
+    pub price: Option<f64>,
+    pub emoji: Option<String>,
+    pub description: Option<String>,
+    pub available: Option<bool>,
+    pub duration_minutes: Option<u32>,
+    pub position: Option<i64>,
+    /// An empty string removes the item from its category
+    pub category: Option<String>,
+    pub variants: Option<Vec<MenuVariant>>,
+    pub addons: Option<Vec<MenuAddon>>,
+    /// An empty string removes the photo
+    pub image: Option<String>,
+}
+
+/// A customer, created the first time they message the bot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerRecord {
+    pub phone: String,
+    /// WhatsApp display name, unless someone has set it from the dashboard
+    pub name: Option<String>,
+    pub language: Option<String>,
+    /// Delivery addresses, most recently used first
+    pub addresses: Vec<String>,
+    pub tags: Vec<String>,
+    pub marketing_opt_in: bool,
+    pub marketing_opt_in_at: Option<String>,
+    pub notes: Option<String>,
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+    pub order_count: i64,
+    /// Total of orders that went ahead (confirmed or later, not cancelled)
+    pub lifetime_value: f64,
+}
+
+/// Changes to a customer; `None` fields are left as they are and an empty
+/// string clears `name`, `language` or `notes`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CustomerUpdate {
+    pub name: Option<String>,
+    pub language: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub marketing_opt_in: Option<bool>,
+    pub notes: Option<String>,
+}
+
+/// How many delivery addresses are kept per customer.
+const MAX_SAVED_ADDRESSES: usize = 5;
+
+const CUSTOMER_COLUMNS: &str = "c.phone, c.name, c.language, c.addresses_json, c.tags_json, \
+     c.marketing_opt_in, c.marketing_opt_in_at, c.notes, c.first_seen_at, c.last_seen_at, \
+     (SELECT COUNT(*) FROM orders o WHERE o.customer_phone = c.phone), \
+     (SELECT COALESCE(SUM(o.total), 0) FROM orders o WHERE o.customer_phone = c.phone \
+        AND o.status IN ('confirmed', 'preparing', 'delivering', 'delivered'))";
+
+fn customer_from_row(row: &rusqlite::Row) -> rusqlite::Result<CustomerRecord> {
+    let addresses: String = row.get(3)?;
+    let tags: String = row.get(4)?;
+    Ok(CustomerRecord {
+        phone: row.get(0)?,
+        name: row.get(1)?,
+        language: row.get(2)?,
+        addresses: serde_json::from_str(&addresses).unwrap_or_default(),
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        marketing_opt_in: row.get(5)?,
+        marketing_opt_in_at: row.get(6)?,
+        notes: row.get(7)?,
+        first_seen_at: row.get(8)?,
+        last_seen_at: row.get(9)?,
+        order_count: row.get(10)?,
+        lifetime_value: row.get(11)?,
+    })
+}
+
+/// Put `address` at the front of the customer's saved addresses.
+fn remember_address(conn: &Connection, phone: &str, address: &str) -> Result<()> {
+    conn.execute("INSERT OR IGNORE INTO customers (phone) VALUES (?1)", params![phone])?;
+    let json: String = conn.query_row(
+        "SELECT addresses_json FROM customers WHERE phone = ?1",
+        params![phone],
+        |row| row.get(0),
+    )?;
+    let mut addresses: Vec<String> = serde_json::from_str(&json).unwrap_or_default();
+    addresses.retain(|a| a != address);
+    addresses.insert(0, address.to_string());
+    addresses.truncate(MAX_SAVED_ADDRESSES);
+    conn.execute(
+        "UPDATE customers SET addresses_json = ?1 WHERE phone = ?2",
+        params![serde_json::to_string(&addresses)?, phone],
+    )?;
+    Ok(())
+}
+
+const MENU_ITEM_COLUMNS: &str = "m.id, m.name, m.price, m.emoji, m.description, m.available, \
+     m.duration_minutes, m.position, s.quantity, m.category, m.variants_json, m.addons_json, m.image, m.updated_at";
+
+fn menu_item_from_row(row: &rusqlite::Row) -> rusqlite::Result<MenuItemRecord> {
+    Ok(MenuItemRecord {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        price: row.get(2)?,
+        emoji: row.get(3)?,
+        description: row.get(4)?,
+        available: row.get(5)?,
+        duration_minutes: row.get(6)?,
+        position: row.get(7)?,
+        stock: row.get(8)?,
+        category: row.get(9)?,
+        variants: serde_json::from_str(&row.get::<_, String>(10)?).unwrap_or_default(),
+        addons: serde_json::from_str(&row.get::<_, String>(11)?).unwrap_or_default(),
+        image: row.get(12)?,
+        updated_at: row.get(13)?,
+    })
+}
+
+/// Item name and quantity from an order's `items_json`.
+#[derive(Deserialize)]
+struct StockLine {
+    name: String,
+    quantity: u32,
+}
+
+fn stock_lines(items_json: &str) -> Vec<StockLine> {
+    serde_json::from_str(items_json).unwrap_or_default()
+}
+
+/// Stored appointment booking. Times are local "YYYY-MM-DD HH:MM".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookingRecord {
+    pub id: i64,
+    pub customer_phone: String,
+    pub service: String,
+    pub price: f64,
+    pub start_at: String,
+    pub end_at: String,
+    pub status: BookingStatus,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BookingStatus {
+    Confirmed,
+    Cancelled,
+}
+
+impl BookingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Confirmed => "confirmed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "confirmed" => Some(Self::Confirmed),
+            "cancelled" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+
+    /// Read a status column, failing on anything that isn't a known status.
+    fn from_column(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<Self> {
+        let raw: String = row.get(idx)?;
+        Self::parse(&raw).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(
+                idx,
+                rusqlite::types::Type::Text,
+                format!("unknown booking status '{}'", raw).into(),
+            )
+        })
+    }
+}
+
+impl BookingRecord {
+    /// Parsed start time.
+    pub fn start(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&self.start_at, bookings::DATETIME_FORMAT).ok()
+    }
+
+    /// Parsed end time.
+    pub fn end(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(&self.end_at, bookings::DATETIME_FORMAT).ok()
+    }
+}
+
+const BOOKING_COLUMNS: &str =
+    "id, customer_phone, service, price, start_at, end_at, status, created_at, updated_at";
+
+fn booking_from_row(row: &rusqlite::Row) -> rusqlite::Result<BookingRecord> {
+    Ok(BookingRecord {
+        id: row.get(0)?,
+        customer_phone: row.get(1)?,
+        service: row.get(2)?,
+        price: row.get(3)?,
+        start_at: row.get(4)?,
+        end_at: row.get(5)?,
+        status: BookingStatus::from_column(row, 6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+/// Dashboard user roles, from most to least privileged.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    /// Read-only access to orders, payments and reports
+    Accountant,
+    /// Day-to-day operations: orders, menu, stock, bookings
+    Staff,
+    /// Everything, including refunds, vouchers and user management
+    Owner,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Owner => "owner",
+            Self::Staff => "staff",
+            Self::Accountant => "accountant",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
         match s {
-            "pending" => Self::Pending,
-            "processing" => Self::Processing,
-            "completed" => Self::Completed,
-            "failed" => Self::Failed,
-            "cancelled" => Self::Cancelled,
-            _ => Self::Pending,
+            "owner" => Some(Self::Owner),
+            "staff" => Some(Self::Staff),
+            "accountant" => Some(Self::Accountant),
+            _ => None,
         }
     }
+
+    /// Role stored in the database; anything unrecognised gets the least
+    /// privileged role.
+    fn from_column(s: &str) -> Self {
+        Self::parse(s).unwrap_or(Self::Accountant)
+    }
+
+    /// Whether this role may do what `required` may.
+    pub fn allows(&self, required: UserRole) -> bool {
+        *self >= required
+    }
+}
+
+/// Dashboard login. The password hash is never part of the record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserRecord {
+    pub id: i64,
+    pub username: String,
+    pub role: UserRole,
+    pub created_at: String,
+    pub last_login_at: Option<String>,
+}
+
+/// Long-lived dashboard token for scripts and integrations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    pub id: i64,
+    pub username: String,
+    pub role: UserRole,
+    pub name: Option<String>,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+/// `dashboard_tokens.kind` for login sessions, which expire.
+pub const TOKEN_SESSION: &str = "session";
+/// `dashboard_tokens.kind` for API keys, which last until revoked.
+pub const TOKEN_API_KEY: &str = "api_key";
+
+const USER_COLUMNS: &str = "u.id, u.username, u.role, u.created_at, u.last_login_at";
+
+fn user_from_row(row: &rusqlite::Row) -> rusqlite::Result<UserRecord> {
+    Ok(UserRecord {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        role: UserRole::from_column(&row.get::<_, String>(2)?),
+        created_at: row.get(3)?,
+        last_login_at: row.get(4)?,
+    })
 }
 
 /// Stats summary for the dashboard.
@@ -147,94 +664,56 @@ pub struct Stats {
 impl Store {
     /// Open (or create) the SQLite database and run migrations.
     pub fn new(db_path: &str) -> Result<Self> {
+        let store = Self::open(db_path)?;
+        store.migrate()?;
+        Ok(store)
+    }
+
+    /// Open (or create) the SQLite database without running migrations.
+    pub fn open(db_path: &str) -> Result<Self> {
         let conn = Connection::open(db_path)
             .with_context(|| format!("Failed to open database at {}", db_path))?;
 
         // Enable WAL mode for better concurrent read performance
         conn.execute_batch("PRAGMA journal_mode=WAL;")?;
 
-        // Run schema migrations
-        conn.execute_batch(
-            "
-            CREATE TABLE IF NOT EXISTS orders (
-                id              INTEGER PRIMARY KEY AUTOINCREMENT,
-                customer_phone  TEXT NOT NULL,
-                items_json      TEXT NOT NULL,
-                subtotal        REAL NOT NULL DEFAULT 0,
-                delivery_fee    REAL NOT NULL DEFAULT 0,
-                total           REAL NOT NULL,
-                status          TEXT NOT NULL DEFAULT 'pending',
-                location        TEXT,
-                voucher_code    TEXT,
-                created_at      TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at      TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS vouchers (
-                id          INTEGER PRIMARY KEY AUTOINCREMENT,
-                code        TEXT NOT NULL UNIQUE,
-                amount      REAL NOT NULL,
-                redeemed_by TEXT,
-                created_at  TEXT NOT NULL DEFAULT (datetime('now')),
-                redeemed_at TEXT
-            );
-
-            CREATE TABLE IF NOT EXISTS conversations (
-                phone       TEXT PRIMARY KEY,
-                state_json  TEXT NOT NULL DEFAULT '\"Idle\"',
-                updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS payments (
-                id              TEXT PRIMARY KEY,
-                order_id        INTEGER NOT NULL,
-                amount          REAL NOT NULL,
-                currency        TEXT NOT NULL DEFAULT 'KES',
-                method          TEXT NOT NULL,
-                status          TEXT NOT NULL DEFAULT 'pending',
-                phone           TEXT NOT NULL,
-                reference       TEXT NOT NULL,
-                provider_ref    TEXT,
-                created_at      TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at      TEXT NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (order_id) REFERENCES orders(id)
-            );
-
-            CREATE TABLE IF NOT EXISTS refunds (
-                id                  TEXT PRIMARY KEY,
-                payment_id          TEXT NOT NULL,
-                order_id            INTEGER NOT NULL,
-                amount              REAL NOT NULL,
-                currency            TEXT NOT NULL DEFAULT 'KES',
-                phone               TEXT NOT NULL,
-                reason              TEXT,
-                conversation_id     TEXT,
-                status              TEXT NOT NULL DEFAULT 'pending',
-                admin_id            TEXT,
-                created_at          TEXT NOT NULL DEFAULT (datetime('now')),
-                completed_at        TEXT,
-                FOREIGN KEY (payment_id) REFERENCES payments(id),
-                FOREIGN KEY (order_id) REFERENCES orders(id)
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_orders_status ON orders(status);
-            CREATE INDEX IF NOT EXISTS idx_orders_phone ON orders(customer_phone);
-            CREATE INDEX IF NOT EXISTS idx_vouchers_code ON vouchers(code);
-            CREATE INDEX IF NOT EXISTS idx_payments_order ON payments(order_id);
-            CREATE INDEX IF NOT EXISTS idx_payments_status ON payments(status);
-            CREATE INDEX IF NOT EXISTS idx_refunds_payment ON refunds(payment_id);
-            CREATE INDEX IF NOT EXISTS idx_refunds_status ON refunds(status);
-            ",
-        )?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Open an existing database without writing to it — no WAL switch, no
+    /// migrations. Fails if there's no database at `db_path`.
+    pub fn open_read_only(db_path: &str) -> Result<Self> {
+        let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open database at {}", db_path))?;
 
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
         })
     }
 
+    // ─── Schema ──────────────────────────────────────────────────────
+
+    /// Apply all pending schema migrations. Returns the versions applied.
+    pub fn migrate(&self) -> Result<Vec<i64>> {
+        let mut conn = self.conn.lock().unwrap();
+        let applied = migrations::migrate(&mut conn)?;
+        Ok(applied.iter().map(|m| m.version).collect())
+    }
+
+    /// Current schema version plus applied and pending migrations.
+    pub fn schema_status(&self) -> Result<migrations::SchemaStatus> {
+        let conn = self.conn.lock().unwrap();
+        migrations::status(&conn)
+    }
+
     // ─── Orders ──────────────────────────────────────────────────────
 
-    /// Insert a new order. Returns the order ID.
+    /// Insert a new order and take its items out of stock. Returns the order ID.
+    ///
+    /// Fails with [`OutOfStock`] (and inserts nothing) if a stock-tracked
+    /// item doesn't have enough left.
     pub fn create_order(
         &self,
         customer_phone: &str,
@@ -244,133 +723,351 @@ impl Store {
         total: f64,
         voucher_code: Option<&str>,
     ) -> Result<i64> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        for line in stock_lines(items_json) {
+            let available: Option<i64> = tx
+                .query_row(
+                    "SELECT quantity FROM stock WHERE item_name = ?1",
+                    params![line.name],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            match available {
+                Some(available) if available < line.quantity as i64 => {
+                    return Err(OutOfStock {
+                        item: line.name,
+                        available: available.max(0),
+                    }
+                    .into());
+                }
+                Some(_) => {
+                    tx.execute(
+                        "UPDATE stock SET quantity = quantity - ?1, updated_at = datetime('now') WHERE item_name = ?2",
+                        params![line.quantity, line.name],
+                    )?;
+                }
+                // Not stock-tracked
+                None => {}
+            }
+        }
+
+        tx.execute(
             "INSERT INTO orders (customer_phone, items_json, subtotal, delivery_fee, total, voucher_code)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             params![customer_phone, items_json, subtotal, delivery_fee, total, voucher_code],
         )?;
-        Ok(conn.last_insert_rowid())
+        let order_id = tx.last_insert_rowid();
+        tx.execute(
+            "INSERT INTO order_events (order_id, from_status, to_status, actor) VALUES (?1, NULL, ?2, ?3)",
+            params![order_id, OrderStatus::Pending.as_str(), format!("customer:{}", customer_phone)],
+        )?;
+        tx.commit()?;
+        Ok(order_id)
     }
 
-    /// Update order status.
-    pub fn update_order_status(&self, order_id: i64, status: &OrderStatus) -> Result<()> {
+    /// Move an order to `next` and record the change in its history.
+    ///
+    /// Fails with `InvalidTransition` (changing nothing) unless
+    /// `OrderStatus::can_transition_to` allows the move. Cancelling puts the
+    /// order's items back in stock. Returns the updated order, or `None` if
+    /// there is no such order.
+    pub fn transition_order(
+        &self,
+        order_id: i64,
+        next: &OrderStatus,
+        actor: &str,
+        note: Option<&str>,
+    ) -> Result<Option<OrderRecord>> {
+        {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            let current = tx
+                .query_row("SELECT status FROM orders WHERE id = ?1", params![order_id], |row| {
+                    OrderStatus::from_column(row, 0)
+                })
+                .optional()?;
+            let Some(current) = current else {
+                return Ok(None);
+            };
+            if !current.can_transition_to(next) {
+                return Err(InvalidTransition {
+                    from: current,
+                    to: next.clone(),
+                }
+                .into());
+            }
+            tx.execute(
+                "UPDATE orders SET status = ?1, updated_at = datetime('now') WHERE id = ?2",
+                params![next.as_str(), order_id],
+            )?;
+            // Cancelled is final, so this runs at most once per order
+            if *next == OrderStatus::Cancelled {
+                let items_json: String =
+                    tx.query_row("SELECT items_json FROM orders WHERE id = ?1", params![order_id], |row| row.get(0))?;
+                for line in stock_lines(&items_json) {
+                    tx.execute(
+                        "UPDATE stock SET quantity = quantity + ?1, updated_at = datetime('now') WHERE item_name = ?2",
+                        params![line.quantity, line.name],
+                    )?;
+                }
+            }
+            tx.execute(
+                "INSERT INTO order_events (order_id, from_status, to_status, actor, note)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![order_id, current.as_str(), next.as_str(), actor, note],
+            )?;
+            tx.commit()?;
+        }
+        self.get_order(order_id)
+    }
+
+    /// An order's history, oldest first.
+    pub fn order_events(&self, order_id: i64) -> Result<Vec<OrderEvent>> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "UPDATE orders SET status = ?1, updated_at = datetime('now') WHERE id = ?2",
-            params![status.as_str(), order_id],
+        let mut stmt = conn.prepare(
+            "SELECT id, order_id, from_status, to_status, actor, note, created_at
+             FROM order_events WHERE order_id = ?1 ORDER BY id",
         )?;
-        Ok(())
+        let events = stmt
+            .query_map(params![order_id], |row| {
+                Ok(OrderEvent {
+                    id: row.get(0)?,
+                    order_id: row.get(1)?,
+                    from_status: match row.get::<_, Option<String>>(2)? {
+                        Some(_) => Some(OrderStatus::from_column(row, 2)?),
+                        None => None,
+                    },
+                    to_status: OrderStatus::from_column(row, 3)?,
+                    actor: row.get(4)?,
+                    note: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(events)
     }
 
-    /// Set the delivery location for an order.
+    /// Set the delivery location for an order, and save it to the customer's
+    /// addresses.
     pub fn set_order_location(&self, order_id: i64, location: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "UPDATE orders SET location = ?1, status = 'confirmed', updated_at = datetime('now') WHERE id = ?2",
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE orders SET location = ?1, updated_at = datetime('now') WHERE id = ?2",
             params![location, order_id],
         )?;
+        let phone: Option<String> = tx
+            .query_row("SELECT customer_phone FROM orders WHERE id = ?1", params![order_id], |row| row.get(0))
+            .optional()?;
+        if let Some(phone) = phone {
+            remember_address(&tx, &phone, location)?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
     /// Get a single order by ID.
     pub fn get_order(&self, order_id: i64) -> Result<Option<OrderRecord>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, customer_phone, items_json, subtotal, delivery_fee, total, status, location, voucher_code, created_at, updated_at
-             FROM orders WHERE id = ?1",
-        )?;
-        let mut rows = stmt.query_map(params![order_id], |row| {
-            Ok(OrderRecord {
-                id: row.get(0)?,
-                customer_phone: row.get(1)?,
-                items_json: row.get(2)?,
-                subtotal: row.get(3)?,
-                delivery_fee: row.get(4)?,
-                total: row.get(5)?,
-                status: OrderStatus::from_str(&row.get::<_, String>(6)?),
-                location: row.get(7)?,
-                voucher_code: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            })
-        })?;
-        match rows.next() {
-            Some(Ok(record)) => Ok(Some(record)),
-            Some(Err(e)) => Err(e.into()),
-            None => Ok(None),
-        }
+        Ok(conn
+            .query_row(
+                &format!("SELECT {} FROM orders WHERE id = ?1", ORDER_COLUMNS),
+                params![order_id],
+                order_from_row,
+            )
+            .optional()?)
     }
 
     /// List orders, optionally filtered by status.
     pub fn list_orders(&self, status_filter: Option<&OrderStatus>) -> Result<Vec<OrderRecord>> {
         let conn = self.conn.lock().unwrap();
-        let (sql, param_values): (&str, Vec<Box<dyn rusqlite::types::ToSql>>) = match status_filter {
-            Some(status) => (
-                "SELECT id, customer_phone, items_json, subtotal, delivery_fee, total, status, location, voucher_code, created_at, updated_at
-                 FROM orders WHERE status = ?1 ORDER BY created_at DESC",
-                vec![Box::new(status.as_str().to_string())],
-            ),
-            None => (
-                "SELECT id, customer_phone, items_json, subtotal, delivery_fee, total, status, location, voucher_code, created_at, updated_at
-                 FROM orders ORDER BY created_at DESC",
-                vec![],
-            ),
+        let orders = match status_filter {
+            Some(status) => conn
+                .prepare(&format!(
+                    "SELECT {} FROM orders WHERE status = ?1 ORDER BY created_at DESC",
+                    ORDER_COLUMNS
+                ))?
+                .query_map(params![status.as_str()], order_from_row)?
+                .collect::<Result<Vec<_>, _>>()?,
+            None => conn
+                .prepare(&format!("SELECT {} FROM orders ORDER BY created_at DESC", ORDER_COLUMNS))?
+                .query_map([], order_from_row)?
+                .collect::<Result<Vec<_>, _>>()?,
         };
+        Ok(orders)
+    }
 
-        let mut stmt = conn.prepare(sql)?;
-        let params_refs: Vec<&dyn rusqlite::types::ToSql> = param_values.iter().map(|p| p.as_ref()).collect();
-        let rows = stmt.query_map(params_refs.as_slice(), |row| {
-            Ok(OrderRecord {
-                id: row.get(0)?,
-                customer_phone: row.get(1)?,
-                items_json: row.get(2)?,
-                subtotal: row.get(3)?,
-                delivery_fee: row.get(4)?,
-                total: row.get(5)?,
-                status: OrderStatus::from_str(&row.get::<_, String>(6)?),
-                location: row.get(7)?,
-                voucher_code: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            })
-        })?;
+    /// Get recent orders for a customer.
+    pub fn get_customer_orders(&self, phone: &str, limit: usize) -> Result<Vec<OrderRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM orders WHERE customer_phone = ?1 ORDER BY created_at DESC, id DESC LIMIT ?2",
+            ORDER_COLUMNS
+        ))?;
+        let orders = stmt
+            .query_map(params![phone, limit as i64], order_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(orders)
+    }
 
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row?);
+    /// Where the customer's most recent order was delivered to, if anywhere.
+    pub fn last_delivery_location(&self, phone: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT location FROM orders
+                 WHERE customer_phone = ?1 AND location IS NOT NULL AND location != ''
+                 ORDER BY created_at DESC, id DESC LIMIT 1",
+                params![phone],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    // ─── Customers ───────────────────────────────────────────────────
+
+    /// Record that `phone` has just messaged the bot, creating the customer
+    /// the first time. `name` (their WhatsApp display name) only fills in a
+    /// missing name, so one set from the dashboard sticks. Returns whether
+    /// the customer is new.
+    pub fn touch_customer(&self, phone: &str, name: Option<&str>) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let created = conn.execute(
+            "INSERT OR IGNORE INTO customers (phone, name) VALUES (?1, ?2)",
+            params![phone, name],
+        )? == 1;
+        if !created {
+            conn.execute(
+                "UPDATE customers SET
+                    last_seen_at = datetime('now'),
+                    name = COALESCE(name, ?2)
+                 WHERE phone = ?1",
+                params![phone, name],
+            )?;
         }
-        Ok(result)
+        Ok(created)
     }
 
-    /// Get recent orders for a customer.
-    pub fn get_customer_orders(&self, phone: &str, limit: usize) -> Result<Vec<OrderRecord>> {
+    /// Look up a customer by phone, with their order totals.
+    pub fn get_customer(&self, phone: &str) -> Result<Option<CustomerRecord>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, customer_phone, items_json, subtotal, delivery_fee, total, status, location, voucher_code, created_at, updated_at
-             FROM orders WHERE customer_phone = ?1 ORDER BY created_at DESC LIMIT ?2",
-        )?;
-        let rows = stmt.query_map(params![phone, limit as i64], |row| {
-            Ok(OrderRecord {
-                id: row.get(0)?,
-                customer_phone: row.get(1)?,
-                items_json: row.get(2)?,
-                subtotal: row.get(3)?,
-                delivery_fee: row.get(4)?,
-                total: row.get(5)?,
-                status: OrderStatus::from_str(&row.get::<_, String>(6)?),
-                location: row.get(7)?,
-                voucher_code: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
+        Ok(conn
+            .query_row(
+                &format!("SELECT {} FROM customers c WHERE c.phone = ?1", CUSTOMER_COLUMNS),
+                params![phone],
+                customer_from_row,
+            )
+            .optional()?)
+    }
+
+    /// The language code a customer picked, if any.
+    pub fn customer_language(&self, phone: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT language FROM customers WHERE phone = ?1",
+                params![phone],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten())
+    }
+
+    /// Customers, most recently seen first. `search` matches part of the
+    /// phone number or name; `tag` keeps only customers with that tag.
+    pub fn list_customers(
+        &self,
+        search: Option<&str>,
+        tag: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<CustomerRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM customers c
+             WHERE (?1 IS NULL OR c.phone LIKE '%' || ?1 || '%' OR c.name LIKE '%' || ?1 || '%')
+               AND (?2 IS NULL OR EXISTS (SELECT 1 FROM json_each(c.tags_json) WHERE value = ?2 COLLATE NOCASE))
+             ORDER BY c.last_seen_at DESC, c.phone
+             LIMIT ?3",
+            CUSTOMER_COLUMNS
+        ))?;
+        let customers = stmt
+            .query_map(params![search, tag, limit as i64], customer_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(customers)
+    }
+
+    /// Apply `update` to a customer. Returns false if there is no such customer.
+    pub fn update_customer(&self, phone: &str, update: &CustomerUpdate) -> Result<bool> {
+        let tags = update
+            .tags
+            .as_ref()
+            .map(|tags| {
+                let mut cleaned: Vec<String> = Vec::new();
+                for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+                    if !cleaned.iter().any(|c| c.eq_ignore_ascii_case(tag)) {
+                        cleaned.push(tag.to_string());
+                    }
+                }
+                serde_json::to_string(&cleaned)
             })
-        })?;
+            .transpose()?;
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute(
+            "UPDATE customers SET
+                name = NULLIF(COALESCE(?1, name), ''),
+                language = NULLIF(COALESCE(?2, language), ''),
+                tags_json = COALESCE(?3, tags_json),
+                marketing_opt_in_at = CASE
+                    WHEN ?4 IS NULL OR ?4 = marketing_opt_in THEN marketing_opt_in_at
+                    WHEN ?4 THEN datetime('now')
+                    ELSE NULL END,
+                marketing_opt_in = COALESCE(?4, marketing_opt_in),
+                notes = NULLIF(COALESCE(?5, notes), '')
+             WHERE phone = ?6",
+            params![update.name, update.language, tags, update.marketing_opt_in, update.notes, phone],
+        )?;
+        Ok(changed > 0)
+    }
 
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row?);
-        }
-        Ok(result)
+    /// Payments for a customer's orders, newest first.
+    pub fn get_customer_payments(&self, phone: &str) -> Result<Vec<Payment>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM payments
+             WHERE order_id IN (SELECT id FROM orders WHERE customer_phone = ?1)
+             ORDER BY created_at DESC",
+            PAYMENT_COLUMNS
+        ))?;
+
+        let payments = stmt
+            .query_map(params![phone], payment_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(payments)
+    }
+
+    /// Vouchers a customer has redeemed, newest first.
+    pub fn get_customer_vouchers(&self, phone: &str) -> Result<Vec<VoucherRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, code, amount, redeemed_by, created_at, redeemed_at
+             FROM vouchers WHERE redeemed_by = ?1 ORDER BY redeemed_at DESC",
+        )?;
+        let vouchers = stmt
+            .query_map(params![phone], |row| {
+                Ok(VoucherRecord {
+                    id: row.get(0)?,
+                    code: row.get(1)?,
+                    amount: row.get(2)?,
+                    redeemed_by: row.get(3)?,
+                    created_at: row.get(4)?,
+                    redeemed_at: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(vouchers)
     }
 
     // ─── Vouchers ────────────────────────────────────────────────────
@@ -606,33 +1303,11 @@ impl Store {
     /// Get payment by ID.
     pub fn get_payment(&self, payment_id: &str) -> Result<Option<Payment>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, order_id, amount, currency, method, status, phone, reference, provider_ref, created_at, updated_at
-             FROM payments WHERE id = ?1",
-        )?;
-
-        let result = stmt.query_row(params![payment_id], |row| {
-                Ok(Payment {
-                    id: row.get(0)?,
-                    order_id: row.get(1)?,
-                    amount: row.get(2)?,
-                    currency: row.get(3)?,
-                    method: serde_json::from_str(&format!(r#""{}""#, row.get::<_, String>(4)?)).unwrap(),
-                    status: match row.get::<_, String>(5)?.as_str() {
-                        "pending" => PaymentStatus::Pending,
-                        "processing" => PaymentStatus::Processing,
-                        "completed" => PaymentStatus::Completed,
-                        "failed" => PaymentStatus::Failed,
-                        "cancelled" => PaymentStatus::Cancelled,
-                        _ => PaymentStatus::Pending,
-                    },
-                    phone: row.get(6)?,
-                    reference: row.get(7)?,
-                    provider_ref: row.get(8)?,
-                    created_at: row.get(9)?,
-                    updated_at: row.get(10)?,
-                })
-            });
+        let result = conn.query_row(
+            &format!("SELECT {} FROM payments WHERE id = ?1", PAYMENT_COLUMNS),
+            params![payment_id],
+            payment_from_row,
+        );
 
         match result {
             Ok(payment) => Ok(Some(payment)),
@@ -644,34 +1319,13 @@ impl Store {
     /// Get payments for an order.
     pub fn get_order_payments(&self, order_id: i64) -> Result<Vec<Payment>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, order_id, amount, currency, method, status, phone, reference, provider_ref, created_at, updated_at
-             FROM payments WHERE order_id = ?1 ORDER BY created_at DESC",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM payments WHERE order_id = ?1 ORDER BY created_at DESC",
+            PAYMENT_COLUMNS
+        ))?;
 
         let payments = stmt
-            .query_map(params![order_id], |row| {
-                Ok(Payment {
-                    id: row.get(0)?,
-                    order_id: row.get(1)?,
-                    amount: row.get(2)?,
-                    currency: row.get(3)?,
-                    method: serde_json::from_str(&format!(r#""{}""#, row.get::<_, String>(4)?)).unwrap(),
-                    status: match row.get::<_, String>(5)?.as_str() {
-                        "pending" => PaymentStatus::Pending,
-                        "processing" => PaymentStatus::Processing,
-                        "completed" => PaymentStatus::Completed,
-                        "failed" => PaymentStatus::Failed,
-                        "cancelled" => PaymentStatus::Cancelled,
-                        _ => PaymentStatus::Pending,
-                    },
-                    phone: row.get(6)?,
-                    reference: row.get(7)?,
-                    provider_ref: row.get(8)?,
-                    created_at: row.get(9)?,
-                    updated_at: row.get(10)?,
-                })
-            })?
+            .query_map(params![order_id], payment_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(payments)
@@ -680,33 +1334,11 @@ impl Store {
     /// Get payment by provider reference (e.g., M-Pesa CheckoutRequestID).
     pub fn get_payment_by_provider_ref(&self, provider_ref: &str) -> Result<Option<Payment>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, order_id, amount, currency, method, status, phone, reference, provider_ref, created_at, updated_at
-             FROM payments WHERE provider_ref = ?1",
-        )?;
-
-        let result = stmt.query_row(params![provider_ref], |row| {
-                Ok(Payment {
-                    id: row.get(0)?,
-                    order_id: row.get(1)?,
-                    amount: row.get(2)?,
-                    currency: row.get(3)?,
-                    method: serde_json::from_str(&format!(r#""{}""#, row.get::<_, String>(4)?)).unwrap(),
-                    status: match row.get::<_, String>(5)?.as_str() {
-                        "pending" => PaymentStatus::Pending,
-                        "processing" => PaymentStatus::Processing,
-                        "completed" => PaymentStatus::Completed,
-                        "failed" => PaymentStatus::Failed,
-                        "cancelled" => PaymentStatus::Cancelled,
-                        _ => PaymentStatus::Pending,
-                    },
-                    phone: row.get(6)?,
-                    reference: row.get(7)?,
-                    provider_ref: row.get(8)?,
-                    created_at: row.get(9)?,
-                    updated_at: row.get(10)?,
-                })
-            });
+        let result = conn.query_row(
+            &format!("SELECT {} FROM payments WHERE provider_ref = ?1", PAYMENT_COLUMNS),
+            params![provider_ref],
+            payment_from_row,
+        );
 
         match result {
             Ok(payment) => Ok(Some(payment)),
@@ -715,9 +1347,31 @@ impl Store {
         }
     }
 
+    /// Payments still `processing` that were last updated at least
+    /// `min_age_minutes` ago, oldest first.
+    pub fn get_stale_payments(&self, min_age_minutes: u32) -> Result<Vec<Payment>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM payments
+             WHERE status = 'processing' AND updated_at <= datetime('now', ?1)
+             ORDER BY updated_at",
+            PAYMENT_COLUMNS
+        ))?;
+
+        let payments = stmt
+            .query_map(params![format!("-{} minutes", min_age_minutes)], payment_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(payments)
+    }
+
     // ─── Refunds ─────────────────────────────────────────────────────
 
     /// Create a new refund record.
+    ///
+    /// Fails with [`RefundInProgress`] (and inserts nothing) if the payment
+    /// already has a refund that hasn't failed or been cancelled, so a
+    /// payment can't be paid back twice.
     pub fn create_refund(
         &self,
         refund_id: &str,
@@ -729,12 +1383,30 @@ impl Store {
         reason: Option<&str>,
         admin_id: Option<&str>,
     ) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let active: Option<String> = tx
+            .query_row(
+                "SELECT id FROM refunds WHERE payment_id = ?1 AND status NOT IN ('failed', 'cancelled')",
+                params![payment_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(existing) = active {
+            return Err(RefundInProgress {
+                payment_id: payment_id.to_string(),
+                refund_id: existing,
+            }
+            .into());
+        }
+
+        tx.execute(
             "INSERT INTO refunds (id, payment_id, order_id, amount, currency, phone, reason, admin_id)
              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![refund_id, payment_id, order_id, amount, currency, phone, reason, admin_id],
         )?;
+        tx.commit()?;
         Ok(())
     }
 
@@ -746,135 +1418,755 @@ impl Store {
         conversation_id: Option<&str>,
     ) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        let _completed_at = if status == "completed" { Some("datetime('now')") } else { None };
-        
-        if let Some(conv_id) = conversation_id {
-            conn.execute(
-                "UPDATE refunds SET status = ?1, conversation_id = ?2, completed_at = COALESCE(completed_at, datetime('now')) WHERE id = ?3",
-                params![status, conv_id, refund_id],
+        // Only a finished payout has a completion time
+        let completed = status == "completed";
+        conn.execute(
+            "UPDATE refunds SET status = ?1, conversation_id = COALESCE(?2, conversation_id),
+                 completed_at = CASE WHEN ?3 THEN COALESCE(completed_at, datetime('now')) ELSE completed_at END
+             WHERE id = ?4",
+            params![status, conversation_id, completed, refund_id],
+        )?;
+        Ok(())
+    }
+
+    /// Get refund by ID.
+    pub fn get_refund(&self, refund_id: &str) -> Result<Option<RefundRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let refund = conn
+            .query_row(
+                &format!("SELECT {} FROM refunds WHERE id = ?1", REFUND_COLUMNS),
+                params![refund_id],
+                refund_from_row,
+            )
+            .optional()?;
+        Ok(refund)
+    }
+
+    /// Get the refund whose payout has this B2C ConversationID.
+    pub fn get_refund_by_conversation_id(&self, conversation_id: &str) -> Result<Option<RefundRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let refund = conn
+            .query_row(
+                &format!("SELECT {} FROM refunds WHERE conversation_id = ?1", REFUND_COLUMNS),
+                params![conversation_id],
+                refund_from_row,
+            )
+            .optional()?;
+        Ok(refund)
+    }
+
+    /// List refunds with optional status filter.
+    pub fn list_refunds(&self, status: Option<&RefundStatus>) -> Result<Vec<RefundRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM refunds WHERE ?1 IS NULL OR status = ?1 ORDER BY created_at DESC",
+            REFUND_COLUMNS
+        ))?;
+        let refunds = stmt
+            .query_map(params![status.map(RefundStatus::as_str)], refund_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(refunds)
+    }
+
+    /// Get refunds for a payment.
+    pub fn get_payment_refunds(&self, payment_id: &str) -> Result<Vec<RefundRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM refunds WHERE payment_id = ?1 ORDER BY created_at DESC",
+            REFUND_COLUMNS
+        ))?;
+        let refunds = stmt
+            .query_map(params![payment_id], refund_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(refunds)
+    }
+
+    // ─── Menu ────────────────────────────────────────────────────────
+
+    /// Fill an empty menu from config, and start tracking configured stock.
+    ///
+    /// Once the menu has been seeded the database is the source of truth, so
+    /// edits made from the dashboard or admin chat survive restarts. Returns
+    /// true if the menu was seeded.
+    pub fn seed_menu(&self, menu: &[MenuItem]) -> Result<bool> {
+        let seeded = {
+            let mut conn = self.conn.lock().unwrap();
+            let count: i64 = conn.query_row("SELECT COUNT(*) FROM menu_items", [], |row| row.get(0))?;
+            if count == 0 {
+                let tx = conn.transaction()?;
+                for (position, item) in menu.iter().enumerate() {
+                    tx.execute(
+                        "INSERT INTO menu_items (name, price, emoji, description, available, duration_minutes,
+                                                 position, category, variants_json, addons_json, image)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                        params![
+                            item.name,
+                            item.price,
+                            item.emoji,
+                            item.description,
+                            item.available,
+                            item.duration_minutes,
+                            position as i64,
+                            item.category,
+                            serde_json::to_string(&item.variants)?,
+                            serde_json::to_string(&item.addons)?,
+                            item.image
+                        ],
+                    )?;
+                }
+                tx.commit()?;
+            }
+            count == 0
+        };
+        self.seed_stock(menu)?;
+        Ok(seeded)
+    }
+
+    /// All menu items, including hidden ones, in menu order.
+    pub fn list_menu_items(&self) -> Result<Vec<MenuItemRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM menu_items m LEFT JOIN stock s ON s.item_name = m.name
+             ORDER BY m.position, m.id",
+            MENU_ITEM_COLUMNS
+        ))?;
+        let items = stmt
+            .query_map([], menu_item_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+
+    /// Items customers can order: available and not sold out.
+    pub fn available_menu(&self) -> Result<Vec<MenuItem>> {
+        let items = self
+            .list_menu_items()?
+            .iter()
+            .filter(|m| m.available && m.stock.is_none_or(|q| q > 0))
+            .map(MenuItemRecord::to_menu_item)
+            .collect();
+        Ok(items)
+    }
+
+    /// Get a menu item by ID.
+    pub fn get_menu_item(&self, id: i64) -> Result<Option<MenuItemRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let item = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM menu_items m LEFT JOIN stock s ON s.item_name = m.name WHERE m.id = ?1",
+                    MENU_ITEM_COLUMNS
+                ),
+                params![id],
+                menu_item_from_row,
+            )
+            .optional()?;
+        Ok(item)
+    }
+
+    /// Find a menu item by name, ignoring case.
+    pub fn find_menu_item(&self, name: &str) -> Result<Option<MenuItemRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let item = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM menu_items m LEFT JOIN stock s ON s.item_name = m.name
+                     WHERE m.name = ?1 COLLATE NOCASE",
+                    MENU_ITEM_COLUMNS
+                ),
+                params![name],
+                menu_item_from_row,
+            )
+            .optional()?;
+        Ok(item)
+    }
+
+    /// Add an item to the end of the menu. Returns the item ID.
+    pub fn create_menu_item(&self, item: &MenuItem) -> Result<i64> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO menu_items (name, price, emoji, description, available, duration_minutes,
+                                     position, category, variants_json, addons_json, image)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, (SELECT COALESCE(MAX(position), -1) + 1 FROM menu_items),
+                     ?7, ?8, ?9, ?10)",
+            params![
+                item.name,
+                item.price,
+                item.emoji,
+                item.description,
+                item.available,
+                item.duration_minutes,
+                item.category,
+                serde_json::to_string(&item.variants)?,
+                serde_json::to_string(&item.addons)?,
+                item.image
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        if let Some(quantity) = item.stock {
+            tx.execute(
+                "INSERT OR REPLACE INTO stock (item_name, quantity) VALUES (?1, ?2)",
+                params![item.name, quantity],
             )?;
-        } else {
-            conn.execute(
-                "UPDATE refunds SET status = ?1, completed_at = COALESCE(completed_at, datetime('now')) WHERE id = ?2",
-                params![status, refund_id],
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Apply changes to a menu item. Returns false if it doesn't exist.
+    ///
+    /// Renaming an item carries its stock level over to the new name.
+    pub fn update_menu_item(&self, id: i64, update: &MenuItemUpdate) -> Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let Some(old_name) = tx
+            .query_row("SELECT name FROM menu_items WHERE id = ?1", params![id], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()?
+        else {
+            return Ok(false);
+        };
+
+        tx.execute(
+            "UPDATE menu_items SET
+                name = COALESCE(?1, name),
+                price = COALESCE(?2, price),
+                emoji = COALESCE(?3, emoji),
+                description = COALESCE(?4, description),
+                available = COALESCE(?5, available),
+                duration_minutes = COALESCE(?6, duration_minutes),
+                position = COALESCE(?7, position),
+                category = NULLIF(COALESCE(?8, category), ''),
+                variants_json = COALESCE(?9, variants_json),
+                addons_json = COALESCE(?10, addons_json),
+                image = NULLIF(COALESCE(?11, image), ''),
+                updated_at = datetime('now')
+             WHERE id = ?12",
+            params![
+                update.name,
+                update.price,
+                update.emoji,
+                update.description,
+                update.available,
+                update.duration_minutes,
+                update.position,
+                update.category,
+                update.variants.as_ref().map(serde_json::to_string).transpose()?,
+                update.addons.as_ref().map(serde_json::to_string).transpose()?,
+                update.image,
+                id
+            ],
+        )?;
+
+        if let Some(ref new_name) = update.name
+            && *new_name != old_name
+        {
+            tx.execute(
+                "UPDATE stock SET item_name = ?1 WHERE item_name = ?2",
+                params![new_name, old_name],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Apply edits between two versions of the config menu to the store.
+    ///
+    /// Only items that changed in config are touched: new or edited items
+    /// are written over the stored item with the same name, and items removed
+    /// from config are deleted. Items edited only from the dashboard keep
+    /// their edits. Returns the number of items added, updated or removed.
+    pub fn sync_menu(&self, old: &[MenuItem], new: &[MenuItem]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut synced = 0;
+
+        for (position, item) in new.iter().enumerate() {
+            let previous = old.iter().find(|o| o.name == item.name);
+            if previous == Some(item) {
+                continue;
+            }
+
+            tx.execute(
+                "INSERT INTO menu_items (name, price, emoji, description, available, duration_minutes,
+                                         position, category, variants_json, addons_json, image)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                 ON CONFLICT(name) DO UPDATE SET
+                    price = excluded.price,
+                    emoji = excluded.emoji,
+                    description = excluded.description,
+                    available = excluded.available,
+                    duration_minutes = excluded.duration_minutes,
+                    position = excluded.position,
+                    category = excluded.category,
+                    variants_json = excluded.variants_json,
+                    addons_json = excluded.addons_json,
+                    image = excluded.image,
+                    updated_at = datetime('now')",
+                params![
+                    item.name,
+                    item.price,
+                    item.emoji,
+                    item.description,
+                    item.available,
+                    item.duration_minutes,
+                    position as i64,
+                    item.category,
+                    serde_json::to_string(&item.variants)?,
+                    serde_json::to_string(&item.addons)?,
+                    item.image
+                ],
             )?;
+
+            // A changed stock count in config resets the level
+            if item.stock != previous.and_then(|p| p.stock) {
+                match item.stock {
+                    Some(quantity) => tx.execute(
+                        "INSERT INTO stock (item_name, quantity) VALUES (?1, ?2)
+                         ON CONFLICT(item_name) DO UPDATE SET quantity = excluded.quantity, updated_at = datetime('now')",
+                        params![item.name, quantity],
+                    )?,
+                    None => tx.execute("DELETE FROM stock WHERE item_name = ?1", params![item.name])?,
+                };
+            }
+            synced += 1;
+        }
+
+        for item in old.iter().filter(|o| !new.iter().any(|n| n.name == o.name)) {
+            tx.execute("DELETE FROM menu_items WHERE name = ?1", params![item.name])?;
+            tx.execute("DELETE FROM stock WHERE item_name = ?1", params![item.name])?;
+            synced += 1;
+        }
+
+        tx.commit()?;
+        Ok(synced)
+    }
+
+    /// Remove an item from the menu (and stop tracking its stock).
+    pub fn delete_menu_item(&self, id: i64) -> Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let name: Option<String> = tx
+            .query_row("SELECT name FROM menu_items WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?;
+        let Some(name) = name else {
+            return Ok(false);
+        };
+        tx.execute("DELETE FROM menu_items WHERE id = ?1", params![id])?;
+        tx.execute("DELETE FROM stock WHERE item_name = ?1", params![name])?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    // ─── Stock ───────────────────────────────────────────────────────
+
+    /// Start tracking stock for menu items that declare `stock` in config.
+    /// Items already tracked keep their current count.
+    pub fn seed_stock(&self, menu: &[MenuItem]) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        for item in menu {
+            if let Some(quantity) = item.stock {
+                conn.execute(
+                    "INSERT OR IGNORE INTO stock (item_name, quantity) VALUES (?1, ?2)",
+                    params![item.name, quantity],
+                )?;
+            }
         }
         Ok(())
     }
 
-    /// Get refund by ID.
-    pub fn get_refund(&self, refund_id: &str) -> Result<Option<RefundRecord>> {
+    /// Stock left for an item, or `None` if it isn't stock-tracked.
+    pub fn get_stock(&self, item_name: &str) -> Result<Option<i64>> {
+        let conn = self.conn.lock().unwrap();
+        let quantity = conn
+            .query_row(
+                "SELECT quantity FROM stock WHERE item_name = ?1",
+                params![item_name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(quantity)
+    }
+
+    /// All stock-tracked items.
+    pub fn list_stock(&self) -> Result<Vec<StockRecord>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, payment_id, order_id, amount, currency, phone, reason, conversation_id, status, admin_id, created_at, completed_at
-             FROM refunds WHERE id = ?1",
+            "SELECT item_name, quantity, updated_at FROM stock ORDER BY item_name",
         )?;
+        let stock = stmt
+            .query_map([], |row| {
+                Ok(StockRecord {
+                    item_name: row.get(0)?,
+                    quantity: row.get(1)?,
+                    updated_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(stock)
+    }
 
-        let result = stmt.query_row(params![refund_id], |row| {
-            Ok(RefundRecord {
-                id: row.get(0)?,
-                payment_id: row.get(1)?,
-                order_id: row.get(2)?,
-                amount: row.get(3)?,
-                currency: row.get(4)?,
-                phone: row.get(5)?,
-                reason: row.get(6)?,
-                conversation_id: row.get(7)?,
-                status: RefundStatus::from_str(&row.get::<_, String>(8)?),
-                admin_id: row.get(9)?,
-                created_at: row.get(10)?,
-                completed_at: row.get(11)?,
-            })
-        });
+    /// Names of stock-tracked items with nothing left.
+    pub fn sold_out_items(&self) -> Result<HashSet<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT item_name FROM stock WHERE quantity <= 0")?;
+        let names = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<HashSet<String>, _>>()?;
+        Ok(names)
+    }
+
+    /// Set an item's stock level (starts tracking it if needed).
+    pub fn set_stock(&self, item_name: &str, quantity: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO stock (item_name, quantity) VALUES (?1, ?2)
+             ON CONFLICT(item_name) DO UPDATE SET quantity = excluded.quantity, updated_at = datetime('now')",
+            params![item_name, quantity.max(0)],
+        )?;
+        Ok(())
+    }
+
+    /// Add to (or, with a negative delta, remove from) a tracked item's stock.
+    /// Returns the new level, or `None` if the item isn't stock-tracked.
+    pub fn adjust_stock(&self, item_name: &str, delta: i64) -> Result<Option<i64>> {
+        let conn = self.conn.lock().unwrap();
+        let quantity = conn
+            .query_row(
+                "UPDATE stock SET quantity = MAX(quantity + ?1, 0), updated_at = datetime('now')
+                 WHERE item_name = ?2 RETURNING quantity",
+                params![delta, item_name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(quantity)
+    }
+
+    /// Stop tracking stock for an item.
+    pub fn remove_stock(&self, item_name: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute("DELETE FROM stock WHERE item_name = ?1", params![item_name])?;
+        Ok(changed > 0)
+    }
+
+    // ─── Bookings ────────────────────────────────────────────────────
+
+    /// Book an appointment if capacity allows. Returns the booking ID, or
+    /// `None` if the slot is full.
+    ///
+    /// The capacity check and insert happen under one lock, so two customers
+    /// can't take the last place in a slot at the same time.
+    pub fn create_booking(
+        &self,
+        phone: &str,
+        service: &str,
+        price: f64,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        capacity: u32,
+    ) -> Result<Option<i64>> {
+        let conn = self.conn.lock().unwrap();
+        if !Self::booking_fits(&conn, start, end, capacity, None)? {
+            return Ok(None);
+        }
+        conn.execute(
+            "INSERT INTO bookings (customer_phone, service, price, start_at, end_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                phone,
+                service,
+                price,
+                start.format(bookings::DATETIME_FORMAT).to_string(),
+                end.format(bookings::DATETIME_FORMAT).to_string()
+            ],
+        )?;
+        Ok(Some(conn.last_insert_rowid()))
+    }
+
+    /// Move a confirmed booking to a new time if capacity allows.
+    /// Returns false if the new slot is full or the booking isn't active.
+    pub fn reschedule_booking(
+        &self,
+        id: i64,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        capacity: u32,
+    ) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        if !Self::booking_fits(&conn, start, end, capacity, Some(id))? {
+            return Ok(false);
+        }
+        let changed = conn.execute(
+            "UPDATE bookings SET start_at = ?1, end_at = ?2, updated_at = datetime('now')
+             WHERE id = ?3 AND status = 'confirmed'",
+            params![
+                start.format(bookings::DATETIME_FORMAT).to_string(),
+                end.format(bookings::DATETIME_FORMAT).to_string(),
+                id
+            ],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Cancel a confirmed booking. Returns false if it wasn't active.
+    pub fn cancel_booking(&self, id: i64) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute(
+            "UPDATE bookings SET status = 'cancelled', updated_at = datetime('now')
+             WHERE id = ?1 AND status = 'confirmed'",
+            params![id],
+        )?;
+        Ok(changed > 0)
+    }
 
+    /// Get a booking by ID.
+    pub fn get_booking(&self, id: i64) -> Result<Option<BookingRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM bookings WHERE id = ?1", BOOKING_COLUMNS))?;
+        let result = stmt.query_row(params![id], booking_from_row);
         match result {
-            Ok(refund) => Ok(Some(refund)),
+            Ok(booking) => Ok(Some(booking)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
         }
     }
 
-    /// List refunds with optional status filter.
-    pub fn list_refunds(&self, status: Option<&RefundStatus>) -> Result<Vec<RefundRecord>> {
+    /// All bookings (any status) starting on a date, earliest first.
+    pub fn list_bookings_on(&self, date: NaiveDate) -> Result<Vec<BookingRecord>> {
         let conn = self.conn.lock().unwrap();
-        
-        let refunds = if let Some(s) = status {
-            let mut stmt = conn.prepare(
-                "SELECT id, payment_id, order_id, amount, currency, phone, reason, conversation_id, status, admin_id, created_at, completed_at
-                 FROM refunds WHERE status = ?1 ORDER BY created_at DESC",
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM bookings WHERE start_at LIKE ?1 ORDER BY start_at, id",
+            BOOKING_COLUMNS
+        ))?;
+        let pattern = format!("{}%", date.format(bookings::DATE_FORMAT));
+        let bookings = stmt
+            .query_map(params![pattern], booking_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bookings)
+    }
+
+    /// A customer's confirmed bookings starting at or after `from`.
+    pub fn get_customer_bookings(&self, phone: &str, from: NaiveDateTime) -> Result<Vec<BookingRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM bookings
+             WHERE customer_phone = ?1 AND status = 'confirmed' AND start_at >= ?2
+             ORDER BY start_at",
+            BOOKING_COLUMNS
+        ))?;
+        let bookings = stmt
+            .query_map(
+                params![phone, from.format(bookings::DATETIME_FORMAT).to_string()],
+                booking_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bookings)
+    }
+
+    /// Whether [start, end) stays within capacity, ignoring booking `exclude`.
+    fn booking_fits(
+        conn: &Connection,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        capacity: u32,
+        exclude: Option<i64>,
+    ) -> Result<bool> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM bookings
+             WHERE status = 'confirmed' AND start_at < ?1 AND end_at > ?2 AND id != ?3",
+            BOOKING_COLUMNS
+        ))?;
+        let overlapping = stmt
+            .query_map(
+                params![
+                    end.format(bookings::DATETIME_FORMAT).to_string(),
+                    start.format(bookings::DATETIME_FORMAT).to_string(),
+                    exclude.unwrap_or(-1)
+                ],
+                booking_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bookings::max_concurrent(&overlapping, start, end) < capacity as usize)
+    }
+
+    // ─── Dashboard users ─────────────────────────────────────────────
+
+    /// Number of dashboard users.
+    pub fn count_users(&self) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.query_row("SELECT COUNT(*) FROM dashboard_users", [], |row| row.get(0))?)
+    }
+
+    /// Add a dashboard user. `password_hash` must already be hashed.
+    pub fn create_user(&self, username: &str, password_hash: &str, role: UserRole) -> Result<UserRecord> {
+        let conn = self.conn.lock().unwrap();
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM dashboard_users WHERE username = ?1)",
+            params![username],
+            |row| row.get(0),
+        )?;
+        if exists {
+            anyhow::bail!("User '{}' already exists", username);
+        }
+        conn.execute(
+            "INSERT INTO dashboard_users (username, password_hash, role) VALUES (?1, ?2, ?3)",
+            params![username, password_hash, role.as_str()],
+        )?;
+        let id = conn.last_insert_rowid();
+        Ok(conn.query_row(
+            &format!("SELECT {} FROM dashboard_users u WHERE u.id = ?1", USER_COLUMNS),
+            params![id],
+            user_from_row,
+        )?)
+    }
+
+    /// All dashboard users, oldest first.
+    pub fn list_users(&self) -> Result<Vec<UserRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM dashboard_users u ORDER BY u.id", USER_COLUMNS))?;
+        let users = stmt.query_map([], user_from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(users)
+    }
+
+    /// A user and their password hash, for checking a login.
+    pub fn user_credentials(&self, username: &str) -> Result<Option<(UserRecord, String)>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                &format!(
+                    "SELECT {}, u.password_hash FROM dashboard_users u WHERE u.username = ?1",
+                    USER_COLUMNS
+                ),
+                params![username],
+                |row| Ok((user_from_row(row)?, row.get(5)?)),
+            )
+            .optional()?)
+    }
+
+    /// Replace a user's password hash and sign out their sessions.
+    /// Returns false if there is no such user.
+    pub fn set_user_password(&self, username: &str, password_hash: &str) -> Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let updated = tx.execute(
+            "UPDATE dashboard_users SET password_hash = ?1 WHERE username = ?2",
+            params![password_hash, username],
+        )?;
+        tx.execute(
+            "DELETE FROM dashboard_tokens WHERE kind = ?1
+             AND user_id = (SELECT id FROM dashboard_users WHERE username = ?2)",
+            params![TOKEN_SESSION, username],
+        )?;
+        tx.commit()?;
+        Ok(updated > 0)
+    }
+
+    /// Remove a user along with their sessions and API keys.
+    pub fn delete_user(&self, username: &str) -> Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM dashboard_tokens
+             WHERE user_id = (SELECT id FROM dashboard_users WHERE username = ?1)",
+            params![username],
+        )?;
+        let deleted = tx.execute("DELETE FROM dashboard_users WHERE username = ?1", params![username])?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    /// Store a token (by hash) for a user. Tokens with a `ttl` expire;
+    /// expired sessions are cleared out at the same time.
+    /// Returns the expiry time, if any.
+    pub fn create_token(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        kind: &str,
+        name: Option<&str>,
+        ttl: Option<chrono::Duration>,
+    ) -> Result<Option<String>> {
+        let expires_at = ttl.map(|ttl| (chrono::Utc::now() + ttl).format("%Y-%m-%d %H:%M:%S").to_string());
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM dashboard_tokens WHERE expires_at IS NOT NULL AND expires_at <= datetime('now')",
+            [],
+        )?;
+        conn.execute(
+            "INSERT INTO dashboard_tokens (user_id, token_hash, kind, name, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![user_id, token_hash, kind, name, expires_at],
+        )?;
+        if kind == TOKEN_SESSION {
+            conn.execute(
+                "UPDATE dashboard_users SET last_login_at = datetime('now') WHERE id = ?1",
+                params![user_id],
             )?;
-            stmt.query_map(params![s.as_str()], |row| {
-                Ok(RefundRecord {
-                    id: row.get(0)?,
-                    payment_id: row.get(1)?,
-                    order_id: row.get(2)?,
-                    amount: row.get(3)?,
-                    currency: row.get(4)?,
-                    phone: row.get(5)?,
-                    reason: row.get(6)?,
-                    conversation_id: row.get(7)?,
-                    status: RefundStatus::from_str(&row.get::<_, String>(8)?),
-                    admin_id: row.get(9)?,
-                    created_at: row.get(10)?,
-                    completed_at: row.get(11)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?
-        } else {
-            let mut stmt = conn.prepare(
-                "SELECT id, payment_id, order_id, amount, currency, phone, reason, conversation_id, status, admin_id, created_at, completed_at
-                 FROM refunds ORDER BY created_at DESC",
+        }
+        Ok(expires_at)
+    }
+
+    /// The user a token belongs to, if the token exists and hasn't expired.
+    pub fn user_for_token(&self, token_hash: &str) -> Result<Option<UserRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let user = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM dashboard_tokens t JOIN dashboard_users u ON u.id = t.user_id
+                     WHERE t.token_hash = ?1 AND (t.expires_at IS NULL OR t.expires_at > datetime('now'))",
+                    USER_COLUMNS
+                ),
+                params![token_hash],
+                user_from_row,
+            )
+            .optional()?;
+        if user.is_some() {
+            conn.execute(
+                "UPDATE dashboard_tokens SET last_used_at = datetime('now') WHERE token_hash = ?1",
+                params![token_hash],
             )?;
-            stmt.query_map(params![], |row| {
-                Ok(RefundRecord {
-                    id: row.get(0)?,
-                    payment_id: row.get(1)?,
-                    order_id: row.get(2)?,
-                    amount: row.get(3)?,
-                    currency: row.get(4)?,
-                    phone: row.get(5)?,
-                    reason: row.get(6)?,
-                    conversation_id: row.get(7)?,
-                    status: RefundStatus::from_str(&row.get::<_, String>(8)?),
-                    admin_id: row.get(9)?,
-                    created_at: row.get(10)?,
-                    completed_at: row.get(11)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?
-        };
+        }
+        Ok(user)
+    }
 
-        Ok(refunds)
+    /// Forget a token (logout). Returns false if it didn't exist.
+    pub fn delete_token(&self, token_hash: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute("DELETE FROM dashboard_tokens WHERE token_hash = ?1", params![token_hash])? > 0)
     }
 
-    /// Get refunds for a payment.
-    pub fn get_payment_refunds(&self, payment_id: &str) -> Result<Vec<RefundRecord>> {
+    /// All API keys, newest first.
+    pub fn list_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, payment_id, order_id, amount, currency, phone, reason, conversation_id, status, admin_id, created_at, completed_at
-             FROM refunds WHERE payment_id = ?1 ORDER BY created_at DESC",
+            "SELECT t.id, u.username, u.role, t.name, t.created_at, t.last_used_at
+             FROM dashboard_tokens t JOIN dashboard_users u ON u.id = t.user_id
+             WHERE t.kind = ?1 ORDER BY t.id DESC",
         )?;
-
-        let refunds = stmt
-            .query_map(params![payment_id], |row| {
-                Ok(RefundRecord {
+        let keys = stmt
+            .query_map(params![TOKEN_API_KEY], |row| {
+                Ok(ApiKeyRecord {
                     id: row.get(0)?,
-                    payment_id: row.get(1)?,
-                    order_id: row.get(2)?,
-                    amount: row.get(3)?,
-                    currency: row.get(4)?,
-                    phone: row.get(5)?,
-                    reason: row.get(6)?,
-                    conversation_id: row.get(7)?,
-                    status: RefundStatus::from_str(&row.get::<_, String>(8)?),
-                    admin_id: row.get(9)?,
-                    created_at: row.get(10)?,
-                    completed_at: row.get(11)?,
+                    username: row.get(1)?,
+                    role: UserRole::from_column(&row.get::<_, String>(2)?),
+                    name: row.get(3)?,
+                    created_at: row.get(4)?,
+                    last_used_at: row.get(5)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
+        Ok(keys)
+    }
 
-        Ok(refunds)
+    /// Revoke an API key. Returns false if there is no such key.
+    pub fn revoke_api_key(&self, id: i64) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute(
+            "DELETE FROM dashboard_tokens WHERE id = ?1 AND kind = ?2",
+            params![id, TOKEN_API_KEY],
+        )? > 0)
     }
 }
 
@@ -898,6 +2190,50 @@ mod tests {
         assert_eq!(order.status, OrderStatus::Pending);
     }
 
+    #[test]
+    fn test_order_transitions() {
+        use OrderStatus::*;
+        assert!(Pending.can_transition_to(&Confirmed));
+        assert!(!Pending.can_transition_to(&Preparing));
+        assert!(Confirmed.can_transition_to(&Delivered));
+        assert!(Delivering.can_transition_to(&Cancelled));
+        assert!(!Delivering.can_transition_to(&Preparing));
+        assert!(!Delivered.can_transition_to(&Cancelled));
+        assert!(!Cancelled.can_transition_to(&Confirmed));
+
+        assert_eq!(OrderStatus::parse("delivering"), Some(Delivering));
+        assert_eq!(OrderStatus::parse("shipped"), None);
+
+        let store = test_store();
+        let id = store.create_order("+1", "[]", 10.0, 0.0, 10.0, None).unwrap();
+        let err = store.transition_order(id, &Preparing, "dashboard:amina", None).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<InvalidTransition>(),
+            Some(&InvalidTransition { from: Pending, to: Preparing })
+        );
+        let order = store.transition_order(id, &Confirmed, "mpesa", Some("QK12")).unwrap().unwrap();
+        assert_eq!(order.status, Confirmed);
+        store.transition_order(id, &Delivered, "dashboard:amina", None).unwrap();
+        assert!(store.transition_order(id, &Pending, "dashboard:amina", None).is_err());
+        assert!(store.transition_order(999, &Confirmed, "mpesa", None).unwrap().is_none());
+
+        // Every accepted change is in the history, rejected ones aren't
+        let events = store.order_events(id).unwrap();
+        let steps: Vec<_> = events
+            .iter()
+            .map(|e| (e.from_status.clone(), e.to_status.clone(), e.actor.as_str()))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (None, Pending, "customer:+1"),
+                (Some(Pending), Confirmed, "mpesa"),
+                (Some(Confirmed), Delivered, "dashboard:amina"),
+            ]
+        );
+        assert_eq!(events[1].note.as_deref(), Some("QK12"));
+    }
+
     #[test]
     fn test_voucher_lifecycle() {
         let store = test_store();
@@ -931,4 +2267,259 @@ mod tests {
         let state = store.get_conversation_state(phone).unwrap().unwrap();
         assert_eq!(state, r#""Idle""#);
     }
+
+    #[test]
+    fn test_menu_items() {
+        let store = test_store();
+        let menu: Vec<MenuItem> = serde_yaml::from_str(
+            r#"
+- name: "Kota"
+  price: 35
+  stock: 0
+- name: "Chips"
+  price: 20
+- name: "Secret"
+  price: 99
+  available: false
+"#,
+        )
+        .unwrap();
+
+        assert!(store.seed_menu(&menu).unwrap());
+        // Already seeded: config doesn't overwrite edits
+        assert!(!store.seed_menu(&menu[..1]).unwrap());
+        assert_eq!(store.list_menu_items().unwrap().len(), 3);
+
+        // Sold out and hidden items aren't offered
+        let names: Vec<String> = store.available_menu().unwrap().into_iter().map(|m| m.name).collect();
+        assert_eq!(names, vec!["Chips"]);
+
+        // Renaming keeps the stock level
+        let kota = store.find_menu_item("kota").unwrap().unwrap();
+        let update = MenuItemUpdate {
+            name: Some("Kota Deluxe".to_string()),
+            price: Some(40.0),
+            ..Default::default()
+        };
+        assert!(store.update_menu_item(kota.id, &update).unwrap());
+        let kota = store.get_menu_item(kota.id).unwrap().unwrap();
+        assert_eq!((kota.name.as_str(), kota.price, kota.stock), ("Kota Deluxe", 40.0, Some(0)));
+
+        let soda = MenuItem {
+            name: "Soda".to_string(),
+            ..menu[1].clone()
+        };
+        let id = store.create_menu_item(&soda).unwrap();
+        assert_eq!(store.get_menu_item(id).unwrap().unwrap().position, 3);
+        assert!(store.delete_menu_item(id).unwrap());
+        assert!(!store.delete_menu_item(id).unwrap());
+    }
+
+    #[test]
+    fn test_stock_tracking() {
+        let store = test_store();
+        store.set_stock("Kota", 3).unwrap();
+        let items = |qty: u32| format!(r#"[{{"name":"Kota","price":35,"quantity":{}}},{{"name":"Chips","price":20,"quantity":5}}]"#, qty);
+
+        // Untracked items (Chips) never run out
+        let id = store.create_order("+1", &items(2), 135.0, 0.0, 135.0, None).unwrap();
+        assert_eq!(store.get_stock("Kota").unwrap(), Some(1));
+        assert_eq!(store.get_stock("Chips").unwrap(), None);
+
+        // Not enough left: nothing is inserted or decremented
+        let err = store.create_order("+2", &items(2), 170.0, 0.0, 170.0, None).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<OutOfStock>(),
+            Some(&OutOfStock { item: "Kota".to_string(), available: 1 })
+        );
+        assert!(store.get_order(id + 1).unwrap().is_none());
+        assert_eq!(store.get_stock("Kota").unwrap(), Some(1));
+
+        store.create_order("+2", &items(1), 135.0, 0.0, 135.0, None).unwrap();
+        assert!(store.sold_out_items().unwrap().contains("Kota"));
+
+        // Cancelling restores stock, once
+        store.transition_order(id, &OrderStatus::Cancelled, "test", None).unwrap();
+        assert!(store.transition_order(id, &OrderStatus::Cancelled, "test", None).is_err());
+        assert_eq!(store.get_stock("Kota").unwrap(), Some(2));
+
+        assert_eq!(store.adjust_stock("Kota", -5).unwrap(), Some(0));
+        assert_eq!(store.adjust_stock("Chips", 5).unwrap(), None);
+    }
+
+    #[test]
+    fn test_one_active_refund_per_payment() {
+        let store = test_store();
+        let order = store.create_order("+1", "[]", 10.0, 0.0, 10.0, None).unwrap();
+        store.create_payment("PAY-1", order, 10.0, "KES", "mpesa", "+1", "Order #1").unwrap();
+
+        store.create_refund("REF-a", "PAY-1", order, 10.0, "KES", "+1", None, None).unwrap();
+        let err = store.create_refund("REF-b", "PAY-1", order, 10.0, "KES", "+1", None, None).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<RefundInProgress>(),
+            Some(&RefundInProgress {
+                payment_id: "PAY-1".to_string(),
+                refund_id: "REF-a".to_string(),
+            })
+        );
+
+        // A failed refund can be tried again
+        store.update_refund_status("REF-a", "failed", None).unwrap();
+        store.create_refund("REF-b", "PAY-1", order, 10.0, "KES", "+1", None, None).unwrap();
+        assert_eq!(store.get_payment_refunds("PAY-1").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_payment_and_refund_values_are_errors() {
+        let store = test_store();
+        let order = store.create_order("+1", "[]", 10.0, 0.0, 10.0, None).unwrap();
+        store.create_payment("PAY-1", order, 10.0, "KES", "mpesa", "+1", "Order #1").unwrap();
+        assert_eq!(store.get_customer_payments("+1").unwrap()[0].method, PaymentMethod::MPesa);
+
+        store.create_payment("PAY-2", order, 10.0, "KES", "bitcoin", "+1", "Order #1").unwrap();
+        assert!(store.get_payment("PAY-2").is_err());
+        assert!(store.get_customer_payments("+1").is_err());
+
+        store.update_payment_status("PAY-1", "refunded", None).unwrap();
+        assert!(store.get_payment("PAY-1").is_err());
+
+        // Refunds too: an unknown status isn't quietly read as pending
+        store.create_refund("REF-1", "PAY-1", order, 10.0, "KES", "+1", None, None).unwrap();
+        store.update_refund_status("REF-1", "processing", Some("AG_1")).unwrap();
+        assert_eq!(store.get_refund_by_conversation_id("AG_1").unwrap().unwrap().status, RefundStatus::Processing);
+        assert_eq!(store.list_refunds(Some(&RefundStatus::Processing)).unwrap().len(), 1);
+        assert!(store.list_refunds(Some(&RefundStatus::Pending)).unwrap().is_empty());
+        store.update_refund_status("REF-1", "paid", None).unwrap();
+        assert!(store.get_refund("REF-1").is_err());
+        assert!(store.get_refund_by_conversation_id("AG_1").is_err());
+        assert!(store.list_refunds(None).is_err());
+        assert!(store.get_payment_refunds("PAY-1").is_err());
+    }
+
+    #[test]
+    fn test_booking_capacity() {
+        let store = test_store();
+        let at = |h: u32, m: u32| {
+            NaiveDate::from_ymd_opt(2026, 3, 2).unwrap().and_hms_opt(h, m, 0).unwrap()
+        };
+
+        // Capacity 2: two overlapping bookings fit, a third doesn't
+        let a = store.create_booking("+1", "Cut", 25.0, at(10, 0), at(11, 0), 2).unwrap();
+        let b = store.create_booking("+2", "Cut", 25.0, at(10, 30), at(11, 30), 2).unwrap();
+        assert!(a.is_some() && b.is_some());
+        assert!(store.create_booking("+3", "Cut", 25.0, at(10, 30), at(11, 0), 2).unwrap().is_none());
+
+        // Back-to-back is fine
+        assert!(store.create_booking("+3", "Cut", 25.0, at(11, 30), at(12, 0), 2).unwrap().is_some());
+
+        // Cancelling frees the place
+        assert!(store.cancel_booking(a.unwrap()).unwrap());
+        assert!(!store.cancel_booking(a.unwrap()).unwrap());
+        assert!(store.create_booking("+3", "Cut", 25.0, at(10, 30), at(11, 0), 2).unwrap().is_some());
+
+        assert_eq!(store.list_bookings_on(at(0, 0).date()).unwrap().len(), 4);
+        assert_eq!(store.get_customer_bookings("+2", at(9, 0)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_reschedule_booking_ignores_itself() {
+        let store = test_store();
+        let at = |h: u32| NaiveDate::from_ymd_opt(2026, 3, 2).unwrap().and_hms_opt(h, 0, 0).unwrap();
+
+        let id = store.create_booking("+1", "Cut", 25.0, at(10), at(12), 1).unwrap().unwrap();
+        assert!(store.reschedule_booking(id, at(11), at(13), 1).unwrap());
+
+        let booking = store.get_booking(id).unwrap().unwrap();
+        assert_eq!(booking.start_at, "2026-03-02 11:00");
+        assert_eq!(booking.status, BookingStatus::Confirmed);
+
+        // An unknown status is an error, not a confirmed booking
+        store.conn.lock().unwrap().execute("UPDATE bookings SET status = 'no-show'", []).unwrap();
+        assert!(store.get_booking(id).is_err());
+    }
+
+    #[test]
+    fn test_dashboard_users_and_tokens() {
+        let store = test_store();
+        let owner = store.create_user("Amina", "hash-1", UserRole::Owner).unwrap();
+        assert!(store.create_user("amina", "hash-2", UserRole::Staff).is_err());
+        let staff = store.create_user("joe", "hash-3", UserRole::Staff).unwrap();
+        assert_eq!(store.count_users().unwrap(), 2);
+
+        let (user, hash) = store.user_credentials("AMINA").unwrap().unwrap();
+        assert_eq!((user.id, hash.as_str(), user.role), (owner.id, "hash-1", UserRole::Owner));
+
+        // Sessions expire, API keys don't
+        let ttl = Some(chrono::Duration::hours(1));
+        assert!(store.create_token(owner.id, "s1", TOKEN_SESSION, None, ttl).unwrap().is_some());
+        store.create_token(staff.id, "old", TOKEN_SESSION, None, Some(chrono::Duration::hours(-1))).unwrap();
+        store.create_token(staff.id, "k1", TOKEN_API_KEY, Some("till"), None).unwrap();
+        assert_eq!(store.user_for_token("s1").unwrap().unwrap().username, "Amina");
+        assert!(store.user_for_token("old").unwrap().is_none());
+        assert_eq!(store.user_for_token("k1").unwrap().unwrap().role, UserRole::Staff);
+
+        let keys = store.list_api_keys().unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].name.as_deref(), Some("till"));
+        assert!(keys[0].last_used_at.is_some());
+
+        // A new password signs out sessions but keeps API keys
+        assert!(store.set_user_password("amina", "hash-4").unwrap());
+        assert!(store.user_for_token("s1").unwrap().is_none());
+
+        assert!(store.delete_user("joe").unwrap());
+        assert!(store.user_for_token("k1").unwrap().is_none());
+        assert!(store.list_api_keys().unwrap().is_empty());
+        assert!(!store.revoke_api_key(keys[0].id).unwrap());
+        assert_eq!(store.count_users().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_customers() {
+        let store = test_store();
+        store.touch_customer("254700000001", Some("Wanjiru")).unwrap();
+        store.touch_customer("254700000002", None).unwrap();
+
+        // Orders feed the saved addresses and lifetime value
+        let first = store.create_order("254700000001", "[]", 90.0, 10.0, 100.0, None).unwrap();
+        store.set_order_location(first, "12 Main Street").unwrap();
+        store.transition_order(first, &OrderStatus::Confirmed, "test", None).unwrap();
+        let second = store.create_order("254700000001", "[]", 40.0, 10.0, 50.0, None).unwrap();
+        store.set_order_location(second, "Office").unwrap();
+        store.transition_order(second, &OrderStatus::Cancelled, "test", None).unwrap();
+        store.set_order_location(first, "12 Main Street").unwrap();
+
+        let customer = store.get_customer("254700000001").unwrap().unwrap();
+        assert_eq!(customer.name.as_deref(), Some("Wanjiru"));
+        assert_eq!(customer.addresses, vec!["12 Main Street", "Office"]);
+        assert_eq!(customer.order_count, 2);
+        assert_eq!(customer.lifetime_value, 100.0);
+
+        // A name set from the dashboard isn't replaced by the WhatsApp one
+        let update = CustomerUpdate {
+            name: Some("Wanjiru K.".to_string()),
+            tags: Some(vec!["VIP".to_string(), " vip ".to_string(), "wholesale".to_string()]),
+            marketing_opt_in: Some(true),
+            ..Default::default()
+        };
+        assert!(store.update_customer("254700000001", &update).unwrap());
+        store.touch_customer("254700000001", Some("Wanjiru")).unwrap();
+        let customer = store.get_customer("254700000001").unwrap().unwrap();
+        assert_eq!(customer.name.as_deref(), Some("Wanjiru K."));
+        assert_eq!(customer.tags, vec!["VIP", "wholesale"]);
+        assert!(customer.marketing_opt_in && customer.marketing_opt_in_at.is_some());
+
+        assert_eq!(store.customer_language("254700000001").unwrap(), None);
+        let language = CustomerUpdate {
+            language: Some("sw".to_string()),
+            ..Default::default()
+        };
+        store.update_customer("254700000001", &language).unwrap();
+        assert_eq!(store.customer_language("254700000001").unwrap().as_deref(), Some("sw"));
+
+        assert_eq!(store.list_customers(Some("wanj"), None, 10).unwrap().len(), 1);
+        assert_eq!(store.list_customers(Some("2547000"), None, 10).unwrap().len(), 2);
+        assert_eq!(store.list_customers(None, Some("vip"), 10).unwrap()[0].phone, "254700000001");
+        assert!(!store.update_customer("254799999999", &update).unwrap());
+    }
 }
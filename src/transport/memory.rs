@@ -0,0 +1,126 @@
+//! In-memory transport for tests and local simulation.
+//!
+//! Records every outbound message instead of delivering it, and can replay a
+//! scripted list of inbound messages when run. It sends text only unless
+//! created `with_rich_messages`, so tests see the same fallbacks a
+//! text-only transport would.
+
+use super::{InboundMessage, InboundSender, MessageKind, MessagingTransport, OutgoingMessage, phone_digits};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Mutex;
+
+/// A message the bot tried to send.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboundMessage {
+    /// Chat address, or digits-only phone number for `send_to_phone`
+    pub to: String,
+    /// The text, or a rich message's text fallback
+    pub text: String,
+    /// The message itself, if it wasn't plain text
+    pub rich: Option<OutgoingMessage>,
+}
+
+/// Transport that keeps outbound messages in memory.
+#[derive(Default)]
+pub struct MemoryTransport {
+    script: Mutex<Vec<InboundMessage>>,
+    outbox: Mutex<Vec<OutboundMessage>>,
+    rich: bool,
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inbound messages to deliver (in order) when the transport is run.
+    pub fn with_script(self, script: Vec<InboundMessage>) -> Self {
+        *self.script.lock().unwrap() = script;
+        self
+    }
+
+    /// Accept every kind of message, not just text.
+    pub fn with_rich_messages(mut self) -> Self {
+        self.rich = true;
+        self
+    }
+
+    /// All messages sent so far.
+    pub fn outbox(&self) -> Vec<OutboundMessage> {
+        self.outbox.lock().unwrap().clone()
+    }
+
+    /// Drain and return all messages sent so far.
+    pub fn take_outbox(&self) -> Vec<OutboundMessage> {
+        std::mem::take(&mut *self.outbox.lock().unwrap())
+    }
+
+    /// Messages sent to a given chat or phone number.
+    pub fn sent_to(&self, to: &str) -> Vec<String> {
+        let digits = phone_digits(to);
+        self.outbox
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| m.to == to || (!digits.is_empty() && m.to == digits))
+            .map(|m| m.text.clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl MessagingTransport for MemoryTransport {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn send_text(&self, chat: &str, text: &str) -> Result<()> {
+        self.outbox.lock().unwrap().push(OutboundMessage {
+            to: chat.to_string(),
+            text: text.to_string(),
+            rich: None,
+        });
+        Ok(())
+    }
+
+    async fn send_to_phone(&self, phone: &str, text: &str) -> Result<()> {
+        let clean_number = phone_digits(phone);
+        if clean_number.is_empty() {
+            anyhow::bail!("Invalid phone number: {}", phone);
+        }
+        self.outbox.lock().unwrap().push(OutboundMessage {
+            to: clean_number,
+            text: text.to_string(),
+            rich: None,
+        });
+        Ok(())
+    }
+
+    fn supports(&self, kind: MessageKind) -> bool {
+        self.rich || kind == MessageKind::Text
+    }
+
+    async fn send_rich(&self, chat: &str, message: &OutgoingMessage) -> Result<()> {
+        if !self.supports(message.kind()) {
+            anyhow::bail!("Rich messages are turned off");
+        }
+        self.outbox.lock().unwrap().push(OutboundMessage {
+            to: chat.to_string(),
+            text: message.fallback_text(),
+            rich: Some(message.clone()),
+        });
+        Ok(())
+    }
+
+    async fn run(&self, inbound: InboundSender) -> Result<()> {
+        let script = std::mem::take(&mut *self.script.lock().unwrap());
+        for msg in script {
+            inbound
+                .send(msg)
+                .await
+                .map_err(|_| anyhow::anyhow!("Inbound channel closed"))?;
+        }
+        Ok(())
+    }
+}
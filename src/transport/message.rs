@@ -0,0 +1,195 @@
+//! Outgoing message types.
+//!
+//! Handlers can answer with more than text: images, documents, button and
+//! list messages and location pins. Not every transport can send every
+//! kind (see `MessagingTransport::supports`), so each one also has a plain
+//! text fallback — a list becomes a numbered menu, a location becomes a map
+//! link — that `send_message` uses instead.
+
+use std::path::PathBuf;
+
+/// Something the bot sends to a chat.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutgoingMessage {
+    Text(String),
+    Image(ImageMessage),
+    Document(DocumentMessage),
+    Buttons(ButtonsMessage),
+    List(ListMessage),
+    Location(LocationPin),
+}
+
+/// The kinds of `OutgoingMessage`, for `MessagingTransport::supports`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    Text,
+    Image,
+    Document,
+    Buttons,
+    List,
+    Location,
+}
+
+/// A picture, e.g. a menu item's photo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageMessage {
+    /// Image file; relative paths are relative to the bot's project directory
+    pub path: PathBuf,
+    pub caption: Option<String>,
+}
+
+/// A file attachment, e.g. a PDF receipt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentMessage {
+    pub data: Vec<u8>,
+    pub file_name: String,
+    pub mime_type: String,
+    pub caption: Option<String>,
+    /// Sent instead of the file where documents aren't supported
+    pub fallback: String,
+}
+
+/// A message with up to three quick-reply buttons.
+///
+/// `text` should still say how to answer by typing: the buttons are a
+/// shortcut, and the fallback is `text` on its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ButtonsMessage {
+    pub text: String,
+    pub buttons: Vec<Choice>,
+}
+
+/// A message with a button that opens a list of choices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListMessage {
+    pub text: String,
+    /// Label of the button that opens the list
+    pub button: String,
+    pub rows: Vec<Choice>,
+    /// Short text under the message
+    pub footer: Option<String>,
+}
+
+/// A button or list row. Picking it sends `id` back as the customer's
+/// message, so ids are what they would otherwise type ("1", "YES").
+#[derive(Debug, Clone, PartialEq)]
+pub struct Choice {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+}
+
+/// A pin on the map, e.g. the business's location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationPin {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub name: Option<String>,
+    pub address: Option<String>,
+}
+
+impl OutgoingMessage {
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            OutgoingMessage::Text(_) => MessageKind::Text,
+            OutgoingMessage::Image(_) => MessageKind::Image,
+            OutgoingMessage::Document(_) => MessageKind::Document,
+            OutgoingMessage::Buttons(_) => MessageKind::Buttons,
+            OutgoingMessage::List(_) => MessageKind::List,
+            OutgoingMessage::Location(_) => MessageKind::Location,
+        }
+    }
+
+    /// The message as plain text, for transports that can't send its kind.
+    /// Empty for an image without a caption.
+    pub fn fallback_text(&self) -> String {
+        match self {
+            OutgoingMessage::Text(text) => text.clone(),
+            OutgoingMessage::Image(image) => image.caption.clone().unwrap_or_default(),
+            OutgoingMessage::Document(document) => document.fallback.clone(),
+            OutgoingMessage::Buttons(buttons) => buttons.text.clone(),
+            OutgoingMessage::List(list) => {
+                let rows: Vec<String> = list.rows.iter().map(Choice::fallback_line).collect();
+                let mut parts = vec![list.text.clone(), rows.join("\n")];
+                parts.extend(list.footer.clone());
+                parts.join("\n\n")
+            }
+            OutgoingMessage::Location(pin) => {
+                let mut lines: Vec<String> = Vec::new();
+                if let Some(ref name) = pin.name {
+                    lines.push(format!("📍 *{}*", name));
+                }
+                lines.extend(pin.address.clone());
+                lines.push(pin.map_link());
+                lines.join("\n")
+            }
+        }
+    }
+}
+
+impl Choice {
+    pub fn new(id: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            description: None,
+        }
+    }
+
+    /// "2. Kiswahili", as in a numbered text menu.
+    fn fallback_line(&self) -> String {
+        match self.description {
+            Some(ref description) => format!("{}. {} — {}", self.id, self.title, description),
+            None => format!("{}. {}", self.id, self.title),
+        }
+    }
+}
+
+impl LocationPin {
+    /// A Google Maps link to the pin.
+    pub fn map_link(&self) -> String {
+        format!("https://maps.google.com/?q={},{}", self.latitude, self.longitude)
+    }
+}
+
+impl From<String> for OutgoingMessage {
+    fn from(text: String) -> Self {
+        OutgoingMessage::Text(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_text() {
+        let list = OutgoingMessage::List(ListMessage {
+            text: "🌐 Choose your language".to_string(),
+            button: "Choose".to_string(),
+            rows: vec![Choice::new("1", "English"), Choice::new("2", "Kiswahili")],
+            footer: Some("Reply *0* to go back".to_string()),
+        });
+        assert_eq!(
+            list.fallback_text(),
+            "🌐 Choose your language\n\n1. English\n2. Kiswahili\n\nReply *0* to go back"
+        );
+
+        let pin = OutgoingMessage::Location(LocationPin {
+            latitude: -1.2921,
+            longitude: 36.8219,
+            name: Some("Mama's Kitchen".to_string()),
+            address: Some("Moi Avenue, Nairobi".to_string()),
+        });
+        assert_eq!(
+            pin.fallback_text(),
+            "📍 *Mama's Kitchen*\nMoi Avenue, Nairobi\nhttps://maps.google.com/?q=-1.2921,36.8219"
+        );
+
+        let image = OutgoingMessage::Image(ImageMessage {
+            path: "images/kota.jpg".into(),
+            caption: None,
+        });
+        assert_eq!(image.fallback_text(), "");
+    }
+}
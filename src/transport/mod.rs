@@ -0,0 +1,96 @@
+//! Messaging transports.
+//!
+//! The bot engine and handlers never talk to WhatsApp directly. They go
+//! through a `MessagingTransport`, which knows how to deliver text (and,
+//! where it can, richer messages — see `message`) to a chat or phone number
+//! and how to feed inbound messages back to the engine.
+//!
+//! Implementations:
+//! - `WhatsAppTransport` — the production transport built on whatsapp-rust
+//! - `MemoryTransport` — records outbound messages, for tests and simulation
+
+pub mod memory;
+pub mod message;
+pub mod whatsapp;
+
+pub use memory::{MemoryTransport, OutboundMessage};
+pub use message::{
+    ButtonsMessage, Choice, DocumentMessage, ImageMessage, ListMessage, LocationPin, MessageKind, OutgoingMessage,
+};
+pub use whatsapp::WhatsAppTransport;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+/// A transport-agnostic inbound message.
+#[derive(Debug, Clone, Default)]
+pub struct InboundMessage {
+    /// Sender identifier (e.g. "14152657184@s.whatsapp.net")
+    pub sender: String,
+    /// Sender's display name, if the transport knows it
+    pub sender_name: Option<String>,
+    /// Chat address to reply to
+    pub chat: String,
+    /// Extracted text content (or caption)
+    pub text: String,
+    /// Whether the message was sent by the bot's own account
+    pub is_from_me: bool,
+    /// Whether this is a group message
+    pub is_group: bool,
+    /// Whether the message includes a location
+    pub has_location: bool,
+    /// Extracted location text (address or coordinates)
+    pub location_text: Option<String>,
+}
+
+/// Channel used by transports to hand inbound messages to the engine.
+pub type InboundSender = mpsc::Sender<InboundMessage>;
+
+/// A channel the bot can send and receive messages on.
+#[async_trait]
+pub trait MessagingTransport: Send + Sync {
+    /// Short transport name for logs (e.g. "whatsapp").
+    fn name(&self) -> &'static str;
+
+    /// Send a text message to a chat address as received in `InboundMessage::chat`.
+    async fn send_text(&self, chat: &str, text: &str) -> Result<()>;
+
+    /// Send a text message to a phone number (any formatting, e.g. "+254 712 345678").
+    async fn send_to_phone(&self, phone: &str, text: &str) -> Result<()>;
+
+    /// Whether `send_rich` can send messages of this kind. Text only by default.
+    fn supports(&self, kind: MessageKind) -> bool {
+        kind == MessageKind::Text
+    }
+
+    /// Send a message of a kind `supports` accepts to a chat address.
+    async fn send_rich(&self, _chat: &str, message: &OutgoingMessage) -> Result<()> {
+        anyhow::bail!("The {} transport can't send {:?} messages", self.name(), message.kind())
+    }
+
+    /// Connect and forward inbound messages to `inbound` until the transport
+    /// shuts down.
+    async fn run(&self, inbound: InboundSender) -> Result<()>;
+}
+
+/// Send `message` to `chat`, as text if the transport can't send its kind
+/// or fails to (a missing image file, say).
+pub async fn send_message(transport: &dyn MessagingTransport, chat: &str, message: &OutgoingMessage) -> Result<()> {
+    if message.kind() != MessageKind::Text && transport.supports(message.kind()) {
+        match transport.send_rich(chat, message).await {
+            Ok(()) => return Ok(()),
+            Err(e) => log::warn!("⚠️ Sending {:?} message failed, sending text instead: {:#}", message.kind(), e),
+        }
+    }
+    let text = message.fallback_text();
+    if text.is_empty() {
+        return Ok(());
+    }
+    transport.send_text(chat, &text).await
+}
+
+/// Strip everything except ASCII digits from a phone number or JID.
+pub fn phone_digits(phone: &str) -> String {
+    phone.chars().filter(|c| c.is_ascii_digit()).collect()
+}
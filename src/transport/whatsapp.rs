@@ -0,0 +1,438 @@
+//! WhatsApp transport — wraps whatsapp-rust.
+//!
+//! Owns the WhatsApp connection lifecycle (QR / pair code login, session
+//! storage in `data/whatsapp.db`) and converts whatsapp-rust events into
+//! `InboundMessage`s for the engine.
+//!
+//! Images, documents and location pins are always sent as such. Button and
+//! list messages only show up for some accounts, so they are sent only when
+//! `whatsapp.interactive` is on; a tap on one comes back as a message
+//! containing the choice's id.
+
+use super::{InboundMessage, InboundSender, MessageKind, MessagingTransport, OutgoingMessage, phone_digits};
+use anyhow::Result;
+use async_trait::async_trait;
+use log::{error, info, warn};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use wacore::download::MediaType;
+use wacore_binary::jid::Jid;
+use whatsapp_rust::bot::Bot;
+use whatsapp_rust::client::Client;
+use whatsapp_rust::pair_code::PairCodeOptions;
+use whatsapp_rust::types::events::Event;
+use whatsapp_rust_sqlite_storage::SqliteStore as WaSqliteStore;
+use whatsapp_rust_tokio_transport::TokioWebSocketTransportFactory;
+use whatsapp_rust_ureq_http_client::UreqHttpClient;
+
+/// WhatsApp transport backed by a whatsapp-rust client.
+///
+/// The client only exists once the bot has connected, so sends made before
+/// that (e.g. from a dashboard webhook) fail with a "not connected" error.
+pub struct WhatsAppTransport {
+    project_dir: PathBuf,
+    phone_number: Option<String>,
+    interactive: bool,
+    client: Arc<RwLock<Option<Arc<Client>>>>,
+}
+
+impl WhatsAppTransport {
+    /// Create a transport that stores its session under `<project_dir>/data`.
+    pub fn new(project_dir: &Path) -> Self {
+        Self {
+            project_dir: project_dir.to_path_buf(),
+            phone_number: None,
+            interactive: false,
+            client: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Set a phone number for pair code authentication (alternative to QR scanning).
+    pub fn with_phone_number(mut self, phone: String) -> Self {
+        self.phone_number = Some(phone);
+        self
+    }
+
+    /// Send button and list messages instead of their numbered text fallbacks.
+    pub fn with_interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Get the connected client, if any.
+    async fn client(&self) -> Result<Arc<Client>> {
+        self.client
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("WhatsApp is not connected"))
+    }
+
+    async fn send_to_jid(&self, jid: Jid, text: &str) -> Result<()> {
+        use waproto::whatsapp as wa;
+
+        let message = wa::Message {
+            extended_text_message: Some(Box::new(wa::message::ExtendedTextMessage {
+                text: Some(text.to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        self.send_wa_message(jid, message).await
+    }
+
+    async fn send_wa_message(&self, jid: Jid, message: waproto::whatsapp::Message) -> Result<()> {
+        self.client()
+            .await?
+            .send_message(jid, message)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to send message: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Build the whatsapp-rust message for `message`, uploading any media.
+    async fn wa_message(&self, message: &OutgoingMessage) -> Result<waproto::whatsapp::Message> {
+        use waproto::whatsapp as wa;
+        use wa::message::{buttons_message, list_message};
+
+        let wa_message = match message {
+            OutgoingMessage::Text(text) => wa::Message {
+                extended_text_message: Some(Box::new(wa::message::ExtendedTextMessage {
+                    text: Some(text.clone()),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+            OutgoingMessage::Image(image) => {
+                let path = self.project_dir.join(&image.path);
+                let mimetype = image_mime_type(&path)?;
+                let data = tokio::fs::read(&path)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Can't read {}: {}", path.display(), e))?;
+                let upload = self.client().await?.upload(data, MediaType::Image).await?;
+                wa::Message {
+                    image_message: Some(Box::new(wa::message::ImageMessage {
+                        mimetype: Some(mimetype.to_string()),
+                        caption: image.caption.clone(),
+                        url: Some(upload.url),
+                        direct_path: Some(upload.direct_path),
+                        media_key: Some(upload.media_key),
+                        file_enc_sha256: Some(upload.file_enc_sha256),
+                        file_sha256: Some(upload.file_sha256),
+                        file_length: Some(upload.file_length),
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                }
+            }
+            OutgoingMessage::Document(document) => {
+                let upload = self
+                    .client()
+                    .await?
+                    .upload(document.data.clone(), MediaType::Document)
+                    .await?;
+                wa::Message {
+                    document_message: Some(Box::new(wa::message::DocumentMessage {
+                        mimetype: Some(document.mime_type.clone()),
+                        title: Some(document.file_name.clone()),
+                        file_name: Some(document.file_name.clone()),
+                        caption: document.caption.clone(),
+                        url: Some(upload.url),
+                        direct_path: Some(upload.direct_path),
+                        media_key: Some(upload.media_key),
+                        file_enc_sha256: Some(upload.file_enc_sha256),
+                        file_sha256: Some(upload.file_sha256),
+                        file_length: Some(upload.file_length),
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                }
+            }
+            OutgoingMessage::Buttons(buttons) => wa::Message {
+                buttons_message: Some(Box::new(wa::message::ButtonsMessage {
+                    content_text: Some(buttons.text.clone()),
+                    header_type: Some(buttons_message::HeaderType::Empty as i32),
+                    buttons: buttons
+                        .buttons
+                        .iter()
+                        .map(|choice| buttons_message::Button {
+                            button_id: Some(choice.id.clone()),
+                            button_text: Some(buttons_message::button::ButtonText {
+                                display_text: Some(choice.title.clone()),
+                            }),
+                            r#type: Some(buttons_message::button::Type::Response as i32),
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+            OutgoingMessage::List(list) => wa::Message {
+                list_message: Some(Box::new(wa::message::ListMessage {
+                    description: Some(list.text.clone()),
+                    button_text: Some(list.button.clone()),
+                    footer_text: list.footer.clone(),
+                    list_type: Some(list_message::ListType::SingleSelect as i32),
+                    sections: vec![list_message::Section {
+                        title: None,
+                        rows: list
+                            .rows
+                            .iter()
+                            .map(|choice| list_message::Row {
+                                row_id: Some(choice.id.clone()),
+                                title: Some(choice.title.clone()),
+                                description: choice.description.clone(),
+                            })
+                            .collect(),
+                    }],
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+            OutgoingMessage::Location(pin) => wa::Message {
+                location_message: Some(Box::new(wa::message::LocationMessage {
+                    degrees_latitude: Some(pin.latitude),
+                    degrees_longitude: Some(pin.longitude),
+                    name: pin.name.clone(),
+                    address: pin.address.clone(),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+        };
+        Ok(wa_message)
+    }
+}
+
+/// MIME type of an image file, from its extension.
+fn image_mime_type(path: &Path) -> Result<&'static str> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match extension.as_str() {
+        "jpg" | "jpeg" => Ok("image/jpeg"),
+        "png" => Ok("image/png"),
+        "webp" => Ok("image/webp"),
+        _ => anyhow::bail!("Unsupported image type: {}", path.display()),
+    }
+}
+
+#[async_trait]
+impl MessagingTransport for WhatsAppTransport {
+    fn name(&self) -> &'static str {
+        "whatsapp"
+    }
+
+    async fn send_text(&self, chat: &str, text: &str) -> Result<()> {
+        let jid: Jid = chat
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid chat JID: {}", chat))?;
+        self.send_to_jid(jid, text).await
+    }
+
+    async fn send_to_phone(&self, phone: &str, text: &str) -> Result<()> {
+        let clean_number = phone_digits(phone);
+        if clean_number.is_empty() {
+            anyhow::bail!("Invalid phone number: {}", phone);
+        }
+        self.send_to_jid(Jid::pn(&clean_number), text).await
+    }
+
+    fn supports(&self, kind: MessageKind) -> bool {
+        match kind {
+            MessageKind::Text | MessageKind::Image | MessageKind::Document | MessageKind::Location => true,
+            MessageKind::Buttons | MessageKind::List => self.interactive,
+        }
+    }
+
+    async fn send_rich(&self, chat: &str, message: &OutgoingMessage) -> Result<()> {
+        let jid: Jid = chat
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid chat JID: {}", chat))?;
+        let message = self.wa_message(message).await?;
+        self.send_wa_message(jid, message).await
+    }
+
+    async fn run(&self, inbound: InboundSender) -> Result<()> {
+        info!("Initializing WhatsApp connection...");
+
+        // Set up the whatsapp-rust storage backend
+        let wa_db_path = self
+            .project_dir
+            .join("data")
+            .join("whatsapp.db")
+            .to_string_lossy()
+            .to_string();
+
+        let backend = Arc::new(WaSqliteStore::new(&wa_db_path).await?)
+            as Arc<dyn whatsapp_rust::store::traits::Backend>;
+
+        let client_slot = self.client.clone();
+
+        let mut builder = Bot::builder()
+            .with_backend(backend)
+            .with_transport_factory(TokioWebSocketTransportFactory::new())
+            .with_http_client(UreqHttpClient::new());
+
+        // If phone number is provided, use pair code authentication instead of QR
+        if let Some(ref phone) = self.phone_number {
+            info!("📱 Using pair code authentication for {}", phone);
+            builder = builder.with_pair_code(PairCodeOptions {
+                phone_number: phone.clone(),
+                ..Default::default()
+            });
+        }
+
+        let mut bot = builder
+            .on_event(move |event, client| {
+                let client_slot = client_slot.clone();
+                let inbound = inbound.clone();
+                async move {
+                    match event {
+                        Event::PairingQrCode { code, timeout } => {
+                            println!("\n📱 Scan this QR code with WhatsApp:");
+                            // Generate QR code for terminal display
+                            if let Ok(qr) = qrcode::QrCode::new(&code) {
+                                let string = qr
+                                    .render::<char>()
+                                    .quiet_zone(true)
+                                    .module_dimensions(2, 1)
+                                    .build();
+                                println!("{}", string);
+
+                                // Also save as PNG for remote scanning
+                                let img = qr.render::<image::Luma<u8>>()
+                                    .quiet_zone(true)
+                                    .min_dimensions(600, 600)
+                                    .build();
+                                let png_path = "/tmp/hive-qr.png";
+                                if let Err(e) = img.save(png_path) {
+                                    warn!("Failed to save QR PNG: {}", e);
+                                } else {
+                                    info!("📸 QR code saved to {}", png_path);
+                                }
+                            } else {
+                                println!("QR Data: {}", code);
+                            }
+                            println!(
+                                "⏱  Code expires in {} seconds\n",
+                                timeout.as_secs()
+                            );
+                        }
+                        Event::PairingCode { code, timeout } => {
+                            println!(
+                                "\n🔑 Enter this pairing code on your phone: {}",
+                                code
+                            );
+                            println!(
+                                "⏱  Code expires in {} seconds\n",
+                                timeout.as_secs()
+                            );
+                        }
+                        Event::Connected(_) => {
+                            info!("✅ Connected to WhatsApp!");
+
+                            // Make the client available for outbound sends
+                            *client_slot.write().await = Some(client.clone());
+                        }
+                        Event::Disconnected(_) => {
+                            warn!("⚠️  Disconnected from WhatsApp");
+                        }
+                        Event::LoggedOut(logout) => {
+                            error!(
+                                "🚫 Logged out from WhatsApp: {:?}",
+                                logout.reason
+                            );
+                        }
+                        Event::Message(message, info) => {
+                            let msg = InboundMessage {
+                                sender: info.source.sender.to_string(),
+                                sender_name: Some(info.push_name.clone()).filter(|n| !n.is_empty()),
+                                chat: info.source.chat.to_string(),
+                                is_from_me: info.source.is_from_me,
+                                is_group: info.source.is_group,
+                                ..message_content(&message)
+                            };
+                            if inbound.send(msg).await.is_err() {
+                                warn!("Inbound channel closed, dropping message from {}", info.source.sender);
+                            }
+                        }
+                        _ => {
+                            // Ignore other events (receipts, presence, etc.)
+                        }
+                    }
+                }
+            })
+            .build()
+            .await?;
+
+        info!("🐝 Bot is starting — waiting for WhatsApp connection...");
+
+        // Run the bot (blocks until disconnected)
+        let handle = bot.run().await?;
+        handle.await.map_err(|e| anyhow::anyhow!("Bot task panicked: {}", e))?;
+
+        Ok(())
+    }
+}
+
+/// Extract the text and location content of a whatsapp-rust message.
+fn message_content(message: &waproto::whatsapp::Message) -> InboundMessage {
+    use wacore::proto_helpers::MessageExt;
+
+    // Extract text content from the message; a tapped button or list row
+    // counts as typing its id
+    let base_msg = message.get_base_message();
+    let text = choice_id(base_msg)
+        .or_else(|| base_msg.text_content())
+        .or_else(|| base_msg.get_caption())
+        .unwrap_or("")
+        .trim()
+        .to_string();
+
+    InboundMessage {
+        text,
+        has_location: base_msg.location_message.is_some()
+            || base_msg.live_location_message.is_some(),
+        location_text: extract_location_text(base_msg),
+        ..Default::default()
+    }
+}
+
+/// The id of the button or list row a customer tapped, if the message is a
+/// reply to a button or list message.
+fn choice_id(msg: &waproto::whatsapp::Message) -> Option<&str> {
+    if let Some(ref reply) = msg.buttons_response_message {
+        return reply.selected_button_id.as_deref();
+    }
+    msg.list_response_message
+        .as_ref()
+        .and_then(|reply| reply.single_select_reply.as_ref())
+        .and_then(|reply| reply.selected_row_id.as_deref())
+}
+
+/// Extract a text representation of a location message.
+fn extract_location_text(msg: &waproto::whatsapp::Message) -> Option<String> {
+    if let Some(ref loc) = msg.location_message {
+        let lat = loc.degrees_latitude.unwrap_or(0.0);
+        let lng = loc.degrees_longitude.unwrap_or(0.0);
+        let name = loc.name.as_deref().unwrap_or("");
+        let address = loc.address.as_deref().unwrap_or("");
+        if !name.is_empty() || !address.is_empty() {
+            Some(format!("{} {} ({}, {})", name, address, lat, lng))
+        } else {
+            Some(format!("{}, {}", lat, lng))
+        }
+    } else if let Some(ref loc) = msg.live_location_message {
+        let lat = loc.degrees_latitude.unwrap_or(0.0);
+        let lng = loc.degrees_longitude.unwrap_or(0.0);
+        Some(format!("{}, {}", lat, lng))
+    } else {
+        None
+    }
+}